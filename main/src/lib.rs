@@ -14,21 +14,34 @@ pub mod preprocessing;
 
 pub use crate::preprocessing::error;
 pub use crate::preprocessing::tokenizer::albert_tokenizer::AlbertTokenizer;
+pub use crate::preprocessing::tokenizer::any_tokenizer::AnyTokenizer;
+pub use crate::preprocessing::tokenizer::auto_tokenizer::AutoTokenizer;
 pub use crate::preprocessing::tokenizer::base_tokenizer::{
-    MultiThreadedTokenizer, TokenizedInput, Tokenizer, TruncationStrategy,
+    AlignmentIndex, MultiThreadedTokenizer, OffsetUnit, PaddingOptions, PaddingSide,
+    PaddingStrategy, SubwordSamplingTokenizer, TokenizedInput, Tokenizer, TruncationStrategy,
 };
 pub use crate::preprocessing::tokenizer::bert_tokenizer::BertTokenizer;
+pub use crate::preprocessing::tokenizer::bpe_cache::BpeCache;
 pub use crate::preprocessing::tokenizer::ctrl_tokenizer::CtrlTokenizer;
 pub use crate::preprocessing::tokenizer::gpt2_tokenizer::Gpt2Tokenizer;
+pub use crate::preprocessing::tokenizer::hf_tokenizer::HFTokenizer;
+pub use crate::preprocessing::tokenizer::normalizer::Normalizer;
 pub use crate::preprocessing::tokenizer::openai_gpt_tokenizer::OpenAiGptTokenizer;
+pub use crate::preprocessing::tokenizer::post_processor::{
+    TemplatePiece, TemplateProcessing, TemplateSequence,
+};
+pub use crate::preprocessing::tokenizer::pre_tokenizer::PreTokenizer;
 pub use crate::preprocessing::tokenizer::roberta_tokenizer::RobertaTokenizer;
 pub use crate::preprocessing::tokenizer::sentence_piece_tokenizer::SentencePieceTokenizer;
+pub use crate::preprocessing::tokenizer::tokenizer_json::TokenizerJson;
 pub use crate::preprocessing::tokenizer::xlnet_tokenizer::XLNetTokenizer;
-pub use crate::preprocessing::vocab::base_vocab::Vocab;
+pub use crate::preprocessing::vocab::base_vocab::{AddedToken, SpecialTokenMap, Vocab};
 pub use preprocessing::tokenizer::bert_tokenizer;
+pub use preprocessing::tokenizer::normalizer;
+pub use preprocessing::tokenizer::pre_tokenizer;
 pub use preprocessing::tokenizer::tokenization_utils;
 pub use preprocessing::vocab::{
-    base_vocab::BaseVocab, bert_vocab::BertVocab, gpt2_vocab::Gpt2Vocab,
+    base_vocab::BaseVocab, bert_vocab::BertVocab, gpt2_vocab::Gpt2Vocab, hf_vocab::HFVocab,
     openai_gpt_vocab::OpenAiGptVocab, roberta_vocab::RobertaVocab,
     xlm_roberta_vocab::XLMRobertaVocab, xlnet_vocab::XLNetVocab,
 };
@@ -16,4 +16,28 @@ pub enum TokenizerError {
 
     #[error("Tokenization error: {0}")]
     TokenizationError(String),
+
+    #[error("Sequence too long: {sequence_length} tokens for a maximum length of {max_length}, but no truncation was requested")]
+    SequenceTooLong {
+        sequence_length: usize,
+        max_length: usize,
+    },
+
+    #[error("Sequence too short for truncation: cannot remove {num_tokens_to_remove} tokens from a sequence of {sequence_length} tokens")]
+    SequenceTooShort {
+        sequence_length: usize,
+        num_tokens_to_remove: usize,
+    },
+
+    #[error("Invalid truncation strategy: {0}")]
+    InvalidTruncationStrategy(String),
+
+    #[error("Unsupported tokenizer component: {0}")]
+    UnsupportedComponent(String),
+
+    #[error("File could not be written: {0}")]
+    FileWriteError(String),
+
+    #[error("Invalid post-processing template: {0}")]
+    InvalidTemplate(String),
 }
@@ -11,14 +11,24 @@
 // limitations under the License.
 
 use crate::preprocessing::error::TokenizerError;
-use crate::preprocessing::tokenizer::base_tokenizer::{Mask, Offset, OffsetSize, Token, TokenRef};
-use crate::preprocessing::tokenizer::tokenization_utils::strip_accents;
+use crate::preprocessing::tokenizer::base_tokenizer::{
+    Mask, Offset, SubwordSamplingTokenizer, Token, TokenRef,
+};
+use crate::preprocessing::tokenizer::normalizer::{sentence_piece_normalizer, Normalizer};
+use crate::preprocessing::tokenizer::post_processor::{TemplatePiece, TemplateProcessing};
+use crate::preprocessing::tokenizer::pre_tokenizer::{PreTokenizer, Sequence};
+use crate::preprocessing::tokenizer::tokenizer_json::{
+    check_default_components, post_processor_json, read_normalizer_flags,
+    read_sentence_piece_model, read_vocab, restore_post_processor, sentence_piece_tokenizer_json,
+    TokenizerJson,
+};
 use crate::preprocessing::vocab::albert_vocab::AlbertVocab;
 use crate::preprocessing::vocab::sentence_piece_vocab::SentencePieceModel;
-use crate::tokenization_utils::{
-    _clean_text, decompose_nfkc, is_whitespace, lowercase, replace_string, split_on_special_tokens,
-};
+use crate::tokenization_utils::{merge_byte_pieces, split_on_added_tokens};
 use crate::{MultiThreadedTokenizer, Tokenizer, Vocab};
+use rand::Rng;
+use serde_json::Value;
+use std::io::Read;
 
 #[derive(Debug)]
 pub struct AlbertTokenizer {
@@ -26,6 +36,11 @@ pub struct AlbertTokenizer {
     vocab: AlbertVocab,
     lower_case: bool,
     strip_accents: bool,
+    normalizer: Box<dyn Normalizer>,
+    custom_normalizer: bool,
+    pre_tokenizer: Box<dyn PreTokenizer>,
+    custom_pre_tokenizer: bool,
+    post_processor: Option<TemplateProcessing>,
 }
 
 impl AlbertTokenizer {
@@ -36,25 +51,70 @@ impl AlbertTokenizer {
     ) -> Result<AlbertTokenizer, TokenizerError> {
         let model = SentencePieceModel::from_file(path)?;
         let vocab = AlbertVocab::from_file(path)?;
+        let normalizer =
+            sentence_piece_normalizer(lower_case, strip_accents, true, model.normalizer.as_ref());
         Ok(AlbertTokenizer {
             model,
             vocab,
             lower_case,
             strip_accents,
+            normalizer: Box::new(normalizer),
+            custom_normalizer: false,
+            pre_tokenizer: Box::new(Sequence::default()),
+            custom_pre_tokenizer: false,
+            post_processor: None,
         })
     }
 
+    /// Create a new instance of a `AlbertTokenizer` from a reader for the SentencePiece model (e.g. a file
+    /// or a network stream), with the same content as the file expected by `from_file`.
+    pub fn from_reader<R: Read>(
+        mut reader: R,
+        lower_case: bool,
+        strip_accents: bool,
+    ) -> Result<AlbertTokenizer, TokenizerError> {
+        let mut contents = Vec::new();
+        reader
+            .read_to_end(&mut contents)
+            .map_err(|e| TokenizerError::VocabularyParsingError(e.to_string()))?;
+        AlbertTokenizer::from_bytes(&contents, lower_case, strip_accents)
+    }
+
+    /// Create a new instance of a `AlbertTokenizer` from a SentencePiece model held in memory (e.g. loaded
+    /// with `include_bytes!`).
+    pub fn from_bytes(
+        bytes: &[u8],
+        lower_case: bool,
+        strip_accents: bool,
+    ) -> Result<AlbertTokenizer, TokenizerError> {
+        let model = SentencePieceModel::from_bytes(bytes)?;
+        let vocab = AlbertVocab::from_bytes(bytes)?;
+        Ok(AlbertTokenizer::from_existing_vocab_and_model(
+            vocab,
+            model,
+            lower_case,
+            strip_accents,
+        ))
+    }
+
     pub fn from_existing_vocab_and_model(
         vocab: AlbertVocab,
         model: SentencePieceModel,
         lower_case: bool,
         strip_accents: bool,
     ) -> AlbertTokenizer {
+        let normalizer =
+            sentence_piece_normalizer(lower_case, strip_accents, true, model.normalizer.as_ref());
         AlbertTokenizer {
             model,
             vocab,
             lower_case,
             strip_accents,
+            normalizer: Box::new(normalizer),
+            custom_normalizer: false,
+            pre_tokenizer: Box::new(Sequence::default()),
+            custom_pre_tokenizer: false,
+            post_processor: None,
         }
     }
 
@@ -68,9 +128,7 @@ impl AlbertTokenizer {
                 {
                     let mut new_token = token.clone();
                     let last_char = new_token.text.pop().unwrap();
-                    let updated_tokens = self.model.decode_forward_token_ref(new_token.as_ref());
-                    let updated_tokens = self.model.decode_backward(&updated_tokens);
-                    let mut updated_tokens = self.model.parse_nodes_to_tokens(updated_tokens);
+                    let mut updated_tokens = self.model.tokenize(new_token.as_ref());
 
                     if (token.text.chars().next().unwrap() != '\u{2581}')
                         & (updated_tokens[0].text.chars().next().unwrap() == '\u{2581}')
@@ -103,131 +161,162 @@ impl AlbertTokenizer {
         }
         tokens
     }
-}
 
-impl Tokenizer<AlbertVocab> for AlbertTokenizer {
-    fn vocab(&self) -> &AlbertVocab {
-        &self.vocab
-    }
-
-    fn tokenize_to_tokens(&self, text: TokenRef) -> Vec<Token> {
-        let mut tokens = split_on_special_tokens(text, &self.vocab)
+    ///Splits the added tokens, normalizes and pre-tokenizes the text, the remaining tokens being
+    ///segmented into pieces with `segment`
+    fn tokenize_with_segmentation<F: FnMut(TokenRef) -> Vec<Token>>(
+        &self,
+        text: TokenRef,
+        mut segment: F,
+    ) -> Vec<Token> {
+        let mut tokens = split_on_added_tokens(text, &self.vocab, self.normalizer.is_lowercasing())
             .into_iter()
             .map(|token| token.to_owned())
             .collect::<Vec<Token>>();
 
         let mut sub_tokens: Vec<Token> = Vec::new();
         for token in tokens.iter_mut() {
-            if token.mask != Mask::Special && token.mask != Mask::Unknown {
-                replace_string(token, "``", "\"");
-                replace_string(token, "\'\'", "\"");
-                _clean_text(token, true);
-                decompose_nfkc(token);
-                if self.lower_case {
-                    lowercase(token);
-                }
-                if self.strip_accents {
-                    strip_accents(token);
+            if token.mask != Mask::Special
+                && token.mask != Mask::Added
+                && token.mask != Mask::Unknown
+            {
+                self.normalizer.normalize(token);
+                for token in self.pre_tokenizer.pre_tokenize(token.as_ref()) {
+                    let mut output: Vec<Token> = segment(token);
+                    self.post_process_pieces(&mut output);
+                    sub_tokens.extend(output)
                 }
-                token.text = token.text.replace(|c: char| is_whitespace(&c), "\u{2581}");
-                if !token.text.starts_with('\u{2581}') {
-                    token.text.insert(0, '\u{2581}');
-                    token.reference_offsets.insert(0, 0);
-                };
-                let output = self.model.decode_forward_token_ref(token.as_ref());
-                let decoded = self.model.decode_backward(&output);
-
-                let mut output: Vec<Token> = self.model.parse_nodes_to_tokens(decoded);
-                self.post_process_pieces(&mut output);
-                sub_tokens.extend(output)
             } else {
                 sub_tokens.push(token.clone());
             }
         }
         sub_tokens
     }
+}
+
+impl Tokenizer<AlbertVocab> for AlbertTokenizer {
+    fn vocab(&self) -> &AlbertVocab {
+        &self.vocab
+    }
+
+    fn vocab_mut(&mut self) -> &mut AlbertVocab {
+        &mut self.vocab
+    }
+
+    fn normalizer(&self) -> &dyn Normalizer {
+        self.normalizer.as_ref()
+    }
+
+    fn set_normalizer(&mut self, normalizer: Box<dyn Normalizer>) {
+        self.normalizer = normalizer;
+        self.custom_normalizer = true;
+    }
+
+    fn pre_tokenizer(&self) -> &dyn PreTokenizer {
+        self.pre_tokenizer.as_ref()
+    }
+
+    fn set_pre_tokenizer(&mut self, pre_tokenizer: Box<dyn PreTokenizer>) {
+        self.pre_tokenizer = pre_tokenizer;
+        self.custom_pre_tokenizer = true;
+    }
+
+    fn post_processor(&self) -> Option<&TemplateProcessing> {
+        self.post_processor.as_ref()
+    }
+
+    fn post_processor_mut(&mut self) -> &mut Option<TemplateProcessing> {
+        &mut self.post_processor
+    }
+
+    fn tokenize_to_tokens(&self, text: TokenRef) -> Vec<Token> {
+        self.tokenize_with_segmentation(text, |token| self.model.tokenize(token))
+    }
 
     fn convert_tokens_to_string(&self, tokens: Vec<String>) -> String {
-        tokens
+        merge_byte_pieces(tokens)
             .into_iter()
             .map(|v| v.replace('\u{2581}', " "))
             .collect::<Vec<String>>()
             .join("")
     }
 
-    fn build_input_with_special_tokens(
-        &self,
-        tokens_1: Vec<i64>,
-        tokens_2: Option<Vec<i64>>,
-        offsets_1: Vec<Option<Offset>>,
-        offsets_2: Option<Vec<Option<Offset>>>,
-        original_offsets_1: Vec<Vec<OffsetSize>>,
-        original_offsets_2: Option<Vec<Vec<OffsetSize>>>,
-        mask_1: Vec<Mask>,
-        mask_2: Option<Vec<Mask>>,
-    ) -> (
-        Vec<i64>,
-        Vec<i8>,
-        Vec<i8>,
-        Vec<Option<Offset>>,
-        Vec<Vec<OffsetSize>>,
-        Vec<Mask>,
-    ) {
-        let mut output: Vec<i64> = vec![];
-        let mut token_segment_ids: Vec<i8> = vec![];
-        let mut special_tokens_mask: Vec<i8> = vec![];
-        let mut offsets: Vec<Option<Offset>> = vec![];
-        let mut original_offsets: Vec<Vec<OffsetSize>> = vec![];
-        let mut mask: Vec<Mask> = vec![];
-        special_tokens_mask.push(1);
-        special_tokens_mask.extend(vec![0; tokens_1.len()]);
-        special_tokens_mask.push(1);
-        token_segment_ids.extend(vec![0; tokens_1.len() + 2]);
-        output.push(self.vocab.token_to_id(AlbertVocab::cls_value()));
-        output.extend(tokens_1);
-        output.push(self.vocab.token_to_id(AlbertVocab::sep_value()));
-        offsets.push(None);
-        offsets.extend(offsets_1);
-        offsets.push(None);
-        original_offsets.push(vec![]);
-        original_offsets.extend(original_offsets_1);
-        original_offsets.push(vec![]);
-        mask.push(Mask::Special);
-        mask.extend(mask_1);
-        mask.push(Mask::Special);
-        if let Some(add_tokens) = tokens_2 {
-            let length = add_tokens.len();
-            special_tokens_mask.extend(vec![0; length]);
-            special_tokens_mask.push(1);
-            token_segment_ids.extend(vec![1; length + 1]);
-            output.extend(add_tokens);
-            output.push(self.vocab.token_to_id(AlbertVocab::sep_value()));
-            if let Some(add_offsets) = offsets_2 {
-                offsets.extend(add_offsets);
-            } else {
-                offsets.extend(vec![None; length]);
-            }
-            if let Some(add_original_offsets) = original_offsets_2 {
-                original_offsets.extend(add_original_offsets);
-            }
-            offsets.push(None);
-            original_offsets.push(vec![]);
-            if let Some(mask_2) = mask_2 {
-                mask.extend(mask_2)
-            } else {
-                mask.extend(vec![Mask::None; length]);
-            }
-            mask.push(Mask::Special);
-        }
-        (
-            output,
-            token_segment_ids,
-            special_tokens_mask,
-            offsets,
-            original_offsets,
-            mask,
+    fn default_post_processor(&self) -> TemplateProcessing {
+        let (cls, sep) = (self.vocab.get_cls_value(), self.vocab.get_sep_value());
+        TemplateProcessing::from_pieces(
+            vec![
+                TemplatePiece::special_token(cls, 0),
+                TemplatePiece::sequence(0, 0),
+                TemplatePiece::special_token(sep, 0),
+            ],
+            vec![
+                TemplatePiece::special_token(cls, 0),
+                TemplatePiece::sequence(0, 0),
+                TemplatePiece::special_token(sep, 0),
+                TemplatePiece::sequence(1, 1),
+                TemplatePiece::special_token(sep, 1),
+            ],
         )
+        .unwrap()
     }
 }
 
 impl MultiThreadedTokenizer<AlbertVocab> for AlbertTokenizer {}
+
+impl SubwordSamplingTokenizer<AlbertVocab> for AlbertTokenizer {
+    fn tokenize_to_tokens_with_sampling<R: Rng + ?Sized>(
+        &self,
+        text: TokenRef,
+        nbest_size: i64,
+        alpha: f32,
+        rng: &mut R,
+    ) -> Vec<Token> {
+        self.tokenize_with_segmentation(text, |token| {
+            self.model
+                .tokenize_with_sampling(token, nbest_size, alpha, &mut *rng)
+        })
+    }
+}
+
+impl TokenizerJson for AlbertTokenizer {
+    fn to_tokenizer_json(&self) -> Result<Value, TokenizerError> {
+        check_default_components(self.custom_normalizer, self.custom_pre_tokenizer)?;
+        Ok(sentence_piece_tokenizer_json(
+            &self.vocab,
+            &self.model,
+            self.lower_case,
+            self.strip_accents,
+            true,
+            post_processor_json(self),
+        ))
+    }
+
+    fn from_tokenizer_json(tokenizer_json: &Value) -> Result<AlbertTokenizer, TokenizerError> {
+        let vocab = read_vocab(
+            tokenizer_json,
+            |values,
+             indices,
+             special_token_map,
+             special_values,
+             special_indices,
+             added_values,
+             added_tokens| {
+                AlbertVocab {
+                    values,
+                    indices,
+                    special_token_map,
+                    special_values,
+                    special_indices,
+                    added_values,
+                    added_tokens,
+                }
+            },
+        )?;
+        let model = read_sentence_piece_model(tokenizer_json)?;
+        let (lower_case, strip_accents) = read_normalizer_flags(tokenizer_json);
+        let mut tokenizer =
+            AlbertTokenizer::from_existing_vocab_and_model(vocab, model, lower_case, strip_accents);
+        restore_post_processor(&mut tokenizer, tokenizer_json)?;
+        Ok(tokenizer)
+    }
+}
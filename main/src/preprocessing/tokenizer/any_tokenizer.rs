@@ -0,0 +1,453 @@
+// Copyright 2019-2020 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::albert_tokenizer::AlbertTokenizer;
+use crate::preprocessing::tokenizer::base_tokenizer::{
+    Mask, Offset, OffsetSize, PaddingOptions, TokenizedInput, Tokenizer, TruncationStrategy,
+};
+use crate::preprocessing::tokenizer::bert_tokenizer::BertTokenizer;
+use crate::preprocessing::tokenizer::ctrl_tokenizer::CtrlTokenizer;
+use crate::preprocessing::tokenizer::gpt2_tokenizer::Gpt2Tokenizer;
+use crate::preprocessing::tokenizer::hf_tokenizer::HFTokenizer;
+use crate::preprocessing::tokenizer::marian_tokenizer::MarianTokenizer;
+use crate::preprocessing::tokenizer::normalizer::Normalizer;
+use crate::preprocessing::tokenizer::openai_gpt_tokenizer::OpenAiGptTokenizer;
+use crate::preprocessing::tokenizer::post_processor::TemplateProcessing;
+use crate::preprocessing::tokenizer::pre_tokenizer::PreTokenizer;
+use crate::preprocessing::tokenizer::roberta_tokenizer::RobertaTokenizer;
+use crate::preprocessing::tokenizer::sentence_piece_tokenizer::SentencePieceTokenizer;
+use crate::preprocessing::tokenizer::t5_tokenizer::T5Tokenizer;
+use crate::preprocessing::tokenizer::tokenizer_json::TokenizerJson;
+use crate::preprocessing::tokenizer::xlm_roberta_tokenizer::XLMRobertaTokenizer;
+use crate::preprocessing::tokenizer::xlnet_tokenizer::XLNetTokenizer;
+use crate::preprocessing::vocab::base_vocab::Vocab;
+use serde_json::Value;
+
+/// # Tokenizer handle for any of the tokenizers of the crate
+/// The `Tokenizer` trait is generic over its vocabulary, which prevents storing tokenizers of
+/// different types in a single field. `AnyTokenizer` wraps each of the tokenizers of the crate and
+/// exposes tokenization, encoding, decoding and vocabulary lookups behind a single type, allowing
+/// to pick a tokenizer at runtime (for example from a model configuration).
+///
+/// # Example
+///
+/// ```no_run
+/// use rust_tokenizers::{AnyTokenizer, BertTokenizer, PaddingOptions, TruncationStrategy};
+/// let tokenizer: AnyTokenizer = BertTokenizer::from_file("path/to/vocab", true, true)
+///     .unwrap()
+///     .into();
+/// let encoded = tokenizer.encode(
+///     "Hello, world!",
+///     None,
+///     128,
+///     &TruncationStrategy::LongestFirst,
+///     0,
+///     &PaddingOptions::default(),
+/// );
+/// ```
+#[derive(Debug)]
+pub enum AnyTokenizer {
+    Bert(BertTokenizer),
+    Ctrl(CtrlTokenizer),
+    Gpt2(Gpt2Tokenizer),
+    Roberta(RobertaTokenizer),
+    OpenAiGpt(OpenAiGptTokenizer),
+    SentencePiece(SentencePieceTokenizer),
+    Albert(AlbertTokenizer),
+    XLNet(XLNetTokenizer),
+    T5(T5Tokenizer),
+    XLMRoberta(XLMRobertaTokenizer),
+    Marian(MarianTokenizer),
+    HF(HFTokenizer),
+}
+
+macro_rules! dispatch {
+    ($self:expr, $tokenizer:ident => $body:expr) => {
+        match $self {
+            AnyTokenizer::Bert($tokenizer) => $body,
+            AnyTokenizer::Ctrl($tokenizer) => $body,
+            AnyTokenizer::Gpt2($tokenizer) => $body,
+            AnyTokenizer::Roberta($tokenizer) => $body,
+            AnyTokenizer::OpenAiGpt($tokenizer) => $body,
+            AnyTokenizer::SentencePiece($tokenizer) => $body,
+            AnyTokenizer::Albert($tokenizer) => $body,
+            AnyTokenizer::XLNet($tokenizer) => $body,
+            AnyTokenizer::T5($tokenizer) => $body,
+            AnyTokenizer::XLMRoberta($tokenizer) => $body,
+            AnyTokenizer::Marian($tokenizer) => $body,
+            AnyTokenizer::HF($tokenizer) => $body,
+        }
+    };
+}
+
+impl AnyTokenizer {
+    ///Tokenize a string, returns a vector of tokens as strings.
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        dispatch!(self, tokenizer => tokenizer.tokenize(text))
+    }
+
+    ///Tokenize a string, return offset information
+    pub fn tokenize_with_offsets(
+        &self,
+        text: &str,
+    ) -> (
+        Vec<String>,
+        Vec<Option<Offset>>,
+        Vec<Vec<OffsetSize>>,
+        Vec<Mask>,
+    ) {
+        dispatch!(self, tokenizer => tokenizer.tokenize_with_offsets(text))
+    }
+
+    ///Tokenize a vector of strings, returns a vector of vectors of strings.
+    pub fn tokenize_list(&self, text_list: Vec<&str>) -> Vec<Vec<String>> {
+        dispatch!(self, tokenizer => Tokenizer::tokenize_list(tokenizer, text_list))
+    }
+
+    ///Encode a string or a pair of strings into a `TokenizedInput`, truncated to `max_len` and
+    ///padded following `padding`.
+    ///Panics if the requested truncation cannot be performed, use `try_encode` to handle these cases.
+    pub fn encode(
+        &self,
+        text_1: &str,
+        text_2: Option<&str>,
+        max_len: usize,
+        truncation_strategy: &TruncationStrategy,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> TokenizedInput {
+        dispatch!(self, tokenizer => tokenizer.encode(text_1, text_2, max_len, truncation_strategy, stride, padding))
+    }
+
+    ///Encode a string or a pair of strings into a `TokenizedInput`, truncated to `max_len` and
+    ///padded following `padding`.
+    ///Returns a `TokenizerError` if the truncation strategy cannot be applied.
+    pub fn try_encode(
+        &self,
+        text_1: &str,
+        text_2: Option<&str>,
+        max_len: usize,
+        truncation_strategy: &TruncationStrategy,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Result<TokenizedInput, TokenizerError> {
+        dispatch!(self, tokenizer => tokenizer.try_encode(text_1, text_2, max_len, truncation_strategy, stride, padding))
+    }
+
+    ///Encode a list of strings into a list of `TokenizedInput`, padded as a batch
+    pub fn encode_list(
+        &self,
+        text_list: Vec<&str>,
+        max_len: usize,
+        truncation_strategy: &TruncationStrategy,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Vec<TokenizedInput> {
+        dispatch!(self, tokenizer => Tokenizer::encode_list(tokenizer, text_list, max_len, truncation_strategy, stride, padding))
+    }
+
+    ///Encode a list of pairs of strings into a list of `TokenizedInput`, padded as a batch
+    pub fn encode_pair_list(
+        &self,
+        text_list: Vec<(&str, &str)>,
+        max_len: usize,
+        truncation_strategy: &TruncationStrategy,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Vec<TokenizedInput> {
+        dispatch!(self, tokenizer => Tokenizer::encode_pair_list(tokenizer, text_list, max_len, truncation_strategy, stride, padding))
+    }
+
+    ///Converts a sequence of ids into a string, optionally removing special tokens and cleaning up
+    ///tokenization spaces.
+    pub fn decode(
+        &self,
+        token_ids: Vec<i64>,
+        skip_special_tokens: bool,
+        clean_up_tokenization_spaces: bool,
+    ) -> String {
+        dispatch!(self, tokenizer => tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces))
+    }
+
+    ///Converts a list of sequences of ids into a list of strings
+    pub fn decode_list(
+        &self,
+        token_ids_list: Vec<Vec<i64>>,
+        skip_special_tokens: bool,
+        clean_up_tokenization_spaces: bool,
+    ) -> Vec<String> {
+        dispatch!(self, tokenizer => Tokenizer::decode_list(tokenizer, token_ids_list, skip_special_tokens, clean_up_tokenization_spaces))
+    }
+
+    ///Converts a list of tokens into their ids in the vocabulary
+    pub fn convert_tokens_to_ids(&self, tokens: &Vec<String>) -> Vec<i64> {
+        dispatch!(self, tokenizer => tokenizer.convert_tokens_to_ids(tokens))
+    }
+
+    ///Returns the id of a token, falling back to the id of the unknown token
+    pub fn token_to_id(&self, token: &str) -> i64 {
+        dispatch!(self, tokenizer => Tokenizer::vocab(tokenizer).token_to_id(token))
+    }
+
+    ///Returns the token associated with an id, falling back to the unknown token
+    pub fn id_to_token(&self, id: &i64) -> String {
+        dispatch!(self, tokenizer => Tokenizer::vocab(tokenizer).id_to_token(id))
+    }
+
+    ///Returns the string of the unknown token of the underlying vocabulary
+    pub fn get_unknown_value(&self) -> &str {
+        dispatch!(self, tokenizer => Tokenizer::vocab(tokenizer).get_unknown_value())
+    }
+
+    ///Returns the string of the padding token of the underlying vocabulary
+    pub fn get_pad_value(&self) -> &str {
+        dispatch!(self, tokenizer => Tokenizer::vocab(tokenizer).get_pad_value())
+    }
+
+    ///Number of entries in the vocabulary
+    pub fn vocab_size(&self) -> usize {
+        dispatch!(self, tokenizer => Tokenizer::vocab(tokenizer).values().len())
+    }
+
+    ///Adds regular tokens to the vocabulary, returns the number of tokens not yet in the vocabulary
+    pub fn add_tokens(&mut self, tokens: &[&str]) -> usize {
+        dispatch!(self, tokenizer => tokenizer.add_tokens(tokens))
+    }
+
+    ///Adds special tokens to the vocabulary, returns the number of tokens not yet in the vocabulary
+    pub fn add_special_tokens(&mut self, tokens: &[&str]) -> usize {
+        dispatch!(self, tokenizer => tokenizer.add_special_tokens(tokens))
+    }
+
+    ///Replaces the normalizer applied to the text before the tokenization model runs (see `Normalizer`)
+    pub fn set_normalizer(&mut self, normalizer: Box<dyn Normalizer>) {
+        dispatch!(self, tokenizer => tokenizer.set_normalizer(normalizer))
+    }
+
+    ///Replaces the pre-tokenizer splitting the text into words before the tokenization model runs
+    ///(see `PreTokenizer`)
+    pub fn set_pre_tokenizer(&mut self, pre_tokenizer: Box<dyn PreTokenizer>) {
+        dispatch!(self, tokenizer => tokenizer.set_pre_tokenizer(pre_tokenizer))
+    }
+
+    ///Overrides the template adding special tokens to the encoded sequences (see `TemplateProcessing`)
+    pub fn set_post_processor(
+        &mut self,
+        post_processor: TemplateProcessing,
+    ) -> Result<(), TokenizerError> {
+        dispatch!(self, tokenizer => tokenizer.set_post_processor(post_processor))
+    }
+
+    ///Returns the `tokenizer.json` representation of the tokenizer (see `TokenizerJson`). Fails for
+    ///the CTRL, Marian and `tokenizer.json`-based tokenizers, and for tokenizers with a normalizer
+    ///or a pre-tokenizer set with `set_normalizer` or `set_pre_tokenizer`.
+    pub fn to_tokenizer_json(&self) -> Result<Value, TokenizerError> {
+        match self {
+            AnyTokenizer::Bert(tokenizer) => tokenizer.to_tokenizer_json(),
+            AnyTokenizer::Gpt2(tokenizer) => tokenizer.to_tokenizer_json(),
+            AnyTokenizer::Roberta(tokenizer) => tokenizer.to_tokenizer_json(),
+            AnyTokenizer::OpenAiGpt(tokenizer) => tokenizer.to_tokenizer_json(),
+            AnyTokenizer::SentencePiece(tokenizer) => tokenizer.to_tokenizer_json(),
+            AnyTokenizer::Albert(tokenizer) => tokenizer.to_tokenizer_json(),
+            AnyTokenizer::XLNet(tokenizer) => tokenizer.to_tokenizer_json(),
+            AnyTokenizer::T5(tokenizer) => tokenizer.to_tokenizer_json(),
+            AnyTokenizer::XLMRoberta(tokenizer) => tokenizer.to_tokenizer_json(),
+            AnyTokenizer::Ctrl(_) => Err(unsupported_serialization("CTRL")),
+            AnyTokenizer::Marian(_) => Err(unsupported_serialization("Marian")),
+            AnyTokenizer::HF(_) => Err(unsupported_serialization("tokenizer.json")),
+        }
+    }
+}
+
+fn unsupported_serialization(tokenizer_type: &str) -> TokenizerError {
+    TokenizerError::UnsupportedComponent(format!("serialization of {} tokenizers", tokenizer_type))
+}
+
+macro_rules! impl_from_tokenizer {
+    ($tokenizer:ty, $variant:ident) => {
+        impl From<$tokenizer> for AnyTokenizer {
+            fn from(tokenizer: $tokenizer) -> Self {
+                AnyTokenizer::$variant(tokenizer)
+            }
+        }
+    };
+}
+
+impl_from_tokenizer!(BertTokenizer, Bert);
+impl_from_tokenizer!(CtrlTokenizer, Ctrl);
+impl_from_tokenizer!(Gpt2Tokenizer, Gpt2);
+impl_from_tokenizer!(RobertaTokenizer, Roberta);
+impl_from_tokenizer!(OpenAiGptTokenizer, OpenAiGpt);
+impl_from_tokenizer!(SentencePieceTokenizer, SentencePiece);
+impl_from_tokenizer!(AlbertTokenizer, Albert);
+impl_from_tokenizer!(XLNetTokenizer, XLNet);
+impl_from_tokenizer!(T5Tokenizer, T5);
+impl_from_tokenizer!(XLMRobertaTokenizer, XLMRoberta);
+impl_from_tokenizer!(MarianTokenizer, Marian);
+impl_from_tokenizer!(HFTokenizer, HF);
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessing::vocab::base_vocab::swap_key_values;
+    use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
+    use crate::{BertVocab, Gpt2Vocab};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn generate_bert_vocab() -> BertVocab {
+        let values: HashMap<String, i64> = [
+            ("hello".to_owned(), 0),
+            ("world".to_owned(), 1),
+            ("[UNK]".to_owned(), 2),
+            ("!".to_owned(), 3),
+            ("[CLS]".to_owned(), 4),
+            ("[SEP]".to_owned(), 5),
+            ("[PAD]".to_owned(), 6),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+
+        let special_values: HashMap<String, i64> = [
+            ("[UNK]".to_owned(), 2),
+            ("[CLS]".to_owned(), 4),
+            ("[SEP]".to_owned(), 5),
+            ("[PAD]".to_owned(), 6),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+
+        let indices = swap_key_values(&values);
+        let special_indices = swap_key_values(&special_values);
+
+        BertVocab {
+            values,
+            indices,
+            special_token_map: BertVocab::default_special_token_map(),
+            special_values,
+            special_indices,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
+        }
+    }
+
+    fn generate_gpt2_vocab() -> Gpt2Vocab {
+        let values: HashMap<String, i64> = [
+            ("hello".to_owned(), 0),
+            ("Ġworld".to_owned(), 1),
+            ("<|endoftext|>".to_owned(), 2),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+
+        let special_values: HashMap<String, i64> =
+            [("<|endoftext|>".to_owned(), 2)].iter().cloned().collect();
+
+        let indices = swap_key_values(&values);
+        let special_indices = swap_key_values(&special_values);
+
+        Gpt2Vocab {
+            values,
+            indices,
+            special_token_map: Gpt2Vocab::default_special_token_map(),
+            special_values,
+            special_indices,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
+        }
+    }
+
+    fn generate_gpt2_merges() -> BpePairVocab {
+        let merges = [
+            ("h", "e"),
+            ("l", "l"),
+            ("he", "ll"),
+            ("hell", "o"),
+            ("Ġ", "w"),
+            ("o", "r"),
+            ("Ġw", "or"),
+            ("l", "d"),
+            ("Ġwor", "ld"),
+        ];
+        let values: HashMap<(String, String), i64> = merges
+            .iter()
+            .enumerate()
+            .map(|(rank, (first, second))| {
+                (((*first).to_owned(), (*second).to_owned()), rank as i64)
+            })
+            .collect();
+        BpePairVocab { values }
+    }
+
+    #[test]
+    fn test_any_tokenizer() {
+        //        Given
+        let tokenizers: Vec<AnyTokenizer> = vec![
+            BertTokenizer::from_existing_vocab(Arc::new(generate_bert_vocab()), true, true).into(),
+            Gpt2Tokenizer::from_existing_vocab_and_merges(
+                Arc::new(generate_gpt2_vocab()),
+                Arc::new(generate_gpt2_merges()),
+                true,
+            )
+            .into(),
+        ];
+
+        //        When
+        let tokens = tokenizers
+            .iter()
+            .map(|tokenizer| tokenizer.tokenize("Hello world"))
+            .collect::<Vec<Vec<String>>>();
+        let token_ids = tokenizers
+            .iter()
+            .map(|tokenizer| {
+                tokenizer
+                    .encode(
+                        "Hello world",
+                        None,
+                        128,
+                        &TruncationStrategy::LongestFirst,
+                        0,
+                        &PaddingOptions::default(),
+                    )
+                    .token_ids
+            })
+            .collect::<Vec<Vec<i64>>>();
+
+        //        Then
+        assert_eq!(
+            tokens,
+            vec!(vec!("hello", "world"), vec!("hello", "Ġworld"))
+        );
+        assert_eq!(token_ids, vec!(vec!(4, 0, 1, 5), vec!(0, 1)));
+        assert_eq!(
+            tokenizers[0].decode(vec!(4, 0, 1, 5), true, false),
+            "hello world"
+        );
+        assert_eq!(
+            tokenizers[1].decode(vec!(0, 1), false, false),
+            "hello world"
+        );
+        assert_eq!(tokenizers[0].token_to_id("world"), 1);
+        assert_eq!(tokenizers[0].token_to_id("earth"), 2);
+        assert_eq!(tokenizers[1].id_to_token(&1), "Ġworld");
+        assert_eq!(tokenizers[0].get_unknown_value(), "[UNK]");
+        assert_eq!(tokenizers[0].get_pad_value(), "[PAD]");
+        assert_eq!(tokenizers[1].vocab_size(), 3);
+        assert!(matches!(tokenizers[1], AnyTokenizer::Gpt2(_)));
+    }
+}
@@ -0,0 +1,384 @@
+// Copyright 2019-2020 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::albert_tokenizer::AlbertTokenizer;
+use crate::preprocessing::tokenizer::any_tokenizer::AnyTokenizer;
+use crate::preprocessing::tokenizer::bert_tokenizer::BertTokenizer;
+use crate::preprocessing::tokenizer::ctrl_tokenizer::CtrlTokenizer;
+use crate::preprocessing::tokenizer::gpt2_tokenizer::Gpt2Tokenizer;
+use crate::preprocessing::tokenizer::hf_tokenizer::HFTokenizer;
+use crate::preprocessing::tokenizer::marian_tokenizer::MarianTokenizer;
+use crate::preprocessing::tokenizer::openai_gpt_tokenizer::OpenAiGptTokenizer;
+use crate::preprocessing::tokenizer::roberta_tokenizer::RobertaTokenizer;
+use crate::preprocessing::tokenizer::sentence_piece_tokenizer::SentencePieceTokenizer;
+use crate::preprocessing::tokenizer::t5_tokenizer::T5Tokenizer;
+use crate::preprocessing::tokenizer::xlm_roberta_tokenizer::XLMRobertaTokenizer;
+use crate::preprocessing::tokenizer::xlnet_tokenizer::XLNetTokenizer;
+use serde_json::Value;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenizerType {
+    Bert,
+    Ctrl,
+    Gpt2,
+    Roberta,
+    OpenAiGpt,
+    SentencePiece,
+    Albert,
+    XLNet,
+    T5,
+    XLMRoberta,
+    Marian,
+}
+
+impl TokenizerType {
+    ///Maps a `model_type` (`config.json`) or `tokenizer_class` (`tokenizer_config.json`) value
+    fn from_name(name: &str) -> Option<TokenizerType> {
+        let name = name
+            .to_lowercase()
+            .replace("tokenizerfast", "")
+            .replace("tokenizer", "");
+        match name.replace(&['-', '_'][..], "").as_str() {
+            "bert" | "distilbert" | "electra" | "mobilebert" => Some(TokenizerType::Bert),
+            "ctrl" => Some(TokenizerType::Ctrl),
+            "gpt2" => Some(TokenizerType::Gpt2),
+            "roberta" | "bart" | "longformer" => Some(TokenizerType::Roberta),
+            "openaigpt" => Some(TokenizerType::OpenAiGpt),
+            "albert" => Some(TokenizerType::Albert),
+            "xlnet" => Some(TokenizerType::XLNet),
+            "t5" => Some(TokenizerType::T5),
+            "xlmroberta" | "camembert" => Some(TokenizerType::XLMRoberta),
+            "marian" => Some(TokenizerType::Marian),
+            _ => None,
+        }
+    }
+
+    fn default_lower_case(&self) -> bool {
+        matches!(
+            self,
+            TokenizerType::Bert | TokenizerType::OpenAiGpt | TokenizerType::Albert
+        )
+    }
+}
+
+/// # Automatic tokenizer loader
+/// Creates the appropriate tokenizer from a local model directory. The tokenizer type is read from
+/// the `tokenizer_class` field of `tokenizer_config.json` or the `model_type` field of `config.json`
+/// if present, and inferred from the vocabulary files available otherwise:
+/// - `vocab.txt`: BERT tokenizer
+/// - `vocab.json` and `merges.txt`: GPT2 tokenizer
+/// - `vocab.json` and `source.spm`: Marian tokenizer
+/// - `sentencepiece.bpe.model`: XLM-RoBERTa tokenizer
+/// - `spiece.model`: SentencePiece tokenizer
+///
+/// Directories that do not contain the files expected for the tokenizer type but provide a
+/// `tokenizer.json` file are loaded as a `HFTokenizer`.
+///
+/// The `do_lower_case`, `strip_accents` and `add_prefix_space` options are read from
+/// `tokenizer_config.json` when present, falling back to the defaults of the tokenizer type.
+pub struct AutoTokenizer;
+
+impl AutoTokenizer {
+    /// Create a tokenizer from the files contained in a model directory.
+    ///
+    /// # Parameters
+    /// - path (`&str`): path to the model directory
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_tokenizers::AutoTokenizer;
+    /// let tokenizer = AutoTokenizer::from_pretrained("path/to/model").unwrap();
+    /// let tokens = tokenizer.tokenize("Hello, world!");
+    /// ```
+    pub fn from_pretrained(path: &str) -> Result<AnyTokenizer, TokenizerError> {
+        let directory = Path::new(path);
+        if !directory.is_dir() {
+            return Err(TokenizerError::FileNotFound(format!(
+                "{} is not a directory",
+                path
+            )));
+        }
+        let tokenizer_config = read_json_file(&directory.join("tokenizer_config.json"))?;
+        let model_config = read_json_file(&directory.join("config.json"))?;
+
+        let tokenizer_type = match get_string(&tokenizer_config, "tokenizer_class")
+            .and_then(|name| TokenizerType::from_name(&name))
+            .or_else(|| {
+                get_string(&model_config, "model_type")
+                    .and_then(|name| TokenizerType::from_name(&name))
+            }) {
+            Some(tokenizer_type) => Ok(tokenizer_type),
+            None => infer_tokenizer_type(directory),
+        };
+
+        match tokenizer_type
+            .and_then(|tokenizer_type| load_tokenizer(directory, tokenizer_type, &tokenizer_config))
+        {
+            Err(TokenizerError::FileNotFound(_)) if directory.join("tokenizer.json").is_file() => {
+                Ok(HFTokenizer::from_file(&required_file(directory, "tokenizer.json")?)?.into())
+            }
+            result => result,
+        }
+    }
+}
+
+fn load_tokenizer(
+    directory: &Path,
+    tokenizer_type: TokenizerType,
+    tokenizer_config: &Option<Value>,
+) -> Result<AnyTokenizer, TokenizerError> {
+    let lower_case = get_bool(tokenizer_config, "do_lower_case")
+        .unwrap_or_else(|| tokenizer_type.default_lower_case());
+    let strip_accents = get_bool(tokenizer_config, "strip_accents").unwrap_or(lower_case);
+    let add_prefix_space = get_bool(tokenizer_config, "add_prefix_space").unwrap_or(false);
+
+    let file = |name: &str| required_file(directory, name);
+    Ok(match tokenizer_type {
+        TokenizerType::Bert => {
+            BertTokenizer::from_file(&file("vocab.txt")?, lower_case, strip_accents)?.into()
+        }
+        TokenizerType::Ctrl => {
+            CtrlTokenizer::from_file(&file("vocab.json")?, &file("merges.txt")?, lower_case)?.into()
+        }
+        TokenizerType::Gpt2 => {
+            Gpt2Tokenizer::from_file(&file("vocab.json")?, &file("merges.txt")?, lower_case)?.into()
+        }
+        TokenizerType::Roberta => RobertaTokenizer::from_file(
+            &file("vocab.json")?,
+            &file("merges.txt")?,
+            lower_case,
+            add_prefix_space,
+        )?
+        .into(),
+        TokenizerType::OpenAiGpt => {
+            OpenAiGptTokenizer::from_file(&file("vocab.json")?, &file("merges.txt")?, lower_case)?
+                .into()
+        }
+        TokenizerType::SentencePiece => {
+            SentencePieceTokenizer::from_file(&file("spiece.model")?, lower_case)?.into()
+        }
+        TokenizerType::Albert => {
+            AlbertTokenizer::from_file(&file("spiece.model")?, lower_case, strip_accents)?.into()
+        }
+        TokenizerType::XLNet => {
+            XLNetTokenizer::from_file(&file("spiece.model")?, lower_case, strip_accents)?.into()
+        }
+        TokenizerType::T5 => T5Tokenizer::from_file(&file("spiece.model")?, lower_case)?.into(),
+        TokenizerType::XLMRoberta => {
+            XLMRobertaTokenizer::from_file(&file("sentencepiece.bpe.model")?, lower_case)?.into()
+        }
+        TokenizerType::Marian => {
+            MarianTokenizer::from_files(&file("vocab.json")?, &file("source.spm")?, lower_case)?
+                .into()
+        }
+    })
+}
+
+fn read_json_file(path: &Path) -> Result<Option<Value>, TokenizerError> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let f = File::open(path).map_err(|e| {
+        TokenizerError::FileNotFound(format!("{} file not found :{}", path.display(), e))
+    })?;
+    let value = serde_json::from_reader(BufReader::new(f)).map_err(|e| {
+        TokenizerError::VocabularyParsingError(format!("{}: {}", path.display(), e))
+    })?;
+    Ok(Some(value))
+}
+
+fn get_string(config: &Option<Value>, key: &str) -> Option<String> {
+    config
+        .as_ref()
+        .and_then(|config| config.get(key))
+        .and_then(|value| value.as_str())
+        .map(|value| value.to_owned())
+}
+
+fn get_bool(config: &Option<Value>, key: &str) -> Option<bool> {
+    config
+        .as_ref()
+        .and_then(|config| config.get(key))
+        .and_then(|value| value.as_bool())
+}
+
+fn required_file(directory: &Path, name: &str) -> Result<String, TokenizerError> {
+    let path = directory.join(name);
+    if path.is_file() {
+        Ok(path.to_string_lossy().into_owned())
+    } else {
+        Err(TokenizerError::FileNotFound(format!(
+            "{} not found in {}",
+            name,
+            directory.display()
+        )))
+    }
+}
+
+fn infer_tokenizer_type(directory: &Path) -> Result<TokenizerType, TokenizerError> {
+    let exists = |name: &str| directory.join(name).is_file();
+    if exists("vocab.txt") {
+        Ok(TokenizerType::Bert)
+    } else if exists("vocab.json") && exists("merges.txt") {
+        Ok(TokenizerType::Gpt2)
+    } else if exists("vocab.json") && exists("source.spm") {
+        Ok(TokenizerType::Marian)
+    } else if exists("sentencepiece.bpe.model") {
+        Ok(TokenizerType::XLMRoberta)
+    } else if exists("spiece.model") {
+        Ok(TokenizerType::SentencePiece)
+    } else {
+        Err(TokenizerError::FileNotFound(format!(
+            "No supported tokenizer files found in {}",
+            directory.display()
+        )))
+    }
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write_bert_files(directory: &Path) -> anyhow::Result<()> {
+        fs::write(
+            directory.join("vocab.txt"),
+            "[UNK]\n[PAD]\n[CLS]\n[SEP]\n[MASK]\nhello\nHello\nworld\n",
+        )?;
+        Ok(())
+    }
+
+    fn write_bpe_files(directory: &Path) -> anyhow::Result<()> {
+        fs::write(
+            directory.join("vocab.json"),
+            r#"{"<s>": 0, "</s>": 1, "<pad>": 2, "<unk>": 3, "<mask>": 4, "<|endoftext|>": 5, "hello": 6, "Ġworld": 7}"#,
+        )?;
+        fs::write(
+            directory.join("merges.txt"),
+            "#version: 0.2\nh e\nl l\nhe ll\nhell o\nĠ w\no r\nĠw or\nl d\nĠwor ld\n",
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_infer_from_files() -> anyhow::Result<()> {
+        //        Given
+        let bert_directory = tempfile::tempdir()?;
+        write_bert_files(bert_directory.path())?;
+        let gpt2_directory = tempfile::tempdir()?;
+        write_bpe_files(gpt2_directory.path())?;
+
+        //        When
+        let bert_tokenizer =
+            AutoTokenizer::from_pretrained(bert_directory.path().to_str().unwrap())?;
+        let gpt2_tokenizer =
+            AutoTokenizer::from_pretrained(gpt2_directory.path().to_str().unwrap())?;
+
+        //        Then
+        assert!(matches!(bert_tokenizer, AnyTokenizer::Bert(_)));
+        assert_eq!(
+            bert_tokenizer.tokenize("Hello world"),
+            vec!("hello", "world")
+        );
+        assert!(matches!(gpt2_tokenizer, AnyTokenizer::Gpt2(_)));
+        assert_eq!(
+            gpt2_tokenizer.tokenize("hello world"),
+            vec!("hello", "Ġworld")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_configuration() -> anyhow::Result<()> {
+        //        Given
+        let bert_directory = tempfile::tempdir()?;
+        write_bert_files(bert_directory.path())?;
+        fs::write(
+            bert_directory.path().join("tokenizer_config.json"),
+            r#"{"do_lower_case": false}"#,
+        )?;
+        let roberta_directory = tempfile::tempdir()?;
+        write_bpe_files(roberta_directory.path())?;
+        fs::write(
+            roberta_directory.path().join("config.json"),
+            r#"{"model_type": "roberta"}"#,
+        )?;
+
+        //        When
+        let bert_tokenizer =
+            AutoTokenizer::from_pretrained(bert_directory.path().to_str().unwrap())?;
+        let roberta_tokenizer =
+            AutoTokenizer::from_pretrained(roberta_directory.path().to_str().unwrap())?;
+
+        //        Then
+        assert_eq!(
+            bert_tokenizer.tokenize("Hello world"),
+            vec!("Hello", "world")
+        );
+        assert!(matches!(roberta_tokenizer, AnyTokenizer::Roberta(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_files() -> anyhow::Result<()> {
+        //        Given
+        let empty_directory = tempfile::tempdir()?;
+        let albert_directory = tempfile::tempdir()?;
+        fs::write(
+            albert_directory.path().join("tokenizer_config.json"),
+            r#"{"tokenizer_class": "AlbertTokenizer"}"#,
+        )?;
+
+        //        When
+        let empty_result = AutoTokenizer::from_pretrained(empty_directory.path().to_str().unwrap());
+        let albert_result =
+            AutoTokenizer::from_pretrained(albert_directory.path().to_str().unwrap());
+
+        //        Then
+        assert!(matches!(empty_result, Err(TokenizerError::FileNotFound(_))));
+        assert!(matches!(
+            albert_result,
+            Err(TokenizerError::FileNotFound(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_tokenizer_json() -> anyhow::Result<()> {
+        //        Given
+        let directory = tempfile::tempdir()?;
+        fs::write(
+            directory.path().join("tokenizer.json"),
+            r#"{
+                "added_tokens": [{"id": 0, "content": "[UNK]", "special": true}],
+                "normalizer": {"type": "Lowercase"},
+                "pre_tokenizer": {"type": "WhitespaceSplit"},
+                "model": {"type": "WordPiece", "unk_token": "[UNK]", "vocab": {"[UNK]": 0, "hello": 1}}
+            }"#,
+        )?;
+
+        //        When
+        let tokenizer = AutoTokenizer::from_pretrained(directory.path().to_str().unwrap())?;
+
+        //        Then
+        assert!(matches!(tokenizer, AnyTokenizer::HF(_)));
+        assert_eq!(tokenizer.tokenize("Hello world"), vec!("hello", "[UNK]"));
+        Ok(())
+    }
+}
@@ -13,16 +13,23 @@
 // limitations under the License.
 
 use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::normalizer::{base_normalizer, Normalizer};
+use crate::preprocessing::tokenizer::post_processor::{TemplateProcessing, TemplateSequence};
+use crate::preprocessing::tokenizer::pre_tokenizer::{base_pre_tokenizer, PreTokenizer};
 use crate::preprocessing::tokenizer::tokenization_utils::{
-    split_on_punct, split_on_special_tokens, strip_accents, tokenize_cjk_chars, truncate_sequences,
-    whitespace_tokenize,
+    get_char_positions, get_word_ids, pad_tokenized_input, split_on_added_tokens,
+    truncate_sequences,
 };
 use crate::preprocessing::vocab::base_vocab::Vocab;
-use crate::tokenization_utils::lowercase;
 use itertools::Itertools;
+use rand::Rng;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+use std::sync::{Arc, OnceLock};
 
 #[derive(Debug)]
 pub enum TruncationStrategy {
@@ -32,6 +39,60 @@ pub enum TruncationStrategy {
     DoNotTruncate,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaddingStrategy {
+    ///Do not pad the inputs
+    DoNotPad,
+    ///Pad all inputs to `max_len`
+    MaxLength,
+    ///Pad all inputs to the length of the longest input in the batch
+    Longest,
+    ///Pad all inputs to the length of the longest input in the batch, rounded up to a multiple of
+    ///the value provided
+    MultipleOf(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaddingSide {
+    ///Padding tokens are inserted before the input tokens (e.g. XLNet)
+    Left,
+    ///Padding tokens are appended after the input tokens
+    Right,
+}
+
+///Padding applied by the encoding methods: strategy and side of the padding tokens. The default
+///options do not pad the inputs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaddingOptions {
+    pub strategy: PaddingStrategy,
+    pub side: PaddingSide,
+}
+
+impl PaddingOptions {
+    pub fn new(strategy: PaddingStrategy, side: PaddingSide) -> PaddingOptions {
+        PaddingOptions { strategy, side }
+    }
+}
+
+impl Default for PaddingOptions {
+    fn default() -> Self {
+        PaddingOptions {
+            strategy: PaddingStrategy::DoNotPad,
+            side: PaddingSide::Right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OffsetUnit {
+    ///Offsets are expressed in Unicode scalar values (`char`), as in `Offset` and `OffsetSize`
+    Char,
+    ///Offsets are expressed in UTF-8 bytes, and can be used to slice the original `&str`
+    Byte,
+    ///Offsets are expressed in UTF-16 code units (e.g. for JavaScript strings)
+    Utf16,
+}
+
 pub type OffsetSize = u32;
 
 #[derive(Debug, PartialEq, PartialOrd, Clone, Copy, Serialize, Deserialize)]
@@ -61,6 +122,8 @@ pub enum Mask {
     Unfinished,
     ///The token is out of vocabulary, it is unknown by the tokenizer and it will decode to unknown. Tokens that can be decoded properly (but may still be out of vocabulary) should not set this.
     Unknown,
+    ///the token was added to the vocabulary as a regular (non-special) token after its creation. It is matched before the tokenization model runs and is not processed further
+    Added,
 }
 
 impl Default for Mask {
@@ -69,6 +132,19 @@ impl Default for Mask {
     }
 }
 
+///Output of `Tokenizer::build_input_with_special_tokens`: token IDs, token segment IDs, special
+///tokens mask, offsets, reference offsets, token mask, word IDs and sequence IDs
+pub type TokenIdsWithSpecialTokens = (
+    Vec<i64>,
+    Vec<i8>,
+    Vec<i8>,
+    Vec<Option<Offset>>,
+    Vec<Vec<OffsetSize>>,
+    Vec<Mask>,
+    Vec<Option<u32>>,
+    Vec<Option<u8>>,
+);
+
 pub trait TokenTrait {
     fn offset(&self) -> Option<Offset>;
     fn mask(&self) -> Mask;
@@ -314,16 +390,350 @@ pub struct TokenizedInput {
     ///original source are registered as None.
     pub token_offsets: Vec<Option<Offset>>,
 
+    ///Offsets of the tokens in UTF-8 bytes of the original text, which can be used to slice the
+    ///original `&str`. Tokens that can not be related to the original source are registered as None.
+    pub byte_offsets: Vec<Option<Offset>>,
+
+    ///Offsets of the tokens in UTF-16 code units of the original text (e.g. for JavaScript
+    ///strings). Tokens that can not be related to the original source are registered as None.
+    pub utf16_offsets: Vec<Option<Offset>>,
+
     pub reference_offsets: Vec<Vec<OffsetSize>>,
 
     ///Masks tokens so you can see what type of token something is. This vector has the same length
     ///as token_ids (and also makes special_tokens_mask redundant).
     pub mask: Vec<Mask>,
+
+    ///Flags tokens that should be attended to (1) or that are padding (0). This vector has the same
+    ///length as token_ids.
+    pub attention_mask: Vec<i8>,
+
+    ///Index of the input the `TokenizedInput` originates from when an input is split into several
+    ///overlapping windows (see `encode_with_overflow`), None otherwise.
+    pub overflow_to_sample_mapping: Option<usize>,
+
+    ///Index of the word each token belongs to within its sequence, based on the token masks
+    ///(sub-tokens of a same word share the same index). Special tokens are registered as None.
+    ///This vector has the same length as token_ids.
+    pub word_ids: Vec<Option<u32>>,
+
+    ///Index of the sequence each token belongs to (0 for the first sequence, 1 for the second
+    ///sequence of a pair). Special tokens are registered as None. This vector has the same length
+    ///as token_ids.
+    pub sequence_ids: Vec<Option<u8>>,
+
+    ///Lookup tables used by `char_to_token` and `word_to_chars`, built on first use
+    pub alignment_index: AlignmentIndex,
+}
+
+///Lookup tables mapping the characters and words of each sequence of a `TokenizedInput` to its
+///tokens. The tables are built on the first call to `char_to_token` or `word_to_chars` and are not
+///updated if the `TokenizedInput` is modified afterwards. The index is ignored by comparisons and
+///is not copied when the `TokenizedInput` is cloned.
+#[derive(Default)]
+pub struct AlignmentIndex {
+    tables: OnceLock<AlignmentTables>,
+}
+
+struct AlignmentTables {
+    ///Token covering each character, indexed by (sequence ID, character index)
+    char_to_token: HashMap<(u8, OffsetSize), usize>,
+    ///Character offsets spanned by each word, indexed by (sequence ID, word index)
+    word_to_chars: HashMap<(u8, u32), Offset>,
+}
+
+impl Clone for AlignmentIndex {
+    fn clone(&self) -> Self {
+        AlignmentIndex::default()
+    }
+}
+
+impl PartialEq for AlignmentIndex {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl PartialOrd for AlignmentIndex {
+    fn partial_cmp(&self, _other: &Self) -> Option<Ordering> {
+        Some(Ordering::Equal)
+    }
+}
+
+impl fmt::Debug for AlignmentIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AlignmentIndex").finish_non_exhaustive()
+    }
+}
+
+impl TokenizedInput {
+    ///Returns the index of the sequence (0 or 1 for a pair of sequences) the token at `token_index`
+    ///belongs to, or None for special tokens and out-of-range indices. Falls back to the segment
+    ///IDs if the sequence IDs are not populated.
+    fn token_to_sequence(&self, token_index: usize) -> Option<u8> {
+        if self.special_tokens_mask.get(token_index) == Some(&1)
+            || self.mask.get(token_index) == Some(&Mask::Special)
+        {
+            return None;
+        }
+        if self.sequence_ids.is_empty() {
+            self.segment_ids
+                .get(token_index)
+                .map(|segment_id| *segment_id as u8)
+        } else {
+            self.sequence_ids.get(token_index).copied().flatten()
+        }
+    }
+
+    ///Returns the character offsets in the original text (of the sequence it belongs to) of the
+    ///token at `token_index`, or None for special tokens and tokens without offset.
+    pub fn token_to_chars(&self, token_index: usize) -> Option<Offset> {
+        self.token_to_sequence(token_index)?;
+        self.token_offsets.get(token_index).copied().flatten()
+    }
+
+    ///Returns the index of the token covering the character at `char_index` in the original text
+    ///of sequence `sequence_id` (0 for the first sequence, 1 for the second sequence of a pair),
+    ///or None if no token covers this character (e.g. whitespace or truncated text).
+    pub fn char_to_token(&self, char_index: OffsetSize, sequence_id: u8) -> Option<usize> {
+        self.alignment_tables()
+            .char_to_token
+            .get(&(sequence_id, char_index))
+            .copied()
+    }
+
+    fn alignment_tables(&self) -> &AlignmentTables {
+        self.alignment_index
+            .tables
+            .get_or_init(|| self.build_alignment_tables())
+    }
+
+    fn build_alignment_tables(&self) -> AlignmentTables {
+        let sequence_ids = (0..self.token_ids.len())
+            .map(|token_index| self.token_to_sequence(token_index))
+            .collect::<Vec<Option<u8>>>();
+
+        let mut char_to_token = HashMap::new();
+        for (token_index, sequence_id) in sequence_ids.iter().enumerate() {
+            if let (Some(sequence_id), Some(reference_offsets)) =
+                (sequence_id, self.reference_offsets.get(token_index))
+            {
+                for char_index in reference_offsets {
+                    char_to_token
+                        .entry((*sequence_id, *char_index))
+                        .or_insert(token_index);
+                }
+            }
+        }
+        //Characters removed during normalization (e.g. accents) are not registered in the
+        //reference offsets, but are still covered by the token offsets
+        for (token_index, sequence_id) in sequence_ids.iter().enumerate() {
+            if let (Some(sequence_id), Some(Some(offset))) =
+                (sequence_id, self.token_offsets.get(token_index))
+            {
+                for char_index in offset.begin..offset.end {
+                    char_to_token
+                        .entry((*sequence_id, char_index))
+                        .or_insert(token_index);
+                }
+            }
+        }
+
+        let mut word_to_chars: HashMap<(u8, u32), Offset> = HashMap::new();
+        for (token_index, sequence_id) in sequence_ids.iter().enumerate() {
+            if let (Some(sequence_id), Some(Some(word_id)), Some(Some(offset))) = (
+                sequence_id,
+                self.word_ids.get(token_index),
+                self.token_offsets.get(token_index),
+            ) {
+                word_to_chars
+                    .entry((*sequence_id, *word_id))
+                    .and_modify(|word_offset| {
+                        word_offset.begin = word_offset.begin.min(offset.begin);
+                        word_offset.end = word_offset.end.max(offset.end);
+                    })
+                    .or_insert(*offset);
+            }
+        }
+
+        AlignmentTables {
+            char_to_token,
+            word_to_chars,
+        }
+    }
+
+    ///Returns the offsets of every token in the original text expressed in `offset_unit`
+    ///(`token_offsets`, `byte_offsets` or `utf16_offsets`).
+    pub fn offsets(&self, offset_unit: &OffsetUnit) -> &[Option<Offset>] {
+        match offset_unit {
+            OffsetUnit::Char => &self.token_offsets,
+            OffsetUnit::Byte => &self.byte_offsets,
+            OffsetUnit::Utf16 => &self.utf16_offsets,
+        }
+    }
+
+    ///Populates `byte_offsets` and `utf16_offsets` by mapping the character positions tracked by
+    ///the `reference_offsets` to positions in the original texts. The texts of each sequence are
+    ///either the complete text (`per_word` false) or the words of a pre-tokenized input, indexed
+    ///by the `word_ids` (`per_word` true).
+    fn set_unit_offsets(&mut self, texts_1: &[&str], texts_2: Option<&[&str]>, per_word: bool) {
+        self.byte_offsets = self.unit_offsets(&OffsetUnit::Byte, texts_1, texts_2, per_word);
+        self.utf16_offsets = self.unit_offsets(&OffsetUnit::Utf16, texts_1, texts_2, per_word);
+    }
+
+    fn unit_offsets(
+        &self,
+        offset_unit: &OffsetUnit,
+        texts_1: &[&str],
+        texts_2: Option<&[&str]>,
+        per_word: bool,
+    ) -> Vec<Option<Offset>> {
+        let get_positions = |texts: &[&str]| {
+            texts
+                .iter()
+                .map(|text| get_char_positions(text, offset_unit))
+                .collect::<Vec<Vec<OffsetSize>>>()
+        };
+        let positions_1 = get_positions(texts_1);
+        let positions_2 = texts_2.map(get_positions);
+        (0..self.token_ids.len())
+            .map(|token_index| {
+                //Special tokens found in the input text (e.g. a mask token) have an offset
+                let sequence_id = match self.sequence_ids.get(token_index) {
+                    Some(sequence_id) => (*sequence_id)?,
+                    None => self.token_to_sequence(token_index)?,
+                };
+                let positions = match sequence_id {
+                    0 => &positions_1,
+                    _ => positions_2.as_ref()?,
+                };
+                let positions = if per_word {
+                    positions.get(self.word_ids.get(token_index).copied().flatten()? as usize)?
+                } else {
+                    positions.first()?
+                };
+                let char_offset = match self.reference_offsets.get(token_index) {
+                    Some(reference_offsets) if !reference_offsets.is_empty() => Offset {
+                        begin: *reference_offsets.iter().min().unwrap(),
+                        end: *reference_offsets.iter().max().unwrap() + 1,
+                    },
+                    _ => self.token_offsets.get(token_index).copied().flatten()?,
+                };
+                Some(Offset {
+                    begin: *positions.get(char_offset.begin as usize)?,
+                    end: *positions.get(char_offset.end as usize)?,
+                })
+            })
+            .collect()
+    }
+
+    ///Returns the character offsets in the original text spanned by the word `word_index` of
+    ///sequence `sequence_id`, or None if no token of this word has an offset.
+    pub fn word_to_chars(&self, word_index: u32, sequence_id: u8) -> Option<Offset> {
+        self.alignment_tables()
+            .word_to_chars
+            .get(&(sequence_id, word_index))
+            .copied()
+    }
 }
 
 pub trait Tokenizer<T: Vocab> {
     fn vocab(&self) -> &T;
 
+    ///Returns a mutable reference to the vocabulary. A vocabulary shared with other tokenizers is
+    ///copied first, so that modifications only affect this tokenizer.
+    fn vocab_mut(&mut self) -> &mut T;
+
+    ///Returns the normalizer applied to the text before the tokenization model runs
+    fn normalizer(&self) -> &dyn Normalizer;
+
+    ///Replaces the normalizer applied to the text before the tokenization model runs, e.g. to add
+    ///a unicode normalization or a quotes unification to the default steps of the tokenizer (see
+    ///`Normalizer`). Special, added and unknown tokens are never normalized.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_tokenizers::normalizer::{Lowercase, Nfc, Replace, Sequence};
+    /// use rust_tokenizers::{BertTokenizer, Tokenizer};
+    /// let mut tokenizer = BertTokenizer::from_file("path/to/vocab/file", false, false).unwrap();
+    /// tokenizer.set_normalizer(Box::new(Sequence::new(vec![
+    ///     Box::new(Nfc),
+    ///     Box::new(Replace::new("“", "\"")),
+    ///     Box::new(Lowercase),
+    /// ])));
+    /// ```
+    fn set_normalizer(&mut self, normalizer: Box<dyn Normalizer>);
+
+    ///Returns the pre-tokenizer splitting the text into words before the tokenization model runs
+    fn pre_tokenizer(&self) -> &dyn PreTokenizer;
+
+    ///Replaces the pre-tokenizer splitting the text into words before the tokenization model runs,
+    ///e.g. to isolate digits in addition to the default splitting of the tokenizer (see
+    ///`PreTokenizer`). Special, added and unknown tokens are never split.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_tokenizers::pre_tokenizer::{BertPreTokenizer, CjkChars, Digits, Sequence};
+    /// use rust_tokenizers::{BertTokenizer, Tokenizer};
+    /// let mut tokenizer = BertTokenizer::from_file("path/to/vocab/file", false, false).unwrap();
+    /// tokenizer.set_pre_tokenizer(Box::new(Sequence::new(vec![
+    ///     Box::new(BertPreTokenizer),
+    ///     Box::new(CjkChars),
+    ///     Box::new(Digits::new(true)),
+    /// ])));
+    /// ```
+    fn set_pre_tokenizer(&mut self, pre_tokenizer: Box<dyn PreTokenizer>);
+
+    ///Returns the template post-processor set with `set_post_processor`, if any. The default
+    ///template of the tokenizer (see `default_post_processor`) is used otherwise.
+    fn post_processor(&self) -> Option<&TemplateProcessing>;
+
+    ///Returns a mutable reference to the template post-processor set for this tokenizer
+    fn post_processor_mut(&mut self) -> &mut Option<TemplateProcessing>;
+
+    ///Returns the template adding the special tokens of the model to the encoded sequences, built
+    ///from the special tokens of the vocabulary. Defaults to a concatenation of the sequences.
+    fn default_post_processor(&self) -> TemplateProcessing {
+        TemplateProcessing::default()
+    }
+
+    ///Overrides the template adding special tokens to the encoded sequences (see
+    ///`TemplateProcessing`). Fails if a special token of the template is not part of the vocabulary.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_tokenizers::{BertTokenizer, TemplateProcessing, Tokenizer};
+    /// let mut tokenizer = BertTokenizer::from_file("path/to/vocab/file", false, false).unwrap();
+    /// let post_processor =
+    ///     TemplateProcessing::new("[CLS] $A [SEP]", "[CLS] $A [SEP] [SEP] $B:1 [SEP]:1").unwrap();
+    /// tokenizer.set_post_processor(post_processor).unwrap();
+    /// ```
+    fn set_post_processor(
+        &mut self,
+        post_processor: TemplateProcessing,
+    ) -> Result<(), TokenizerError> {
+        post_processor.check_special_tokens(self.vocab())?;
+        *self.post_processor_mut() = Some(post_processor);
+        Ok(())
+    }
+
+    ///Adds regular tokens to the vocabulary (see `Vocab::add_tokens`). Added tokens are split out
+    ///of the input text before the tokenization model runs and are never split.
+    ///Returns the number of tokens that were not yet in the vocabulary.
+    fn add_tokens(&mut self, tokens: &[&str]) -> usize {
+        self.vocab_mut().add_tokens(tokens)
+    }
+
+    ///Adds special tokens to the vocabulary (see `Vocab::add_special_tokens`). Special tokens are
+    ///split out of the input text before the tokenization model runs and can be skipped when decoding.
+    ///Returns the number of tokens that were not yet in the vocabulary.
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> usize {
+        self.vocab_mut().add_special_tokens(tokens)
+    }
+
     ///Tokenize a string, returns a vector of tokens as strings.
     ///Use `tokenize_with_offsets` or `tokenize_to_tokens` if you also want offset information.
     fn tokenize(&self, text: &str) -> Vec<String> {
@@ -340,32 +750,7 @@ pub trait Tokenizer<T: Vocab> {
         Vec<Vec<OffsetSize>>,
         Vec<Mask>,
     ) {
-        if text.trim().is_empty() {
-            return (vec![], vec![], vec![], vec![]);
-        }
-        let initial_offsets = (0..text.chars().count() as OffsetSize).collect::<Vec<OffsetSize>>();
-        let initial_token: TokenRef<'_> = TokenRef::new(text, &initial_offsets);
-        let tokens = self.tokenize_to_tokens(initial_token);
-        let length = tokens.len();
-        let mut texts = Vec::with_capacity(length);
-        let mut offsets = Vec::with_capacity(length);
-        let mut original_positions = Vec::with_capacity(length);
-        let mut masks = Vec::with_capacity(length);
-
-        for token in tokens {
-            texts.push(token.text);
-            offsets.push(if !token.reference_offsets.is_empty() {
-                Some(Offset {
-                    begin: *token.reference_offsets.first().unwrap(),
-                    end: *token.reference_offsets.last().unwrap() + 1,
-                })
-            } else {
-                None
-            });
-            original_positions.push(token.reference_offsets);
-            masks.push(token.mask);
-        }
-        (texts, offsets, original_positions, masks)
+        tokenize_text_with_offsets(text, |token| self.tokenize_to_tokens(token))
     }
 
     ///Tokenize a text, returns a vector of tokens (contains offset information and more)
@@ -403,6 +788,9 @@ pub trait Tokenizer<T: Vocab> {
             .collect()
     }
 
+    ///Encode a string or a pair of strings into a `TokenizedInput`, truncated to `max_len` and
+    ///padded following `padding` (see `pad_tokenized_inputs`).
+    ///Panics if the requested truncation cannot be performed, use `try_encode` to handle these cases.
     fn encode(
         &self,
         text_1: &str,
@@ -410,126 +798,568 @@ pub trait Tokenizer<T: Vocab> {
         max_len: usize,
         truncation_strategy: &TruncationStrategy,
         stride: usize,
+        padding: &PaddingOptions,
     ) -> TokenizedInput {
-        let (token_strings, token_offsets, original_positions, token_mask) =
+        self.try_encode(
+            text_1,
+            text_2,
+            max_len,
+            truncation_strategy,
+            stride,
+            padding,
+        )
+        .unwrap()
+    }
+
+    ///Encode a string or a pair of strings into a `TokenizedInput`, truncated to `max_len` and
+    ///padded following `padding` (see `pad_tokenized_inputs`).
+    ///Returns a `TokenizerError` if the truncation strategy cannot be applied (for example if the
+    ///input is longer than `max_len` and `TruncationStrategy::DoNotTruncate` is requested).
+    fn try_encode(
+        &self,
+        text_1: &str,
+        text_2: Option<&str>,
+        max_len: usize,
+        truncation_strategy: &TruncationStrategy,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Result<TokenizedInput, TokenizerError> {
+        encode_texts(
+            self,
+            text_1,
+            text_2,
+            max_len,
+            truncation_strategy,
+            stride,
+            padding,
+            |text| self.tokenize_with_offsets(text),
+        )
+    }
+
+    ///Tokenize a list of words (pre-tokenized text). Every word is tokenized independently with
+    ///`tokenize_to_tokens`, so that sub-tokens never span several words. Returns the tokens as
+    ///strings, their offsets and reference offsets relative to the word they belong to, their
+    ///masks and the index of the word they belong to.
+    ///If `add_prefix_space` is true, a space is inserted before every word: byte-level BPE
+    ///tokenizers (`Gpt2Tokenizer`, `RobertaTokenizer`) encode the leading space in the first
+    ///token of a word, and should usually be given words with a leading space. The inserted space
+    ///refers to the first character of the word.
+    fn tokenize_words_with_offsets(
+        &self,
+        words: &[&str],
+        add_prefix_space: bool,
+    ) -> (
+        Vec<String>,
+        Vec<Option<Offset>>,
+        Vec<Vec<OffsetSize>>,
+        Vec<Mask>,
+        Vec<u32>,
+    ) {
+        let mut texts = vec![];
+        let mut offsets = vec![];
+        let mut original_positions = vec![];
+        let mut masks = vec![];
+        let mut word_indices = vec![];
+        for (word_index, word) in words.iter().enumerate() {
+            if word.trim().is_empty() {
+                continue;
+            }
+            let mut word_offsets =
+                (0..word.chars().count() as OffsetSize).collect::<Vec<OffsetSize>>();
+            let text = if add_prefix_space {
+                word_offsets.insert(0, 0);
+                format!(" {}", word)
+            } else {
+                word.to_string()
+            };
+            for token in self.tokenize_to_tokens(TokenRef::new(&text, &word_offsets)) {
+                texts.push(token.text);
+                offsets.push(if !token.reference_offsets.is_empty() {
+                    Some(Offset {
+                        begin: *token.reference_offsets.first().unwrap(),
+                        end: *token.reference_offsets.last().unwrap() + 1,
+                    })
+                } else {
+                    None
+                });
+                original_positions.push(token.reference_offsets);
+                masks.push(token.mask);
+                word_indices.push(word_index as u32);
+            }
+        }
+        (texts, offsets, original_positions, masks, word_indices)
+    }
+
+    ///Encode a pre-tokenized sequence or pair of sequences (lists of words) into a
+    ///`TokenizedInput`, truncated to `max_len` and padded following `padding` (see
+    ///`pad_tokenized_inputs`). The offsets are relative to the word each token belongs to, and
+    ///`word_ids` contains the index of this word in the input list. See
+    ///`tokenize_words_with_offsets` for the `add_prefix_space` option.
+    ///Panics if the requested truncation cannot be performed, use `try_encode_pretokenized` to
+    ///handle these cases.
+    #[allow(clippy::too_many_arguments)]
+    fn encode_pretokenized(
+        &self,
+        words_1: &[&str],
+        words_2: Option<&[&str]>,
+        add_prefix_space: bool,
+        max_len: usize,
+        truncation_strategy: &TruncationStrategy,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> TokenizedInput {
+        self.try_encode_pretokenized(
+            words_1,
+            words_2,
+            add_prefix_space,
+            max_len,
+            truncation_strategy,
+            stride,
+            padding,
+        )
+        .unwrap()
+    }
+
+    ///Fallible version of `encode_pretokenized`, returns a `TokenizerError` if the truncation
+    ///strategy cannot be applied.
+    #[allow(clippy::too_many_arguments)]
+    fn try_encode_pretokenized(
+        &self,
+        words_1: &[&str],
+        words_2: Option<&[&str]>,
+        add_prefix_space: bool,
+        max_len: usize,
+        truncation_strategy: &TruncationStrategy,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Result<TokenizedInput, TokenizerError> {
+        let (token_strings, token_offsets, original_positions, token_mask, word_indices_1) =
+            self.tokenize_words_with_offsets(words_1, add_prefix_space);
+        let sequence_1 = EncodedSequence {
+            token_ids: self.convert_tokens_to_ids(&token_strings),
+            offsets: token_offsets,
+            reference_offsets: original_positions,
+            mask: token_mask,
+        };
+        let (sequence_2, word_indices_2) = if let Some(words) = words_2 {
+            let (
+                token_strings_2,
+                token_offsets_2,
+                original_positions_2,
+                token_mask_2,
+                word_indices_2,
+            ) = self.tokenize_words_with_offsets(words, add_prefix_space);
+            (
+                Some(EncodedSequence {
+                    token_ids: self.convert_tokens_to_ids(&token_strings_2),
+                    offsets: token_offsets_2,
+                    reference_offsets: original_positions_2,
+                    mask: token_mask_2,
+                }),
+                word_indices_2,
+            )
+        } else {
+            (None, vec![])
+        };
+        let mut tokenized_input = encode_token_ids(
+            self,
+            sequence_1,
+            sequence_2,
+            max_len,
+            truncation_strategy,
+            stride,
+        )?;
+
+        //Truncation only removes tokens at the end of each sequence: the remaining tokens are
+        //matched in order with their word indices
+        let mut word_indices_1 = word_indices_1.into_iter();
+        let mut word_indices_2 = word_indices_2.into_iter();
+        for (word_id, sequence_id) in tokenized_input
+            .word_ids
+            .iter_mut()
+            .zip(tokenized_input.sequence_ids.iter())
+        {
+            *word_id = match sequence_id {
+                Some(0) => word_indices_1.next(),
+                Some(_) => word_indices_2.next(),
+                None => None,
+            };
+        }
+        tokenized_input.set_unit_offsets(words_1, words_2, true);
+        self.pad_tokenized_inputs(std::slice::from_mut(&mut tokenized_input), max_len, padding);
+        Ok(tokenized_input)
+    }
+
+    ///Encode a string or a pair of strings into a list of `TokenizedInput` windows of at most
+    ///`max_len` tokens. For a pair of strings the first sequence (e.g. a question) is kept in every
+    ///window and the second sequence (e.g. a context) is split, otherwise the single sequence is
+    ///split. Consecutive windows overlap by `stride` tokens.
+    ///Every window contains the special tokens, offsets and masks for the tokens it holds, and its
+    ///`overflow_to_sample_mapping` is set to `Some(0)` (see `encode_list_with_overflow` and
+    ///`encode_pair_list_with_overflow` to encode several inputs). The windows are padded as a
+    ///batch following `padding` (see `pad_tokenized_inputs`).
+    ///Returns a `TokenizerError` if the fixed part of the input does not fit in `max_len` or if
+    ///`stride` is not smaller than the resulting window size.
+    fn encode_with_overflow(
+        &self,
+        text_1: &str,
+        text_2: Option<&str>,
+        max_len: usize,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Result<Vec<TokenizedInput>, TokenizerError> {
+        let (token_strings, token_offsets_1, original_positions_1, token_mask_1) =
             self.tokenize_with_offsets(text_1);
         let token_ids_1 = self.convert_tokens_to_ids(&token_strings);
-        let len_1 = token_ids_1.len();
-        let (token_ids_2, token_offsets_2, original_positions_2, token_mask_2, len_2, pair) = {
+        let (token_ids_2, token_offsets_2, original_positions_2, token_mask_2) =
             if let Some(text) = text_2 {
                 let (token_strings_2, token_offsets_2, original_positions_2, token_mask_2) =
                     self.tokenize_with_offsets(text);
-                let token_ids_2: Vec<i64> = self.convert_tokens_to_ids(&token_strings_2);
-                let len_2 = token_ids_2.len();
                 (
-                    Some(token_ids_2),
+                    Some(self.convert_tokens_to_ids(&token_strings_2)),
                     Some(token_offsets_2),
                     Some(original_positions_2),
                     Some(token_mask_2),
-                    len_2,
-                    Some(vec![]),
                 )
             } else {
-                (None, None, None, None, 0, None)
-            }
+                (None, None, None, None)
+            };
+        let (additional_tokens, _, _, _, _, _, _, _) = self.build_input_with_special_tokens(
+            vec![],
+            token_ids_2.as_ref().map(|_| vec![]),
+            vec![],
+            Some(vec![]),
+            vec![],
+            Some(vec![]),
+            vec![],
+            Some(vec![]),
+        );
+
+        //The sequence to split into windows is the second one if provided, the first one otherwise
+        let (truncation_strategy, fixed_len, sliding_len) = match &token_ids_2 {
+            Some(token_ids_2) => (
+                TruncationStrategy::OnlySecond,
+                token_ids_1.len() + additional_tokens.len(),
+                token_ids_2.len(),
+            ),
+            None => (
+                TruncationStrategy::OnlyFirst,
+                additional_tokens.len(),
+                token_ids_1.len(),
+            ),
         };
-        let (additional_tokens, _, _, _, _additional_offsets, _additional_mask) = self
-            .build_input_with_special_tokens(
-                vec![],
-                pair,
-                vec![],
-                Some(vec![]),
-                vec![],
-                Some(vec![]),
-                vec![],
-                Some(vec![]),
+
+        let mut windows: Vec<TokenizedInput> = vec![];
+        let mut window_start = 0;
+        loop {
+            let num_truncated_tokens =
+                (fixed_len + sliding_len - window_start).saturating_sub(max_len);
+            let (
+                window_ids_1,
+                window_ids_2,
+                window_offsets_1,
+                window_offsets_2,
+                window_positions_1,
+                window_positions_2,
+                window_mask_1,
+                window_mask_2,
+                overflowing_tokens,
+                _overflowing_offsets,
+            ) = match &token_ids_2 {
+                Some(token_ids_2) => truncate_sequences(
+                    token_ids_1.clone(),
+                    Some(token_ids_2[window_start..].to_vec()),
+                    token_offsets_1.clone(),
+                    token_offsets_2
+                        .as_ref()
+                        .map(|offsets| offsets[window_start..].to_vec()),
+                    original_positions_1.clone(),
+                    original_positions_2
+                        .as_ref()
+                        .map(|positions| positions[window_start..].to_vec()),
+                    token_mask_1.clone(),
+                    token_mask_2
+                        .as_ref()
+                        .map(|mask| mask[window_start..].to_vec()),
+                    num_truncated_tokens,
+                    &truncation_strategy,
+                    stride,
+                )?,
+                None => truncate_sequences(
+                    token_ids_1[window_start..].to_vec(),
+                    None,
+                    token_offsets_1[window_start..].to_vec(),
+                    None,
+                    original_positions_1[window_start..].to_vec(),
+                    None,
+                    token_mask_1[window_start..].to_vec(),
+                    None,
+                    num_truncated_tokens,
+                    &truncation_strategy,
+                    stride,
+                )?,
+            };
+            let window_len = match &window_ids_2 {
+                Some(window_ids_2) => window_ids_2.len(),
+                None => window_ids_1.len(),
+            };
+
+            let (
+                token_ids,
+                segment_ids,
+                special_tokens_mask,
+                token_offsets,
+                reference_offsets,
+                token_mask,
+                mut word_ids,
+                sequence_ids,
+            ) = self.build_input_with_special_tokens(
+                window_ids_1,
+                window_ids_2,
+                window_offsets_1,
+                window_offsets_2,
+                window_positions_1,
+                window_positions_2,
+                window_mask_1,
+                window_mask_2,
             );
-        let total_len = len_1 + len_2 + additional_tokens.len();
-        let num_truncated_tokens = if total_len > max_len {
-            total_len - max_len
-        } else {
-            0
+            //Word indices of the sliding sequence are relative to the window, shift them so that they
+            //refer to the words of the complete sequence
+            let sliding_mask = match &token_mask_2 {
+                Some(token_mask_2) => token_mask_2,
+                None => &token_mask_1,
+            };
+            let word_offset = if window_start > 0 {
+                get_word_ids(&sliding_mask[..window_start + 1])
+                    .last()
+                    .copied()
+                    .flatten()
+                    .unwrap_or(0)
+            } else {
+                0
+            };
+            let sliding_sequence_id = if token_ids_2.is_some() { 1 } else { 0 };
+            for (word_id, sequence_id) in word_ids.iter_mut().zip(sequence_ids.iter()) {
+                if *sequence_id == Some(sliding_sequence_id) {
+                    if let Some(word_id) = word_id {
+                        *word_id += word_offset;
+                    }
+                }
+            }
+            let attention_mask = vec![1; token_ids.len()];
+            let mut window = TokenizedInput {
+                token_ids,
+                segment_ids,
+                special_tokens_mask,
+                overflowing_tokens,
+                num_truncated_tokens,
+                token_offsets,
+                byte_offsets: vec![],
+                utf16_offsets: vec![],
+                reference_offsets,
+                mask: token_mask,
+                attention_mask,
+                overflow_to_sample_mapping: Some(0),
+                word_ids,
+                sequence_ids,
+                alignment_index: AlignmentIndex::default(),
+            };
+            window.set_unit_offsets(&[text_1], text_2.as_ref().map(std::slice::from_ref), false);
+            windows.push(window);
+
+            if num_truncated_tokens == 0 {
+                break;
+            }
+            if window_len <= stride {
+                return Err(TokenizerError::InvalidTruncationStrategy(format!(
+                    "stride ({}) must be smaller than the window size ({})",
+                    stride, window_len
+                )));
+            }
+            window_start += window_len - stride;
+        }
+        self.pad_tokenized_inputs(&mut windows, max_len, padding);
+        Ok(windows)
+    }
+
+    ///Pads a batch of `TokenizedInput` in place following the `padding` strategy:
+    ///- `PaddingStrategy::MaxLength` pads every input to `max_len`
+    ///- `PaddingStrategy::Longest` pads every input to the longest input of the batch
+    ///- `PaddingStrategy::MultipleOf(n)` pads every input to the longest input of the batch,
+    ///  rounded up to a multiple of `n`
+    ///
+    ///Padding tokens are registered with a segment ID of 0, a special token mask of 1, no offset,
+    ///a `Mask::Special` mask and an attention mask of 0.
+    fn pad_tokenized_inputs(
+        &self,
+        tokenized_inputs: &mut [TokenizedInput],
+        max_len: usize,
+        padding: &PaddingOptions,
+    ) {
+        let longest = tokenized_inputs
+            .iter()
+            .map(|tokenized_input| tokenized_input.token_ids.len())
+            .max()
+            .unwrap_or(0);
+        let target_length = match padding.strategy {
+            PaddingStrategy::DoNotPad => return,
+            PaddingStrategy::MaxLength => max_len,
+            PaddingStrategy::Longest => longest,
+            PaddingStrategy::MultipleOf(multiple) => {
+                if multiple > 0 && longest % multiple != 0 {
+                    (longest / multiple + 1) * multiple
+                } else {
+                    longest
+                }
+            }
         };
-        let (
-            token_ids_1,
-            token_ids_2,
-            token_offsets,
-            token_offsets_2,
-            original_positions,
-            original_positions_2,
-            token_mask,
-            token_mask_2,
-            overflowing_tokens,
-            _overflowing_offsets,
-        ) = truncate_sequences(
-            token_ids_1,
-            token_ids_2,
-            token_offsets,
-            token_offsets_2,
-            original_positions,
-            original_positions_2,
-            token_mask,
-            token_mask_2,
-            num_truncated_tokens,
+        let pad_token_id = self.vocab().token_to_id(self.vocab().get_pad_value());
+        for tokenized_input in tokenized_inputs.iter_mut() {
+            pad_tokenized_input(tokenized_input, target_length, pad_token_id, &padding.side);
+        }
+    }
+
+    ///Encode a list of strings into `TokenizedInput`s, padded as a batch following `padding`.
+    fn encode_list(
+        &self,
+        text_list: Vec<&str>,
+        max_len: usize,
+        truncation_strategy: &TruncationStrategy,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Vec<TokenizedInput> {
+        Tokenizer::try_encode_list(
+            self,
+            text_list,
+            max_len,
             truncation_strategy,
             stride,
+            padding,
         )
-        .unwrap();
+        .unwrap()
+    }
 
-        let (
-            token_ids,
-            segment_ids,
-            special_tokens_mask,
-            token_offsets,
-            reference_offsets,
-            token_mask,
-        ) = self.build_input_with_special_tokens(
-            token_ids_1,
-            token_ids_2,
-            token_offsets,
-            token_offsets_2,
-            original_positions,
-            original_positions_2,
-            token_mask,
-            token_mask_2,
-        );
+    ///Encode a list of string pairs into `TokenizedInput`s, padded as a batch following `padding`.
+    fn encode_pair_list(
+        &self,
+        text_list: Vec<(&str, &str)>,
+        max_len: usize,
+        truncation_strategy: &TruncationStrategy,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Vec<TokenizedInput> {
+        Tokenizer::try_encode_pair_list(
+            self,
+            text_list,
+            max_len,
+            truncation_strategy,
+            stride,
+            padding,
+        )
+        .unwrap()
+    }
 
-        TokenizedInput {
-            token_ids,
-            segment_ids,
-            special_tokens_mask,
-            overflowing_tokens,
-            num_truncated_tokens,
-            token_offsets,
-            reference_offsets,
-            mask: token_mask,
+    ///Encode a list of strings into `TokenizedInput` windows, see `encode_with_overflow`. The
+    ///`overflow_to_sample_mapping` of each window is set to the index of the text it originates from.
+    ///The windows of all the texts are padded as a single batch following `padding`.
+    fn encode_list_with_overflow(
+        &self,
+        text_list: Vec<&str>,
+        max_len: usize,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Result<Vec<TokenizedInput>, TokenizerError> {
+        let mut output = vec![];
+        for (sample_index, text) in text_list.into_iter().enumerate() {
+            let mut windows =
+                self.encode_with_overflow(text, None, max_len, stride, &PaddingOptions::default())?;
+            windows
+                .iter_mut()
+                .for_each(|window| window.overflow_to_sample_mapping = Some(sample_index));
+            output.extend(windows);
         }
+        self.pad_tokenized_inputs(&mut output, max_len, padding);
+        Ok(output)
     }
 
-    fn encode_list(
+    ///Encode a list of string pairs into `TokenizedInput` windows, see `encode_with_overflow`. The
+    ///`overflow_to_sample_mapping` of each window is set to the index of the pair it originates
+    ///from. The windows of all the pairs are padded as a single batch following `padding`.
+    fn encode_pair_list_with_overflow(
+        &self,
+        text_list: Vec<(&str, &str)>,
+        max_len: usize,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Result<Vec<TokenizedInput>, TokenizerError> {
+        let mut output = vec![];
+        for (sample_index, text) in text_list.into_iter().enumerate() {
+            let mut windows = self.encode_with_overflow(
+                text.0,
+                Some(text.1),
+                max_len,
+                stride,
+                &PaddingOptions::default(),
+            )?;
+            windows
+                .iter_mut()
+                .for_each(|window| window.overflow_to_sample_mapping = Some(sample_index));
+            output.extend(windows);
+        }
+        self.pad_tokenized_inputs(&mut output, max_len, padding);
+        Ok(output)
+    }
+
+    ///Fallible version of `encode_list`, returns the first `TokenizerError` encountered.
+    fn try_encode_list(
         &self,
         text_list: Vec<&str>,
         max_len: usize,
         truncation_strategy: &TruncationStrategy,
         stride: usize,
-    ) -> Vec<TokenizedInput> {
-        text_list
+        padding: &PaddingOptions,
+    ) -> Result<Vec<TokenizedInput>, TokenizerError> {
+        let mut tokenized_inputs = text_list
             .into_iter()
-            .map(|text| self.encode(text, None, max_len, truncation_strategy, stride))
-            .collect()
+            .map(|text| {
+                self.try_encode(
+                    text,
+                    None,
+                    max_len,
+                    truncation_strategy,
+                    stride,
+                    &PaddingOptions::default(),
+                )
+            })
+            .collect::<Result<Vec<TokenizedInput>, TokenizerError>>()?;
+        self.pad_tokenized_inputs(tokenized_inputs.as_mut_slice(), max_len, padding);
+        Ok(tokenized_inputs)
     }
 
-    fn encode_pair_list(
+    ///Fallible version of `encode_pair_list`, returns the first `TokenizerError` encountered.
+    fn try_encode_pair_list(
         &self,
         text_list: Vec<(&str, &str)>,
         max_len: usize,
         truncation_strategy: &TruncationStrategy,
         stride: usize,
-    ) -> Vec<TokenizedInput> {
-        text_list
+        padding: &PaddingOptions,
+    ) -> Result<Vec<TokenizedInput>, TokenizerError> {
+        let mut tokenized_inputs = text_list
             .into_iter()
-            .map(|text| self.encode(text.0, Some(text.1), max_len, truncation_strategy, stride))
-            .collect()
+            .map(|text| {
+                self.try_encode(
+                    text.0,
+                    Some(text.1),
+                    max_len,
+                    truncation_strategy,
+                    stride,
+                    &PaddingOptions::default(),
+                )
+            })
+            .collect::<Result<Vec<TokenizedInput>, TokenizerError>>()?;
+        self.pad_tokenized_inputs(tokenized_inputs.as_mut_slice(), max_len, padding);
+        Ok(tokenized_inputs)
     }
 
     fn decode_to_vec(&self, token_ids: Vec<i64>, skip_special_tokens: bool) -> Vec<String> {
@@ -603,72 +1433,384 @@ pub trait Tokenizer<T: Vocab> {
     }
 
     /// Build model inputs from a sequence or a pair of sequence for sequence classification tasks
-    /// by concatenating and adding special tokens.
-    /// A RoBERTa sequence has the following format:
-    /// single sequence: <s> X </s>
-    /// pair of sequences: <s> A </s></s> B </s>
+    /// by concatenating and adding special tokens, following the template post-processor of the
+    /// tokenizer (see `post_processor` and `default_post_processor`).
+    /// A BERT sequence has for example the following format:
+    /// single sequence: [CLS] X [SEP]
+    /// pair of sequences: [CLS] A [SEP] B [SEP]
     ///
     /// Returns a tuple of:
     ///  * output token IDs
     ///  * token segment IDs
     ///  * special token mask
     ///  * offsets (as a vector of `Option<Offset>` because some added markers may not have associated offsets
+    ///  * reference offsets
     ///  * token mask
+    ///  * word IDs (index of the word each token belongs to in its sequence, `None` for special tokens)
+    ///  * sequence IDs (index of the sequence each token belongs to, `None` for special tokens)
     fn build_input_with_special_tokens(
         &self,
-        mut tokens_1: Vec<i64>,
+        tokens_1: Vec<i64>,
         tokens_2: Option<Vec<i64>>,
-        mut offsets_1: Vec<Option<Offset>>,
+        offsets_1: Vec<Option<Offset>>,
         offsets_2: Option<Vec<Option<Offset>>>,
         original_offsets_1: Vec<Vec<OffsetSize>>,
         original_offsets_2: Option<Vec<Vec<OffsetSize>>>,
-        mut mask: Vec<Mask>,
+        mask_1: Vec<Mask>,
         mask_2: Option<Vec<Mask>>,
-    ) -> (
-        Vec<i64>,
-        Vec<i8>,
-        Vec<i8>,
-        Vec<Option<Offset>>,
-        Vec<Vec<OffsetSize>>,
-        Vec<Mask>,
-    ) {
-        let mut token_segment_ids: Vec<i8> = vec![0; tokens_1.len()];
-        let mut special_tokens_mask: Vec<i8> = vec![0; tokens_1.len()];
-        let mut original_offsets: Vec<Vec<OffsetSize>> = original_offsets_1;
-        let output = match tokens_2 {
-            Some(tokens) => {
-                let length = tokens.len();
-                token_segment_ids.extend(vec![1; length]);
-                special_tokens_mask.extend(vec![0; length]);
-                tokens_1.extend(tokens);
-                if let Some(offsets_2) = offsets_2 {
-                    offsets_1.extend(offsets_2);
-                } else {
-                    offsets_1.extend(vec![None; length]);
-                }
-                if let Some(original_offset_2) = original_offsets_2 {
-                    original_offsets.extend(original_offset_2)
-                }
-                if let Some(mask_2) = mask_2 {
-                    mask.extend(mask_2)
-                } else {
-                    mask.extend(vec![Mask::None; length]);
-                }
-                tokens_1
-            }
-            None => tokens_1,
+    ) -> TokenIdsWithSpecialTokens {
+        let sequence_1 = TemplateSequence {
+            token_ids: tokens_1,
+            offsets: Some(offsets_1),
+            reference_offsets: Some(original_offsets_1),
+            mask: Some(mask_1),
         };
-        (
-            output,
-            token_segment_ids,
-            special_tokens_mask,
-            offsets_1,
-            original_offsets,
-            mask,
+        let sequence_2 = tokens_2.map(|tokens_2| TemplateSequence {
+            token_ids: tokens_2,
+            offsets: offsets_2,
+            reference_offsets: original_offsets_2,
+            mask: mask_2,
+        });
+        match self.post_processor() {
+            Some(post_processor) => post_processor.apply(self.vocab(), sequence_1, sequence_2),
+            None => self
+                .default_post_processor()
+                .apply(self.vocab(), sequence_1, sequence_2),
+        }
+    }
+}
+
+/// # Subword regularization
+/// Tokenizers relying on a SentencePiece unigram model can sample the segmentation of the text
+/// from the lattice of the model instead of returning the most likely segmentation, as a data
+/// augmentation during training. The random number generator is passed to every call: seeding it
+/// makes the sampled segmentations reproducible.
+///
+/// The sampling is parametrized as in the reference SentencePiece library:
+/// - nbest_size: `0` or `1` disables sampling (most likely segmentation), a negative value samples
+///   from all the segmentations (forward-filtering and backward-sampling), a value larger than 1
+///   samples from the `nbest_size` most likely segmentations
+/// - alpha: smoothing parameter, the probability of a segmentation being proportional to
+///   `exp(alpha * score)`
+///
+/// BPE models always return their single segmentation.
+pub trait SubwordSamplingTokenizer<T: Vocab>: Tokenizer<T> {
+    ///Tokenize a text sampling its segmentation, returns a vector of tokens
+    fn tokenize_to_tokens_with_sampling<R: Rng + ?Sized>(
+        &self,
+        text: TokenRef,
+        nbest_size: i64,
+        alpha: f32,
+        rng: &mut R,
+    ) -> Vec<Token>;
+
+    ///Tokenize a string sampling its segmentation, returns a vector of tokens as strings
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    /// use rust_tokenizers::{SubwordSamplingTokenizer, XLNetTokenizer};
+    /// let tokenizer = XLNetTokenizer::from_file("path/to/vocab/file", false, true).unwrap();
+    /// let mut rng = StdRng::seed_from_u64(42);
+    ///
+    /// let tokens = tokenizer.tokenize_with_sampling("Hello, world!", -1, 0.1, &mut rng);
+    /// ```
+    fn tokenize_with_sampling<R: Rng + ?Sized>(
+        &self,
+        text: &str,
+        nbest_size: i64,
+        alpha: f32,
+        rng: &mut R,
+    ) -> Vec<String> {
+        tokenize_text_with_offsets(text, |token| {
+            self.tokenize_to_tokens_with_sampling(token, nbest_size, alpha, rng)
+        })
+        .0
+    }
+
+    ///Encode a string or a pair of strings into a `TokenizedInput` as `Tokenizer::encode`,
+    ///sampling the segmentation of the texts.
+    ///Panics if the requested truncation cannot be performed, use `try_encode_with_sampling` to
+    ///handle these cases.
+    #[allow(clippy::too_many_arguments)]
+    fn encode_with_sampling<R: Rng + ?Sized>(
+        &self,
+        text_1: &str,
+        text_2: Option<&str>,
+        max_len: usize,
+        truncation_strategy: &TruncationStrategy,
+        stride: usize,
+        padding: &PaddingOptions,
+        nbest_size: i64,
+        alpha: f32,
+        rng: &mut R,
+    ) -> TokenizedInput {
+        self.try_encode_with_sampling(
+            text_1,
+            text_2,
+            max_len,
+            truncation_strategy,
+            stride,
+            padding,
+            nbest_size,
+            alpha,
+            rng,
+        )
+        .unwrap()
+    }
+
+    ///Encode a string or a pair of strings into a `TokenizedInput` as `Tokenizer::try_encode`,
+    ///sampling the segmentation of the texts.
+    ///Returns a `TokenizerError` if the truncation strategy cannot be applied.
+    #[allow(clippy::too_many_arguments)]
+    fn try_encode_with_sampling<R: Rng + ?Sized>(
+        &self,
+        text_1: &str,
+        text_2: Option<&str>,
+        max_len: usize,
+        truncation_strategy: &TruncationStrategy,
+        stride: usize,
+        padding: &PaddingOptions,
+        nbest_size: i64,
+        alpha: f32,
+        rng: &mut R,
+    ) -> Result<TokenizedInput, TokenizerError> {
+        encode_texts(
+            self,
+            text_1,
+            text_2,
+            max_len,
+            truncation_strategy,
+            stride,
+            padding,
+            |text| {
+                tokenize_text_with_offsets(text, |token| {
+                    self.tokenize_to_tokens_with_sampling(token, nbest_size, alpha, &mut *rng)
+                })
+            },
         )
     }
 }
 
+///Tokens as strings with their offsets, reference offsets and masks
+type TokensWithOffsets = (
+    Vec<String>,
+    Vec<Option<Offset>>,
+    Vec<Vec<OffsetSize>>,
+    Vec<Mask>,
+);
+
+///Tokenizes a text with `tokenize`, returns the tokens as strings with their offsets, reference
+///offsets and masks
+fn tokenize_text_with_offsets<F: FnOnce(TokenRef) -> Vec<Token>>(
+    text: &str,
+    tokenize: F,
+) -> TokensWithOffsets {
+    if text.trim().is_empty() {
+        return (vec![], vec![], vec![], vec![]);
+    }
+    let initial_offsets = (0..text.chars().count() as OffsetSize).collect::<Vec<OffsetSize>>();
+    let initial_token: TokenRef<'_> = TokenRef::new(text, &initial_offsets);
+    let tokens = tokenize(initial_token);
+    let length = tokens.len();
+    let mut texts = Vec::with_capacity(length);
+    let mut offsets = Vec::with_capacity(length);
+    let mut original_positions = Vec::with_capacity(length);
+    let mut masks = Vec::with_capacity(length);
+
+    for token in tokens {
+        texts.push(token.text);
+        offsets.push(if !token.reference_offsets.is_empty() {
+            Some(Offset {
+                begin: *token.reference_offsets.first().unwrap(),
+                end: *token.reference_offsets.last().unwrap() + 1,
+            })
+        } else {
+            None
+        });
+        original_positions.push(token.reference_offsets);
+        masks.push(token.mask);
+    }
+    (texts, offsets, original_positions, masks)
+}
+
+///Encodes a string or a pair of strings tokenized with `tokenize`, shared by `Tokenizer::try_encode`
+///and `SubwordSamplingTokenizer::encode_with_sampling`
+#[allow(clippy::too_many_arguments)]
+fn encode_texts<T: Vocab, U: Tokenizer<T> + ?Sized, F>(
+    tokenizer: &U,
+    text_1: &str,
+    text_2: Option<&str>,
+    max_len: usize,
+    truncation_strategy: &TruncationStrategy,
+    stride: usize,
+    padding: &PaddingOptions,
+    mut tokenize: F,
+) -> Result<TokenizedInput, TokenizerError>
+where
+    F: FnMut(&str) -> TokensWithOffsets,
+{
+    let mut encode_sequence = |text: &str| {
+        let (token_strings, offsets, reference_offsets, mask) = tokenize(text);
+        EncodedSequence {
+            token_ids: tokenizer.convert_tokens_to_ids(&token_strings),
+            offsets,
+            reference_offsets,
+            mask,
+        }
+    };
+    let sequence_1 = encode_sequence(text_1);
+    let sequence_2 = text_2.map(encode_sequence);
+    encode_token_ids(
+        tokenizer,
+        sequence_1,
+        sequence_2,
+        max_len,
+        truncation_strategy,
+        stride,
+    )
+    .map(|mut tokenized_input| {
+        tokenized_input.set_unit_offsets(
+            &[text_1],
+            text_2.as_ref().map(std::slice::from_ref),
+            false,
+        );
+        tokenizer.pad_tokenized_inputs(
+            std::slice::from_mut(&mut tokenized_input),
+            max_len,
+            padding,
+        );
+        tokenized_input
+    })
+}
+
+///Encoded sequence passed to `encode_token_ids`: token IDs with their offsets, reference offsets
+///and masks, before truncation and addition of the special tokens.
+struct EncodedSequence {
+    token_ids: Vec<i64>,
+    offsets: Vec<Option<Offset>>,
+    reference_offsets: Vec<Vec<OffsetSize>>,
+    mask: Vec<Mask>,
+}
+
+///Truncates a sequence or pair of sequences of token IDs (with their offsets and masks) to
+///`max_len` and adds the special tokens of the tokenizer, shared by the `Tokenizer` encoding methods.
+fn encode_token_ids<T: Vocab, U: Tokenizer<T> + ?Sized>(
+    tokenizer: &U,
+    sequence_1: EncodedSequence,
+    sequence_2: Option<EncodedSequence>,
+    max_len: usize,
+    truncation_strategy: &TruncationStrategy,
+    stride: usize,
+) -> Result<TokenizedInput, TokenizerError> {
+    let EncodedSequence {
+        token_ids: token_ids_1,
+        offsets: token_offsets,
+        reference_offsets: original_positions,
+        mask: token_mask,
+    } = sequence_1;
+    let (token_ids_2, token_offsets_2, original_positions_2, token_mask_2) = match sequence_2 {
+        Some(sequence) => (
+            Some(sequence.token_ids),
+            Some(sequence.offsets),
+            Some(sequence.reference_offsets),
+            Some(sequence.mask),
+        ),
+        None => (None, None, None, None),
+    };
+    let (additional_tokens, _, _, _, _additional_offsets, _additional_mask, _, _) = tokenizer
+        .build_input_with_special_tokens(
+            vec![],
+            token_ids_2.as_ref().map(|_| vec![]),
+            vec![],
+            Some(vec![]),
+            vec![],
+            Some(vec![]),
+            vec![],
+            Some(vec![]),
+        );
+    let total_len = token_ids_1.len()
+        + token_ids_2.as_ref().map_or(0, |token_ids| token_ids.len())
+        + additional_tokens.len();
+    let num_truncated_tokens = total_len.saturating_sub(max_len);
+    let (
+        token_ids_1,
+        token_ids_2,
+        token_offsets,
+        token_offsets_2,
+        original_positions,
+        original_positions_2,
+        token_mask,
+        token_mask_2,
+        overflowing_tokens,
+        _overflowing_offsets,
+    ) = truncate_sequences(
+        token_ids_1,
+        token_ids_2,
+        token_offsets,
+        token_offsets_2,
+        original_positions,
+        original_positions_2,
+        token_mask,
+        token_mask_2,
+        num_truncated_tokens,
+        truncation_strategy,
+        stride,
+    )
+    .map_err(|error| match error {
+        //`truncate_sequences` reports lengths excluding the special tokens
+        TokenizerError::SequenceTooLong {
+            sequence_length,
+            max_length,
+        } => TokenizerError::SequenceTooLong {
+            sequence_length: sequence_length + additional_tokens.len(),
+            max_length: max_length + additional_tokens.len(),
+        },
+        error => error,
+    })?;
+
+    let (
+        token_ids,
+        segment_ids,
+        special_tokens_mask,
+        token_offsets,
+        reference_offsets,
+        token_mask,
+        word_ids,
+        sequence_ids,
+    ) = tokenizer.build_input_with_special_tokens(
+        token_ids_1,
+        token_ids_2,
+        token_offsets,
+        token_offsets_2,
+        original_positions,
+        original_positions_2,
+        token_mask,
+        token_mask_2,
+    );
+
+    let attention_mask = vec![1; token_ids.len()];
+    Ok(TokenizedInput {
+        token_ids,
+        segment_ids,
+        special_tokens_mask,
+        overflowing_tokens,
+        num_truncated_tokens,
+        token_offsets,
+        byte_offsets: vec![],
+        utf16_offsets: vec![],
+        reference_offsets,
+        mask: token_mask,
+        attention_mask,
+        overflow_to_sample_mapping: None,
+        word_ids,
+        sequence_ids,
+        alignment_index: AlignmentIndex::default(),
+    })
+}
+
 pub trait MultiThreadedTokenizer<T: Vocab>
 where
     Self: std::marker::Sync + Send + Tokenizer<T>,
@@ -705,11 +1847,17 @@ where
         max_len: usize,
         truncation_strategy: &TruncationStrategy,
         stride: usize,
+        padding: &PaddingOptions,
     ) -> Vec<TokenizedInput> {
-        text_list
-            .par_iter()
-            .map(|text| self.encode(text, None, max_len, truncation_strategy, stride))
-            .collect()
+        MultiThreadedTokenizer::try_encode_list(
+            self,
+            text_list,
+            max_len,
+            truncation_strategy,
+            stride,
+            padding,
+        )
+        .unwrap()
     }
 
     fn encode_pair_list(
@@ -718,11 +1866,131 @@ where
         max_len: usize,
         truncation_strategy: &TruncationStrategy,
         stride: usize,
+        padding: &PaddingOptions,
     ) -> Vec<TokenizedInput> {
-        text_list
+        MultiThreadedTokenizer::try_encode_pair_list(
+            self,
+            text_list,
+            max_len,
+            truncation_strategy,
+            stride,
+            padding,
+        )
+        .unwrap()
+    }
+
+    fn encode_list_with_overflow(
+        &self,
+        text_list: Vec<&str>,
+        max_len: usize,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Result<Vec<TokenizedInput>, TokenizerError> {
+        let windows = text_list
+            .par_iter()
+            .enumerate()
+            .map(|(sample_index, text)| {
+                let mut windows = self.encode_with_overflow(
+                    text,
+                    None,
+                    max_len,
+                    stride,
+                    &PaddingOptions::default(),
+                )?;
+                windows
+                    .iter_mut()
+                    .for_each(|window| window.overflow_to_sample_mapping = Some(sample_index));
+                Ok(windows)
+            })
+            .collect::<Result<Vec<Vec<TokenizedInput>>, TokenizerError>>()?;
+        let mut windows = windows
+            .into_iter()
+            .flatten()
+            .collect::<Vec<TokenizedInput>>();
+        self.pad_tokenized_inputs(&mut windows, max_len, padding);
+        Ok(windows)
+    }
+
+    fn encode_pair_list_with_overflow(
+        &self,
+        text_list: Vec<(&str, &str)>,
+        max_len: usize,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Result<Vec<TokenizedInput>, TokenizerError> {
+        let windows = text_list
+            .par_iter()
+            .enumerate()
+            .map(|(sample_index, text)| {
+                let mut windows = self.encode_with_overflow(
+                    text.0,
+                    Some(text.1),
+                    max_len,
+                    stride,
+                    &PaddingOptions::default(),
+                )?;
+                windows
+                    .iter_mut()
+                    .for_each(|window| window.overflow_to_sample_mapping = Some(sample_index));
+                Ok(windows)
+            })
+            .collect::<Result<Vec<Vec<TokenizedInput>>, TokenizerError>>()?;
+        let mut windows = windows
+            .into_iter()
+            .flatten()
+            .collect::<Vec<TokenizedInput>>();
+        self.pad_tokenized_inputs(&mut windows, max_len, padding);
+        Ok(windows)
+    }
+
+    fn try_encode_list(
+        &self,
+        text_list: Vec<&str>,
+        max_len: usize,
+        truncation_strategy: &TruncationStrategy,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Result<Vec<TokenizedInput>, TokenizerError> {
+        let mut tokenized_inputs = text_list
+            .par_iter()
+            .map(|text| {
+                self.try_encode(
+                    text,
+                    None,
+                    max_len,
+                    truncation_strategy,
+                    stride,
+                    &PaddingOptions::default(),
+                )
+            })
+            .collect::<Result<Vec<TokenizedInput>, TokenizerError>>()?;
+        self.pad_tokenized_inputs(tokenized_inputs.as_mut_slice(), max_len, padding);
+        Ok(tokenized_inputs)
+    }
+
+    fn try_encode_pair_list(
+        &self,
+        text_list: Vec<(&str, &str)>,
+        max_len: usize,
+        truncation_strategy: &TruncationStrategy,
+        stride: usize,
+        padding: &PaddingOptions,
+    ) -> Result<Vec<TokenizedInput>, TokenizerError> {
+        let mut tokenized_inputs = text_list
             .par_iter()
-            .map(|text| self.encode(text.0, Some(text.1), max_len, truncation_strategy, stride))
-            .collect()
+            .map(|text| {
+                self.try_encode(
+                    text.0,
+                    Some(text.1),
+                    max_len,
+                    truncation_strategy,
+                    stride,
+                    &PaddingOptions::default(),
+                )
+            })
+            .collect::<Result<Vec<TokenizedInput>, TokenizerError>>()?;
+        self.pad_tokenized_inputs(tokenized_inputs.as_mut_slice(), max_len, padding);
+        Ok(tokenized_inputs)
     }
 
     fn decode_list(
@@ -749,6 +2017,11 @@ pub struct BaseTokenizer<T: Vocab> {
     vocab: Arc<T>,
     lower_case: bool,
     strip_accents: bool,
+    normalizer: Box<dyn Normalizer>,
+    custom_normalizer: bool,
+    pre_tokenizer: Box<dyn PreTokenizer>,
+    custom_pre_tokenizer: bool,
+    post_processor: Option<TemplateProcessing>,
 }
 
 impl<T: Vocab + Sync + Send> BaseTokenizer<T> {
@@ -762,9 +2035,40 @@ impl<T: Vocab + Sync + Send> BaseTokenizer<T> {
             vocab: Arc::new(vocab),
             lower_case,
             strip_accents,
+            normalizer: Box::new(base_normalizer(lower_case, strip_accents)),
+            custom_normalizer: false,
+            pre_tokenizer: Box::new(base_pre_tokenizer()),
+            custom_pre_tokenizer: false,
+            post_processor: None,
+        })
+    }
+
+    pub fn from_reader<R: Read>(
+        vocab: R,
+        lower_case: bool,
+        strip_accents: bool,
+    ) -> Result<BaseTokenizer<T>, TokenizerError> {
+        let vocab = T::from_reader(vocab)?;
+        Ok(BaseTokenizer {
+            vocab: Arc::new(vocab),
+            lower_case,
+            strip_accents,
+            normalizer: Box::new(base_normalizer(lower_case, strip_accents)),
+            custom_normalizer: false,
+            pre_tokenizer: Box::new(base_pre_tokenizer()),
+            custom_pre_tokenizer: false,
+            post_processor: None,
         })
     }
 
+    pub fn from_bytes(
+        vocab: &[u8],
+        lower_case: bool,
+        strip_accents: bool,
+    ) -> Result<BaseTokenizer<T>, TokenizerError> {
+        BaseTokenizer::from_reader(vocab, lower_case, strip_accents)
+    }
+
     pub fn from_existing_vocab(
         vocab: Arc<T>,
         lower_case: bool,
@@ -774,61 +2078,112 @@ impl<T: Vocab + Sync + Send> BaseTokenizer<T> {
             vocab,
             lower_case,
             strip_accents,
+            normalizer: Box::new(base_normalizer(lower_case, strip_accents)),
+            custom_normalizer: false,
+            pre_tokenizer: Box::new(base_pre_tokenizer()),
+            custom_pre_tokenizer: false,
+            post_processor: None,
         }
     }
+
+    pub(crate) fn lower_case(&self) -> bool {
+        self.lower_case
+    }
+
+    pub(crate) fn strip_accents(&self) -> bool {
+        self.strip_accents
+    }
+
+    ///Returns true if the normalizer built from the options was replaced with `set_normalizer`
+    pub(crate) fn custom_normalizer(&self) -> bool {
+        self.custom_normalizer
+    }
+
+    ///Returns true if the default pre-tokenizer was replaced with `set_pre_tokenizer`
+    pub(crate) fn custom_pre_tokenizer(&self) -> bool {
+        self.custom_pre_tokenizer
+    }
 }
 
-impl<T: Vocab + Sync + Send> Tokenizer<T> for BaseTokenizer<T> {
+impl<T: Vocab + Clone + Sync + Send> Tokenizer<T> for BaseTokenizer<T> {
     fn vocab(&self) -> &T {
         &self.vocab
     }
 
+    fn vocab_mut(&mut self) -> &mut T {
+        Arc::make_mut(&mut self.vocab)
+    }
+
+    fn normalizer(&self) -> &dyn Normalizer {
+        self.normalizer.as_ref()
+    }
+
+    fn set_normalizer(&mut self, normalizer: Box<dyn Normalizer>) {
+        self.normalizer = normalizer;
+        self.custom_normalizer = true;
+    }
+
+    fn pre_tokenizer(&self) -> &dyn PreTokenizer {
+        self.pre_tokenizer.as_ref()
+    }
+
+    fn set_pre_tokenizer(&mut self, pre_tokenizer: Box<dyn PreTokenizer>) {
+        self.pre_tokenizer = pre_tokenizer;
+        self.custom_pre_tokenizer = true;
+    }
+
+    fn post_processor(&self) -> Option<&TemplateProcessing> {
+        self.post_processor.as_ref()
+    }
+
+    fn post_processor_mut(&mut self) -> &mut Option<TemplateProcessing> {
+        &mut self.post_processor
+    }
+
     fn tokenize_to_tokens(&self, initial_token: TokenRef) -> Vec<Token> {
-        //split on whitespace
-        let tokens: Vec<Token> = whitespace_tokenize(initial_token)
-            .into_iter()
-            .map(|token| {
-                //split on special tokens
-                split_on_special_tokens(token, self.vocab.as_ref())
-            })
-            .flatten()
-            .map(|token| {
-                //split on punctuation (with care for maintaining special values)
-                split_on_punct(token)
-            })
-            .flatten()
-            .map(|token| {
-                //tokenize CJK characters so each character is one token
-                tokenize_cjk_chars(token)
-            })
-            .flatten()
-            .map(|token| {
-                // v-- this is where the token gets owned, all steps above handle TokenRefs (dealing with &str)
-                let mut token = Token {
-                    text: token.text.to_string(),
-                    offset: token.offset,
-                    reference_offsets: token.reference_offsets.to_vec(),
-                    mask: token.mask,
-                };
-                if token.mask != Mask::Special && token.mask != Mask::Unknown {
-                    //apply the necessary transformations to the actual tokens (unless it's a special value)
-                    if self.lower_case {
-                        lowercase(&mut token);
-                    }
-                    if self.strip_accents {
-                        strip_accents(&mut token);
-                    }
-                }
-                token
-            })
-            .filter(|token| !token.text.is_empty())
-            .collect();
+        //split on special tokens
+        let tokens: Vec<Token> = split_on_added_tokens(
+            initial_token,
+            self.vocab.as_ref(),
+            self.normalizer.is_lowercasing(),
+        )
+        .into_iter()
+        .flat_map(|token| {
+            //split on whitespace, punctuation and CJK characters (unless it's a special value)
+            if token.mask == Mask::Special
+                || token.mask == Mask::Added
+                || token.mask == Mask::Unknown
+            {
+                vec![token]
+            } else {
+                self.pre_tokenizer.pre_tokenize(token)
+            }
+        })
+        .map(|token| {
+            // v-- this is where the token gets owned, all steps above handle TokenRefs (dealing with &str)
+            let mut token = Token {
+                text: token.text.to_string(),
+                offset: token.offset,
+                reference_offsets: token.reference_offsets.to_vec(),
+                mask: token.mask,
+            };
+            if token.mask != Mask::Special
+                && token.mask != Mask::Added
+                && token.mask != Mask::Unknown
+            {
+                //apply the necessary transformations to the actual tokens (unless it's a special value)
+                self.normalizer.normalize(&mut token);
+            }
+            token
+        })
+        .filter(|token| !token.text.is_empty())
+        .collect();
 
         tokens
     }
 }
 
-impl<T: Vocab + Sync + Send> MultiThreadedTokenizer<T> for BaseTokenizer<T> {}
+impl<T: Vocab + Clone + Sync + Send> MultiThreadedTokenizer<T> for BaseTokenizer<T> {}
 
 //==============================
 // Unit tests
@@ -880,9 +2235,11 @@ mod tests {
         BertVocab {
             values,
             indices,
-            unknown_value: "[UNK]",
+            special_token_map: BertVocab::default_special_token_map(),
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
         }
     }
 
@@ -1368,8 +2725,23 @@ mod tests {
                         Some(Offset::new(6, 11)),
                         Some(Offset::new(11, 12)),
                     ],
+                    byte_offsets: vec![
+                        Some(Offset::new(0, 5)),
+                        Some(Offset::new(6, 11)),
+                        Some(Offset::new(11, 12)),
+                    ],
+                    utf16_offsets: vec![
+                        Some(Offset::new(0, 5)),
+                        Some(Offset::new(6, 11)),
+                        Some(Offset::new(11, 12)),
+                    ],
                     reference_offsets: vec![vec![0, 1, 2, 3, 4], vec![6, 7, 8, 9, 10], vec![11]],
                     mask: vec![Mask::None, Mask::None, Mask::Punctuation],
+                    attention_mask: vec![1, 1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![Some(0), Some(1), Some(2)],
+                    sequence_ids: vec![Some(0), Some(0), Some(0)],
+                    alignment_index: Default::default(),
                 },
             ),
             (
@@ -1387,6 +2759,20 @@ mod tests {
                         Some(Offset::new(17, 22)),
                         Some(Offset::new(22, 23)),
                     ],
+                    byte_offsets: vec![
+                        Some(Offset::new(0, 5)),
+                        Some(Offset::new(5, 6)),
+                        Some(Offset::new(7, 16)),
+                        Some(Offset::new(17, 22)),
+                        Some(Offset::new(22, 23)),
+                    ],
+                    utf16_offsets: vec![
+                        Some(Offset::new(0, 5)),
+                        Some(Offset::new(5, 6)),
+                        Some(Offset::new(7, 16)),
+                        Some(Offset::new(17, 22)),
+                        Some(Offset::new(22, 23)),
+                    ],
                     reference_offsets: vec![
                         vec![0, 1, 2, 3, 4],
                         vec![5],
@@ -1401,6 +2787,11 @@ mod tests {
                         Mask::None,
                         Mask::Punctuation,
                     ],
+                    attention_mask: vec![1, 1, 1, 1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![Some(0), Some(1), Some(2), Some(3), Some(4)],
+                    sequence_ids: vec![Some(0), Some(0), Some(0), Some(0), Some(0)],
+                    alignment_index: Default::default(),
                 },
             ),
             (
@@ -1423,6 +2814,30 @@ mod tests {
                         Some(Offset { begin: 13, end: 18 }),
                         Some(Offset { begin: 19, end: 23 }),
                     ],
+                    byte_offsets: vec![
+                        Some(Offset { begin: 0, end: 5 }),
+                        Some(Offset { begin: 5, end: 8 }),
+                        Some(Offset { begin: 8, end: 11 }),
+                        Some(Offset { begin: 11, end: 14 }),
+                        Some(Offset { begin: 14, end: 17 }),
+                        Some(Offset { begin: 17, end: 20 }),
+                        Some(Offset { begin: 20, end: 23 }),
+                        Some(Offset { begin: 23, end: 26 }),
+                        Some(Offset { begin: 27, end: 32 }),
+                        Some(Offset { begin: 33, end: 37 }),
+                    ],
+                    utf16_offsets: vec![
+                        Some(Offset { begin: 0, end: 5 }),
+                        Some(Offset { begin: 5, end: 6 }),
+                        Some(Offset { begin: 6, end: 7 }),
+                        Some(Offset { begin: 7, end: 8 }),
+                        Some(Offset { begin: 8, end: 9 }),
+                        Some(Offset { begin: 9, end: 10 }),
+                        Some(Offset { begin: 10, end: 11 }),
+                        Some(Offset { begin: 11, end: 12 }),
+                        Some(Offset { begin: 13, end: 18 }),
+                        Some(Offset { begin: 19, end: 23 }),
+                    ],
                     reference_offsets: vec![
                         vec![0, 1, 2, 3, 4],
                         vec![5],
@@ -1447,6 +2862,33 @@ mod tests {
                         Mask::Special,
                         Mask::None,
                     ],
+                    attention_mask: vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![
+                        Some(0),
+                        Some(1),
+                        Some(2),
+                        Some(3),
+                        Some(4),
+                        Some(5),
+                        Some(6),
+                        Some(7),
+                        Some(8),
+                        Some(9),
+                    ],
+                    sequence_ids: vec![
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                    ],
+                    alignment_index: Default::default(),
                 },
             ),
             (
@@ -1469,6 +2911,30 @@ mod tests {
                         Some(Offset { begin: 20, end: 21 }),
                         Some(Offset { begin: 22, end: 23 }),
                     ],
+                    byte_offsets: vec![
+                        Some(Offset { begin: 0, end: 5 }),
+                        Some(Offset { begin: 6, end: 7 }),
+                        Some(Offset { begin: 8, end: 9 }),
+                        Some(Offset { begin: 10, end: 11 }),
+                        Some(Offset { begin: 12, end: 13 }),
+                        Some(Offset { begin: 14, end: 15 }),
+                        Some(Offset { begin: 16, end: 17 }),
+                        Some(Offset { begin: 18, end: 19 }),
+                        Some(Offset { begin: 20, end: 21 }),
+                        Some(Offset { begin: 22, end: 23 }),
+                    ],
+                    utf16_offsets: vec![
+                        Some(Offset { begin: 0, end: 5 }),
+                        Some(Offset { begin: 6, end: 7 }),
+                        Some(Offset { begin: 8, end: 9 }),
+                        Some(Offset { begin: 10, end: 11 }),
+                        Some(Offset { begin: 12, end: 13 }),
+                        Some(Offset { begin: 14, end: 15 }),
+                        Some(Offset { begin: 16, end: 17 }),
+                        Some(Offset { begin: 18, end: 19 }),
+                        Some(Offset { begin: 20, end: 21 }),
+                        Some(Offset { begin: 22, end: 23 }),
+                    ],
                     reference_offsets: vec![
                         vec![0, 1, 2, 3, 4],
                         vec![6],
@@ -1493,6 +2959,33 @@ mod tests {
                         Mask::Punctuation,
                         Mask::None,
                     ],
+                    attention_mask: vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![
+                        Some(0),
+                        Some(1),
+                        Some(2),
+                        Some(3),
+                        Some(4),
+                        Some(5),
+                        Some(6),
+                        Some(7),
+                        Some(8),
+                        Some(9),
+                    ],
+                    sequence_ids: vec![
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                    ],
+                    alignment_index: Default::default(),
                 },
             ),
         ];
@@ -1502,8 +2995,14 @@ mod tests {
 
         //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
-            let tokenized_input =
-                base_tokenizer.encode(source_text, None, 10, &truncation_strategy, 0);
+            let tokenized_input = base_tokenizer.encode(
+                source_text,
+                None,
+                10,
+                &truncation_strategy,
+                0,
+                &PaddingOptions::default(),
+            );
             assert_eq!(
                 tokenized_input.token_ids.len(),
                 tokenized_input.token_offsets.len(),
@@ -1517,7 +3016,8 @@ mod tests {
                 source_texts.clone(),
                 10,
                 &truncation_strategy,
-                0
+                0,
+                &PaddingOptions::default()
             ),
             expected_results
         );
@@ -1527,7 +3027,8 @@ mod tests {
                 source_texts.clone(),
                 10,
                 &truncation_strategy,
-                0
+                0,
+                &PaddingOptions::default()
             ),
             expected_results
         );
@@ -1551,8 +3052,15 @@ mod tests {
                     overflowing_tokens: vec!(),
                     num_truncated_tokens: 0,
                     token_offsets: vec!(Some(Offset::new(0, 5)), Some(Offset::new(6, 11)), Some(Offset::new(11, 12)), Some(Offset::new(0, 4)), Some(Offset::new(5, 7)), Some(Offset::new(8, 11)), Some(Offset::new(12, 18)), Some(Offset::new(19, 27))),
+                    byte_offsets: vec!(Some(Offset::new(0, 5)), Some(Offset::new(6, 11)), Some(Offset::new(11, 12)), Some(Offset::new(0, 4)), Some(Offset::new(5, 7)), Some(Offset::new(8, 11)), Some(Offset::new(12, 18)), Some(Offset::new(19, 27))),
+                    utf16_offsets: vec!(Some(Offset::new(0, 5)), Some(Offset::new(6, 11)), Some(Offset::new(11, 12)), Some(Offset::new(0, 4)), Some(Offset::new(5, 7)), Some(Offset::new(8, 11)), Some(Offset::new(12, 18)), Some(Offset::new(19, 27))),
                     reference_offsets: vec!(vec!(0, 1, 2, 3, 4), vec!(6, 7, 8, 9, 10), vec!(11), vec!(0, 1, 2, 3), vec!(5, 6), vec!(8, 9, 10), vec!(12, 13, 14, 15, 16, 17), vec!(19, 20, 21, 22, 23, 24, 25, 26)),
                     mask: vec!(Mask::None, Mask::None, Mask::Punctuation, Mask::None, Mask::None, Mask::None, Mask::None, Mask::None),
+                    attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1),
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec!(Some(0), Some(1), Some(2), Some(0), Some(1), Some(2), Some(3), Some(4)),
+                    sequence_ids: vec!(Some(0), Some(0), Some(0), Some(1), Some(1), Some(1), Some(1), Some(1)),
+                    alignment_index: Default::default(),
                 }
             ),
 //            Truncation of sentence 2 (longest)
@@ -1567,8 +3075,19 @@ mod tests {
                     token_offsets: vec!(
                         Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 11, end: 12 }), Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 5 }), Some(Offset { begin: 6, end: 8 }), Some(Offset { begin: 9, end: 12 }), Some(Offset { begin: 13, end: 19 }), Some(Offset { begin: 20, end: 28 }), Some(Offset { begin: 28, end: 29 })
                     ),
+                    byte_offsets: vec!(
+                        Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 11, end: 12 }), Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 5 }), Some(Offset { begin: 6, end: 8 }), Some(Offset { begin: 9, end: 12 }), Some(Offset { begin: 13, end: 19 }), Some(Offset { begin: 20, end: 28 }), Some(Offset { begin: 28, end: 29 })
+                    ),
+                    utf16_offsets: vec!(
+                        Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 11, end: 12 }), Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 5 }), Some(Offset { begin: 6, end: 8 }), Some(Offset { begin: 9, end: 12 }), Some(Offset { begin: 13, end: 19 }), Some(Offset { begin: 20, end: 28 }), Some(Offset { begin: 28, end: 29 })
+                    ),
                     reference_offsets: vec!(vec!(0, 1, 2, 3, 4), vec!(6, 7, 8, 9, 10), vec!(11), vec!(0), vec!(1, 2, 3, 4), vec!(6, 7), vec!(9, 10, 11), vec!(13, 14, 15, 16, 17, 18), vec!(20, 21, 22, 23, 24, 25, 26, 27), vec!(28)),
                     mask: vec!(Mask::None, Mask::None, Mask::Punctuation, Mask::Punctuation, Mask::None, Mask::None, Mask::None, Mask::None, Mask::None, Mask::Punctuation),
+                    attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1),
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec!(Some(0), Some(1), Some(2), Some(0), Some(1), Some(2), Some(3), Some(4), Some(5), Some(6)),
+                    sequence_ids: vec!(Some(0), Some(0), Some(0), Some(1), Some(1), Some(1), Some(1), Some(1), Some(1), Some(1)),
+                    alignment_index: Default::default(),
                 }
             ),
 //            Truncation of sentence 1 (longest)
@@ -1583,8 +3102,19 @@ mod tests {
                     token_offsets: vec!(
                         Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 20, end: 25 }), Some(Offset { begin: 27, end: 32 }), Some(Offset { begin: 34, end: 39 }), Some(Offset { begin: 41, end: 46 }), Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 })
                     ),
+                    byte_offsets: vec!(
+                        Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 20, end: 25 }), Some(Offset { begin: 27, end: 32 }), Some(Offset { begin: 34, end: 39 }), Some(Offset { begin: 41, end: 46 }), Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 })
+                    ),
+                    utf16_offsets: vec!(
+                        Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 20, end: 25 }), Some(Offset { begin: 27, end: 32 }), Some(Offset { begin: 34, end: 39 }), Some(Offset { begin: 41, end: 46 }), Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 })
+                    ),
                     reference_offsets: vec!(vec!(0, 1, 2, 3, 4), vec!(6, 7, 8, 9, 10), vec!(13, 14, 15, 16, 17), vec!(20, 21, 22, 23, 24), vec!(27, 28, 29, 30, 31), vec!(34, 35, 36, 37, 38), vec!(41, 42, 43, 44, 45), vec!(0), vec!(1), vec!(2)),
                     mask: vec!(Mask::Unknown, Mask::None, Mask::None, Mask::None, Mask::None, Mask::None, Mask::None, Mask::Punctuation, Mask::Punctuation, Mask::Punctuation),
+                    attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1),
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec!(Some(0), Some(1), Some(2), Some(3), Some(4), Some(5), Some(6), Some(0), Some(1), Some(2)),
+                    sequence_ids: vec!(Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(0), Some(1), Some(1), Some(1)),
+                    alignment_index: Default::default(),
                 }
             ),
 //            Truncation of both sentences (longest)
@@ -1599,8 +3129,19 @@ mod tests {
                     token_offsets: vec!(
                         Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 20, end: 25 }), Some(Offset { begin: 27, end: 32 }), Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 }), Some(Offset { begin: 3, end: 4 }), Some(Offset { begin: 4, end: 5 })
                     ),
+                    byte_offsets: vec!(
+                        Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 20, end: 25 }), Some(Offset { begin: 27, end: 32 }), Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 }), Some(Offset { begin: 3, end: 4 }), Some(Offset { begin: 4, end: 5 })
+                    ),
+                    utf16_offsets: vec!(
+                        Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 20, end: 25 }), Some(Offset { begin: 27, end: 32 }), Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 }), Some(Offset { begin: 3, end: 4 }), Some(Offset { begin: 4, end: 5 })
+                    ),
                     reference_offsets: vec!(vec!(0, 1, 2, 3, 4), vec!(6, 7, 8, 9, 10), vec!(13, 14, 15, 16, 17), vec!(20, 21, 22, 23, 24), vec!(27, 28, 29, 30, 31), vec!(0), vec!(1), vec!(2), vec!(3), vec!(4)),
                     mask: vec!(Mask::Unknown, Mask::None, Mask::None, Mask::None, Mask::None, Mask::Punctuation, Mask::Punctuation, Mask::Punctuation, Mask::Punctuation, Mask::Punctuation),
+                    attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1),
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec!(Some(0), Some(1), Some(2), Some(3), Some(4), Some(0), Some(1), Some(2), Some(3), Some(4)),
+                    sequence_ids: vec!(Some(0), Some(0), Some(0), Some(0), Some(0), Some(1), Some(1), Some(1), Some(1), Some(1)),
+                    alignment_index: Default::default(),
                 }
             )
         ];
@@ -1616,6 +3157,7 @@ mod tests {
                 10,
                 &truncation_strategy,
                 0,
+                &PaddingOptions::default(),
             );
             assert_eq!(
                 tokenized_input.token_ids.len(),
@@ -1630,7 +3172,8 @@ mod tests {
                 source_texts.clone(),
                 10,
                 &truncation_strategy,
-                0
+                0,
+                &PaddingOptions::default()
             ),
             expected_results
         );
@@ -1640,12 +3183,325 @@ mod tests {
                 source_texts.clone(),
                 10,
                 &truncation_strategy,
-                0
+                0,
+                &PaddingOptions::default()
             ),
             expected_results
         );
     }
 
+    #[test]
+    fn test_try_encode() {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let base_tokenizer: BaseTokenizer<BertVocab> =
+            BaseTokenizer::from_existing_vocab(vocab, true, true);
+        let source_texts = vec!["hello world!", "hello, unaffable world!"];
+
+        //        When & Then
+        assert_eq!(
+            base_tokenizer
+                .try_encode(
+                    "hello world!",
+                    None,
+                    10,
+                    &TruncationStrategy::DoNotTruncate,
+                    0,
+                    &PaddingOptions::default()
+                )
+                .unwrap(),
+            base_tokenizer.encode(
+                "hello world!",
+                None,
+                10,
+                &TruncationStrategy::DoNotTruncate,
+                0,
+                &PaddingOptions::default()
+            )
+        );
+        match base_tokenizer.try_encode(
+            "hello, unaffable world!",
+            None,
+            3,
+            &TruncationStrategy::DoNotTruncate,
+            0,
+            &PaddingOptions::default(),
+        ) {
+            Err(TokenizerError::SequenceTooLong {
+                sequence_length,
+                max_length,
+            }) => {
+                assert_eq!(sequence_length, 5);
+                assert_eq!(max_length, 3);
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        assert!(matches!(
+            base_tokenizer.try_encode(
+                "hello world!",
+                None,
+                2,
+                &TruncationStrategy::OnlySecond,
+                0,
+                &PaddingOptions::default()
+            ),
+            Err(TokenizerError::InvalidTruncationStrategy(_))
+        ));
+        assert!(matches!(
+            base_tokenizer.try_encode(
+                "hello world!",
+                Some("hello"),
+                2,
+                &TruncationStrategy::OnlySecond,
+                0,
+                &PaddingOptions::default()
+            ),
+            Err(TokenizerError::SequenceTooShort {
+                sequence_length: 1,
+                num_tokens_to_remove: 2
+            })
+        ));
+        assert_eq!(
+            Tokenizer::try_encode_list(
+                &base_tokenizer,
+                source_texts.clone(),
+                10,
+                &TruncationStrategy::DoNotTruncate,
+                0,
+                &PaddingOptions::default()
+            )
+            .unwrap()
+            .len(),
+            2
+        );
+        assert!(MultiThreadedTokenizer::try_encode_list(
+            &base_tokenizer,
+            source_texts.clone(),
+            4,
+            &TruncationStrategy::DoNotTruncate,
+            0,
+            &PaddingOptions::default()
+        )
+        .is_err());
+        assert!(MultiThreadedTokenizer::try_encode_pair_list(
+            &base_tokenizer,
+            vec![("hello world!", "hello")],
+            0,
+            &TruncationStrategy::OnlyFirst,
+            0,
+            &PaddingOptions::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_encode_with_padding() {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let base_tokenizer: BaseTokenizer<BertVocab> =
+            BaseTokenizer::from_existing_vocab(vocab, true, true);
+        let source_texts = vec!["hello world!", "hello, unaffable world!"];
+        let truncation_strategy = TruncationStrategy::LongestFirst;
+
+        //        When
+        let right_padded = Tokenizer::encode_list(
+            &base_tokenizer,
+            source_texts.clone(),
+            10,
+            &truncation_strategy,
+            0,
+            &PaddingOptions::new(PaddingStrategy::Longest, PaddingSide::Right),
+        );
+        let left_padded = MultiThreadedTokenizer::encode_list(
+            &base_tokenizer,
+            source_texts.clone(),
+            10,
+            &truncation_strategy,
+            0,
+            &PaddingOptions::new(PaddingStrategy::MaxLength, PaddingSide::Left),
+        );
+        let multiple_padded = Tokenizer::encode_list(
+            &base_tokenizer,
+            source_texts.clone(),
+            10,
+            &truncation_strategy,
+            0,
+            &PaddingOptions::new(PaddingStrategy::MultipleOf(4), PaddingSide::Right),
+        );
+        let not_padded = Tokenizer::encode_list(
+            &base_tokenizer,
+            source_texts.clone(),
+            10,
+            &truncation_strategy,
+            0,
+            &PaddingOptions::new(PaddingStrategy::DoNotPad, PaddingSide::Right),
+        );
+
+        //        Then
+        assert_eq!(right_padded[0].token_ids, vec!(0, 1, 3, 10, 10));
+        assert_eq!(right_padded[0].segment_ids, vec!(0, 0, 0, 0, 0));
+        assert_eq!(right_padded[0].special_tokens_mask, vec!(0, 0, 0, 1, 1));
+        assert_eq!(right_padded[0].attention_mask, vec!(1, 1, 1, 0, 0));
+        assert_eq!(
+            right_padded[0].token_offsets,
+            vec!(
+                Some(Offset { begin: 0, end: 5 }),
+                Some(Offset { begin: 6, end: 11 }),
+                Some(Offset { begin: 11, end: 12 }),
+                None,
+                None
+            )
+        );
+        assert_eq!(right_padded[0].mask[3..], [Mask::Special, Mask::Special]);
+        assert_eq!(right_padded[1].token_ids, vec!(0, 2, 2, 1, 3));
+        assert_eq!(right_padded[1].attention_mask, vec!(1, 1, 1, 1, 1));
+
+        assert_eq!(
+            left_padded[0].token_ids,
+            vec!(10, 10, 10, 10, 10, 10, 10, 0, 1, 3)
+        );
+        assert_eq!(
+            left_padded[0].attention_mask,
+            vec!(0, 0, 0, 0, 0, 0, 0, 1, 1, 1)
+        );
+        assert_eq!(left_padded[0].token_offsets[6], None);
+        assert_eq!(
+            left_padded[0].token_offsets[7],
+            Some(Offset { begin: 0, end: 5 })
+        );
+        assert_eq!(
+            left_padded[1].token_ids,
+            vec!(10, 10, 10, 10, 10, 0, 2, 2, 1, 3)
+        );
+
+        assert_eq!(
+            multiple_padded[0].token_ids,
+            vec!(0, 1, 3, 10, 10, 10, 10, 10)
+        );
+        assert_eq!(
+            multiple_padded[1].token_ids,
+            vec!(0, 2, 2, 1, 3, 10, 10, 10)
+        );
+
+        assert_eq!(not_padded[0].token_ids, vec!(0, 1, 3));
+        assert_eq!(not_padded[0].attention_mask, vec!(1, 1, 1));
+        assert_eq!(
+            base_tokenizer.encode(
+                "hello world!",
+                None,
+                5,
+                &truncation_strategy,
+                0,
+                &PaddingOptions::new(PaddingStrategy::MaxLength, PaddingSide::Right),
+            ),
+            right_padded[0].clone()
+        );
+        assert!(MultiThreadedTokenizer::try_encode_list(
+            &base_tokenizer,
+            source_texts,
+            3,
+            &TruncationStrategy::DoNotTruncate,
+            0,
+            &PaddingOptions::new(PaddingStrategy::MaxLength, PaddingSide::Right),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_byte_and_utf16_offsets() {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let base_tokenizer: BaseTokenizer<BertVocab> =
+            BaseTokenizer::from_existing_vocab(vocab, true, true);
+        let text_1 = "he\u{301}llo \u{1F30D} world!";
+        let text_2 = "\u{4E2D}\u{534E} hello";
+
+        //        When
+        let tokenized_input = base_tokenizer.encode(
+            text_1,
+            Some(text_2),
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+
+        //        Then
+        assert_eq!(tokenized_input.token_ids, vec!(0, 2, 1, 3, 7, 8, 0));
+        assert_eq!(
+            tokenized_input.token_offsets,
+            vec!(
+                Some(Offset::new(0, 6)),
+                Some(Offset::new(7, 8)),
+                Some(Offset::new(9, 14)),
+                Some(Offset::new(14, 15)),
+                Some(Offset::new(0, 1)),
+                Some(Offset::new(1, 2)),
+                Some(Offset::new(3, 8)),
+            )
+        );
+        assert_eq!(
+            tokenized_input.byte_offsets,
+            vec!(
+                Some(Offset::new(0, 7)),
+                Some(Offset::new(8, 12)),
+                Some(Offset::new(13, 18)),
+                Some(Offset::new(18, 19)),
+                Some(Offset::new(0, 3)),
+                Some(Offset::new(3, 6)),
+                Some(Offset::new(7, 12)),
+            )
+        );
+        assert_eq!(
+            tokenized_input.utf16_offsets,
+            vec!(
+                Some(Offset::new(0, 6)),
+                Some(Offset::new(7, 9)),
+                Some(Offset::new(10, 15)),
+                Some(Offset::new(15, 16)),
+                Some(Offset::new(0, 1)),
+                Some(Offset::new(1, 2)),
+                Some(Offset::new(3, 8)),
+            )
+        );
+        assert_eq!(&text_1[13..18], "world");
+        assert_eq!(
+            tokenized_input.offsets(&OffsetUnit::Utf16),
+            tokenized_input.utf16_offsets.as_slice()
+        );
+        assert_eq!(
+            base_tokenizer
+                .encode_pretokenized(
+                    &["\u{1F30D}", "world!"],
+                    None,
+                    false,
+                    128,
+                    &TruncationStrategy::LongestFirst,
+                    0,
+                    &PaddingOptions::default()
+                )
+                .byte_offsets,
+            vec!(
+                Some(Offset::new(0, 4)),
+                Some(Offset::new(0, 5)),
+                Some(Offset::new(5, 6)),
+            )
+        );
+        assert_eq!(
+            base_tokenizer
+                .encode(
+                    "\u{1F30D}",
+                    None,
+                    3,
+                    &TruncationStrategy::LongestFirst,
+                    0,
+                    &PaddingOptions::new(PaddingStrategy::MaxLength, PaddingSide::Right),
+                )
+                .utf16_offsets,
+            vec!(Some(Offset::new(0, 2)), None, None)
+        );
+    }
+
     #[test]
     fn test_decode() {
         //        Given
@@ -14,16 +14,24 @@
 
 use crate::preprocessing::error::TokenizerError;
 use crate::preprocessing::tokenizer::base_tokenizer::{
-    BaseTokenizer, Mask, MultiThreadedTokenizer, Offset, OffsetSize, Token, TokenRef, Tokenizer,
+    BaseTokenizer, MultiThreadedTokenizer, Token, TokenRef, Tokenizer,
 };
+use crate::preprocessing::tokenizer::normalizer::Normalizer;
+use crate::preprocessing::tokenizer::post_processor::{TemplatePiece, TemplateProcessing};
+use crate::preprocessing::tokenizer::pre_tokenizer::PreTokenizer;
 use crate::preprocessing::tokenizer::tokenization_utils::tokenize_wordpiece;
+use crate::preprocessing::tokenizer::tokenizer_json::{
+    check_default_components, post_processor_json, read_normalizer_flags, read_vocab,
+    restore_post_processor, tokenizer_json, word_piece_model_json, TokenizerJson,
+};
 use crate::preprocessing::vocab::base_vocab::Vocab;
 use crate::BertVocab;
+use serde_json::{json, Value};
+use std::io::Read;
 use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct BertTokenizer {
-    vocab: Arc<BertVocab>,
     base_tokenizer: BaseTokenizer<BertVocab>,
 }
 
@@ -34,12 +42,33 @@ impl BertTokenizer {
         strip_accents: bool,
     ) -> Result<BertTokenizer, TokenizerError> {
         let vocab = Arc::new(BertVocab::from_file(path)?);
-        let base_tokenizer =
-            BaseTokenizer::from_existing_vocab(vocab.clone(), lower_case, strip_accents);
-        Ok(BertTokenizer {
+        let base_tokenizer = BaseTokenizer::from_existing_vocab(vocab, lower_case, strip_accents);
+        Ok(BertTokenizer { base_tokenizer })
+    }
+
+    /// Create a new instance of a `BertTokenizer` from a reader for the vocabulary (e.g. a file or a
+    /// network stream), with the same content as the file expected by `from_file`.
+    pub fn from_reader<R: Read>(
+        vocab: R,
+        lower_case: bool,
+        strip_accents: bool,
+    ) -> Result<BertTokenizer, TokenizerError> {
+        let vocab = Arc::new(BertVocab::from_reader(vocab)?);
+        Ok(BertTokenizer::from_existing_vocab(
             vocab,
-            base_tokenizer,
-        })
+            lower_case,
+            strip_accents,
+        ))
+    }
+
+    /// Create a new instance of a `BertTokenizer` from a vocabulary held in memory (e.g. loaded
+    /// with `include_bytes!`).
+    pub fn from_bytes(
+        vocab: &[u8],
+        lower_case: bool,
+        strip_accents: bool,
+    ) -> Result<BertTokenizer, TokenizerError> {
+        BertTokenizer::from_reader(vocab, lower_case, strip_accents)
     }
 
     pub fn from_existing_vocab(
@@ -47,18 +76,42 @@ impl BertTokenizer {
         lower_case: bool,
         strip_accents: bool,
     ) -> BertTokenizer {
-        let base_tokenizer =
-            BaseTokenizer::from_existing_vocab(vocab.clone(), lower_case, strip_accents);
-        BertTokenizer {
-            vocab,
-            base_tokenizer,
-        }
+        let base_tokenizer = BaseTokenizer::from_existing_vocab(vocab, lower_case, strip_accents);
+        BertTokenizer { base_tokenizer }
     }
 }
 
 impl Tokenizer<BertVocab> for BertTokenizer {
     fn vocab(&self) -> &BertVocab {
-        self.vocab.as_ref()
+        Tokenizer::vocab(&self.base_tokenizer)
+    }
+
+    fn vocab_mut(&mut self) -> &mut BertVocab {
+        self.base_tokenizer.vocab_mut()
+    }
+
+    fn normalizer(&self) -> &dyn Normalizer {
+        self.base_tokenizer.normalizer()
+    }
+
+    fn set_normalizer(&mut self, normalizer: Box<dyn Normalizer>) {
+        self.base_tokenizer.set_normalizer(normalizer);
+    }
+
+    fn pre_tokenizer(&self) -> &dyn PreTokenizer {
+        self.base_tokenizer.pre_tokenizer()
+    }
+
+    fn set_pre_tokenizer(&mut self, pre_tokenizer: Box<dyn PreTokenizer>) {
+        self.base_tokenizer.set_pre_tokenizer(pre_tokenizer);
+    }
+
+    fn post_processor(&self) -> Option<&TemplateProcessing> {
+        self.base_tokenizer.post_processor()
+    }
+
+    fn post_processor_mut(&mut self) -> &mut Option<TemplateProcessing> {
+        self.base_tokenizer.post_processor_mut()
     }
 
     fn tokenize_to_tokens(&self, initial_token: TokenRef) -> Vec<Token> {
@@ -66,8 +119,7 @@ impl Tokenizer<BertVocab> for BertTokenizer {
         self.base_tokenizer
             .tokenize_to_tokens(initial_token)
             .into_iter()
-            .map(|token| tokenize_wordpiece(token.as_ref(), self.vocab.as_ref(), 100))
-            .flatten()
+            .flat_map(|token| tokenize_wordpiece(token.as_ref(), Tokenizer::vocab(self), 100))
             .collect()
     }
 
@@ -75,91 +127,94 @@ impl Tokenizer<BertVocab> for BertTokenizer {
         tokens.join(" ").replace(" ##", "").trim().to_owned()
     }
 
-    fn build_input_with_special_tokens(
-        &self,
-        tokens_1: Vec<i64>,
-        tokens_2: Option<Vec<i64>>,
-        offsets_1: Vec<Option<Offset>>,
-        offsets_2: Option<Vec<Option<Offset>>>,
-        original_offsets_1: Vec<Vec<OffsetSize>>,
-        original_offsets_2: Option<Vec<Vec<OffsetSize>>>,
-        mask_1: Vec<Mask>,
-        mask_2: Option<Vec<Mask>>,
-    ) -> (
-        Vec<i64>,
-        Vec<i8>,
-        Vec<i8>,
-        Vec<Option<Offset>>,
-        Vec<Vec<OffsetSize>>,
-        Vec<Mask>,
-    ) {
-        let mut output: Vec<i64> = vec![];
-        let mut token_segment_ids: Vec<i8> = vec![];
-        let mut special_tokens_mask: Vec<i8> = vec![];
-        let mut offsets: Vec<Option<Offset>> = vec![];
-        let mut original_offsets: Vec<Vec<OffsetSize>> = vec![];
-        let mut mask: Vec<Mask> = vec![];
-        special_tokens_mask.push(1);
-        special_tokens_mask.extend(vec![0; tokens_1.len()]);
-        special_tokens_mask.push(1);
-        token_segment_ids.extend(vec![0; tokens_1.len() + 2]);
-        output.push(self.vocab.token_to_id(BertVocab::cls_value()));
-        output.extend(tokens_1);
-        output.push(self.vocab.token_to_id(BertVocab::sep_value()));
-        offsets.push(None);
-        offsets.extend(offsets_1);
-        offsets.push(None);
-        original_offsets.push(vec![]);
-        original_offsets.extend(original_offsets_1);
-        original_offsets.push(vec![]);
-        mask.push(Mask::Special);
-        mask.extend(mask_1);
-        mask.push(Mask::Special);
-        if let Some(add_tokens) = tokens_2 {
-            let length = add_tokens.len();
-            special_tokens_mask.extend(vec![0; length]);
-            special_tokens_mask.push(1);
-            token_segment_ids.extend(vec![1; length + 1]);
-            output.extend(add_tokens);
-            output.push(self.vocab.token_to_id(BertVocab::sep_value()));
-            if let Some(add_offsets) = offsets_2 {
-                offsets.extend(add_offsets);
-            } else {
-                offsets.extend(vec![None; length]);
-            }
-            if let Some(add_original_offsets) = original_offsets_2 {
-                original_offsets.extend(add_original_offsets);
-            }
-            offsets.push(None);
-            original_offsets.push(vec![]);
-            if let Some(mask_2) = mask_2 {
-                mask.extend(mask_2)
-            } else {
-                mask.extend(vec![Mask::None; length]);
-            }
-            mask.push(Mask::Special);
-        }
-        (
-            output,
-            token_segment_ids,
-            special_tokens_mask,
-            offsets,
-            original_offsets,
-            mask,
+    fn default_post_processor(&self) -> TemplateProcessing {
+        let vocab = Tokenizer::vocab(self);
+        let (cls, sep) = (vocab.get_cls_value(), vocab.get_sep_value());
+        TemplateProcessing::from_pieces(
+            vec![
+                TemplatePiece::special_token(cls, 0),
+                TemplatePiece::sequence(0, 0),
+                TemplatePiece::special_token(sep, 0),
+            ],
+            vec![
+                TemplatePiece::special_token(cls, 0),
+                TemplatePiece::sequence(0, 0),
+                TemplatePiece::special_token(sep, 0),
+                TemplatePiece::sequence(1, 1),
+                TemplatePiece::special_token(sep, 1),
+            ],
         )
+        .unwrap()
     }
 }
 
 impl MultiThreadedTokenizer<BertVocab> for BertTokenizer {}
 
+impl TokenizerJson for BertTokenizer {
+    fn to_tokenizer_json(&self) -> Result<Value, TokenizerError> {
+        check_default_components(
+            self.base_tokenizer.custom_normalizer(),
+            self.base_tokenizer.custom_pre_tokenizer(),
+        )?;
+        let vocab = Tokenizer::vocab(self);
+        Ok(tokenizer_json(
+            vocab,
+            json!({
+                "type": "BertNormalizer",
+                "clean_text": false,
+                "handle_chinese_chars": true,
+                "strip_accents": self.base_tokenizer.strip_accents(),
+                "lowercase": self.base_tokenizer.lower_case()
+            }),
+            json!({"type": "BertPreTokenizer"}),
+            word_piece_model_json(vocab),
+            post_processor_json(self),
+            json!({"type": "WordPiece", "prefix": "##", "cleanup": true}),
+        ))
+    }
+
+    fn from_tokenizer_json(tokenizer_json: &Value) -> Result<BertTokenizer, TokenizerError> {
+        let vocab = read_vocab(
+            tokenizer_json,
+            |values,
+             indices,
+             special_token_map,
+             special_values,
+             special_indices,
+             added_values,
+             added_tokens| {
+                BertVocab {
+                    values,
+                    indices,
+                    special_token_map,
+                    special_values,
+                    special_indices,
+                    added_values,
+                    added_tokens,
+                }
+            },
+        )?;
+        let (lower_case, strip_accents) = read_normalizer_flags(tokenizer_json);
+        let mut tokenizer =
+            BertTokenizer::from_existing_vocab(Arc::new(vocab), lower_case, strip_accents);
+        restore_post_processor(&mut tokenizer, tokenizer_json)?;
+        Ok(tokenizer)
+    }
+}
+
 //==============================
 // Unit tests
 //==============================
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::preprocessing::tokenizer::base_tokenizer::{TokenizedInput, TruncationStrategy};
-    use crate::preprocessing::vocab::base_vocab::swap_key_values;
+    use crate::preprocessing::tokenizer::base_tokenizer::{
+        Mask, Offset, PaddingOptions, PaddingSide, PaddingStrategy, TokenizedInput,
+        TruncationStrategy,
+    };
+    use crate::preprocessing::tokenizer::normalizer::{Lowercase, Replace, Sequence};
+    use crate::preprocessing::tokenizer::pre_tokenizer::WhitespaceSplit;
+    use crate::preprocessing::vocab::base_vocab::{swap_key_values, SpecialTokenMap};
     use crate::BertVocab;
     use itertools::Itertools;
     use std::collections::HashMap;
@@ -202,9 +257,11 @@ mod tests {
         BertVocab {
             values,
             indices,
-            unknown_value: "[UNK]",
+            special_token_map: BertVocab::default_special_token_map(),
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
         }
     }
 
@@ -244,6 +301,172 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bert_tokenizer_from_bytes() -> anyhow::Result<()> {
+        //        Given
+        let vocab_bytes = b"[UNK]\n[CLS]\n[SEP]\n[PAD]\n[MASK]\nhello\nworld\n!";
+
+        //        When
+        let bert_tokenizer = BertTokenizer::from_bytes(vocab_bytes, true, true)?;
+
+        //        Then
+        assert_eq!(
+            bert_tokenizer.tokenize("Hello world!"),
+            vec!["hello", "world", "!"]
+        );
+        assert_eq!(Tokenizer::vocab(&bert_tokenizer).token_to_id("hello"), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bert_tokenizer_special_token_map() -> anyhow::Result<()> {
+        //        Given
+        let vocab_bytes = b"<unk>\n<s>\n</s>\n<pad>\nhello\nworld\n!";
+        let special_token_map = SpecialTokenMap {
+            pad_token: Some("<pad>".to_owned()),
+            cls_token: Some("<s>".to_owned()),
+            sep_token: Some("</s>".to_owned()),
+            ..SpecialTokenMap::new("<unk>")
+        };
+        let vocab =
+            BertVocab::from_reader_with_special_token_map(&vocab_bytes[..], special_token_map)?;
+        let bert_tokenizer = BertTokenizer::from_existing_vocab(Arc::new(vocab), true, true);
+
+        //        When
+        let encoded = bert_tokenizer.encode(
+            "Hello world!",
+            Some("Hello"),
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+
+        //        Then
+        assert_eq!(encoded.token_ids, vec![1, 4, 5, 6, 2, 4, 2]);
+        assert_eq!(encoded.special_tokens_mask, vec![1, 0, 0, 0, 1, 0, 1]);
+        assert_eq!(
+            bert_tokenizer.tokenize("Hello <s> world"),
+            vec!["hello", "<s>", "world"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bert_tokenizer_normalizer() {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let mut bert_tokenizer: BertTokenizer =
+            BertTokenizer::from_existing_vocab(vocab, false, false);
+
+        //        When
+        bert_tokenizer.set_normalizer(Box::new(Sequence::new(vec![
+            Box::new(Lowercase),
+            Box::new(Replace::new("\u{a1}", "!")),
+        ])));
+        let (tokens, offsets, _, _) = bert_tokenizer.tokenize_with_offsets("HELLO World\u{a1}");
+
+        //        Then
+        assert_eq!(tokens, vec!["hello", "world", "!"]);
+        assert_eq!(
+            offsets,
+            vec![
+                Some(Offset::new(0, 5)),
+                Some(Offset::new(6, 11)),
+                Some(Offset::new(11, 12))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bert_tokenizer_pre_tokenizer() {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let mut bert_tokenizer: BertTokenizer =
+            BertTokenizer::from_existing_vocab(vocab, true, true);
+        let default_tokens = bert_tokenizer.tokenize("hello [MASK] world! 中华");
+
+        //        When
+        bert_tokenizer.set_pre_tokenizer(Box::new(WhitespaceSplit));
+        let tokens = bert_tokenizer.tokenize("hello [MASK] world! 中华");
+
+        //        Then
+        assert_eq!(
+            default_tokens,
+            vec!["hello", "[MASK]", "world", "!", "中", "华"]
+        );
+        assert_eq!(tokens, vec!["hello", "[MASK]", "[UNK]", "[UNK]"]);
+    }
+
+    #[test]
+    fn test_bert_tokenizer_post_processor() -> anyhow::Result<()> {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let mut bert_tokenizer: BertTokenizer =
+            BertTokenizer::from_existing_vocab(vocab, true, true);
+        let post_processor =
+            TemplateProcessing::new("[CLS] $A [SEP]", "[CLS] $A [SEP] [SEP]:1 $B:1 [SEP]:1")?;
+
+        //        When
+        bert_tokenizer.set_post_processor(post_processor)?;
+        let encoded = bert_tokenizer.encode(
+            "hello world",
+            Some("hello"),
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+
+        //        Then
+        assert_eq!(encoded.token_ids, vec![4, 0, 1, 5, 5, 0, 5]);
+        assert_eq!(encoded.segment_ids, vec![0, 0, 0, 0, 1, 1, 1]);
+        assert_eq!(encoded.special_tokens_mask, vec![1, 0, 0, 1, 1, 0, 1]);
+        assert_eq!(
+            encoded.sequence_ids,
+            vec![None, Some(0), Some(0), None, None, Some(1), None]
+        );
+        assert!(bert_tokenizer
+            .set_post_processor(TemplateProcessing::new("<s> $A", "<s> $A $B")?)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bert_tokenizer_added_tokens() {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let mut bert_tokenizer: BertTokenizer =
+            BertTokenizer::from_existing_vocab(vocab.clone(), true, true);
+
+        //        When
+        bert_tokenizer.add_tokens(&["Covid19"]);
+        bert_tokenizer.add_special_tokens(&["<PRODUCT>"]);
+        let encoded = bert_tokenizer.encode(
+            "Hello <PRODUCT> Covid19!",
+            None,
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+
+        //        Then
+        assert_eq!(
+            bert_tokenizer.tokenize("Hello <PRODUCT> Covid19!"),
+            vec!["hello", "<PRODUCT>", "Covid19", "!"]
+        );
+        assert_eq!(
+            encoded.mask[1..5],
+            [Mask::None, Mask::Special, Mask::Added, Mask::Punctuation]
+        );
+        assert_eq!(
+            bert_tokenizer.decode(encoded.token_ids, true, true),
+            "hello Covid19!"
+        );
+        assert!(!vocab.values.contains_key("<PRODUCT>"));
+    }
+
     #[test]
     fn test_bert_tokenizer_no_lower_casing() {
         //        Given
@@ -303,6 +526,22 @@ mod tests {
                         Some(Offset { begin: 17, end: 18 }),
                         None,
                     ],
+                    byte_offsets: vec![
+                        None,
+                        Some(Offset { begin: 0, end: 5 }),
+                        Some(Offset { begin: 5, end: 11 }),
+                        Some(Offset { begin: 12, end: 17 }),
+                        Some(Offset { begin: 17, end: 18 }),
+                        None,
+                    ],
+                    utf16_offsets: vec![
+                        None,
+                        Some(Offset { begin: 0, end: 5 }),
+                        Some(Offset { begin: 5, end: 11 }),
+                        Some(Offset { begin: 12, end: 17 }),
+                        Some(Offset { begin: 17, end: 18 }),
+                        None,
+                    ],
                     reference_offsets: vec![
                         vec![],
                         vec![0, 1, 2, 3, 4],
@@ -319,6 +558,11 @@ mod tests {
                         Mask::Punctuation,
                         Mask::Special,
                     ],
+                    attention_mask: vec![1, 1, 1, 1, 1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![None, Some(0), Some(1), Some(2), Some(3), None],
+                    sequence_ids: vec![None, Some(0), Some(0), Some(0), Some(0), None],
+                    alignment_index: Default::default(),
                 },
             ),
             (
@@ -340,6 +584,28 @@ mod tests {
                         Some(Offset { begin: 22, end: 23 }),
                         None,
                     ],
+                    byte_offsets: vec![
+                        None,
+                        Some(Offset { begin: 0, end: 5 }),
+                        Some(Offset { begin: 5, end: 6 }),
+                        Some(Offset { begin: 7, end: 10 }),
+                        Some(Offset { begin: 10, end: 13 }),
+                        Some(Offset { begin: 13, end: 16 }),
+                        Some(Offset { begin: 17, end: 22 }),
+                        Some(Offset { begin: 22, end: 23 }),
+                        None,
+                    ],
+                    utf16_offsets: vec![
+                        None,
+                        Some(Offset { begin: 0, end: 5 }),
+                        Some(Offset { begin: 5, end: 6 }),
+                        Some(Offset { begin: 7, end: 10 }),
+                        Some(Offset { begin: 10, end: 13 }),
+                        Some(Offset { begin: 13, end: 16 }),
+                        Some(Offset { begin: 17, end: 22 }),
+                        Some(Offset { begin: 22, end: 23 }),
+                        None,
+                    ],
                     reference_offsets: vec![
                         vec![],
                         vec![0, 1, 2, 3, 4],
@@ -362,6 +628,31 @@ mod tests {
                         Mask::Punctuation,
                         Mask::Special,
                     ],
+                    attention_mask: vec![1, 1, 1, 1, 1, 1, 1, 1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![
+                        None,
+                        Some(0),
+                        Some(1),
+                        Some(2),
+                        Some(2),
+                        Some(2),
+                        Some(3),
+                        Some(4),
+                        None,
+                    ],
+                    sequence_ids: vec![
+                        None,
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        None,
+                    ],
+                    alignment_index: Default::default(),
                 },
             ),
             (
@@ -386,6 +677,34 @@ mod tests {
                         Some(Offset { begin: 19, end: 23 }),
                         None,
                     ],
+                    byte_offsets: vec![
+                        None,
+                        Some(Offset { begin: 0, end: 5 }),
+                        Some(Offset { begin: 5, end: 8 }),
+                        Some(Offset { begin: 8, end: 11 }),
+                        Some(Offset { begin: 11, end: 14 }),
+                        Some(Offset { begin: 14, end: 17 }),
+                        Some(Offset { begin: 17, end: 20 }),
+                        Some(Offset { begin: 20, end: 23 }),
+                        Some(Offset { begin: 23, end: 26 }),
+                        Some(Offset { begin: 27, end: 32 }),
+                        Some(Offset { begin: 33, end: 37 }),
+                        None,
+                    ],
+                    utf16_offsets: vec![
+                        None,
+                        Some(Offset { begin: 0, end: 5 }),
+                        Some(Offset { begin: 5, end: 6 }),
+                        Some(Offset { begin: 6, end: 7 }),
+                        Some(Offset { begin: 7, end: 8 }),
+                        Some(Offset { begin: 8, end: 9 }),
+                        Some(Offset { begin: 9, end: 10 }),
+                        Some(Offset { begin: 10, end: 11 }),
+                        Some(Offset { begin: 11, end: 12 }),
+                        Some(Offset { begin: 13, end: 18 }),
+                        Some(Offset { begin: 19, end: 23 }),
+                        None,
+                    ],
                     reference_offsets: vec![
                         vec![],
                         vec![0, 1, 2, 3, 4],
@@ -414,6 +733,37 @@ mod tests {
                         Mask::Unknown,
                         Mask::Special,
                     ],
+                    attention_mask: vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![
+                        None,
+                        Some(0),
+                        Some(1),
+                        Some(2),
+                        Some(3),
+                        Some(4),
+                        Some(5),
+                        Some(6),
+                        Some(7),
+                        Some(8),
+                        Some(9),
+                        None,
+                    ],
+                    sequence_ids: vec![
+                        None,
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        None,
+                    ],
+                    alignment_index: Default::default(),
                 },
             ),
         ];
@@ -423,8 +773,14 @@ mod tests {
 
         //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
-            let tokenized_input =
-                bert_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0);
+            let tokenized_input = bert_tokenizer.encode(
+                source_text,
+                None,
+                128,
+                &truncation_strategy,
+                0,
+                &PaddingOptions::default(),
+            );
             assert_eq!(
                 tokenized_input.token_ids.len(),
                 tokenized_input.token_offsets.len(),
@@ -438,7 +794,8 @@ mod tests {
                 source_texts.clone(),
                 128,
                 &truncation_strategy,
-                0
+                0,
+                &PaddingOptions::default()
             ),
             expected_results
         );
@@ -448,7 +805,8 @@ mod tests {
                 source_texts.clone(),
                 128,
                 &truncation_strategy,
-                0
+                0,
+                &PaddingOptions::default()
             ),
             expected_results
         );
@@ -473,8 +831,19 @@ mod tests {
                     token_offsets: vec!(
                         None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), None, Some(Offset { begin: 0, end: 4 }), Some(Offset { begin: 5, end: 7 }), Some(Offset { begin: 8, end: 11 }), Some(Offset { begin: 12, end: 18 }), Some(Offset { begin: 19, end: 27 }), None
                     ),
+                    byte_offsets: vec!(
+                        None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), None, Some(Offset { begin: 0, end: 4 }), Some(Offset { begin: 5, end: 7 }), Some(Offset { begin: 8, end: 11 }), Some(Offset { begin: 12, end: 18 }), Some(Offset { begin: 19, end: 27 }), None
+                    ),
+                    utf16_offsets: vec!(
+                        None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), None, Some(Offset { begin: 0, end: 4 }), Some(Offset { begin: 5, end: 7 }), Some(Offset { begin: 8, end: 11 }), Some(Offset { begin: 12, end: 18 }), Some(Offset { begin: 19, end: 27 }), None
+                    ),
                     reference_offsets: vec!(vec!(), vec!(0, 1, 2, 3, 4), vec!(6, 7, 8, 9, 10), vec!(), vec!(0, 1, 2, 3), vec!(5, 6), vec!(8, 9, 10), vec!(12, 13, 14, 15, 16, 17), vec!(19, 20, 21, 22, 23, 24, 25, 26), vec!()),
                     mask: vec!(Mask::Special, Mask::None, Mask::None, Mask::Special, Mask::Unknown, Mask::Unknown, Mask::Unknown, Mask::Unknown, Mask::Unknown, Mask::Special),
+                    attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1),
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec!(None, Some(0), Some(1), None, Some(0), Some(1), Some(2), Some(3), Some(4), None),
+                    sequence_ids: vec!(None, Some(0), Some(0), None, Some(1), Some(1), Some(1), Some(1), Some(1), None),
+                    alignment_index: Default::default(),
                 }
             ),
 //            Truncation of sentence 2 (longest)
@@ -489,8 +858,19 @@ mod tests {
                     token_offsets: vec!(
                         None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), None, Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 5 }), Some(Offset { begin: 6, end: 8 }), Some(Offset { begin: 9, end: 12 }), Some(Offset { begin: 13, end: 19 }), None
                     ),
+                    byte_offsets: vec!(
+                        None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), None, Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 5 }), Some(Offset { begin: 6, end: 8 }), Some(Offset { begin: 9, end: 12 }), Some(Offset { begin: 13, end: 19 }), None
+                    ),
+                    utf16_offsets: vec!(
+                        None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), None, Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 5 }), Some(Offset { begin: 6, end: 8 }), Some(Offset { begin: 9, end: 12 }), Some(Offset { begin: 13, end: 19 }), None
+                    ),
                     reference_offsets: vec!(vec!(), vec!(0, 1, 2, 3, 4), vec!(6, 7, 8, 9, 10), vec!(), vec!(0), vec!(1, 2, 3, 4), vec!(6, 7), vec!(9, 10, 11), vec!(13, 14, 15, 16, 17, 18), vec!()),
                     mask: vec!(Mask::Special, Mask::None, Mask::None, Mask::Special, Mask::Punctuation, Mask::Unknown, Mask::Unknown, Mask::Unknown, Mask::Unknown, Mask::Special),
+                    attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1),
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec!(None, Some(0), Some(1), None, Some(0), Some(1), Some(2), Some(3), Some(4), None),
+                    sequence_ids: vec!(None, Some(0), Some(0), None, Some(1), Some(1), Some(1), Some(1), Some(1), None),
+                    alignment_index: Default::default(),
                 }
             ),
 //            Truncation of sentence 1 (longest)
@@ -505,8 +885,19 @@ mod tests {
                     token_offsets: vec!(
                         None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 20, end: 25 }), None, Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 }), None
                     ),
+                    byte_offsets: vec!(
+                        None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 20, end: 25 }), None, Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 }), None
+                    ),
+                    utf16_offsets: vec!(
+                        None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), Some(Offset { begin: 20, end: 25 }), None, Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 }), None
+                    ),
                     reference_offsets: vec!(vec!(), vec!(0, 1, 2, 3, 4), vec!(6, 7, 8, 9, 10), vec!(13, 14, 15, 16, 17), vec!(20, 21, 22, 23, 24), vec!(), vec!(0), vec!(1), vec!(2), vec!()),
                     mask: vec!(Mask::Special, Mask::Unknown, Mask::None, Mask::None, Mask::None, Mask::Special, Mask::Punctuation, Mask::Punctuation, Mask::Punctuation, Mask::Special),
+                    attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1),
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec!(None, Some(0), Some(1), Some(2), Some(3), None, Some(0), Some(1), Some(2), None),
+                    sequence_ids: vec!(None, Some(0), Some(0), Some(0), Some(0), None, Some(1), Some(1), Some(1), None),
+                    alignment_index: Default::default(),
                 }
             ),
 //            Truncation of both sentences (longest)
@@ -521,8 +912,19 @@ mod tests {
                     token_offsets: vec!(
                         None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), None, Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 }), Some(Offset { begin: 3, end: 4 }), None
                     ),
+                    byte_offsets: vec!(
+                        None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), None, Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 }), Some(Offset { begin: 3, end: 4 }), None
+                    ),
+                    utf16_offsets: vec!(
+                        None, Some(Offset { begin: 0, end: 5 }), Some(Offset { begin: 6, end: 11 }), Some(Offset { begin: 13, end: 18 }), None, Some(Offset { begin: 0, end: 1 }), Some(Offset { begin: 1, end: 2 }), Some(Offset { begin: 2, end: 3 }), Some(Offset { begin: 3, end: 4 }), None
+                    ),
                     reference_offsets: vec!(vec!(), vec!(0, 1, 2, 3, 4), vec!(6, 7, 8, 9, 10), vec!(13, 14, 15, 16, 17), vec!(), vec!(0), vec!(1), vec!(2), vec!(3), vec!()),
                     mask: vec!(Mask::Special, Mask::Unknown, Mask::None, Mask::None, Mask::Special, Mask::Punctuation, Mask::Punctuation, Mask::Punctuation, Mask::Punctuation, Mask::Special),
+                    attention_mask: vec!(1, 1, 1, 1, 1, 1, 1, 1, 1, 1),
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec!(None, Some(0), Some(1), Some(2), None, Some(0), Some(1), Some(2), Some(3), None),
+                    sequence_ids: vec!(None, Some(0), Some(0), Some(0), None, Some(1), Some(1), Some(1), Some(1), None),
+                    alignment_index: Default::default(),
                 }
             )
         ];
@@ -538,7 +940,8 @@ mod tests {
                     Some(source_text.1),
                     10,
                     &truncation_strategy,
-                    0
+                    0,
+                    &PaddingOptions::default()
                 ),
                 *expected_result
             );
@@ -549,7 +952,8 @@ mod tests {
                 source_texts.clone(),
                 10,
                 &truncation_strategy,
-                0
+                0,
+                &PaddingOptions::default()
             ),
             expected_results
         );
@@ -559,13 +963,357 @@ mod tests {
                 source_texts.clone(),
                 10,
                 &truncation_strategy,
-                0
+                0,
+                &PaddingOptions::default()
             ),
             expected_results
         );
         Ok(())
     }
 
+    #[test]
+    fn test_encode_with_overflow() -> anyhow::Result<()> {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab, true, true);
+
+        //        When
+        let windows = bert_tokenizer.encode_with_overflow(
+            "hello world",
+            Some("hello world ! hello world !"),
+            8,
+            1,
+            &PaddingOptions::default(),
+        )?;
+
+        //        Then
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[0].token_ids, vec!(4, 0, 1, 5, 0, 1, 3, 5));
+        assert_eq!(windows[1].token_ids, vec!(4, 0, 1, 5, 3, 0, 1, 5));
+        assert_eq!(windows[2].token_ids, vec!(4, 0, 1, 5, 1, 3, 5));
+        assert_eq!(windows[0].overflowing_tokens, vec!(3, 0, 1, 3));
+        assert_eq!(windows[1].overflowing_tokens, vec!(1, 3));
+        assert!(windows[2].overflowing_tokens.is_empty());
+        assert_eq!(
+            windows
+                .iter()
+                .map(|window| window.num_truncated_tokens)
+                .collect::<Vec<usize>>(),
+            vec!(3, 1, 0)
+        );
+        assert_eq!(windows[1].segment_ids, vec!(0, 0, 0, 0, 1, 1, 1, 1));
+        assert_eq!(
+            windows[1].word_ids,
+            vec!(
+                None,
+                Some(0),
+                Some(1),
+                None,
+                Some(2),
+                Some(3),
+                Some(4),
+                None
+            )
+        );
+        assert_eq!(
+            windows[1].sequence_ids,
+            vec!(
+                None,
+                Some(0),
+                Some(0),
+                None,
+                Some(1),
+                Some(1),
+                Some(1),
+                None
+            )
+        );
+        assert_eq!(windows[1].special_tokens_mask, vec!(1, 0, 0, 1, 0, 0, 0, 1));
+        assert_eq!(
+            windows[1].token_offsets,
+            vec!(
+                None,
+                Some(Offset { begin: 0, end: 5 }),
+                Some(Offset { begin: 6, end: 11 }),
+                None,
+                Some(Offset { begin: 12, end: 13 }),
+                Some(Offset { begin: 14, end: 19 }),
+                Some(Offset { begin: 20, end: 25 }),
+                None
+            )
+        );
+        assert_eq!(
+            windows[1].reference_offsets[4..7],
+            [vec!(12), vec!(14, 15, 16, 17, 18), vec!(20, 21, 22, 23, 24)]
+        );
+        assert_eq!(
+            windows[1].mask[3..],
+            [
+                Mask::Special,
+                Mask::Punctuation,
+                Mask::None,
+                Mask::None,
+                Mask::Special
+            ]
+        );
+        assert!(windows
+            .iter()
+            .all(|window| window.overflow_to_sample_mapping == Some(0)));
+
+        let single_windows = bert_tokenizer.encode_with_overflow(
+            "hello world ! hello",
+            None,
+            4,
+            0,
+            &PaddingOptions::default(),
+        )?;
+        assert_eq!(
+            single_windows
+                .iter()
+                .map(|window| window.token_ids.clone())
+                .collect::<Vec<Vec<i64>>>(),
+            vec!(vec!(4, 0, 1, 5), vec!(4, 3, 0, 5))
+        );
+
+        let batch_windows = Tokenizer::encode_pair_list_with_overflow(
+            &bert_tokenizer,
+            vec![
+                ("hello world", "hello world ! hello world !"),
+                ("hello", "world"),
+            ],
+            8,
+            1,
+            &PaddingOptions::new(PaddingStrategy::Longest, PaddingSide::Right),
+        )?;
+        assert_eq!(
+            batch_windows
+                .iter()
+                .map(|window| window.overflow_to_sample_mapping)
+                .collect::<Vec<Option<usize>>>(),
+            vec!(Some(0), Some(0), Some(0), Some(1))
+        );
+        assert_eq!(batch_windows[3].token_ids, vec!(4, 0, 5, 1, 5, 10, 10, 10));
+        assert_eq!(
+            batch_windows[3].attention_mask,
+            vec!(1, 1, 1, 1, 1, 0, 0, 0)
+        );
+        assert_eq!(
+            MultiThreadedTokenizer::encode_pair_list_with_overflow(
+                &bert_tokenizer,
+                vec![
+                    ("hello world", "hello world ! hello world !"),
+                    ("hello", "world"),
+                ],
+                8,
+                1,
+                &PaddingOptions::new(PaddingStrategy::Longest, PaddingSide::Right),
+            )?,
+            batch_windows
+        );
+
+        assert!(bert_tokenizer
+            .encode_with_overflow(
+                "hello world",
+                Some("hello world ! hello world !"),
+                8,
+                3,
+                &PaddingOptions::default()
+            )
+            .is_err());
+        assert!(bert_tokenizer
+            .encode_with_overflow(
+                "hello world ! hello",
+                Some("world"),
+                4,
+                0,
+                &PaddingOptions::default()
+            )
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_offset_lookups() {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab, true, true);
+        let tokenized_input = bert_tokenizer.encode(
+            "hello world",
+            Some("Hello unaffable world!"),
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+
+        //        When & Then
+        assert_eq!(
+            tokenized_input.token_ids,
+            vec!(4, 0, 1, 5, 0, 11, 12, 13, 1, 3, 5)
+        );
+        assert_eq!(tokenized_input.token_to_chars(0), None);
+        assert_eq!(tokenized_input.token_to_chars(3), None);
+        assert_eq!(tokenized_input.token_to_chars(2), Some(Offset::new(6, 11)));
+        assert_eq!(tokenized_input.token_to_chars(6), Some(Offset::new(9, 12)));
+        assert_eq!(tokenized_input.token_to_chars(42), None);
+
+        assert_eq!(tokenized_input.char_to_token(7, 0), Some(2));
+        assert_eq!(tokenized_input.char_to_token(7, 1), Some(5));
+        assert_eq!(tokenized_input.char_to_token(13, 1), Some(7));
+        assert_eq!(tokenized_input.char_to_token(21, 1), Some(9));
+        assert_eq!(tokenized_input.char_to_token(5, 1), None);
+        assert_eq!(tokenized_input.char_to_token(30, 1), None);
+        assert_eq!(tokenized_input.char_to_token(0, 2), None);
+
+        assert_eq!(tokenized_input.word_to_chars(0, 0), Some(Offset::new(0, 5)));
+        assert_eq!(
+            tokenized_input.word_to_chars(1, 1),
+            Some(Offset::new(6, 15))
+        );
+        assert_eq!(
+            tokenized_input.word_to_chars(3, 1),
+            Some(Offset::new(21, 22))
+        );
+        assert_eq!(tokenized_input.word_to_chars(4, 1), None);
+    }
+
+    #[test]
+    fn test_encode_pretokenized() {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab, true, true);
+        let words_1 = ["Hello", "unaffable"];
+        let words_2 = ["world", "!"];
+
+        //        When
+        let tokenized_input = bert_tokenizer.encode_pretokenized(
+            &words_1,
+            Some(&words_2),
+            false,
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+        let truncated_input = bert_tokenizer.encode_pretokenized(
+            &words_1,
+            Some(&words_2),
+            false,
+            7,
+            &TruncationStrategy::OnlyFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+        let padded_input = bert_tokenizer.encode_pretokenized(
+            &words_1,
+            None,
+            false,
+            8,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::new(PaddingStrategy::MaxLength, PaddingSide::Right),
+        );
+
+        //        Then
+        assert_eq!(
+            tokenized_input.token_ids,
+            vec!(4, 0, 11, 12, 13, 5, 1, 3, 5)
+        );
+        assert_eq!(tokenized_input.segment_ids, vec!(0, 0, 0, 0, 0, 0, 1, 1, 1));
+        assert_eq!(
+            tokenized_input.token_offsets,
+            vec!(
+                None,
+                Some(Offset::new(0, 5)),
+                Some(Offset::new(0, 3)),
+                Some(Offset::new(3, 6)),
+                Some(Offset::new(6, 9)),
+                None,
+                Some(Offset::new(0, 5)),
+                Some(Offset::new(0, 1)),
+                None
+            )
+        );
+        assert_eq!(
+            tokenized_input.word_ids,
+            vec!(
+                None,
+                Some(0),
+                Some(1),
+                Some(1),
+                Some(1),
+                None,
+                Some(0),
+                Some(1),
+                None
+            )
+        );
+        assert_eq!(
+            bert_tokenizer.encode_pretokenized(
+                &words_1,
+                Some(&words_2),
+                true,
+                128,
+                &TruncationStrategy::LongestFirst,
+                0,
+                &PaddingOptions::default(),
+            ),
+            tokenized_input
+        );
+        assert_eq!(truncated_input.token_ids, vec!(4, 0, 11, 5, 1, 3, 5));
+        assert_eq!(
+            truncated_input.word_ids,
+            vec!(None, Some(0), Some(1), None, Some(0), Some(1), None)
+        );
+        assert_eq!(padded_input.token_ids, vec!(4, 0, 11, 12, 13, 5, 10, 10));
+        assert_eq!(padded_input.attention_mask, vec!(1, 1, 1, 1, 1, 1, 0, 0));
+        assert_eq!(
+            padded_input.word_ids,
+            vec!(None, Some(0), Some(1), Some(1), Some(1), None, None, None)
+        );
+        assert_eq!(padded_input.token_offsets[6], None);
+        assert!(bert_tokenizer
+            .try_encode_pretokenized(
+                &words_1,
+                None,
+                false,
+                3,
+                &TruncationStrategy::DoNotTruncate,
+                0,
+                &PaddingOptions::default()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_encode_sequence_too_long() {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let bert_tokenizer: BertTokenizer = BertTokenizer::from_existing_vocab(vocab, true, true);
+
+        //        When
+        let result = bert_tokenizer.try_encode(
+            "hello world!",
+            Some("hello world!"),
+            5,
+            &TruncationStrategy::DoNotTruncate,
+            0,
+            &PaddingOptions::default(),
+        );
+
+        //        Then
+        match result {
+            Err(TokenizerError::SequenceTooLong {
+                sequence_length,
+                max_length,
+            }) => {
+                assert_eq!(sequence_length, 9);
+                assert_eq!(max_length, 5);
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
     #[test]
     fn test_decode() {
         //        Given
@@ -0,0 +1,362 @@
+// Copyright 2019-2020 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+const NUM_SHARDS: usize = 16;
+
+///Default maximum number of words held by a `BpeCache`
+pub const DEFAULT_BPE_CACHE_CAPACITY: usize = 10_000;
+
+///Output of a BPE function for a word: the list of sub-tokens and their length in characters
+pub type BpeCacheEntry = (Vec<String>, Vec<usize>);
+
+#[derive(Debug)]
+struct LruShard {
+    entries: HashMap<String, (BpeCacheEntry, u64)>,
+    recency: BTreeMap<u64, String>,
+    counter: u64,
+    capacity: usize,
+}
+
+impl LruShard {
+    fn new(capacity: usize) -> LruShard {
+        LruShard {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            counter: 0,
+            capacity,
+        }
+    }
+
+    fn touch(&mut self, key: &str) -> Option<BpeCacheEntry> {
+        let counter = self.counter;
+        let (value, last_used) = self.entries.get_mut(key)?;
+        self.recency.remove(last_used);
+        *last_used = counter;
+        self.recency.insert(counter, key.to_owned());
+        self.counter += 1;
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, key: String, value: BpeCacheEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some((_, last_used)) = self.entries.remove(&key) {
+            self.recency.remove(&last_used);
+        } else if self.entries.len() >= self.capacity {
+            let oldest = self.recency.keys().next().cloned();
+            if let Some(oldest) = oldest {
+                if let Some(evicted_key) = self.recency.remove(&oldest) {
+                    self.entries.remove(&evicted_key);
+                }
+            }
+        }
+        self.recency.insert(self.counter, key.clone());
+        self.entries.insert(key, (value, self.counter));
+        self.counter += 1;
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+}
+
+/// # BPE cache
+/// Thread-safe, capacity-bounded cache storing the output of the BPE merges for words already processed.
+/// The cache is split in shards protected by their own lock, limiting contention when a
+/// tokenizer is shared between threads (e.g. with the `MultiThreadedTokenizer` methods).
+/// Each shard evicts its least recently used entry once full, so that the total number of cached
+/// words never exceeds the capacity set at construction.
+///
+/// Hit and miss counters are maintained to help sizing the cache, and the cache can be cleared or
+/// disabled at runtime.
+#[derive(Debug)]
+pub struct BpeCache {
+    shards: Vec<Mutex<LruShard>>,
+    capacity: usize,
+    enabled: AtomicBool,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl BpeCache {
+    /// Create a new cache holding up to `DEFAULT_BPE_CACHE_CAPACITY` words
+    pub fn new() -> BpeCache {
+        BpeCache::with_capacity(DEFAULT_BPE_CACHE_CAPACITY)
+    }
+
+    /// Create a new cache holding up to `capacity` words. A capacity of 0 results in a cache
+    /// that never stores any entry.
+    ///
+    /// # Parameters
+    /// - capacity (`usize`): maximum number of words held by the cache
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_tokenizers::preprocessing::tokenizer::bpe_cache::BpeCache;
+    /// let cache = BpeCache::with_capacity(1000);
+    /// assert_eq!(cache.capacity(), 1000);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> BpeCache {
+        let num_shards = capacity.clamp(1, NUM_SHARDS);
+        let shards = (0..num_shards)
+            .map(|shard_index| {
+                let shard_capacity =
+                    capacity / num_shards + (shard_index < capacity % num_shards) as usize;
+                Mutex::new(LruShard::new(shard_capacity))
+            })
+            .collect();
+        BpeCache {
+            shards,
+            capacity,
+            enabled: AtomicBool::new(true),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn shard(&self, key: &str) -> &Mutex<LruShard> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % self.shards.len()]
+    }
+
+    ///Returns a copy of the cached BPE output for `key` if present, marking it as recently used
+    pub fn get(&self, key: &str) -> Option<BpeCacheEntry> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let value = self.shard(key).lock().unwrap().touch(key);
+        if value.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        value
+    }
+
+    ///Stores the BPE output for `key`, evicting the least recently used entry of its shard if full
+    pub fn insert(&self, key: String, value: BpeCacheEntry) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.shard(&key).lock().unwrap().insert(key, value);
+    }
+
+    ///Removes all entries from the cache. Hit and miss counters are left untouched.
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            shard.lock().unwrap().clear();
+        }
+    }
+
+    ///Enables or disables the cache. A disabled cache is cleared, does not store new entries and
+    ///does not update its counters.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.clear();
+        }
+    }
+
+    ///Returns `true` if the cache is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    ///Maximum number of words held by the cache
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    ///Number of words currently held by the cache
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().entries.len())
+            .sum()
+    }
+
+    ///Returns `true` if the cache holds no entry
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///Number of lookups that found an entry in the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    ///Number of lookups that did not find an entry in the cache
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    ///Resets the hit and miss counters to 0
+    pub fn reset_stats(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Default for BpeCache {
+    fn default() -> Self {
+        BpeCache::new()
+    }
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_get_insert() {
+        //        Given
+        let cache = BpeCache::new();
+
+        //        When
+        cache.insert(
+            "hello".to_owned(),
+            (vec!["hel".to_owned(), "lo".to_owned()], vec![3, 2]),
+        );
+
+        //        Then
+        assert_eq!(
+            cache.get("hello"),
+            Some((vec!["hel".to_owned(), "lo".to_owned()], vec![3, 2]))
+        );
+        assert_eq!(cache.get("world"), None);
+    }
+
+    #[test]
+    fn test_concurrent_access() {
+        //        Given
+        let cache = Arc::new(BpeCache::new());
+
+        //        When
+        let handles = (0..8)
+            .map(|thread_index| {
+                let cache = cache.clone();
+                thread::spawn(move || {
+                    for word_index in 0..100 {
+                        let key = format!("{}_{}", thread_index, word_index);
+                        cache.insert(key.clone(), (vec![key], vec![word_index]));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        //        Then
+        for thread_index in 0..8 {
+            for word_index in 0..100 {
+                let key = format!("{}_{}", thread_index, word_index);
+                assert_eq!(cache.get(&key), Some((vec![key.clone()], vec![word_index])));
+            }
+        }
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        //        Given
+        let cache = BpeCache::with_capacity(2);
+        let entry = |word: &str| (vec![word.to_owned()], vec![word.chars().count()]);
+
+        //        When
+        cache.insert("first".to_owned(), entry("first"));
+        cache.insert("second".to_owned(), entry("second"));
+        let _ = cache.get("first");
+        cache.insert("third".to_owned(), entry("third"));
+
+        //        Then
+        assert!(cache.len() <= cache.capacity());
+        assert!(cache.get("third").is_some());
+    }
+
+    #[test]
+    fn test_lru_eviction_single_shard() {
+        //        Given
+        let mut shard = LruShard::new(2);
+        let entry = |word: &str| (vec![word.to_owned()], vec![word.chars().count()]);
+
+        //        When
+        shard.insert("first".to_owned(), entry("first"));
+        shard.insert("second".to_owned(), entry("second"));
+        let _ = shard.touch("first");
+        shard.insert("third".to_owned(), entry("third"));
+
+        //        Then
+        assert_eq!(shard.touch("first"), Some(entry("first")));
+        assert_eq!(shard.touch("second"), None);
+        assert_eq!(shard.touch("third"), Some(entry("third")));
+        assert_eq!(shard.entries.len(), shard.recency.len());
+    }
+
+    #[test]
+    fn test_statistics_clear_and_disable() {
+        //        Given
+        let cache = BpeCache::with_capacity(10);
+        cache.insert("hello".to_owned(), (vec!["hello".to_owned()], vec![5]));
+
+        //        When
+        let _ = cache.get("hello");
+        let _ = cache.get("hello");
+        let _ = cache.get("world");
+
+        //        Then
+        assert_eq!(cache.hits(), 2);
+        assert_eq!(cache.misses(), 1);
+        assert_eq!(cache.len(), 1);
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.hits(), 2);
+
+        cache.set_enabled(false);
+        cache.insert("hello".to_owned(), (vec!["hello".to_owned()], vec![5]));
+        assert_eq!(cache.get("hello"), None);
+        assert!(cache.is_empty());
+        assert_eq!(cache.misses(), 1);
+
+        cache.set_enabled(true);
+        cache.reset_stats();
+        assert_eq!(cache.hits(), 0);
+        assert_eq!(cache.misses(), 0);
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        //        Given
+        let cache = BpeCache::with_capacity(0);
+
+        //        When
+        cache.insert("hello".to_owned(), (vec!["hello".to_owned()], vec![5]));
+
+        //        Then
+        assert_eq!(cache.get("hello"), None);
+        assert!(cache.is_empty());
+    }
+}
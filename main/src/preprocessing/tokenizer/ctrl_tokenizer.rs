@@ -13,25 +13,36 @@
 // limitations under the License.
 
 use crate::preprocessing::error::TokenizerError;
-use crate::preprocessing::tokenizer::base_tokenizer::{Mask, Token, TokenRef, Tokenizer};
+use crate::preprocessing::tokenizer::base_tokenizer::{
+    Mask, MultiThreadedTokenizer, Token, TokenRef, Tokenizer,
+};
+use crate::preprocessing::tokenizer::bpe_cache::BpeCache;
+use crate::preprocessing::tokenizer::normalizer::{base_normalizer, Normalizer};
+use crate::preprocessing::tokenizer::post_processor::TemplateProcessing;
+use crate::preprocessing::tokenizer::pre_tokenizer::{PreTokenizer, Split, SplitBehavior};
 use crate::preprocessing::tokenizer::tokenization_utils::{
-    ctrl_bpe, fix_mask, lowercase, split_on_bpe_pairs, split_on_regex, split_on_special_tokens,
+    ctrl_bpe, fix_mask, split_on_added_tokens, split_on_bpe_pairs,
 };
 use crate::preprocessing::vocab::base_vocab::Vocab;
 use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
 use crate::OpenAiGptVocab;
 use regex::Regex;
-use std::cell::RefCell;
-use std::collections::HashMap;
-use std::rc::Rc;
+use std::io::Read;
+use std::sync::Arc;
+
+///Splits the text into non-whitespace sequences, keeping a trailing new line
+fn ctrl_pre_tokenizer() -> Split {
+    Split::new(Regex::new(r"\S+\n?").unwrap(), SplitBehavior::Removed, true)
+}
 
 #[derive(Debug)]
 pub struct CtrlTokenizer {
-    vocab: Rc<OpenAiGptVocab>,
-    bpe_ranks: Rc<BpePairVocab>,
-    cache: RefCell<HashMap<String, (Vec<String>, Vec<usize>)>>,
-    regex_pattern: Regex,
-    lower_case: bool,
+    vocab: Arc<OpenAiGptVocab>,
+    bpe_ranks: Arc<BpePairVocab>,
+    cache: BpeCache,
+    pre_tokenizer: Box<dyn PreTokenizer>,
+    normalizer: Box<dyn Normalizer>,
+    post_processor: Option<TemplateProcessing>,
 }
 
 impl CtrlTokenizer {
@@ -40,34 +51,72 @@ impl CtrlTokenizer {
         merges_path: &str,
         lower_case: bool,
     ) -> Result<CtrlTokenizer, TokenizerError> {
-        let vocab = Rc::new(OpenAiGptVocab::from_file(vocab_path)?);
-        let bpe_ranks = Rc::new(BpePairVocab::from_file(merges_path)?);
-        let cache = RefCell::new(HashMap::new());
-        let regex_pattern = Regex::new(r"\S+\n?").unwrap();
+        let vocab = Arc::new(OpenAiGptVocab::from_file(vocab_path)?);
+        let bpe_ranks = Arc::new(BpePairVocab::from_file(merges_path)?);
+        let cache = BpeCache::new();
         Ok(CtrlTokenizer {
             vocab,
             bpe_ranks,
             cache,
-            regex_pattern,
-            lower_case,
+            pre_tokenizer: Box::new(ctrl_pre_tokenizer()),
+            normalizer: Box::new(base_normalizer(lower_case, false)),
+            post_processor: None,
         })
     }
 
+    /// Create a new instance of a `CtrlTokenizer` from readers for the vocabulary and merges (e.g. files
+    /// or network streams), with the same content as the files expected by `from_file`.
+    pub fn from_reader<R: Read, S: Read>(
+        vocab: R,
+        merges: S,
+        lower_case: bool,
+    ) -> Result<CtrlTokenizer, TokenizerError> {
+        let vocab = Arc::new(OpenAiGptVocab::from_reader(vocab)?);
+        let bpe_ranks = Arc::new(BpePairVocab::from_reader(merges)?);
+        Ok(CtrlTokenizer::from_existing_vocab_and_merges(
+            vocab, bpe_ranks, lower_case,
+        ))
+    }
+
+    /// Create a new instance of a `CtrlTokenizer` from the vocabulary and merges held in memory (e.g.
+    /// loaded with `include_bytes!`).
+    pub fn from_bytes(
+        vocab: &[u8],
+        merges: &[u8],
+        lower_case: bool,
+    ) -> Result<CtrlTokenizer, TokenizerError> {
+        CtrlTokenizer::from_reader(vocab, merges, lower_case)
+    }
+
     pub fn from_existing_vocab_and_merges(
-        vocab: Rc<OpenAiGptVocab>,
-        merges: Rc<BpePairVocab>,
+        vocab: Arc<OpenAiGptVocab>,
+        merges: Arc<BpePairVocab>,
         lower_case: bool,
     ) -> CtrlTokenizer {
-        let cache = RefCell::new(HashMap::new());
-        let regex_pattern = Regex::new(r"\S+\n?").unwrap();
+        let cache = BpeCache::new();
         CtrlTokenizer {
             vocab,
             bpe_ranks: merges,
             cache,
-            regex_pattern,
-            lower_case,
+            pre_tokenizer: Box::new(ctrl_pre_tokenizer()),
+            normalizer: Box::new(base_normalizer(lower_case, false)),
+            post_processor: None,
         }
     }
+
+    /// Replaces the BPE cache of the tokenizer by an empty cache holding up to `capacity` words.
+    ///
+    /// # Parameters
+    /// - capacity (`usize`): maximum number of words held by the BPE cache
+    pub fn with_cache_capacity(mut self, capacity: usize) -> CtrlTokenizer {
+        self.cache = BpeCache::with_capacity(capacity);
+        self
+    }
+
+    /// Returns a reference to the BPE cache, allowing to clear or disable it and to read its statistics.
+    pub fn cache(&self) -> &BpeCache {
+        &self.cache
+    }
 }
 
 impl Tokenizer<OpenAiGptVocab> for CtrlTokenizer {
@@ -75,19 +124,52 @@ impl Tokenizer<OpenAiGptVocab> for CtrlTokenizer {
         self.vocab.as_ref()
     }
 
+    fn vocab_mut(&mut self) -> &mut OpenAiGptVocab {
+        Arc::make_mut(&mut self.vocab)
+    }
+
+    fn normalizer(&self) -> &dyn Normalizer {
+        self.normalizer.as_ref()
+    }
+
+    fn set_normalizer(&mut self, normalizer: Box<dyn Normalizer>) {
+        self.normalizer = normalizer;
+    }
+
+    fn pre_tokenizer(&self) -> &dyn PreTokenizer {
+        self.pre_tokenizer.as_ref()
+    }
+
+    fn set_pre_tokenizer(&mut self, pre_tokenizer: Box<dyn PreTokenizer>) {
+        self.pre_tokenizer = pre_tokenizer;
+    }
+
+    fn post_processor(&self) -> Option<&TemplateProcessing> {
+        self.post_processor.as_ref()
+    }
+
+    fn post_processor_mut(&mut self) -> &mut Option<TemplateProcessing> {
+        &mut self.post_processor
+    }
+
     fn tokenize_to_tokens(&self, initial_token: TokenRef) -> Vec<Token> {
-        let mut tokens = split_on_special_tokens(initial_token, self.vocab.as_ref())
-            .into_iter()
-            .map(|token| token.to_owned())
-            .collect::<Vec<Token>>();
+        let mut tokens = split_on_added_tokens(
+            initial_token,
+            self.vocab.as_ref(),
+            self.normalizer.is_lowercasing(),
+        )
+        .into_iter()
+        .map(|token| token.to_owned())
+        .collect::<Vec<Token>>();
         let mut sub_tokens = Vec::new();
 
         for token in tokens.iter_mut() {
-            if token.mask != Mask::Special && token.mask != Mask::Unknown {
-                if self.lower_case {
-                    lowercase(token);
-                }
-                for token in split_on_regex(token.as_ref(), &self.regex_pattern) {
+            if token.mask != Mask::Special
+                && token.mask != Mask::Added
+                && token.mask != Mask::Unknown
+            {
+                self.normalizer.normalize(token);
+                for token in self.pre_tokenizer.pre_tokenize(token.as_ref()) {
                     sub_tokens.extend(split_on_bpe_pairs(
                         token,
                         ctrl_bpe,
@@ -109,11 +191,13 @@ impl Tokenizer<OpenAiGptVocab> for CtrlTokenizer {
     }
 }
 
+impl MultiThreadedTokenizer<OpenAiGptVocab> for CtrlTokenizer {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::preprocessing::tokenizer::base_tokenizer::{
-        Offset, TokenizedInput, TruncationStrategy,
+        Offset, PaddingOptions, TokenizedInput, TruncationStrategy,
     };
     use crate::preprocessing::vocab::base_vocab::swap_key_values;
     use crate::OpenAiGptVocab;
@@ -144,9 +228,11 @@ mod tests {
         OpenAiGptVocab {
             values,
             indices,
-            unknown_value: "<unk>",
+            special_token_map: OpenAiGptVocab::default_special_token_map(),
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
         }
     }
 
@@ -170,8 +256,8 @@ mod tests {
     #[test]
     fn test_ctrl_tokenizer() {
         //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let ctrl_tokenizer: CtrlTokenizer =
             CtrlTokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let test_tuples = [
@@ -195,7 +281,12 @@ mod tests {
         }
 
         assert_eq!(
-            ctrl_tokenizer.tokenize_list(source_texts.clone()),
+            Tokenizer::tokenize_list(&ctrl_tokenizer, source_texts.clone()),
+            expected_results
+        );
+
+        assert_eq!(
+            MultiThreadedTokenizer::tokenize_list(&ctrl_tokenizer, source_texts.clone()),
             expected_results
         );
     }
@@ -203,8 +294,8 @@ mod tests {
     #[test]
     fn test_ctrl_tokenizer_no_lower_casing() {
         //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let ctrl_tokenizer: CtrlTokenizer =
             CtrlTokenizer::from_existing_vocab_and_merges(vocab, merges, false);
         let test_tuples = [
@@ -228,7 +319,12 @@ mod tests {
         }
 
         assert_eq!(
-            ctrl_tokenizer.tokenize_list(source_texts.clone()),
+            Tokenizer::tokenize_list(&ctrl_tokenizer, source_texts.clone()),
+            expected_results
+        );
+
+        assert_eq!(
+            MultiThreadedTokenizer::tokenize_list(&ctrl_tokenizer, source_texts.clone()),
             expected_results
         );
     }
@@ -236,8 +332,8 @@ mod tests {
     #[test]
     fn test_encode() {
         //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let ctrl_tokenizer: CtrlTokenizer =
             CtrlTokenizer::from_existing_vocab_and_merges(vocab, merges, false);
         let truncation_strategy = TruncationStrategy::LongestFirst;
@@ -258,6 +354,22 @@ mod tests {
                         Some(Offset { begin: 7, end: 8 }),
                         Some(Offset { begin: 8, end: 9 }),
                     ],
+                    byte_offsets: vec![
+                        Some(Offset { begin: 0, end: 3 }),
+                        Some(Offset { begin: 4, end: 5 }),
+                        Some(Offset { begin: 5, end: 6 }),
+                        Some(Offset { begin: 6, end: 7 }),
+                        Some(Offset { begin: 7, end: 8 }),
+                        Some(Offset { begin: 8, end: 9 }),
+                    ],
+                    utf16_offsets: vec![
+                        Some(Offset { begin: 0, end: 3 }),
+                        Some(Offset { begin: 4, end: 5 }),
+                        Some(Offset { begin: 5, end: 6 }),
+                        Some(Offset { begin: 6, end: 7 }),
+                        Some(Offset { begin: 7, end: 8 }),
+                        Some(Offset { begin: 8, end: 9 }),
+                    ],
                     reference_offsets: vec![
                         vec![0, 1, 2],
                         vec![4],
@@ -274,6 +386,11 @@ mod tests {
                         Mask::Continuation,
                         Mask::Continuation,
                     ],
+                    attention_mask: vec![1, 1, 1, 1, 1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![Some(0), Some(1), Some(1), Some(1), Some(1), Some(1)],
+                    sequence_ids: vec![Some(0), Some(0), Some(0), Some(0), Some(0), Some(0)],
+                    alignment_index: Default::default(),
                 },
             ),
             (
@@ -297,6 +414,32 @@ mod tests {
                         Some(Offset { begin: 11, end: 12 }),
                         Some(Offset { begin: 12, end: 13 }),
                     ],
+                    byte_offsets: vec![
+                        Some(Offset { begin: 0, end: 1 }),
+                        Some(Offset { begin: 1, end: 2 }),
+                        Some(Offset { begin: 2, end: 4 }),
+                        Some(Offset { begin: 4, end: 5 }),
+                        Some(Offset { begin: 5, end: 6 }),
+                        Some(Offset { begin: 7, end: 8 }),
+                        Some(Offset { begin: 8, end: 9 }),
+                        Some(Offset { begin: 9, end: 10 }),
+                        Some(Offset { begin: 10, end: 11 }),
+                        Some(Offset { begin: 11, end: 12 }),
+                        Some(Offset { begin: 12, end: 13 }),
+                    ],
+                    utf16_offsets: vec![
+                        Some(Offset { begin: 0, end: 1 }),
+                        Some(Offset { begin: 1, end: 2 }),
+                        Some(Offset { begin: 2, end: 4 }),
+                        Some(Offset { begin: 4, end: 5 }),
+                        Some(Offset { begin: 5, end: 6 }),
+                        Some(Offset { begin: 7, end: 8 }),
+                        Some(Offset { begin: 8, end: 9 }),
+                        Some(Offset { begin: 9, end: 10 }),
+                        Some(Offset { begin: 10, end: 11 }),
+                        Some(Offset { begin: 11, end: 12 }),
+                        Some(Offset { begin: 12, end: 13 }),
+                    ],
                     reference_offsets: vec![
                         vec![0],
                         vec![1],
@@ -323,6 +466,35 @@ mod tests {
                         Mask::Continuation,
                         Mask::Continuation,
                     ],
+                    attention_mask: vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(1),
+                        Some(1),
+                        Some(1),
+                        Some(1),
+                        Some(1),
+                        Some(1),
+                    ],
+                    sequence_ids: vec![
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                        Some(0),
+                    ],
+                    alignment_index: Default::default(),
                 },
             ),
             (
@@ -334,8 +506,15 @@ mod tests {
                     overflowing_tokens: vec![],
                     num_truncated_tokens: 0,
                     token_offsets: vec![],
+                    byte_offsets: vec![],
+                    utf16_offsets: vec![],
                     reference_offsets: vec![],
                     mask: vec![],
+                    attention_mask: vec![],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![],
+                    sequence_ids: vec![],
+                    alignment_index: Default::default(),
                 },
             ),
         ];
@@ -346,12 +525,37 @@ mod tests {
         //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
             assert_eq!(
-                ctrl_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0),
+                ctrl_tokenizer.encode(
+                    source_text,
+                    None,
+                    128,
+                    &truncation_strategy,
+                    0,
+                    &PaddingOptions::default()
+                ),
                 *expected_result
             );
         }
         assert_eq!(
-            ctrl_tokenizer.encode_list(source_texts.clone(), 128, &truncation_strategy, 0),
+            Tokenizer::encode_list(
+                &ctrl_tokenizer,
+                source_texts.clone(),
+                128,
+                &truncation_strategy,
+                0,
+                &PaddingOptions::default()
+            ),
+            expected_results
+        );
+        assert_eq!(
+            MultiThreadedTokenizer::encode_list(
+                &ctrl_tokenizer,
+                source_texts.clone(),
+                128,
+                &truncation_strategy,
+                0,
+                &PaddingOptions::default()
+            ),
             expected_results
         );
     }
@@ -359,8 +563,8 @@ mod tests {
     #[test]
     fn test_decode() {
         //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let ctrl_tokenizer: CtrlTokenizer =
             CtrlTokenizer::from_existing_vocab_and_merges(vocab, merges, false);
         let skip_special_tokens = false;
@@ -394,8 +598,8 @@ mod tests {
     #[test]
     fn test_decode_skip_special_tokens() {
         //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let ctrl_tokenizer: CtrlTokenizer =
             CtrlTokenizer::from_existing_vocab_and_merges(vocab, merges, false);
         let skip_special_tokens = true;
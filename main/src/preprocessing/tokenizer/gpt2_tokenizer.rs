@@ -13,30 +13,41 @@
 // limitations under the License.
 
 use crate::preprocessing::error::TokenizerError;
-use crate::preprocessing::tokenizer::base_tokenizer::{Mask, Token, TokenRef, Tokenizer};
+use crate::preprocessing::tokenizer::base_tokenizer::{
+    Mask, MultiThreadedTokenizer, Token, TokenRef, Tokenizer,
+};
+use crate::preprocessing::tokenizer::bpe_cache::BpeCache;
 use crate::preprocessing::tokenizer::constants::UNICODE_TO_BYTES;
+use crate::preprocessing::tokenizer::normalizer::{base_normalizer, Normalizer};
+use crate::preprocessing::tokenizer::post_processor::TemplateProcessing;
+use crate::preprocessing::tokenizer::pre_tokenizer::{ByteLevel, PreTokenizer};
 use crate::preprocessing::tokenizer::tokenization_utils::{
-    bpe, fix_mask, split_on_bpe_pairs, split_on_regex_with_lookahead, split_on_special_tokens,
+    bpe, fix_mask, split_on_added_tokens, split_on_bpe_pairs,
+};
+use crate::preprocessing::tokenizer::tokenizer_json::{
+    bpe_model_json, check_default_components, custom_post_processor_json, get_section, read_merges,
+    read_normalizer_flags, read_vocab, restore_post_processor, tokenizer_json, TokenizerJson,
 };
 use crate::preprocessing::vocab::base_vocab::Vocab;
 use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
-use crate::tokenization_utils::lowercase;
 use crate::Gpt2Vocab;
 use itertools::Itertools;
-use regex::Regex;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use serde_json::{json, Value};
+use std::io::Read;
 use std::iter::Iterator;
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct Gpt2Tokenizer {
-    vocab: Rc<Gpt2Vocab>,
-    bpe_ranks: Rc<BpePairVocab>,
-    cache: RefCell<HashMap<String, (Vec<String>, Vec<usize>)>>,
-    pattern_lookahead: Regex,
-    pattern_tokenization: Regex,
+    vocab: Arc<Gpt2Vocab>,
+    bpe_ranks: Arc<BpePairVocab>,
+    cache: BpeCache,
+    pre_tokenizer: Box<dyn PreTokenizer>,
+    custom_pre_tokenizer: bool,
     lower_case: bool,
+    normalizer: Box<dyn Normalizer>,
+    custom_normalizer: bool,
+    post_processor: Option<TemplateProcessing>,
 }
 
 impl Gpt2Tokenizer {
@@ -45,42 +56,78 @@ impl Gpt2Tokenizer {
         merges_path: &str,
         lower_case: bool,
     ) -> Result<Gpt2Tokenizer, TokenizerError> {
-        let vocab = Rc::new(Gpt2Vocab::from_file(vocab_path)?);
-        let bpe_ranks = Rc::new(BpePairVocab::from_file(merges_path)?);
-        let cache = RefCell::new(HashMap::new());
-        let pattern_lookahead = Regex::new(r"\s+\S").unwrap();
-        let pattern_tokenization =
-            Regex::new(r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+")
-                .unwrap();
+        let vocab = Arc::new(Gpt2Vocab::from_file(vocab_path)?);
+        let bpe_ranks = Arc::new(BpePairVocab::from_file(merges_path)?);
+        let cache = BpeCache::new();
         Ok(Gpt2Tokenizer {
             vocab,
             bpe_ranks,
             cache,
-            pattern_lookahead,
-            pattern_tokenization,
+            pre_tokenizer: Box::new(ByteLevel::default()),
+            custom_pre_tokenizer: false,
             lower_case,
+            normalizer: Box::new(base_normalizer(lower_case, false)),
+            custom_normalizer: false,
+            post_processor: None,
         })
     }
 
+    /// Create a new instance of a `Gpt2Tokenizer` from readers for the vocabulary and merges (e.g. files
+    /// or network streams), with the same content as the files expected by `from_file`.
+    pub fn from_reader<R: Read, S: Read>(
+        vocab: R,
+        merges: S,
+        lower_case: bool,
+    ) -> Result<Gpt2Tokenizer, TokenizerError> {
+        let vocab = Arc::new(Gpt2Vocab::from_reader(vocab)?);
+        let bpe_ranks = Arc::new(BpePairVocab::from_reader(merges)?);
+        Ok(Gpt2Tokenizer::from_existing_vocab_and_merges(
+            vocab, bpe_ranks, lower_case,
+        ))
+    }
+
+    /// Create a new instance of a `Gpt2Tokenizer` from the vocabulary and merges held in memory (e.g.
+    /// loaded with `include_bytes!`).
+    pub fn from_bytes(
+        vocab: &[u8],
+        merges: &[u8],
+        lower_case: bool,
+    ) -> Result<Gpt2Tokenizer, TokenizerError> {
+        Gpt2Tokenizer::from_reader(vocab, merges, lower_case)
+    }
+
     pub fn from_existing_vocab_and_merges(
-        vocab: Rc<Gpt2Vocab>,
-        merges: Rc<BpePairVocab>,
+        vocab: Arc<Gpt2Vocab>,
+        merges: Arc<BpePairVocab>,
         lower_case: bool,
     ) -> Gpt2Tokenizer {
-        let cache = RefCell::new(HashMap::new());
-        let pattern_lookahead = Regex::new(r"\s+\S").unwrap();
-        let pattern_tokenization =
-            Regex::new(r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+")
-                .unwrap();
+        let cache = BpeCache::new();
         Gpt2Tokenizer {
             vocab,
             bpe_ranks: merges,
             cache,
-            pattern_lookahead,
-            pattern_tokenization,
+            pre_tokenizer: Box::new(ByteLevel::default()),
+            custom_pre_tokenizer: false,
             lower_case,
+            normalizer: Box::new(base_normalizer(lower_case, false)),
+            custom_normalizer: false,
+            post_processor: None,
         }
     }
+
+    /// Replaces the BPE cache of the tokenizer by an empty cache holding up to `capacity` words.
+    ///
+    /// # Parameters
+    /// - capacity (`usize`): maximum number of words held by the BPE cache
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Gpt2Tokenizer {
+        self.cache = BpeCache::with_capacity(capacity);
+        self
+    }
+
+    /// Returns a reference to the BPE cache, allowing to clear or disable it and to read its statistics.
+    pub fn cache(&self) -> &BpeCache {
+        &self.cache
+    }
 }
 
 impl Tokenizer<Gpt2Vocab> for Gpt2Tokenizer {
@@ -88,23 +135,54 @@ impl Tokenizer<Gpt2Vocab> for Gpt2Tokenizer {
         self.vocab.as_ref()
     }
 
+    fn vocab_mut(&mut self) -> &mut Gpt2Vocab {
+        Arc::make_mut(&mut self.vocab)
+    }
+
+    fn normalizer(&self) -> &dyn Normalizer {
+        self.normalizer.as_ref()
+    }
+
+    fn set_normalizer(&mut self, normalizer: Box<dyn Normalizer>) {
+        self.normalizer = normalizer;
+        self.custom_normalizer = true;
+    }
+
+    fn pre_tokenizer(&self) -> &dyn PreTokenizer {
+        self.pre_tokenizer.as_ref()
+    }
+
+    fn set_pre_tokenizer(&mut self, pre_tokenizer: Box<dyn PreTokenizer>) {
+        self.pre_tokenizer = pre_tokenizer;
+        self.custom_pre_tokenizer = true;
+    }
+
+    fn post_processor(&self) -> Option<&TemplateProcessing> {
+        self.post_processor.as_ref()
+    }
+
+    fn post_processor_mut(&mut self) -> &mut Option<TemplateProcessing> {
+        &mut self.post_processor
+    }
+
     fn tokenize_to_tokens(&self, initial_token: TokenRef) -> Vec<Token> {
-        let mut tokens = split_on_special_tokens(initial_token, self.vocab.as_ref())
-            .into_iter()
-            .map(|token| token.to_owned())
-            .collect::<Vec<Token>>();
+        let mut tokens = split_on_added_tokens(
+            initial_token,
+            self.vocab.as_ref(),
+            self.normalizer.is_lowercasing(),
+        )
+        .into_iter()
+        .map(|token| token.to_owned())
+        .collect::<Vec<Token>>();
 
         let mut sub_tokens = Vec::new();
         for token in tokens.iter_mut() {
-            if token.mask != Mask::Special && token.mask != Mask::Unknown {
-                if self.lower_case {
-                    lowercase(token);
-                }
-                for token in split_on_regex_with_lookahead(
-                    token.as_ref(),
-                    &self.pattern_lookahead,
-                    &self.pattern_tokenization,
-                ) {
+            if token.mask != Mask::Special
+                && token.mask != Mask::Added
+                && token.mask != Mask::Unknown
+            {
+                self.normalizer.normalize(token);
+                for token in self.pre_tokenizer.pre_tokenize(token.as_ref()) {
                     sub_tokens.extend(split_on_bpe_pairs(
                         token,
                         bpe,
@@ -135,11 +213,67 @@ impl Tokenizer<Gpt2Vocab> for Gpt2Tokenizer {
     }
 }
 
+impl MultiThreadedTokenizer<Gpt2Vocab> for Gpt2Tokenizer {}
+
+impl TokenizerJson for Gpt2Tokenizer {
+    fn to_tokenizer_json(&self) -> Result<Value, TokenizerError> {
+        check_default_components(self.custom_normalizer, self.custom_pre_tokenizer)?;
+        let normalizer = if self.lower_case {
+            json!({"type": "Lowercase"})
+        } else {
+            Value::Null
+        };
+        Ok(tokenizer_json(
+            self.vocab.as_ref(),
+            normalizer,
+            json!({"type": "ByteLevel", "add_prefix_space": false, "trim_offsets": true, "use_regex": true}),
+            bpe_model_json(self.vocab.as_ref(), self.bpe_ranks.as_ref(), None),
+            custom_post_processor_json(
+                self,
+                json!({"type": "ByteLevel", "add_prefix_space": false, "trim_offsets": true, "use_regex": true}),
+            ),
+            json!({"type": "ByteLevel", "add_prefix_space": false, "trim_offsets": true, "use_regex": true}),
+        ))
+    }
+
+    fn from_tokenizer_json(tokenizer_json: &Value) -> Result<Gpt2Tokenizer, TokenizerError> {
+        let vocab = read_vocab(
+            tokenizer_json,
+            |values,
+             indices,
+             special_token_map,
+             special_values,
+             special_indices,
+             added_values,
+             added_tokens| {
+                Gpt2Vocab {
+                    values,
+                    indices,
+                    special_token_map,
+                    special_values,
+                    special_indices,
+                    added_values,
+                    added_tokens,
+                }
+            },
+        )?;
+        let merges = read_merges(get_section(tokenizer_json, "model")?)?;
+        let (lower_case, _) = read_normalizer_flags(tokenizer_json);
+        let mut tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(
+            Arc::new(vocab),
+            Arc::new(merges),
+            lower_case,
+        );
+        restore_post_processor(&mut tokenizer, tokenizer_json)?;
+        Ok(tokenizer)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::preprocessing::tokenizer::base_tokenizer::{
-        Offset, TokenizedInput, TruncationStrategy,
+        Offset, OffsetUnit, PaddingOptions, TokenizedInput, TruncationStrategy,
     };
     use crate::preprocessing::vocab::base_vocab::swap_key_values;
     use crate::Gpt2Vocab;
@@ -171,9 +305,11 @@ mod tests {
         Gpt2Vocab {
             values,
             indices,
-            unknown_value: "<|endoftext|>",
+            special_token_map: Gpt2Vocab::default_special_token_map(),
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
         }
     }
 
@@ -200,8 +336,8 @@ mod tests {
     #[test]
     fn test_gpt2_tokenizer() {
         //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let gpt2_tokenizer: Gpt2Tokenizer =
             Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let test_tuples = [
@@ -221,7 +357,12 @@ mod tests {
         }
 
         assert_eq!(
-            gpt2_tokenizer.tokenize_list(source_texts.clone()),
+            Tokenizer::tokenize_list(&gpt2_tokenizer, source_texts.clone()),
+            expected_results
+        );
+
+        assert_eq!(
+            MultiThreadedTokenizer::tokenize_list(&gpt2_tokenizer, source_texts.clone()),
             expected_results
         );
     }
@@ -229,8 +370,8 @@ mod tests {
     #[test]
     fn test_gpt2_tokenizer_no_lower_casing() {
         //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let gpt2_tokenizer: Gpt2Tokenizer =
             Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, false);
         let test_tuples = [
@@ -249,7 +390,12 @@ mod tests {
         }
 
         assert_eq!(
-            gpt2_tokenizer.tokenize_list(source_texts.clone()),
+            Tokenizer::tokenize_list(&gpt2_tokenizer, source_texts.clone()),
+            expected_results
+        );
+
+        assert_eq!(
+            MultiThreadedTokenizer::tokenize_list(&gpt2_tokenizer, source_texts.clone()),
             expected_results
         );
     }
@@ -257,8 +403,8 @@ mod tests {
     #[test]
     fn test_encode() {
         //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let gpt2_tokenizer: Gpt2Tokenizer =
             Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let truncation_strategy = TruncationStrategy::LongestFirst;
@@ -276,8 +422,23 @@ mod tests {
                         Some(Offset { begin: 3, end: 7 }),
                         Some(Offset { begin: 7, end: 9 }),
                     ],
+                    byte_offsets: vec![
+                        Some(Offset { begin: 0, end: 3 }),
+                        Some(Offset { begin: 3, end: 7 }),
+                        Some(Offset { begin: 7, end: 9 }),
+                    ],
+                    utf16_offsets: vec![
+                        Some(Offset { begin: 0, end: 3 }),
+                        Some(Offset { begin: 3, end: 7 }),
+                        Some(Offset { begin: 7, end: 9 }),
+                    ],
                     reference_offsets: vec![vec![0, 1, 2], vec![3, 4, 5, 6], vec![7, 8]],
                     mask: vec![Mask::None, Mask::Begin, Mask::Continuation],
+                    attention_mask: vec![1, 1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![Some(0), Some(1), Some(1)],
+                    sequence_ids: vec![Some(0), Some(0), Some(0)],
+                    alignment_index: Default::default(),
                 },
             ),
             (
@@ -289,8 +450,15 @@ mod tests {
                     overflowing_tokens: vec![],
                     num_truncated_tokens: 0,
                     token_offsets: vec![],
+                    byte_offsets: vec![],
+                    utf16_offsets: vec![],
                     reference_offsets: vec![],
                     mask: vec![],
+                    attention_mask: vec![],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![],
+                    sequence_ids: vec![],
+                    alignment_index: Default::default(),
                 },
             ),
             (
@@ -302,8 +470,15 @@ mod tests {
                     overflowing_tokens: vec![],
                     num_truncated_tokens: 0,
                     token_offsets: vec![],
+                    byte_offsets: vec![],
+                    utf16_offsets: vec![],
                     reference_offsets: vec![],
                     mask: vec![],
+                    attention_mask: vec![],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![],
+                    sequence_ids: vec![],
+                    alignment_index: Default::default(),
                 },
             ),
         ];
@@ -314,21 +489,183 @@ mod tests {
         //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
             assert_eq!(
-                gpt2_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0),
+                gpt2_tokenizer.encode(
+                    source_text,
+                    None,
+                    128,
+                    &truncation_strategy,
+                    0,
+                    &PaddingOptions::default()
+                ),
                 *expected_result
             );
         }
         assert_eq!(
-            gpt2_tokenizer.encode_list(source_texts.clone(), 128, &truncation_strategy, 0),
+            Tokenizer::encode_list(
+                &gpt2_tokenizer,
+                source_texts.clone(),
+                128,
+                &truncation_strategy,
+                0,
+                &PaddingOptions::default()
+            ),
             expected_results
         );
+        assert_eq!(
+            MultiThreadedTokenizer::encode_list(
+                &gpt2_tokenizer,
+                source_texts.clone(),
+                128,
+                &truncation_strategy,
+                0,
+                &PaddingOptions::default()
+            ),
+            expected_results
+        );
+    }
+
+    #[test]
+    fn test_byte_and_utf16_offsets() {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
+        let gpt2_tokenizer: Gpt2Tokenizer =
+            Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
+        let text = "the \u{1F30D}";
+
+        //        When
+        let tokenized_input = gpt2_tokenizer.encode(
+            text,
+            None,
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+
+        //        Then
+        assert_eq!(
+            tokenized_input.offsets(&OffsetUnit::Char),
+            tokenized_input.token_offsets.as_slice()
+        );
+        assert_eq!(
+            tokenized_input.byte_offsets,
+            vec![
+                Some(Offset { begin: 0, end: 3 }),
+                Some(Offset { begin: 3, end: 4 }),
+                Some(Offset { begin: 4, end: 8 }),
+                Some(Offset { begin: 4, end: 8 }),
+                Some(Offset { begin: 4, end: 8 }),
+                Some(Offset { begin: 4, end: 8 }),
+            ]
+        );
+        assert_eq!(
+            tokenized_input.utf16_offsets,
+            vec![
+                Some(Offset { begin: 0, end: 3 }),
+                Some(Offset { begin: 3, end: 4 }),
+                Some(Offset { begin: 4, end: 6 }),
+                Some(Offset { begin: 4, end: 6 }),
+                Some(Offset { begin: 4, end: 6 }),
+                Some(Offset { begin: 4, end: 6 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encode_pretokenized() {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
+        let gpt2_tokenizer: Gpt2Tokenizer =
+            Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
+        let words = ["The", "earth"];
+
+        //        When
+        let with_prefix_space = gpt2_tokenizer.encode_pretokenized(
+            &words,
+            None,
+            true,
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+        let without_prefix_space = gpt2_tokenizer.encode_pretokenized(
+            &words,
+            None,
+            false,
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+
+        //        Then
+        assert_eq!(with_prefix_space.token_ids, vec![6, 8, 9]);
+        assert_eq!(
+            with_prefix_space.token_offsets,
+            vec![
+                Some(Offset { begin: 0, end: 3 }),
+                Some(Offset { begin: 0, end: 3 }),
+                Some(Offset { begin: 3, end: 5 }),
+            ]
+        );
+        assert_eq!(with_prefix_space.word_ids, vec![Some(0), Some(1), Some(1)]);
+        assert_eq!(without_prefix_space.token_ids, vec![4, 6, 6, 6, 9]);
+        assert_eq!(
+            without_prefix_space.token_offsets,
+            vec![
+                Some(Offset { begin: 0, end: 3 }),
+                Some(Offset { begin: 0, end: 1 }),
+                Some(Offset { begin: 1, end: 2 }),
+                Some(Offset { begin: 2, end: 3 }),
+                Some(Offset { begin: 3, end: 5 }),
+            ]
+        );
+        assert_eq!(
+            without_prefix_space.word_ids,
+            vec![Some(0), Some(1), Some(1), Some(1), Some(1)]
+        );
+    }
+
+    #[test]
+    fn test_bpe_cache() {
+        //        Given
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
+        let gpt2_tokenizer: Gpt2Tokenizer =
+            Gpt2Tokenizer::from_existing_vocab_and_merges(vocab.clone(), merges.clone(), true);
+        let bounded_tokenizer: Gpt2Tokenizer =
+            Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true)
+                .with_cache_capacity(1);
+
+        //        When
+        let first_pass = gpt2_tokenizer.tokenize("the Earth");
+        let second_pass = gpt2_tokenizer.tokenize("the Earth");
+        let bounded_pass = bounded_tokenizer.tokenize("the Earth");
+
+        //        Then
+        assert_eq!(first_pass, vec!("the", "Ġear", "th"));
+        assert_eq!(second_pass, first_pass);
+        assert_eq!(bounded_pass, first_pass);
+        assert_eq!(gpt2_tokenizer.cache().len(), 2);
+        assert_eq!(gpt2_tokenizer.cache().misses(), 2);
+        assert_eq!(gpt2_tokenizer.cache().hits(), 2);
+        assert_eq!(bounded_tokenizer.cache().capacity(), 1);
+        assert_eq!(bounded_tokenizer.cache().len(), 1);
+
+        gpt2_tokenizer.cache().set_enabled(false);
+        assert_eq!(gpt2_tokenizer.tokenize("the Earth"), first_pass);
+        assert!(gpt2_tokenizer.cache().is_empty());
+        assert_eq!(gpt2_tokenizer.cache().hits(), 2);
     }
 
     #[test]
     fn test_decode() {
         //        Given
-        let vocab = Rc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let vocab = Arc::new(generate_test_vocab());
+        let merges = Arc::new(generate_test_merges());
         let gpt2_tokenizer: Gpt2Tokenizer =
             Gpt2Tokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let skip_special_tokens = false;
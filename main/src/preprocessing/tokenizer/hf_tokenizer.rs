@@ -0,0 +1,1060 @@
+// Copyright 2018 The HuggingFace Inc. team.
+// Copyright 2019-2020 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::base_tokenizer::{
+    Mask, MultiThreadedTokenizer, Token, TokenRef, Tokenizer,
+};
+use crate::preprocessing::tokenizer::bpe_cache::BpeCache;
+use crate::preprocessing::tokenizer::constants::UNICODE_TO_BYTES;
+use crate::preprocessing::tokenizer::normalizer::{
+    BertNormalizer, Lowercase, Nfc, Nfd, Nfkc, Nfkd, Normalizer, Prepend, RegexReplace, Replace,
+    Sequence, Strip, StripAccents,
+};
+use crate::preprocessing::tokenizer::post_processor::{TemplatePiece, TemplateProcessing};
+use crate::preprocessing::tokenizer::pre_tokenizer::{
+    BertPreTokenizer, ByteLevel, Digits, PreTokenizer, Punctuation,
+    Sequence as PreTokenizerSequence, Split, SplitBehavior, Whitespace, WhitespaceSplit,
+};
+use crate::preprocessing::tokenizer::tokenization_utils::{
+    bpe, fix_mask, insert_prefix, openai_gpt_bpe, split_on_added_tokens, split_on_bpe_pairs,
+    tokenize_wordpiece,
+};
+use crate::preprocessing::tokenizer::tokenizer_json::{
+    read_merges, read_precompiled_charsmap, read_template_processing, read_tokenizer_json,
+    read_tokenizer_json_file, read_unigram_pieces,
+};
+use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
+use crate::preprocessing::vocab::hf_vocab::HFVocab;
+use crate::preprocessing::vocab::sentence_piece_vocab::SentencePieceModel;
+use regex::Regex;
+use serde_json::Value;
+use std::io::Read;
+use std::sync::Arc;
+
+fn unsupported(section: &str, component_type: &str) -> TokenizerError {
+    TokenizerError::UnsupportedComponent(format!("{} of type {}", section, component_type))
+}
+
+fn component_type<'a>(value: &'a Value, section: &str) -> Result<&'a str, TokenizerError> {
+    value
+        .get("type")
+        .and_then(|value| value.as_str())
+        .ok_or_else(|| {
+            TokenizerError::VocabularyParsingError(format!("missing type for the {}", section))
+        })
+}
+
+fn get_bool(value: &Value, key: &str, default: bool) -> bool {
+    value
+        .get(key)
+        .and_then(|value| value.as_bool())
+        .unwrap_or(default)
+}
+
+fn get_str<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(|value| value.as_str())
+}
+
+fn get_char(value: &Value, key: &str, default: char) -> char {
+    get_str(value, key)
+        .and_then(|value| value.chars().next())
+        .unwrap_or(default)
+}
+
+fn get_components<'a>(value: &'a Value, key: &str) -> Result<&'a Vec<Value>, TokenizerError> {
+    value
+        .get(key)
+        .and_then(|value| value.as_array())
+        .ok_or_else(|| TokenizerError::VocabularyParsingError(format!("missing `{}` list", key)))
+}
+
+///Reads the `normalizer` section into the equivalent `Normalizer`
+fn read_normalizer(value: &Value) -> Result<Box<dyn Normalizer>, TokenizerError> {
+    let normalizer_type = component_type(value, "normalizer")?;
+    Ok(match normalizer_type {
+        "BertNormalizer" => {
+            let lowercase = get_bool(value, "lowercase", true);
+            Box::new(BertNormalizer {
+                clean_text: get_bool(value, "clean_text", true),
+                handle_chinese_chars: get_bool(value, "handle_chinese_chars", true),
+                strip_accents: get_bool(value, "strip_accents", lowercase),
+                lowercase,
+            })
+        }
+        "Lowercase" => Box::new(Lowercase),
+        "StripAccents" => Box::new(StripAccents),
+        "NFC" => Box::new(Nfc),
+        "NFD" => Box::new(Nfd),
+        "NFKC" => Box::new(Nfkc),
+        "NFKD" => Box::new(Nfkd),
+        "Strip" => Box::new(Strip {
+            left: get_bool(value, "strip_left", true),
+            right: get_bool(value, "strip_right", true),
+        }),
+        "Replace" => {
+            let content = get_str(value, "content").unwrap_or("");
+            let pattern = value.get("pattern");
+            match (
+                pattern.and_then(|pattern| get_str(pattern, "String")),
+                pattern.and_then(|pattern| get_str(pattern, "Regex")),
+            ) {
+                (Some(pattern), _) => Box::new(Replace::new(pattern, content)),
+                (None, Some(pattern)) => Box::new(RegexReplace::new(
+                    Regex::new(pattern).map_err(|e| {
+                        TokenizerError::VocabularyParsingError(format!(
+                            "invalid Replace normalizer pattern: {}",
+                            e
+                        ))
+                    })?,
+                    content,
+                )),
+                (None, None) => {
+                    return Err(TokenizerError::VocabularyParsingError(
+                        "missing pattern for the Replace normalizer".to_owned(),
+                    ))
+                }
+            }
+        }
+        "Prepend" => Box::new(Prepend {
+            prefix: get_str(value, "prepend").unwrap_or("").to_owned(),
+        }),
+        "Precompiled" => match read_precompiled_charsmap(value)? {
+            Some(precompiled) => Box::new(precompiled),
+            None => Box::new(Sequence::default()),
+        },
+        "Sequence" => Box::new(Sequence::new(
+            get_components(value, "normalizers")?
+                .iter()
+                .map(read_normalizer)
+                .collect::<Result<Vec<Box<dyn Normalizer>>, TokenizerError>>()?,
+        )),
+        _ => return Err(unsupported("normalizer", normalizer_type)),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PrependScheme {
+    Always,
+    First,
+    Never,
+}
+
+///Text transformations of the `pre_tokenizer` section, applied before splitting
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HFPrefix {
+    ///Inserts a space at the beginning of the text (`ByteLevel` with `add_prefix_space`)
+    PrefixSpace,
+    ///Replaces the spaces by the replacement character, optionally prepending it (`Metaspace`)
+    Metaspace {
+        replacement: char,
+        prepend_scheme: PrependScheme,
+    },
+}
+
+impl HFPrefix {
+    fn apply(&self, token: &mut Token, is_first: bool) {
+        match self {
+            HFPrefix::PrefixSpace => {
+                if !token.text.starts_with(' ') {
+                    insert_prefix(token, " ");
+                }
+            }
+            HFPrefix::Metaspace {
+                replacement,
+                prepend_scheme,
+            } => {
+                token.text = token.text.replace(' ', &replacement.to_string());
+                let prepend = match prepend_scheme {
+                    PrependScheme::Always => true,
+                    PrependScheme::First => is_first,
+                    PrependScheme::Never => false,
+                };
+                if prepend && !token.text.starts_with(*replacement) {
+                    insert_prefix(token, &replacement.to_string());
+                }
+            }
+        }
+    }
+}
+
+///Reads the `pre_tokenizer` section into the equivalent `PreTokenizer`. The transformations
+///modifying the text before splitting are appended to `prefixes`.
+fn read_pre_tokenizer(
+    value: &Value,
+    prefixes: &mut Vec<HFPrefix>,
+) -> Result<Box<dyn PreTokenizer>, TokenizerError> {
+    let pre_tokenizer_type = component_type(value, "pre_tokenizer")?;
+    Ok(match pre_tokenizer_type {
+        "BertPreTokenizer" => Box::new(BertPreTokenizer),
+        "Whitespace" => Box::new(Whitespace::default()),
+        "WhitespaceSplit" => Box::new(WhitespaceSplit),
+        "Punctuation" => Box::new(Punctuation),
+        "Digits" => Box::new(Digits::new(get_bool(value, "individual_digits", false))),
+        "ByteLevel" => {
+            if get_bool(value, "add_prefix_space", true) {
+                prefixes.push(HFPrefix::PrefixSpace);
+            }
+            Box::new(ByteLevel::new(get_bool(value, "use_regex", true)))
+        }
+        "Metaspace" => {
+            let prepend_scheme = match get_str(value, "prepend_scheme") {
+                Some("first") => PrependScheme::First,
+                Some("never") => PrependScheme::Never,
+                Some(_) => PrependScheme::Always,
+                None => {
+                    if get_bool(value, "add_prefix_space", true) {
+                        PrependScheme::Always
+                    } else {
+                        PrependScheme::Never
+                    }
+                }
+            };
+            let replacement = get_char(value, "replacement", '\u{2581}');
+            prefixes.push(HFPrefix::Metaspace {
+                replacement,
+                prepend_scheme,
+            });
+            if get_bool(value, "split", true) {
+                Box::new(Split::new(
+                    Regex::new(&regex::escape(&replacement.to_string())).unwrap(),
+                    SplitBehavior::MergedWithNext,
+                    false,
+                ))
+            } else {
+                Box::new(PreTokenizerSequence::default())
+            }
+        }
+        "Sequence" => Box::new(PreTokenizerSequence::new(
+            get_components(value, "pretokenizers")?
+                .iter()
+                .map(|value| read_pre_tokenizer(value, prefixes))
+                .collect::<Result<Vec<Box<dyn PreTokenizer>>, TokenizerError>>()?,
+        )),
+        _ => return Err(unsupported("pre_tokenizer", pre_tokenizer_type)),
+    })
+}
+
+enum HFModel {
+    WordPiece {
+        max_input_chars_per_word: usize,
+    },
+    Bpe {
+        bpe_ranks: BpePairVocab,
+        cache: BpeCache,
+        end_of_word_suffix: bool,
+    },
+    Unigram {
+        model: Box<SentencePieceModel>,
+    },
+}
+
+impl std::fmt::Debug for HFModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HFModel::WordPiece { .. } => write!(f, "WordPiece"),
+            HFModel::Bpe { .. } => write!(f, "BPE"),
+            HFModel::Unigram { .. } => write!(f, "Unigram"),
+        }
+    }
+}
+
+impl HFModel {
+    fn from_json_value(value: &Value) -> Result<HFModel, TokenizerError> {
+        let model_type = match component_type(value, "model") {
+            Ok(model_type) => model_type,
+            //Files generated by older versions may omit the model type
+            Err(_) if value.get("merges").is_some() => "BPE",
+            Err(_) if matches!(value.get("vocab"), Some(Value::Array(_))) => "Unigram",
+            Err(_) => "WordPiece",
+        };
+        Ok(match model_type {
+            "WordPiece" => {
+                let prefix = get_str(value, "continuing_subword_prefix").unwrap_or("##");
+                if prefix != "##" {
+                    return Err(unsupported(
+                        "model",
+                        &format!("WordPiece with continuing subword prefix {}", prefix),
+                    ));
+                }
+                HFModel::WordPiece {
+                    max_input_chars_per_word: value
+                        .get("max_input_chars_per_word")
+                        .and_then(|value| value.as_u64())
+                        .unwrap_or(100) as usize,
+                }
+            }
+            "BPE" => {
+                if matches!(value.get("dropout"), Some(dropout) if !dropout.is_null()) {
+                    return Err(unsupported("model", "BPE with dropout"));
+                }
+                if get_bool(value, "byte_fallback", false) {
+                    return Err(unsupported("model", "BPE with byte fallback"));
+                }
+                if matches!(get_str(value, "continuing_subword_prefix"), Some(prefix) if !prefix.is_empty())
+                {
+                    return Err(unsupported("model", "BPE with continuing subword prefix"));
+                }
+                let end_of_word_suffix = match get_str(value, "end_of_word_suffix") {
+                    None | Some("") => false,
+                    Some("</w>") => true,
+                    Some(suffix) => {
+                        return Err(unsupported(
+                            "model",
+                            &format!("BPE with end of word suffix {}", suffix),
+                        ));
+                    }
+                };
+                HFModel::Bpe {
+                    bpe_ranks: read_merges(value)?,
+                    cache: BpeCache::new(),
+                    end_of_word_suffix,
+                }
+            }
+            "Unigram" => {
+                let pieces = read_unigram_pieces(value)?;
+                let pieces = pieces
+                    .iter()
+                    .map(|(piece, score)| (piece.as_str(), *score))
+                    .collect::<Vec<(&str, f32)>>();
+                let mut model = SentencePieceModel::from_pieces(&pieces);
+                model.byte_fallback = get_bool(value, "byte_fallback", false);
+                HFModel::Unigram {
+                    model: Box::new(model),
+                }
+            }
+            _ => return Err(unsupported("model", model_type)),
+        })
+    }
+
+    fn tokenize(&self, token: TokenRef, vocab: &HFVocab, byte_level: bool) -> Vec<Token> {
+        match self {
+            HFModel::WordPiece {
+                max_input_chars_per_word,
+            } => tokenize_wordpiece(token, vocab, *max_input_chars_per_word),
+            HFModel::Bpe {
+                bpe_ranks,
+                cache,
+                end_of_word_suffix,
+            } => {
+                if *end_of_word_suffix {
+                    split_on_bpe_pairs(token, openai_gpt_bpe, bpe_ranks, cache, byte_level)
+                } else {
+                    split_on_bpe_pairs(token, bpe, bpe_ranks, cache, byte_level)
+                }
+            }
+            HFModel::Unigram { model } => model.tokenize(token),
+        }
+    }
+}
+
+///Reads the `post_processor` section. `BertProcessing` and `RobertaProcessing` are expressed as
+///templates, `ByteLevel` only trims offsets and does not add special tokens. Also returns whether
+///the offsets of the tokens should be trimmed (`trim_offsets` of `RobertaProcessing` and `ByteLevel`).
+fn read_post_processor(
+    value: &Value,
+) -> Result<(Option<TemplateProcessing>, bool), TokenizerError> {
+    let special_token = |key: &str| {
+        value
+            .get(key)
+            .and_then(|special_token| special_token.get(0))
+            .and_then(|token| token.as_str())
+            .ok_or_else(|| {
+                TokenizerError::VocabularyParsingError(format!(
+                    "missing or invalid `{}` special token in post_processor",
+                    key
+                ))
+            })
+    };
+    let post_processor_type = component_type(value, "post_processor")?;
+    let trim_offsets = match post_processor_type {
+        "RobertaProcessing" | "ByteLevel" => get_bool(value, "trim_offsets", true),
+        _ => false,
+    };
+    let template = match post_processor_type {
+        "BertProcessing" => {
+            let (cls, sep) = (special_token("cls")?, special_token("sep")?);
+            Some(TemplateProcessing::from_pieces(
+                vec![
+                    TemplatePiece::special_token(cls, 0),
+                    TemplatePiece::sequence(0, 0),
+                    TemplatePiece::special_token(sep, 0),
+                ],
+                vec![
+                    TemplatePiece::special_token(cls, 0),
+                    TemplatePiece::sequence(0, 0),
+                    TemplatePiece::special_token(sep, 0),
+                    TemplatePiece::sequence(1, 1),
+                    TemplatePiece::special_token(sep, 1),
+                ],
+            )?)
+        }
+        "RobertaProcessing" => {
+            let (cls, sep) = (special_token("cls")?, special_token("sep")?);
+            Some(TemplateProcessing::from_pieces(
+                vec![
+                    TemplatePiece::special_token(cls, 0),
+                    TemplatePiece::sequence(0, 0),
+                    TemplatePiece::special_token(sep, 0),
+                ],
+                vec![
+                    TemplatePiece::special_token(cls, 0),
+                    TemplatePiece::sequence(0, 0),
+                    TemplatePiece::special_token(sep, 0),
+                    TemplatePiece::special_token(sep, 1),
+                    TemplatePiece::sequence(1, 1),
+                    TemplatePiece::special_token(sep, 1),
+                ],
+            )?)
+        }
+        "TemplateProcessing" => Some(read_template_processing(value)?),
+        "ByteLevel" => None,
+        _ => return Err(unsupported("post_processor", post_processor_type)),
+    };
+    Ok((template, trim_offsets))
+}
+
+///Removes the leading and trailing spaces (byte-level `Ġ` or whitespace characters) from the
+///offsets of a token, leaving its text unchanged.
+fn trim_offsets(token: &mut Token) {
+    let is_space = |character: &char| {
+        character.is_whitespace() || UNICODE_TO_BYTES.get(character) == Some(&b' ')
+    };
+    let characters = token.text.chars().collect::<Vec<char>>();
+    if characters.len() != token.reference_offsets.len() {
+        return;
+    }
+    let leading = characters.iter().take_while(|c| is_space(c)).count();
+    if leading == characters.len() {
+        token.offset.begin = token.offset.end;
+        token.reference_offsets.clear();
+        return;
+    }
+    let trailing = characters.iter().rev().take_while(|c| is_space(c)).count();
+    let end = characters.len() - trailing;
+    token.reference_offsets = token.reference_offsets[leading..end].to_vec();
+    token.offset.begin = token.reference_offsets[0];
+    token.offset.end = token.reference_offsets[end - leading - 1] + 1;
+}
+
+#[derive(Debug)]
+enum HFDecoder {
+    WordPiece {
+        prefix: String,
+    },
+    ByteLevel,
+    Metaspace {
+        replacement: char,
+        strip_prefix: bool,
+    },
+    Bpe {
+        suffix: String,
+    },
+}
+
+impl HFDecoder {
+    fn from_json_value(value: &Value) -> Result<HFDecoder, TokenizerError> {
+        let decoder_type = component_type(value, "decoder")?;
+        Ok(match decoder_type {
+            "WordPiece" => HFDecoder::WordPiece {
+                prefix: get_str(value, "prefix").unwrap_or("##").to_owned(),
+            },
+            "ByteLevel" => HFDecoder::ByteLevel,
+            "Metaspace" => HFDecoder::Metaspace {
+                replacement: get_char(value, "replacement", '\u{2581}'),
+                strip_prefix: get_str(value, "prepend_scheme").map_or_else(
+                    || get_bool(value, "add_prefix_space", true),
+                    |v| v != "never",
+                ),
+            },
+            "BPEDecoder" => HFDecoder::Bpe {
+                suffix: get_str(value, "suffix").unwrap_or("</w>").to_owned(),
+            },
+            _ => return Err(unsupported("decoder", decoder_type)),
+        })
+    }
+
+    fn decode(&self, tokens: Vec<String>) -> String {
+        match self {
+            HFDecoder::WordPiece { prefix } => tokens
+                .join(" ")
+                .replace(&format!(" {}", prefix), "")
+                .trim()
+                .to_owned(),
+            HFDecoder::ByteLevel => {
+                let mut bytes = vec![];
+                for character in tokens.concat().chars() {
+                    match UNICODE_TO_BYTES.get(&character) {
+                        Some(byte) => bytes.push(*byte),
+                        None => {
+                            let mut buffer = [0; 4];
+                            bytes.extend_from_slice(character.encode_utf8(&mut buffer).as_bytes())
+                        }
+                    }
+                }
+                String::from_utf8_lossy(&bytes).to_string()
+            }
+            HFDecoder::Metaspace {
+                replacement,
+                strip_prefix,
+            } => {
+                let text = tokens.concat().replace(*replacement, " ");
+                if *strip_prefix && text.starts_with(' ') {
+                    text[1..].to_owned()
+                } else {
+                    text
+                }
+            }
+            HFDecoder::Bpe { suffix } => tokens.concat().replace(suffix, " ").trim_end().to_owned(),
+        }
+    }
+}
+
+/// # Tokenizer loaded from a Hugging Face `tokenizer.json` file
+/// Maps the sections of the file onto the building blocks of the crate:
+/// - normalizer: `BertNormalizer`, `Lowercase`, `StripAccents`, `NFC`, `NFD`, `NFKC`, `NFKD`,
+///   `Strip`, `Replace`, `Prepend`, `Precompiled` (SentencePiece charsmap) and `Sequence`
+/// - pre_tokenizer: `BertPreTokenizer`, `Whitespace`, `WhitespaceSplit`, `Punctuation`, `Digits`,
+///   `ByteLevel`, `Metaspace` and `Sequence`, mapped onto the `PreTokenizer` implementations
+/// - model: `WordPiece`, `BPE` (with optional `</w>` end of word suffix) and `Unigram`
+/// - post_processor: `BertProcessing`, `RobertaProcessing`, `TemplateProcessing` and `ByteLevel`
+///   (the `trim_offsets` option removes the spaces from the token offsets)
+/// - decoder: `WordPiece`, `ByteLevel`, `Metaspace` and `BPEDecoder`
+///
+/// The entries of `added_tokens` are never split by the model. Loading a file containing any other
+/// component fails with a `TokenizerError::UnsupportedComponent` error.
+#[derive(Debug)]
+pub struct HFTokenizer {
+    vocab: Arc<HFVocab>,
+    normalizer: Box<dyn Normalizer>,
+    pre_tokenizer: Box<dyn PreTokenizer>,
+    prefixes: Vec<HFPrefix>,
+    model: HFModel,
+    post_processor: Option<TemplateProcessing>,
+    trim_offsets: bool,
+    decoder: Option<HFDecoder>,
+}
+
+impl HFTokenizer {
+    /// Create a new instance of a `HFTokenizer` from a `tokenizer.json` file.
+    ///
+    /// # Parameters
+    /// - path (`&str`): path to the `tokenizer.json` file
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_tokenizers::preprocessing::tokenizer::hf_tokenizer::HFTokenizer;
+    /// let tokenizer = HFTokenizer::from_file("path/to/tokenizer.json").unwrap();
+    /// ```
+    pub fn from_file(path: &str) -> Result<HFTokenizer, TokenizerError> {
+        let tokenizer_json = read_tokenizer_json_file(path)?;
+        HFTokenizer::from_json_value(&tokenizer_json)
+    }
+
+    /// Create a new instance of a `HFTokenizer` from a reader for the `tokenizer.json` content (e.g.
+    /// a file or a network stream).
+    pub fn from_reader<R: Read>(reader: R) -> Result<HFTokenizer, TokenizerError> {
+        HFTokenizer::from_json_value(&read_tokenizer_json(reader)?)
+    }
+
+    /// Create a new instance of a `HFTokenizer` from a `tokenizer.json` content held in memory
+    /// (e.g. loaded with `include_bytes!`).
+    pub fn from_bytes(bytes: &[u8]) -> Result<HFTokenizer, TokenizerError> {
+        HFTokenizer::from_reader(bytes)
+    }
+
+    /// Create a new instance of a `HFTokenizer` from the parsed content of a `tokenizer.json` file.
+    pub fn from_json_value(tokenizer_json: &Value) -> Result<HFTokenizer, TokenizerError> {
+        let section = |key: &str| tokenizer_json.get(key).filter(|value| !value.is_null());
+        let vocab = Arc::new(HFVocab::from_json_value(tokenizer_json)?);
+        let normalizer = match section("normalizer") {
+            Some(value) => read_normalizer(value)?,
+            None => Box::new(Sequence::default()),
+        };
+        let mut prefixes = vec![];
+        let pre_tokenizer = match section("pre_tokenizer") {
+            Some(value) => read_pre_tokenizer(value, &mut prefixes)?,
+            None => Box::new(PreTokenizerSequence::default()),
+        };
+        let model = HFModel::from_json_value(section("model").ok_or_else(|| {
+            TokenizerError::VocabularyParsingError("missing `model` section".to_owned())
+        })?)?;
+        let (post_processor, trim_offsets) = match section("post_processor") {
+            Some(value) => read_post_processor(value)?,
+            None => (None, false),
+        };
+        if let Some(post_processor) = &post_processor {
+            post_processor.check_special_tokens(vocab.as_ref())?;
+        }
+        let decoder = section("decoder")
+            .map(HFDecoder::from_json_value)
+            .transpose()?;
+        Ok(HFTokenizer {
+            vocab,
+            normalizer,
+            pre_tokenizer,
+            prefixes,
+            model,
+            post_processor,
+            trim_offsets,
+            decoder,
+        })
+    }
+}
+
+impl Tokenizer<HFVocab> for HFTokenizer {
+    fn vocab(&self) -> &HFVocab {
+        self.vocab.as_ref()
+    }
+
+    fn vocab_mut(&mut self) -> &mut HFVocab {
+        Arc::make_mut(&mut self.vocab)
+    }
+
+    fn normalizer(&self) -> &dyn Normalizer {
+        self.normalizer.as_ref()
+    }
+
+    fn set_normalizer(&mut self, normalizer: Box<dyn Normalizer>) {
+        self.normalizer = normalizer;
+    }
+
+    fn pre_tokenizer(&self) -> &dyn PreTokenizer {
+        self.pre_tokenizer.as_ref()
+    }
+
+    ///Replaces the pre-tokenizer read from the `tokenizer.json` file. The transformations of the
+    ///original `pre_tokenizer` section modifying the text (prefix space insertion for `ByteLevel`,
+    ///whitespace replacement for `Metaspace`) are still applied before splitting.
+    fn set_pre_tokenizer(&mut self, pre_tokenizer: Box<dyn PreTokenizer>) {
+        self.pre_tokenizer = pre_tokenizer;
+    }
+
+    fn post_processor(&self) -> Option<&TemplateProcessing> {
+        self.post_processor.as_ref()
+    }
+
+    fn post_processor_mut(&mut self) -> &mut Option<TemplateProcessing> {
+        &mut self.post_processor
+    }
+
+    fn tokenize_to_tokens(&self, initial_token: TokenRef) -> Vec<Token> {
+        let byte_level = self.pre_tokenizer.is_byte_level();
+        let mut tokens: Vec<Token> = vec![];
+        for (index, token) in split_on_added_tokens(
+            initial_token,
+            self.vocab.as_ref(),
+            self.normalizer.is_lowercasing(),
+        )
+        .into_iter()
+        .enumerate()
+        {
+            if token.mask == Mask::Special
+                || token.mask == Mask::Added
+                || token.mask == Mask::Unknown
+            {
+                tokens.push(token.to_owned());
+                continue;
+            }
+            let mut token = token.to_owned();
+            self.normalizer.normalize(&mut token);
+            for prefix in &self.prefixes {
+                prefix.apply(&mut token, index == 0);
+            }
+            for piece in self.pre_tokenizer.pre_tokenize(token.as_ref()) {
+                if piece.text.is_empty() || (!byte_level && piece.text.trim().is_empty()) {
+                    continue;
+                }
+                let mut piece_tokens = self.model.tokenize(piece, self.vocab.as_ref(), byte_level);
+                if self.trim_offsets {
+                    piece_tokens.iter_mut().for_each(trim_offsets);
+                }
+                tokens.extend(piece_tokens);
+            }
+        }
+        fix_mask(&mut tokens);
+        tokens
+    }
+
+    fn convert_tokens_to_string(&self, tokens: Vec<String>) -> String {
+        match &self.decoder {
+            Some(decoder) => decoder.decode(tokens),
+            None => tokens.join(" "),
+        }
+    }
+}
+
+impl MultiThreadedTokenizer<HFVocab> for HFTokenizer {}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessing::tokenizer::base_tokenizer::{
+        Offset, PaddingOptions, TruncationStrategy,
+    };
+    use serde_json::json;
+
+    fn word_piece_json() -> Value {
+        json!({
+            "version": "1.0",
+            "added_tokens": [
+                {"id": 0, "content": "[PAD]", "special": true},
+                {"id": 1, "content": "[UNK]", "special": true},
+                {"id": 2, "content": "[CLS]", "special": true},
+                {"id": 3, "content": "[SEP]", "special": true},
+                {"id": 4, "content": "[MASK]", "special": true}
+            ],
+            "normalizer": {
+                "type": "BertNormalizer",
+                "clean_text": true,
+                "handle_chinese_chars": true,
+                "strip_accents": null,
+                "lowercase": true
+            },
+            "pre_tokenizer": {"type": "BertPreTokenizer"},
+            "post_processor": {
+                "type": "BertProcessing",
+                "sep": ["[SEP]", 3],
+                "cls": ["[CLS]", 2]
+            },
+            "decoder": {"type": "WordPiece", "prefix": "##", "cleanup": true},
+            "model": {
+                "type": "WordPiece",
+                "unk_token": "[UNK]",
+                "continuing_subword_prefix": "##",
+                "max_input_chars_per_word": 100,
+                "vocab": {
+                    "[PAD]": 0, "[UNK]": 1, "[CLS]": 2, "[SEP]": 3, "[MASK]": 4,
+                    "hello": 5, "world": 6, "!": 7, "una": 8, "##ffa": 9, "##ble": 10, "中": 11
+                }
+            }
+        })
+    }
+
+    fn byte_level_bpe_json() -> Value {
+        json!({
+            "added_tokens": [{"id": 0, "content": "<|endoftext|>", "special": true}],
+            "normalizer": null,
+            "pre_tokenizer": {"type": "ByteLevel", "add_prefix_space": false, "trim_offsets": true},
+            "post_processor": {"type": "ByteLevel", "trim_offsets": true},
+            "decoder": {"type": "ByteLevel"},
+            "model": {
+                "type": "BPE",
+                "dropout": null,
+                "unk_token": null,
+                "vocab": {"<|endoftext|>": 0, "hello": 1, "Ġworld": 2, "!": 3},
+                "merges": ["h e", "l l", "he ll", "hell o", "Ġ w", "o r", "Ġw or", "l d", "Ġwor ld"]
+            }
+        })
+    }
+
+    fn unigram_json() -> Value {
+        json!({
+            "added_tokens": [
+                {"id": 0, "content": "<pad>", "special": true},
+                {"id": 1, "content": "</s>", "special": true},
+                {"id": 2, "content": "<unk>", "special": true}
+            ],
+            "normalizer": {"type": "Sequence", "normalizers": [{"type": "NFKC"}, {"type": "Lowercase"}]},
+            "pre_tokenizer": {"type": "Metaspace", "replacement": "\u{2581}", "add_prefix_space": true},
+            "post_processor": null,
+            "decoder": {"type": "Metaspace", "replacement": "\u{2581}", "add_prefix_space": true},
+            "model": {
+                "type": "Unigram",
+                "unk_id": 2,
+                "vocab": [
+                    ["<pad>", 0.0], ["</s>", 0.0], ["<unk>", 0.0],
+                    ["\u{2581}hello", -1.0], ["\u{2581}world", -1.0], ["\u{2581}", -2.0],
+                    ["h", -3.0], ["e", -3.0], ["l", -3.0], ["o", -3.0]
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn test_word_piece_tokenizer() -> anyhow::Result<()> {
+        //        Given
+        let tokenizer = HFTokenizer::from_json_value(&word_piece_json())?;
+
+        //        When
+        let tokens = tokenizer.tokenize("Hello [MASK] unaffable World!中");
+        let encoded = tokenizer.encode(
+            "Hello world!",
+            Some("unaffable"),
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+
+        //        Then
+        assert_eq!(
+            tokens,
+            vec!("hello", "[MASK]", "una", "##ffa", "##ble", "world", "!", "中")
+        );
+        assert_eq!(encoded.token_ids, vec!(2, 5, 6, 7, 3, 8, 9, 10, 3));
+        assert_eq!(encoded.segment_ids, vec!(0, 0, 0, 0, 0, 1, 1, 1, 1));
+        assert_eq!(encoded.special_tokens_mask, vec!(1, 0, 0, 0, 1, 0, 0, 0, 1));
+        assert_eq!(
+            encoded.token_offsets,
+            vec!(
+                None,
+                Some(Offset::new(0, 5)),
+                Some(Offset::new(6, 11)),
+                Some(Offset::new(11, 12)),
+                None,
+                Some(Offset::new(0, 3)),
+                Some(Offset::new(3, 6)),
+                Some(Offset::new(6, 9)),
+                None
+            )
+        );
+        assert_eq!(
+            encoded.word_ids,
+            vec!(
+                None,
+                Some(0),
+                Some(1),
+                Some(2),
+                None,
+                Some(0),
+                Some(0),
+                Some(0),
+                None
+            )
+        );
+        assert_eq!(
+            tokenizer.decode(vec!(2, 5, 8, 9, 10, 6, 3), true, false),
+            "hello unaffable world"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_byte_level_bpe_tokenizer() -> anyhow::Result<()> {
+        //        Given
+        let tokenizer = HFTokenizer::from_json_value(&byte_level_bpe_json())?;
+
+        //        When
+        let encoded = tokenizer.encode(
+            "hello world!<|endoftext|>",
+            None,
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+
+        //        Then
+        assert_eq!(
+            tokenizer.tokenize("hello world!"),
+            vec!("hello", "Ġworld", "!")
+        );
+        assert_eq!(encoded.token_ids, vec!(1, 2, 3, 0));
+        assert_eq!(encoded.special_tokens_mask, vec!(0, 0, 0, 0));
+        assert_eq!(
+            encoded.token_offsets,
+            vec!(
+                Some(Offset::new(0, 5)),
+                Some(Offset::new(6, 11)),
+                Some(Offset::new(11, 12)),
+                Some(Offset::new(12, 25))
+            )
+        );
+        assert_eq!(
+            tokenizer.decode(vec!(1, 2, 3), false, false),
+            "hello world!"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_byte_level_untrimmed_offsets() -> anyhow::Result<()> {
+        //        Given
+        let mut tokenizer_json = byte_level_bpe_json();
+        tokenizer_json["post_processor"]["trim_offsets"] = json!(false);
+        let tokenizer = HFTokenizer::from_json_value(&tokenizer_json)?;
+
+        //        When
+        let encoded = tokenizer.encode(
+            "hello world!",
+            None,
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+
+        //        Then
+        assert_eq!(
+            encoded.token_offsets,
+            vec!(
+                Some(Offset::new(0, 5)),
+                Some(Offset::new(5, 11)),
+                Some(Offset::new(11, 12))
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unigram_tokenizer() -> anyhow::Result<()> {
+        //        Given
+        let tokenizer = HFTokenizer::from_json_value(&unigram_json())?;
+
+        //        When
+        let tokens = tokenizer.tokenize("Hello world hole");
+        let encoded = tokenizer.encode(
+            "Hello world",
+            None,
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+
+        //        Then
+        assert_eq!(
+            tokens,
+            vec!(
+                "\u{2581}hello",
+                "\u{2581}world",
+                "\u{2581}",
+                "h",
+                "o",
+                "l",
+                "e"
+            )
+        );
+        assert_eq!(encoded.token_ids, vec!(3, 4));
+        assert_eq!(
+            encoded.token_offsets,
+            vec!(Some(Offset::new(0, 5)), Some(Offset::new(5, 11)))
+        );
+        assert_eq!(tokenizer.decode(vec!(3, 4), false, false), "hello world");
+        Ok(())
+    }
+
+    #[test]
+    fn test_template_processing() -> anyhow::Result<()> {
+        //        Given
+        let mut tokenizer_json = unigram_json();
+        tokenizer_json["post_processor"] = json!({
+            "type": "TemplateProcessing",
+            "single": [
+                {"Sequence": {"id": "A", "type_id": 0}},
+                {"SpecialToken": {"id": "</s>", "type_id": 0}}
+            ],
+            "pair": [
+                {"Sequence": {"id": "A", "type_id": 0}},
+                {"SpecialToken": {"id": "</s>", "type_id": 0}},
+                {"Sequence": {"id": "B", "type_id": 1}},
+                {"SpecialToken": {"id": "</s>", "type_id": 1}}
+            ],
+            "special_tokens": {"</s>": {"id": "</s>", "ids": [1], "tokens": ["</s>"]}}
+        });
+        let tokenizer = HFTokenizer::from_json_value(&tokenizer_json)?;
+
+        //        When
+        let encoded = tokenizer.encode(
+            "Hello world",
+            None,
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+        let encoded_pair = tokenizer.encode(
+            "Hello",
+            Some("world"),
+            128,
+            &TruncationStrategy::LongestFirst,
+            0,
+            &PaddingOptions::default(),
+        );
+
+        //        Then
+        assert_eq!(encoded.token_ids, vec!(3, 4, 1));
+        assert_eq!(encoded.special_tokens_mask, vec!(0, 0, 1));
+        assert_eq!(encoded_pair.token_ids, vec!(3, 1, 4, 1));
+        assert_eq!(encoded_pair.segment_ids, vec!(0, 0, 1, 1));
+        assert_eq!(
+            encoded_pair.token_offsets,
+            vec!(Some(Offset::new(0, 5)), None, Some(Offset::new(0, 5)), None)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_precompiled_normalizer() -> anyhow::Result<()> {
+        //        Given
+        let mut empty_charsmap = unigram_json();
+        empty_charsmap["normalizer"] = json!({"type": "Precompiled", "precompiled_charsmap": ""});
+        let mut identity_charsmap = unigram_json();
+        identity_charsmap["normalizer"] = json!({
+            "type": "Precompiled",
+            "precompiled_charsmap": base64::encode(&[4, 0, 0, 0, 0, 0, 0, 0])
+        });
+        let mut invalid_charsmap = unigram_json();
+        invalid_charsmap["normalizer"] =
+            json!({"type": "Precompiled", "precompiled_charsmap": "not base64!"});
+
+        //        When
+        let empty_charsmap_tokenizer = HFTokenizer::from_json_value(&empty_charsmap)?;
+        let identity_charsmap_tokenizer = HFTokenizer::from_json_value(&identity_charsmap)?;
+        let invalid_result = HFTokenizer::from_json_value(&invalid_charsmap);
+
+        //        Then
+        assert_eq!(
+            empty_charsmap_tokenizer.tokenize("hello world"),
+            identity_charsmap_tokenizer.tokenize("hello world")
+        );
+        assert!(matches!(
+            invalid_result,
+            Err(TokenizerError::VocabularyParsingError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_components() {
+        //        Given
+        let mut bpe_dropout = byte_level_bpe_json();
+        bpe_dropout["model"]["dropout"] = json!(0.1);
+
+        //        When
+        let dropout_result = HFTokenizer::from_json_value(&bpe_dropout);
+
+        //        Then
+        assert!(matches!(
+            dropout_result,
+            Err(TokenizerError::UnsupportedComponent(_))
+        ));
+    }
+
+    #[test]
+    fn test_post_processor_unknown_special_token() {
+        //        Given
+        let mut tokenizer_json = word_piece_json();
+        tokenizer_json["post_processor"]["cls"] = json!(["<cls>", 2]);
+
+        //        When
+        let result = HFTokenizer::from_json_value(&tokenizer_json);
+
+        //        Then
+        assert!(matches!(result, Err(TokenizerError::TokenNotFound(_))));
+    }
+}
@@ -12,21 +12,25 @@
 // limitations under the License.
 
 use crate::preprocessing::error::TokenizerError;
-use crate::preprocessing::tokenizer::base_tokenizer::{Mask, Offset, OffsetSize, Token, TokenRef};
+use crate::preprocessing::tokenizer::base_tokenizer::{Mask, Offset, Token, TokenRef};
+use crate::preprocessing::tokenizer::normalizer::{sentence_piece_normalizer, Normalizer};
+use crate::preprocessing::tokenizer::post_processor::{TemplatePiece, TemplateProcessing};
+use crate::preprocessing::tokenizer::pre_tokenizer::{PreTokenizer, Sequence};
 use crate::preprocessing::vocab::marian_vocab::MarianVocab;
 use crate::preprocessing::vocab::sentence_piece_vocab::SentencePieceModel;
-use crate::tokenization_utils::{
-    _clean_text, decompose_nfkc, is_whitespace, lowercase, split_at_regex,
-};
+use crate::tokenization_utils::{merge_byte_pieces, split_at_regex, split_on_added_tokens};
 use crate::{MultiThreadedTokenizer, Tokenizer, Vocab};
 use regex::Regex;
+use std::io::Read;
 
 #[derive(Debug)]
 pub struct MarianTokenizer {
     model: SentencePieceModel,
     vocab: MarianVocab,
     pattern_language_code: Regex,
-    lower_case: bool,
+    normalizer: Box<dyn Normalizer>,
+    pre_tokenizer: Box<dyn PreTokenizer>,
+    post_processor: Option<TemplateProcessing>,
 }
 
 impl MarianTokenizer {
@@ -38,25 +42,58 @@ impl MarianTokenizer {
         let vocab = MarianVocab::from_file(vocab_path)?;
         let model = SentencePieceModel::from_file(model_path)?;
         let pattern_language_code = Regex::new(r">>.+<<").unwrap();
+        let normalizer =
+            sentence_piece_normalizer(lower_case, false, false, model.normalizer.as_ref());
         Ok(MarianTokenizer {
             model,
             vocab,
             pattern_language_code,
-            lower_case,
+            normalizer: Box::new(normalizer),
+            pre_tokenizer: Box::new(Sequence::default()),
+            post_processor: None,
         })
     }
 
+    /// Create a new instance of a `MarianTokenizer` from readers for the vocabulary and the
+    /// SentencePiece model (e.g. files or network streams), with the same content as the files
+    /// expected by `from_files`.
+    pub fn from_readers<R: Read, S: Read>(
+        vocab: R,
+        model: S,
+        lower_case: bool,
+    ) -> Result<MarianTokenizer, TokenizerError> {
+        let vocab = MarianVocab::from_reader(vocab)?;
+        let model = SentencePieceModel::from_reader(model)?;
+        Ok(MarianTokenizer::from_existing_vocab_and_model(
+            vocab, model, lower_case,
+        ))
+    }
+
+    /// Create a new instance of a `MarianTokenizer` from the vocabulary and SentencePiece model
+    /// held in memory (e.g. loaded with `include_bytes!`).
+    pub fn from_bytes(
+        vocab: &[u8],
+        model: &[u8],
+        lower_case: bool,
+    ) -> Result<MarianTokenizer, TokenizerError> {
+        MarianTokenizer::from_readers(vocab, model, lower_case)
+    }
+
     pub fn from_existing_vocab_and_model(
         vocab: MarianVocab,
         model: SentencePieceModel,
         lower_case: bool,
     ) -> MarianTokenizer {
         let pattern_language_code = Regex::new(r">>.+<<").unwrap();
+        let normalizer =
+            sentence_piece_normalizer(lower_case, false, false, model.normalizer.as_ref());
         MarianTokenizer {
             model,
             vocab,
             pattern_language_code,
-            lower_case,
+            normalizer: Box::new(normalizer),
+            pre_tokenizer: Box::new(Sequence::default()),
+            post_processor: None,
         }
     }
 }
@@ -66,9 +103,37 @@ impl Tokenizer<MarianVocab> for MarianTokenizer {
         &self.vocab
     }
 
+    fn vocab_mut(&mut self) -> &mut MarianVocab {
+        &mut self.vocab
+    }
+
+    fn normalizer(&self) -> &dyn Normalizer {
+        self.normalizer.as_ref()
+    }
+
+    fn set_normalizer(&mut self, normalizer: Box<dyn Normalizer>) {
+        self.normalizer = normalizer;
+    }
+
+    fn pre_tokenizer(&self) -> &dyn PreTokenizer {
+        self.pre_tokenizer.as_ref()
+    }
+
+    fn set_pre_tokenizer(&mut self, pre_tokenizer: Box<dyn PreTokenizer>) {
+        self.pre_tokenizer = pre_tokenizer;
+    }
+
+    fn post_processor(&self) -> Option<&TemplateProcessing> {
+        self.post_processor.as_ref()
+    }
+
+    fn post_processor_mut(&mut self) -> &mut Option<TemplateProcessing> {
+        &mut self.post_processor
+    }
+
     fn tokenize_to_tokens(&self, text: TokenRef) -> Vec<Token> {
         let tokens = split_at_regex(text, &self.pattern_language_code);
-        let (code_token, mut token) = match tokens.len() {
+        let (code_token, token) = match tokens.len() {
             0 => {
                 return vec![];
             }
@@ -87,127 +152,87 @@ impl Tokenizer<MarianVocab> for MarianTokenizer {
             }
         };
 
-        _clean_text(&mut token, true);
-        decompose_nfkc(&mut token);
-        if self.lower_case {
-            lowercase(&mut token);
-        }
-        token.text = token.text.replace(|c: char| is_whitespace(&c), "\u{2581}");
-        if !token.text.starts_with('\u{2581}') {
-            token.text.insert(0, '\u{2581}');
-            token.reference_offsets.insert(0, 0);
-        };
-        let output = self.model.decode_forward_token_ref(token.as_ref());
-        let decoded = self.model.decode_backward(&output);
-
-        let mut output: Vec<Token> = Vec::with_capacity(decoded.len() + 1);
+        let mut output: Vec<Token> = Vec::new();
         if let Some(code) = code_token {
             output.push(code);
         };
-        let mut is_prev_unknown = false;
-        for node in decoded {
-            // Group unknown tokens
-            if is_prev_unknown & (node.index == 0) {
-                let prev_token = output.last().unwrap();
-                let mut text = prev_token.text.clone();
-                text.push_str(node.text);
-                let mut reference_offsets = prev_token.reference_offsets.clone();
-                reference_offsets.extend_from_slice(node.reference_offsets);
-                let consolidated_unknown = Token {
-                    text,
-                    offset: Offset { begin: 0, end: 0 },
-                    reference_offsets,
-                    mask: Default::default(),
-                };
-                output.pop();
-                output.push(consolidated_unknown);
-            } else {
-                output.push(Token {
-                    text: node.text.to_owned(),
-                    offset: Offset { begin: 0, end: 0 },
-                    reference_offsets: node.reference_offsets.to_vec(),
-                    mask: Default::default(),
-                });
+        let mut preserved_masks: Vec<(usize, Mask)> = Vec::new();
+        for sub_token in split_on_added_tokens(
+            token.as_ref(),
+            &self.vocab,
+            self.normalizer.is_lowercasing(),
+        ) {
+            if sub_token.mask == Mask::Special
+                || sub_token.mask == Mask::Added
+                || sub_token.mask == Mask::Unknown
+            {
+                preserved_masks.push((output.len(), sub_token.mask));
+                output.push(sub_token.to_owned());
+                continue;
+            }
+            let mut token = sub_token.to_owned();
+            self.normalizer.normalize(&mut token);
+            for token in self.pre_tokenizer.pre_tokenize(token.as_ref()) {
+                let decoded = self.model.decode(token);
+
+                let mut is_prev_unknown = false;
+                for node in decoded {
+                    // Group unknown tokens
+                    if is_prev_unknown & node.unknown {
+                        let prev_token = output.last().unwrap();
+                        let mut text = prev_token.text.clone();
+                        text.push_str(node.text);
+                        let mut reference_offsets = prev_token.reference_offsets.clone();
+                        reference_offsets.extend_from_slice(node.reference_offsets);
+                        let consolidated_unknown = Token {
+                            text,
+                            offset: Offset { begin: 0, end: 0 },
+                            reference_offsets,
+                            mask: Default::default(),
+                        };
+                        output.pop();
+                        output.push(consolidated_unknown);
+                    } else {
+                        output.push(Token {
+                            text: node.text.to_owned(),
+                            offset: Offset { begin: 0, end: 0 },
+                            reference_offsets: node.reference_offsets.to_vec(),
+                            mask: Default::default(),
+                        });
+                    }
+                    is_prev_unknown = node.unknown;
+                }
             }
-            is_prev_unknown = node.index == 0;
         }
         self.model.populate_masks(output.as_mut_slice(), '\u{2581}');
+        for (position, mask) in preserved_masks {
+            output[position].mask = mask;
+        }
         output
     }
 
     fn convert_tokens_to_string(&self, tokens: Vec<String>) -> String {
-        tokens
+        merge_byte_pieces(tokens)
             .into_iter()
             .map(|v| v.replace('\u{2581}', " "))
             .collect::<Vec<String>>()
             .join("")
     }
 
-    fn build_input_with_special_tokens(
-        &self,
-        tokens_1: Vec<i64>,
-        tokens_2: Option<Vec<i64>>,
-        offsets_1: Vec<Option<Offset>>,
-        offsets_2: Option<Vec<Option<Offset>>>,
-        original_offsets_1: Vec<Vec<OffsetSize>>,
-        original_offsets_2: Option<Vec<Vec<OffsetSize>>>,
-        mask_1: Vec<Mask>,
-        mask_2: Option<Vec<Mask>>,
-    ) -> (
-        Vec<i64>,
-        Vec<i8>,
-        Vec<i8>,
-        Vec<Option<Offset>>,
-        Vec<Vec<OffsetSize>>,
-        Vec<Mask>,
-    ) {
-        let mut output: Vec<i64> = vec![];
-        let mut token_segment_ids: Vec<i8> = vec![];
-        let mut special_tokens_mask: Vec<i8> = vec![];
-        let mut offsets: Vec<Option<Offset>> = vec![];
-        let mut original_offsets: Vec<Vec<OffsetSize>> = vec![];
-        let mut mask: Vec<Mask> = vec![];
-        special_tokens_mask.extend(vec![0; tokens_1.len()]);
-        token_segment_ids.extend(vec![0; tokens_1.len()]);
-        output.extend(tokens_1);
-        offsets.extend(offsets_1);
-        original_offsets.extend(original_offsets_1);
-        mask.extend(mask_1);
-
-        if let Some(add_tokens) = tokens_2 {
-            let length = add_tokens.len();
-            special_tokens_mask.extend(vec![0; length]);
-            token_segment_ids.extend(vec![1; length + 1]);
-            output.extend(add_tokens);
-            if let Some(add_offsets) = offsets_2 {
-                offsets.extend(add_offsets);
-            } else {
-                offsets.extend(vec![None; length]);
-            }
-            if let Some(add_original_offsets) = original_offsets_2 {
-                original_offsets.extend(add_original_offsets);
-            }
-            if let Some(mask_2) = mask_2 {
-                mask.extend(mask_2)
-            } else {
-                mask.extend(vec![Mask::None; length]);
-            }
-        }
-        special_tokens_mask.push(1);
-        token_segment_ids.push(1);
-        output.push(self.vocab.token_to_id(MarianVocab::eos_value()));
-        offsets.push(None);
-        original_offsets.push(vec![]);
-        mask.push(Mask::Special);
-
-        (
-            output,
-            token_segment_ids,
-            special_tokens_mask,
-            offsets,
-            original_offsets,
-            mask,
+    fn default_post_processor(&self) -> TemplateProcessing {
+        let eos = self.vocab.get_eos_value();
+        TemplateProcessing::from_pieces(
+            vec![
+                TemplatePiece::sequence(0, 0),
+                TemplatePiece::special_token(eos, 0),
+            ],
+            vec![
+                TemplatePiece::sequence(0, 0),
+                TemplatePiece::sequence(1, 1),
+                TemplatePiece::special_token(eos, 1),
+            ],
         )
+        .unwrap()
     }
 }
 
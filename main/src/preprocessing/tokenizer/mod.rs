@@ -10,16 +10,24 @@
 // limitations under the License.
 
 pub mod albert_tokenizer;
+pub mod any_tokenizer;
+pub mod auto_tokenizer;
 pub mod base_tokenizer;
 pub mod bert_tokenizer;
+pub mod bpe_cache;
 mod constants;
 pub mod ctrl_tokenizer;
 pub mod gpt2_tokenizer;
+pub mod hf_tokenizer;
 pub mod marian_tokenizer;
+pub mod normalizer;
 pub mod openai_gpt_tokenizer;
+pub mod post_processor;
+pub mod pre_tokenizer;
 pub mod roberta_tokenizer;
 pub mod sentence_piece_tokenizer;
 pub mod t5_tokenizer;
 pub mod tokenization_utils;
+pub mod tokenizer_json;
 pub mod xlm_roberta_tokenizer;
 pub mod xlnet_tokenizer;
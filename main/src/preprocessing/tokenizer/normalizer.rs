@@ -0,0 +1,779 @@
+// Copyright 2019-2020 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::base_tokenizer::{OffsetSize, Token};
+use crate::preprocessing::tokenizer::tokenization_utils::{
+    _clean_text, compose_nfc, decompose_nfd, decompose_nfkc, decompose_nfkd, insert_prefix,
+    is_whitespace, lowercase, pad_cjk_chars, replace_regex, replace_string, strip_accents,
+    strip_whitespace,
+};
+use crate::preprocessing::vocab::sentencepiece_proto::sentencepiece_model::NormalizerSpec;
+use regex::Regex;
+use std::convert::TryInto;
+use std::fmt;
+use std::fmt::Debug;
+
+/// # Normalizer
+/// Transformation applied to the text of a token before the tokenization model runs. Normalizers
+/// keep the `reference_offsets` of the token aligned with its characters, so that the offsets of
+/// the resulting sub-tokens still point to the original text.
+///
+/// Normalizers are not applied to special, added and unknown tokens. They can be chained with a
+/// `Sequence` and set on a tokenizer with `Tokenizer::set_normalizer`.
+pub trait Normalizer: Debug + Send + Sync {
+    ///Normalizes the text of a token in place
+    fn normalize(&self, token: &mut Token);
+
+    ///Returns true if the normalizer lower cases the text. Tokens added to the vocabulary are then
+    ///matched regardless of their case.
+    fn is_lowercasing(&self) -> bool {
+        false
+    }
+}
+
+/// # BERT normalizer
+/// Normalization of the original BERT implementation: text cleaning, padding of CJK characters
+/// with whitespaces, accents removal and lower casing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BertNormalizer {
+    pub clean_text: bool,
+    pub handle_chinese_chars: bool,
+    pub strip_accents: bool,
+    pub lowercase: bool,
+}
+
+impl Normalizer for BertNormalizer {
+    fn normalize(&self, token: &mut Token) {
+        if self.clean_text {
+            _clean_text(token, true);
+        }
+        if self.handle_chinese_chars {
+            pad_cjk_chars(token);
+        }
+        if self.strip_accents {
+            strip_accents(token);
+        }
+        if self.lowercase {
+            lowercase(token);
+        }
+    }
+
+    fn is_lowercasing(&self) -> bool {
+        self.lowercase
+    }
+}
+
+/// # Text cleaning
+/// Removes control characters and replaces whitespace characters by a space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CleanText;
+
+impl Normalizer for CleanText {
+    fn normalize(&self, token: &mut Token) {
+        _clean_text(token, true);
+    }
+}
+
+/// # Lower casing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lowercase;
+
+impl Normalizer for Lowercase {
+    fn normalize(&self, token: &mut Token) {
+        lowercase(token);
+    }
+
+    fn is_lowercasing(&self) -> bool {
+        true
+    }
+}
+
+/// # Accents removal
+/// Decomposes the characters (NFD) and removes the combining diacritical marks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StripAccents;
+
+impl Normalizer for StripAccents {
+    fn normalize(&self, token: &mut Token) {
+        strip_accents(token);
+    }
+}
+
+/// # NFC unicode normalization
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nfc;
+
+impl Normalizer for Nfc {
+    fn normalize(&self, token: &mut Token) {
+        compose_nfc(token);
+    }
+}
+
+/// # NFD unicode normalization
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nfd;
+
+impl Normalizer for Nfd {
+    fn normalize(&self, token: &mut Token) {
+        decompose_nfd(token);
+    }
+}
+
+/// # NFKC unicode normalization
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nfkc;
+
+impl Normalizer for Nfkc {
+    fn normalize(&self, token: &mut Token) {
+        decompose_nfkc(token);
+    }
+}
+
+/// # NFKD unicode normalization
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nfkd;
+
+impl Normalizer for Nfkd {
+    fn normalize(&self, token: &mut Token) {
+        decompose_nfkd(token);
+    }
+}
+
+/// # Whitespace stripping
+/// Removes the leading (`left`) and/or trailing (`right`) whitespaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Strip {
+    pub left: bool,
+    pub right: bool,
+}
+
+impl Normalizer for Strip {
+    fn normalize(&self, token: &mut Token) {
+        strip_whitespace(token, self.left, self.right);
+    }
+}
+
+/// # String replacement
+/// Replaces all occurrences of a string by another string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replace {
+    pub pattern: String,
+    pub content: String,
+}
+
+impl Replace {
+    pub fn new(pattern: &str, content: &str) -> Replace {
+        Replace {
+            pattern: pattern.to_owned(),
+            content: content.to_owned(),
+        }
+    }
+}
+
+impl Normalizer for Replace {
+    fn normalize(&self, token: &mut Token) {
+        replace_string(token, &self.pattern, &self.content);
+    }
+}
+
+/// # Regular expression replacement
+/// Replaces all matches of a regular expression by a string (inserted literally)
+#[derive(Debug, Clone)]
+pub struct RegexReplace {
+    pub pattern: Regex,
+    pub content: String,
+}
+
+impl RegexReplace {
+    pub fn new(pattern: Regex, content: &str) -> RegexReplace {
+        RegexReplace {
+            pattern,
+            content: content.to_owned(),
+        }
+    }
+}
+
+impl Normalizer for RegexReplace {
+    fn normalize(&self, token: &mut Token) {
+        replace_regex(token, &self.pattern, &self.content);
+    }
+}
+
+/// # Prefix insertion
+/// Inserts a prefix at the beginning of non-empty texts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prepend {
+    pub prefix: String,
+}
+
+impl Normalizer for Prepend {
+    fn normalize(&self, token: &mut Token) {
+        if !token.text.is_empty() {
+            insert_prefix(token, &self.prefix);
+        }
+    }
+}
+
+/// # Metaspace
+/// Replaces the whitespaces by a meta symbol (`▁` for SentencePiece models), optionally adding the
+/// meta symbol at the beginning of the text if it does not start with one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metaspace {
+    pub replacement: char,
+    pub add_prefix_space: bool,
+}
+
+impl Default for Metaspace {
+    fn default() -> Self {
+        Metaspace {
+            replacement: '\u{2581}',
+            add_prefix_space: true,
+        }
+    }
+}
+
+impl Normalizer for Metaspace {
+    fn normalize(&self, token: &mut Token) {
+        let replacement = self.replacement.to_string();
+        token.text = token
+            .text
+            .replace(|c: char| is_whitespace(&c), &replacement);
+        if self.add_prefix_space && !token.text.starts_with(self.replacement) {
+            insert_prefix(token, &replacement);
+        }
+    }
+}
+
+/// # Sequence of normalizers
+/// Applies a list of normalizers in order
+#[derive(Debug, Default)]
+pub struct Sequence {
+    pub normalizers: Vec<Box<dyn Normalizer>>,
+}
+
+impl Sequence {
+    pub fn new(normalizers: Vec<Box<dyn Normalizer>>) -> Sequence {
+        Sequence { normalizers }
+    }
+}
+
+impl Normalizer for Sequence {
+    fn normalize(&self, token: &mut Token) {
+        for normalizer in &self.normalizers {
+            normalizer.normalize(token);
+        }
+    }
+
+    fn is_lowercasing(&self) -> bool {
+        self.normalizers
+            .iter()
+            .any(|normalizer| normalizer.is_lowercasing())
+    }
+}
+
+/// # Precompiled SentencePiece normalization rules
+/// Character mapping rules compiled by SentencePiece into the `precompiled_charsmap` of a model
+/// (e.g. `nmt_nfkc` or `nmt_nfkc_cf`). The charsmap holds a double-array trie of the source
+/// sequences (bytes) followed by the null-terminated replacement strings. At each position of the
+/// text, the longest source sequence found in the trie is replaced.
+#[derive(Clone)]
+pub struct Precompiled {
+    trie: Vec<u32>,
+    normalized: String,
+}
+
+impl Debug for Precompiled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Precompiled {{ trie units: {}, normalized bytes: {} }}",
+            self.trie.len(),
+            self.normalized.len()
+        )
+    }
+}
+
+impl Precompiled {
+    ///Reads the charsmap from the `precompiled_charsmap` of a SentencePiece model: the size in
+    ///bytes of the trie (little endian `u32`), the trie units and the replacement strings.
+    pub fn from_bytes(precompiled_charsmap: &[u8]) -> Result<Precompiled, TokenizerError> {
+        let invalid = |reason: &str| {
+            TokenizerError::VocabularyParsingError(format!(
+                "invalid precompiled charsmap: {}",
+                reason
+            ))
+        };
+        if precompiled_charsmap.len() < 4 {
+            return Err(invalid("missing trie size"));
+        }
+        let (trie_size, data) = precompiled_charsmap.split_at(4);
+        let trie_size = u32::from_le_bytes(trie_size.try_into().unwrap()) as usize;
+        if trie_size > data.len() {
+            return Err(invalid("inconsistent trie size"));
+        }
+        let (trie, normalized) = data.split_at(trie_size);
+        let units = trie.chunks_exact(4);
+        if !units.remainder().is_empty() {
+            return Err(invalid("inconsistent trie size"));
+        }
+        let trie = units
+            .map(|unit| u32::from_le_bytes(unit.try_into().unwrap()))
+            .collect();
+        let normalized = String::from_utf8(normalized.to_vec())
+            .map_err(|_| invalid("replacement strings are not valid UTF-8"))?;
+        Ok(Precompiled { trie, normalized })
+    }
+
+    ///Returns the charsmap in the `precompiled_charsmap` format read by `from_bytes`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut precompiled_charsmap =
+            Vec::with_capacity(4 + self.trie.len() * 4 + self.normalized.len());
+        precompiled_charsmap.extend_from_slice(&((self.trie.len() * 4) as u32).to_le_bytes());
+        for unit in &self.trie {
+            precompiled_charsmap.extend_from_slice(&unit.to_le_bytes());
+        }
+        precompiled_charsmap.extend_from_slice(self.normalized.as_bytes());
+        precompiled_charsmap
+    }
+
+    ///Returns the replacement string offsets and the lengths (in bytes) of the prefixes of `key`
+    ///found in the trie, from the shortest to the longest
+    fn common_prefix_search(&self, key: &[u8]) -> Vec<(usize, usize)> {
+        let offset = |unit: u32| ((unit >> 10) << ((unit & (1 << 9)) >> 6)) as usize;
+        let label = |unit: u32| unit & ((1 << 31) | 0xFF);
+        let has_leaf = |unit: u32| (unit >> 8) & 1 == 1;
+        let value = |unit: u32| (unit & ((1 << 31) - 1)) as usize;
+
+        let mut results = vec![];
+        let mut node_position = match self.trie.first() {
+            Some(&unit) => offset(unit),
+            None => return results,
+        };
+        for (length, &byte) in key.iter().enumerate() {
+            node_position ^= byte as usize;
+            let unit = match self.trie.get(node_position) {
+                Some(&unit) if label(unit) == byte as u32 => unit,
+                _ => break,
+            };
+            node_position ^= offset(unit);
+            if has_leaf(unit) {
+                match self.trie.get(node_position) {
+                    Some(&leaf) => results.push((value(leaf), length + 1)),
+                    None => break,
+                }
+            }
+        }
+        results
+    }
+
+    ///Normalizes the beginning of the text, returning the normalized string and the number of
+    ///bytes consumed. Characters without rule are returned unchanged.
+    pub fn normalize_prefix<'a>(&'a self, text: &'a str) -> (&'a str, usize) {
+        for (value, length) in self.common_prefix_search(text.as_bytes()).into_iter().rev() {
+            if !text.is_char_boundary(length) {
+                continue;
+            }
+            if let Some(normalized) = self.normalized.get(value..) {
+                let end = normalized.find('\0').unwrap_or(normalized.len());
+                return (&normalized[..end], length);
+            }
+        }
+        let length = text
+            .chars()
+            .next()
+            .map_or(0, |character| character.len_utf8());
+        (&text[..length], length)
+    }
+}
+
+impl Normalizer for Precompiled {
+    fn normalize(&self, token: &mut Token) {
+        let mut normalized = String::with_capacity(token.text.len());
+        let mut reference_offsets = Vec::with_capacity(token.reference_offsets.len());
+        let mut byte_position = 0;
+        let mut char_position = 0;
+        while byte_position < token.text.len() {
+            let (piece, length) = self.normalize_prefix(&token.text[byte_position..]);
+            let reference_offset = reference_offset_at(token, char_position);
+            normalized.push_str(piece);
+            reference_offsets.extend(piece.chars().map(|_| reference_offset));
+            char_position += token.text[byte_position..byte_position + length]
+                .chars()
+                .count();
+            byte_position += length;
+        }
+        token.text = normalized;
+        token.reference_offsets = reference_offsets;
+        token.offset.begin = *token
+            .reference_offsets
+            .first()
+            .unwrap_or(&(0 as OffsetSize));
+        token.offset.end = *token.reference_offsets.last().unwrap_or(&(0 as OffsetSize)) + 1;
+    }
+}
+
+fn reference_offset_at(token: &Token, char_position: usize) -> OffsetSize {
+    token
+        .reference_offsets
+        .get(char_position)
+        .or_else(|| token.reference_offsets.last())
+        .copied()
+        .unwrap_or(0)
+}
+
+/// # SentencePiece normalizer
+/// Normalization described by the `NormalizerSpec` of a SentencePiece model, reproducing the
+/// reference implementation:
+/// - precompiled_charsmap: character mapping rules (identity if `None`)
+/// - add_dummy_prefix: inserts a whitespace at the beginning of the text
+/// - remove_extra_whitespaces: removes the leading, trailing and consecutive whitespaces
+/// - escape_whitespaces: replaces the whitespaces by the `▁` meta symbol
+#[derive(Debug, Clone)]
+pub struct SentencePieceNormalizer {
+    pub precompiled_charsmap: Option<Precompiled>,
+    pub add_dummy_prefix: bool,
+    pub remove_extra_whitespaces: bool,
+    pub escape_whitespaces: bool,
+}
+
+impl Default for SentencePieceNormalizer {
+    fn default() -> Self {
+        SentencePieceNormalizer {
+            precompiled_charsmap: None,
+            add_dummy_prefix: true,
+            remove_extra_whitespaces: true,
+            escape_whitespaces: true,
+        }
+    }
+}
+
+impl SentencePieceNormalizer {
+    ///Reads the normalizer from the `normalizer_spec` of a SentencePiece `ModelProto`
+    pub fn from_proto(spec: &NormalizerSpec) -> Result<SentencePieceNormalizer, TokenizerError> {
+        let precompiled_charsmap = if spec.get_precompiled_charsmap().is_empty() {
+            None
+        } else {
+            Some(Precompiled::from_bytes(spec.get_precompiled_charsmap())?)
+        };
+        Ok(SentencePieceNormalizer {
+            precompiled_charsmap,
+            add_dummy_prefix: spec.get_add_dummy_prefix(),
+            remove_extra_whitespaces: spec.get_remove_extra_whitespaces(),
+            escape_whitespaces: spec.get_escape_whitespaces(),
+        })
+    }
+
+    fn normalize_prefix<'a>(&'a self, text: &'a str) -> (&'a str, usize) {
+        match &self.precompiled_charsmap {
+            Some(precompiled_charsmap) => precompiled_charsmap.normalize_prefix(text),
+            None => {
+                let length = text
+                    .chars()
+                    .next()
+                    .map_or(0, |character| character.len_utf8());
+                (&text[..length], length)
+            }
+        }
+    }
+}
+
+impl Normalizer for SentencePieceNormalizer {
+    fn normalize(&self, token: &mut Token) {
+        let space = if self.escape_whitespaces {
+            '\u{2581}'
+        } else {
+            ' '
+        };
+        let mut byte_position = 0;
+        let mut char_position = 0;
+        if self.remove_extra_whitespaces {
+            while byte_position < token.text.len() {
+                let (piece, length) = self.normalize_prefix(&token.text[byte_position..]);
+                if piece != " " {
+                    break;
+                }
+                char_position += token.text[byte_position..byte_position + length]
+                    .chars()
+                    .count();
+                byte_position += length;
+            }
+        }
+
+        let mut normalized = String::with_capacity(token.text.len() + space.len_utf8());
+        let mut reference_offsets = Vec::with_capacity(token.reference_offsets.len() + 1);
+        if byte_position < token.text.len() && self.add_dummy_prefix {
+            normalized.push(space);
+            reference_offsets.push(reference_offset_at(token, char_position));
+        }
+        let mut is_previous_space = self.remove_extra_whitespaces;
+        while byte_position < token.text.len() {
+            let (mut piece, length) = self.normalize_prefix(&token.text[byte_position..]);
+            if self.remove_extra_whitespaces && is_previous_space {
+                piece = piece.trim_start_matches(' ');
+            }
+            if !piece.is_empty() {
+                let reference_offset = reference_offset_at(token, char_position);
+                for character in piece.chars() {
+                    normalized.push(if character == ' ' { space } else { character });
+                    reference_offsets.push(reference_offset);
+                }
+                is_previous_space = piece.ends_with(' ');
+            }
+            char_position += token.text[byte_position..byte_position + length]
+                .chars()
+                .count();
+            byte_position += length;
+        }
+        if self.remove_extra_whitespaces {
+            while normalized.ends_with(space) {
+                normalized.pop();
+                reference_offsets.pop();
+            }
+        }
+        token.text = normalized;
+        token.reference_offsets = reference_offsets;
+        token.offset.begin = *token
+            .reference_offsets
+            .first()
+            .unwrap_or(&(0 as OffsetSize));
+        token.offset.end = *token.reference_offsets.last().unwrap_or(&(0 as OffsetSize)) + 1;
+    }
+}
+
+///Lower casing and accents removal applied to words by the BERT-like tokenizers
+pub(crate) fn base_normalizer(lower_case: bool, strip_accents: bool) -> Sequence {
+    let mut normalizers: Vec<Box<dyn Normalizer>> = vec![];
+    if lower_case {
+        normalizers.push(Box::new(Lowercase));
+    }
+    if strip_accents {
+        normalizers.push(Box::new(StripAccents));
+    }
+    Sequence::new(normalizers)
+}
+
+///Normalization of the tokenizers relying on a SentencePiece model, optionally replacing the ``
+///and '' quotes by " (ALBERT and XLNet). The `NormalizerSpec` read from the model is used if
+///available, otherwise the text is NFKC-normalized and the whitespaces replaced by `▁`.
+pub(crate) fn sentence_piece_normalizer(
+    lower_case: bool,
+    strip_accents: bool,
+    replace_quotes: bool,
+    model_normalizer: Option<&SentencePieceNormalizer>,
+) -> Sequence {
+    let mut normalizers: Vec<Box<dyn Normalizer>> = vec![];
+    if replace_quotes {
+        normalizers.push(Box::new(Replace::new("``", "\"")));
+        normalizers.push(Box::new(Replace::new("''", "\"")));
+    }
+    match model_normalizer {
+        Some(model_normalizer) => {
+            normalizers.extend(base_normalizer(lower_case, strip_accents).normalizers);
+            normalizers.push(Box::new(model_normalizer.clone()));
+        }
+        None => {
+            normalizers.push(Box::new(CleanText));
+            normalizers.push(Box::new(Nfkc));
+            normalizers.extend(base_normalizer(lower_case, strip_accents).normalizers);
+            normalizers.push(Box::new(Metaspace::default()));
+        }
+    }
+    Sequence::new(normalizers)
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::preprocessing::tokenizer::base_tokenizer::Offset;
+    use std::collections::BTreeMap;
+
+    fn token(text: &str) -> Token {
+        Token {
+            text: text.to_owned(),
+            offset: Offset::new(0, text.chars().count() as OffsetSize),
+            reference_offsets: (0..text.chars().count() as OffsetSize).collect(),
+            mask: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_normalizer_sequence() {
+        //        Given
+        let normalizer = Sequence::new(vec![
+            Box::new(Nfc),
+            Box::new(RegexReplace::new(Regex::new("[“”]").unwrap(), "\"")),
+            Box::new(Lowercase),
+            Box::new(Metaspace::default()),
+        ]);
+        let mut input = token("Ce\u{301} “Quoted”");
+
+        //        When
+        normalizer.normalize(&mut input);
+
+        //        Then
+        assert_eq!(input.text, "\u{2581}cé\u{2581}\"quoted\"");
+        assert_eq!(
+            input.reference_offsets,
+            vec![0, 0, 1, 3, 4, 5, 6, 7, 8, 9, 10, 11]
+        );
+        assert!(normalizer.is_lowercasing());
+    }
+
+    #[test]
+    fn test_sentence_piece_normalizer() {
+        //        Given
+        let normalizer = sentence_piece_normalizer(true, true, true, None);
+        let mut input = token("``Héllo''\tWorld");
+
+        //        When
+        normalizer.normalize(&mut input);
+
+        //        Then
+        assert_eq!(input.text, "\u{2581}\"hello\"\u{2581}world");
+        assert_eq!(
+            input.reference_offsets,
+            vec![0, 0, 2, 3, 4, 5, 6, 7, 9, 10, 11, 12, 13, 14]
+        );
+    }
+
+    #[derive(Default)]
+    struct CharsmapNode {
+        children: BTreeMap<u8, CharsmapNode>,
+        value: Option<u32>,
+    }
+
+    ///Places the children of a node in the double-array, with the same unit layout as the
+    ///charsmaps compiled by SentencePiece
+    fn place(node: &CharsmapNode, position: usize, units: &mut Vec<u32>, used: &mut Vec<bool>) {
+        let mut labels: Vec<usize> = node.children.keys().map(|&label| label as usize).collect();
+        if node.value.is_some() {
+            labels.push(0);
+        }
+        let base = (1..)
+            .find(|base: &usize| {
+                labels
+                    .iter()
+                    .all(|label| !used.get(base ^ label).copied().unwrap_or(false))
+            })
+            .unwrap();
+        let size = labels
+            .iter()
+            .map(|label| (base ^ label) + 1)
+            .max()
+            .unwrap_or(0);
+        if units.len() < size {
+            units.resize(size, 0);
+            used.resize(size, false);
+        }
+        for label in &labels {
+            used[base ^ label] = true;
+        }
+        units[position] |= ((position ^ base) as u32) << 10;
+        if let Some(value) = node.value {
+            units[base] = (1 << 31) | value;
+        }
+        for (&label, child) in &node.children {
+            let child_position = base ^ label as usize;
+            units[child_position] = label as u32 | ((child.value.is_some() as u32) << 8);
+            place(child, child_position, units, used);
+        }
+    }
+
+    ///Compiles normalization rules into a `precompiled_charsmap`
+    pub(crate) fn build_charsmap(rules: &[(&str, &str)]) -> Vec<u8> {
+        let mut root = CharsmapNode::default();
+        let mut normalized = vec![];
+        for (source, target) in rules {
+            let mut node = &mut root;
+            for byte in source.bytes() {
+                node = node.children.entry(byte).or_default();
+            }
+            node.value = Some(normalized.len() as u32);
+            normalized.extend_from_slice(target.as_bytes());
+            normalized.push(0);
+        }
+        let mut units = vec![0u32];
+        let mut used = vec![true];
+        place(&root, 0, &mut units, &mut used);
+
+        let mut charsmap = ((units.len() * 4) as u32).to_le_bytes().to_vec();
+        for unit in units {
+            charsmap.extend_from_slice(&unit.to_le_bytes());
+        }
+        charsmap.extend(normalized);
+        charsmap
+    }
+
+    #[test]
+    fn test_precompiled_charsmap() -> anyhow::Result<()> {
+        //        Given
+        let precompiled = Precompiled::from_bytes(&build_charsmap(&[
+            ("a", "x"),
+            ("ab", "y"),
+            ("\u{FB01}", "fi"),
+            ("\u{200B}", ""),
+        ]))?;
+        let mut input = token("abac\u{FB01}e\u{200B}");
+
+        //        When
+        precompiled.normalize(&mut input);
+
+        //        Then
+        assert_eq!(input.text, "yxcfie");
+        assert_eq!(input.reference_offsets, vec![0, 2, 3, 4, 4, 5]);
+        assert_eq!(input.offset, Offset::new(0, 6));
+        assert_eq!(
+            Precompiled::from_bytes(&precompiled.to_bytes())?.normalized,
+            precompiled.normalized
+        );
+        assert!(Precompiled::from_bytes(&[8, 0, 0, 0, 1, 2]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sentence_piece_normalizer_spec() -> anyhow::Result<()> {
+        //        Given
+        let normalizer = SentencePieceNormalizer {
+            precompiled_charsmap: Some(Precompiled::from_bytes(&build_charsmap(&[
+                ("\u{FF21}", "A"),
+                ("\u{FB01}", "fi"),
+                ("\t", " "),
+            ]))?),
+            ..Default::default()
+        };
+        let raw_normalizer = SentencePieceNormalizer {
+            precompiled_charsmap: None,
+            add_dummy_prefix: false,
+            remove_extra_whitespaces: false,
+            escape_whitespaces: false,
+        };
+        let text = "  \u{FB01}\u{FF21}\t\tb  ";
+        let mut input = token(text);
+        let mut raw_input = token(text);
+
+        //        When
+        normalizer.normalize(&mut input);
+        raw_normalizer.normalize(&mut raw_input);
+
+        //        Then
+        assert_eq!(input.text, "\u{2581}fiA\u{2581}b");
+        assert_eq!(input.reference_offsets, vec![2, 2, 2, 3, 4, 6]);
+        assert_eq!(input.offset, Offset::new(2, 7));
+        assert_eq!(raw_input.text, text);
+        assert_eq!(raw_input.reference_offsets, (0..9).collect::<Vec<_>>());
+        assert_eq!(raw_input.offset, Offset::new(0, 9));
+        Ok(())
+    }
+}
@@ -14,22 +14,30 @@
 
 use crate::preprocessing::error::TokenizerError;
 use crate::preprocessing::tokenizer::base_tokenizer::{
-    BaseTokenizer, Mask, Token, TokenRef, Tokenizer,
+    BaseTokenizer, Mask, MultiThreadedTokenizer, Token, TokenRef, Tokenizer,
 };
+use crate::preprocessing::tokenizer::bpe_cache::BpeCache;
+use crate::preprocessing::tokenizer::normalizer::Normalizer;
+use crate::preprocessing::tokenizer::post_processor::TemplateProcessing;
+use crate::preprocessing::tokenizer::pre_tokenizer::PreTokenizer;
 use crate::preprocessing::tokenizer::tokenization_utils::{openai_gpt_bpe, split_on_bpe_pairs};
+use crate::preprocessing::tokenizer::tokenizer_json::{
+    bpe_model_json, check_default_components, custom_post_processor_json, get_section, read_merges,
+    read_normalizer_flags, read_vocab, restore_post_processor, tokenizer_json, TokenizerJson,
+};
 use crate::preprocessing::vocab::base_vocab::Vocab;
 use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
 use crate::OpenAiGptVocab;
-use std::cell::RefCell;
-use std::collections::HashMap;
-use std::rc::Rc;
+use serde_json::{json, Value};
+use std::io::Read;
 use std::sync::Arc;
 
+#[derive(Debug)]
 pub struct OpenAiGptTokenizer {
-    vocab: Arc<OpenAiGptVocab>,
     base_tokenizer: BaseTokenizer<OpenAiGptVocab>,
-    bpe_ranks: Rc<BpePairVocab>,
-    cache: RefCell<HashMap<String, (Vec<String>, Vec<usize>)>>,
+    bpe_ranks: Arc<BpePairVocab>,
+    cache: BpeCache,
+    post_processor: Option<TemplateProcessing>,
 }
 
 impl OpenAiGptTokenizer {
@@ -39,36 +47,102 @@ impl OpenAiGptTokenizer {
         lower_case: bool,
     ) -> Result<OpenAiGptTokenizer, TokenizerError> {
         let vocab = Arc::new(OpenAiGptVocab::from_file(vocab_path)?);
-        let base_tokenizer = BaseTokenizer::from_existing_vocab(vocab.clone(), lower_case, true);
-        let bpe_ranks = Rc::new(BpePairVocab::from_file(merges_path)?);
-        let cache = RefCell::new(HashMap::new());
+        let base_tokenizer = BaseTokenizer::from_existing_vocab(vocab, lower_case, true);
+        let bpe_ranks = Arc::new(BpePairVocab::from_file(merges_path)?);
+        let cache = BpeCache::new();
         Ok(OpenAiGptTokenizer {
-            vocab,
             base_tokenizer,
             bpe_ranks,
             cache,
+            post_processor: None,
         })
     }
 
+    /// Create a new instance of a `OpenAiGptTokenizer` from readers for the vocabulary and merges (e.g. files
+    /// or network streams), with the same content as the files expected by `from_file`.
+    pub fn from_reader<R: Read, S: Read>(
+        vocab: R,
+        merges: S,
+        lower_case: bool,
+    ) -> Result<OpenAiGptTokenizer, TokenizerError> {
+        let vocab = Arc::new(OpenAiGptVocab::from_reader(vocab)?);
+        let bpe_ranks = Arc::new(BpePairVocab::from_reader(merges)?);
+        Ok(OpenAiGptTokenizer::from_existing_vocab_and_merges(
+            vocab, bpe_ranks, lower_case,
+        ))
+    }
+
+    /// Create a new instance of a `OpenAiGptTokenizer` from the vocabulary and merges held in memory (e.g.
+    /// loaded with `include_bytes!`).
+    pub fn from_bytes(
+        vocab: &[u8],
+        merges: &[u8],
+        lower_case: bool,
+    ) -> Result<OpenAiGptTokenizer, TokenizerError> {
+        OpenAiGptTokenizer::from_reader(vocab, merges, lower_case)
+    }
+
     pub fn from_existing_vocab_and_merges(
         vocab: Arc<OpenAiGptVocab>,
-        merges: Rc<BpePairVocab>,
+        merges: Arc<BpePairVocab>,
         lower_case: bool,
     ) -> OpenAiGptTokenizer {
-        let base_tokenizer = BaseTokenizer::from_existing_vocab(vocab.clone(), lower_case, true);
-        let cache = RefCell::new(HashMap::new());
+        let base_tokenizer = BaseTokenizer::from_existing_vocab(vocab, lower_case, true);
+        let cache = BpeCache::new();
         OpenAiGptTokenizer {
-            vocab,
             base_tokenizer,
             bpe_ranks: merges,
             cache,
+            post_processor: None,
         }
     }
+
+    /// Replaces the BPE cache of the tokenizer by an empty cache holding up to `capacity` words.
+    ///
+    /// # Parameters
+    /// - capacity (`usize`): maximum number of words held by the BPE cache
+    pub fn with_cache_capacity(mut self, capacity: usize) -> OpenAiGptTokenizer {
+        self.cache = BpeCache::with_capacity(capacity);
+        self
+    }
+
+    /// Returns a reference to the BPE cache, allowing to clear or disable it and to read its statistics.
+    pub fn cache(&self) -> &BpeCache {
+        &self.cache
+    }
 }
 
 impl Tokenizer<OpenAiGptVocab> for OpenAiGptTokenizer {
     fn vocab(&self) -> &OpenAiGptVocab {
-        self.vocab.as_ref()
+        Tokenizer::vocab(&self.base_tokenizer)
+    }
+
+    fn vocab_mut(&mut self) -> &mut OpenAiGptVocab {
+        self.base_tokenizer.vocab_mut()
+    }
+
+    fn normalizer(&self) -> &dyn Normalizer {
+        self.base_tokenizer.normalizer()
+    }
+
+    fn set_normalizer(&mut self, normalizer: Box<dyn Normalizer>) {
+        self.base_tokenizer.set_normalizer(normalizer);
+    }
+
+    fn pre_tokenizer(&self) -> &dyn PreTokenizer {
+        self.base_tokenizer.pre_tokenizer()
+    }
+
+    fn set_pre_tokenizer(&mut self, pre_tokenizer: Box<dyn PreTokenizer>) {
+        self.base_tokenizer.set_pre_tokenizer(pre_tokenizer);
+    }
+
+    fn post_processor(&self) -> Option<&TemplateProcessing> {
+        self.post_processor.as_ref()
+    }
+
+    fn post_processor_mut(&mut self) -> &mut Option<TemplateProcessing> {
+        &mut self.post_processor
     }
 
     fn tokenize_to_tokens(&self, initial_token: TokenRef) -> Vec<Token> {
@@ -77,7 +151,10 @@ impl Tokenizer<OpenAiGptVocab> for OpenAiGptTokenizer {
             .tokenize_to_tokens(initial_token)
             .into_iter()
             .map(|token| {
-                if token.mask != Mask::Special && token.mask != Mask::Unknown {
+                if token.mask != Mask::Special
+                    && token.mask != Mask::Added
+                    && token.mask != Mask::Unknown
+                {
                     split_on_bpe_pairs(
                         token.as_ref(),
                         openai_gpt_bpe,
@@ -100,11 +177,72 @@ impl Tokenizer<OpenAiGptVocab> for OpenAiGptTokenizer {
     }
 }
 
+impl MultiThreadedTokenizer<OpenAiGptVocab> for OpenAiGptTokenizer {}
+
+impl TokenizerJson for OpenAiGptTokenizer {
+    fn to_tokenizer_json(&self) -> Result<Value, TokenizerError> {
+        check_default_components(
+            self.base_tokenizer.custom_normalizer(),
+            self.base_tokenizer.custom_pre_tokenizer(),
+        )?;
+        Ok(tokenizer_json(
+            Tokenizer::vocab(self),
+            json!({
+                "type": "BertNormalizer",
+                "clean_text": false,
+                "handle_chinese_chars": true,
+                "strip_accents": self.base_tokenizer.strip_accents(),
+                "lowercase": self.base_tokenizer.lower_case()
+            }),
+            json!({"type": "BertPreTokenizer"}),
+            bpe_model_json(
+                Tokenizer::vocab(self),
+                self.bpe_ranks.as_ref(),
+                Some("</w>"),
+            ),
+            custom_post_processor_json(self, Value::Null),
+            json!({"type": "BPEDecoder", "suffix": "</w>"}),
+        ))
+    }
+
+    fn from_tokenizer_json(tokenizer_json: &Value) -> Result<OpenAiGptTokenizer, TokenizerError> {
+        let vocab = read_vocab(
+            tokenizer_json,
+            |values,
+             indices,
+             special_token_map,
+             special_values,
+             special_indices,
+             added_values,
+             added_tokens| {
+                OpenAiGptVocab {
+                    values,
+                    indices,
+                    special_token_map,
+                    special_values,
+                    special_indices,
+                    added_values,
+                    added_tokens,
+                }
+            },
+        )?;
+        let merges = read_merges(get_section(tokenizer_json, "model")?)?;
+        let (lower_case, _) = read_normalizer_flags(tokenizer_json);
+        let mut tokenizer = OpenAiGptTokenizer::from_existing_vocab_and_merges(
+            Arc::new(vocab),
+            Arc::new(merges),
+            lower_case,
+        );
+        restore_post_processor(&mut tokenizer, tokenizer_json)?;
+        Ok(tokenizer)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::preprocessing::tokenizer::base_tokenizer::{
-        Offset, TokenizedInput, TruncationStrategy,
+        Offset, PaddingOptions, TokenizedInput, TruncationStrategy,
     };
     use crate::preprocessing::vocab::base_vocab::swap_key_values;
     use crate::OpenAiGptVocab;
@@ -138,9 +276,11 @@ mod tests {
         OpenAiGptVocab {
             values,
             indices,
-            unknown_value: "<unk>",
+            special_token_map: OpenAiGptVocab::default_special_token_map(),
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
         }
     }
 
@@ -167,7 +307,7 @@ mod tests {
     fn test_openai_gpt_tokenizer() {
         //        Given
         let vocab = Arc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let merges = Arc::new(generate_test_merges());
         let openai_gpt_tokenizer: OpenAiGptTokenizer =
             OpenAiGptTokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let test_tuples = [
@@ -188,7 +328,12 @@ mod tests {
         }
 
         assert_eq!(
-            openai_gpt_tokenizer.tokenize_list(source_texts.clone()),
+            Tokenizer::tokenize_list(&openai_gpt_tokenizer, source_texts.clone()),
+            expected_results
+        );
+
+        assert_eq!(
+            MultiThreadedTokenizer::tokenize_list(&openai_gpt_tokenizer, source_texts.clone()),
             expected_results
         );
     }
@@ -197,7 +342,7 @@ mod tests {
     fn test_openai_gpt_tokenizer_no_lower_casing() {
         //        Given
         let vocab = Arc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let merges = Arc::new(generate_test_merges());
         let openai_gpt_tokenizer: OpenAiGptTokenizer =
             OpenAiGptTokenizer::from_existing_vocab_and_merges(vocab, merges, false);
         let test_tuples = [
@@ -218,7 +363,12 @@ mod tests {
         }
 
         assert_eq!(
-            openai_gpt_tokenizer.tokenize_list(source_texts.clone()),
+            Tokenizer::tokenize_list(&openai_gpt_tokenizer, source_texts.clone()),
+            expected_results
+        );
+
+        assert_eq!(
+            MultiThreadedTokenizer::tokenize_list(&openai_gpt_tokenizer, source_texts.clone()),
             expected_results
         );
     }
@@ -227,7 +377,7 @@ mod tests {
     fn test_encode() {
         //        Given
         let vocab = Arc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let merges = Arc::new(generate_test_merges());
         let openai_gpt_tokenizer: OpenAiGptTokenizer =
             OpenAiGptTokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let truncation_strategy = TruncationStrategy::LongestFirst;
@@ -245,8 +395,23 @@ mod tests {
                         Some(Offset { begin: 4, end: 6 }),
                         Some(Offset { begin: 6, end: 9 }),
                     ],
+                    byte_offsets: vec![
+                        Some(Offset { begin: 0, end: 3 }),
+                        Some(Offset { begin: 4, end: 6 }),
+                        Some(Offset { begin: 6, end: 9 }),
+                    ],
+                    utf16_offsets: vec![
+                        Some(Offset { begin: 0, end: 3 }),
+                        Some(Offset { begin: 4, end: 6 }),
+                        Some(Offset { begin: 6, end: 9 }),
+                    ],
                     reference_offsets: vec![vec![0, 1, 2], vec![4, 5], vec![6, 7, 8]],
                     mask: vec![Mask::None, Mask::Begin, Mask::Continuation],
+                    attention_mask: vec![1, 1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![Some(0), Some(1), Some(1)],
+                    sequence_ids: vec![Some(0), Some(0), Some(0)],
+                    alignment_index: Default::default(),
                 },
             ),
             (
@@ -258,8 +423,15 @@ mod tests {
                     overflowing_tokens: vec![],
                     num_truncated_tokens: 0,
                     token_offsets: vec![],
+                    byte_offsets: vec![],
+                    utf16_offsets: vec![],
                     reference_offsets: vec![],
                     mask: vec![],
+                    attention_mask: vec![],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![],
+                    sequence_ids: vec![],
+                    alignment_index: Default::default(),
                 },
             ),
             (
@@ -271,8 +443,15 @@ mod tests {
                     overflowing_tokens: vec![],
                     num_truncated_tokens: 0,
                     token_offsets: vec![],
+                    byte_offsets: vec![],
+                    utf16_offsets: vec![],
                     reference_offsets: vec![],
                     mask: vec![],
+                    attention_mask: vec![],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![],
+                    sequence_ids: vec![],
+                    alignment_index: Default::default(),
                 },
             ),
         ];
@@ -283,12 +462,37 @@ mod tests {
         //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
             assert_eq!(
-                openai_gpt_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0),
+                openai_gpt_tokenizer.encode(
+                    source_text,
+                    None,
+                    128,
+                    &truncation_strategy,
+                    0,
+                    &PaddingOptions::default()
+                ),
                 *expected_result
             );
         }
         assert_eq!(
-            openai_gpt_tokenizer.encode_list(source_texts.clone(), 128, &truncation_strategy, 0),
+            Tokenizer::encode_list(
+                &openai_gpt_tokenizer,
+                source_texts.clone(),
+                128,
+                &truncation_strategy,
+                0,
+                &PaddingOptions::default()
+            ),
+            expected_results
+        );
+        assert_eq!(
+            MultiThreadedTokenizer::encode_list(
+                &openai_gpt_tokenizer,
+                source_texts.clone(),
+                128,
+                &truncation_strategy,
+                0,
+                &PaddingOptions::default()
+            ),
             expected_results
         );
     }
@@ -297,7 +501,7 @@ mod tests {
     fn test_decode() {
         //        Given
         let vocab = Arc::new(generate_test_vocab());
-        let merges = Rc::new(generate_test_merges());
+        let merges = Arc::new(generate_test_merges());
         let openai_gpt_tokenizer: OpenAiGptTokenizer =
             OpenAiGptTokenizer::from_existing_vocab_and_merges(vocab, merges, true);
         let skip_special_tokens = false;
@@ -0,0 +1,442 @@
+// Copyright 2019-2020 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::base_tokenizer::{
+    Mask, Offset, OffsetSize, TokenIdsWithSpecialTokens,
+};
+use crate::preprocessing::tokenizer::tokenization_utils::get_word_ids;
+use crate::preprocessing::vocab::base_vocab::Vocab;
+
+/// # Template piece
+/// Element of a post-processing template: either one of the input sequences or a special token,
+/// with the segment (token type) ID assigned to its tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplatePiece {
+    ///Input sequence, `0` for the first sequence (`$A`) and `1` for the second one (`$B`)
+    Sequence { sequence_id: u8, type_id: i8 },
+    ///Special token, looked up in the vocabulary when the template is applied
+    SpecialToken { token: String, type_id: i8 },
+}
+
+impl TemplatePiece {
+    ///Creates a piece for the input sequence `sequence_id` (`0` for `$A`, `1` for `$B`)
+    pub fn sequence(sequence_id: u8, type_id: i8) -> TemplatePiece {
+        TemplatePiece::Sequence {
+            sequence_id,
+            type_id,
+        }
+    }
+
+    ///Creates a piece for a special token
+    pub fn special_token(token: &str, type_id: i8) -> TemplatePiece {
+        TemplatePiece::SpecialToken {
+            token: token.to_owned(),
+            type_id,
+        }
+    }
+
+    ///Parses a piece such as `$A`, `$B:1`, `[CLS]` or `[SEP]:1`. The segment ID defaults to 0.
+    fn parse(piece: &str) -> Result<TemplatePiece, TokenizerError> {
+        let (content, type_id) = match piece.rfind(':') {
+            Some(position) if position > 0 => match piece[position + 1..].parse::<i8>() {
+                Ok(type_id) => (&piece[..position], type_id),
+                Err(_) => {
+                    return Err(TokenizerError::InvalidTemplate(format!(
+                        "invalid segment ID in {}, expected an integer after the colon",
+                        piece
+                    )));
+                }
+            },
+            _ => (piece, 0),
+        };
+        match content {
+            "$A" => Ok(TemplatePiece::sequence(0, type_id)),
+            "$B" => Ok(TemplatePiece::sequence(1, type_id)),
+            _ if content.starts_with('$') => Err(TokenizerError::InvalidTemplate(format!(
+                "unknown sequence {}, expected $A or $B",
+                content
+            ))),
+            _ => Ok(TemplatePiece::special_token(content, type_id)),
+        }
+    }
+}
+
+/// # Template post-processor
+/// Adds the special tokens of a model around the encoded sequences, following a template for
+/// single sequences and a template for pairs of sequences. Templates are written as whitespace
+/// separated pieces, `$A` and `$B` standing for the first and second sequence and any other piece
+/// for a special token. The segment (token type) ID of a piece follows a colon and defaults to 0.
+///
+/// The BERT format is for example expressed as:
+/// - single sequence: `[CLS] $A [SEP]`
+/// - pair of sequences: `[CLS] $A [SEP] $B:1 [SEP]:1`
+///
+/// Special tokens get a special tokens mask of 1, no offsets and no word or sequence ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateProcessing {
+    ///Template for single sequences, containing `$A` only
+    single: Vec<TemplatePiece>,
+    ///Template for pairs of sequences, containing `$A` and `$B`
+    pair: Vec<TemplatePiece>,
+}
+
+impl Default for TemplateProcessing {
+    ///Concatenates the sequences without special tokens, with segment IDs 0 and 1
+    fn default() -> Self {
+        TemplateProcessing::from_pieces(
+            vec![TemplatePiece::sequence(0, 0)],
+            vec![TemplatePiece::sequence(0, 0), TemplatePiece::sequence(1, 1)],
+        )
+        .unwrap()
+    }
+}
+
+impl TemplateProcessing {
+    /// Create a new template post-processor from its single sequence and pair of sequences
+    /// templates.
+    ///
+    /// # Parameters
+    /// - single (`&str`): template for single sequences, e.g. `[CLS] $A [SEP]`
+    /// - pair (`&str`): template for pairs of sequences, e.g. `[CLS] $A [SEP] $B:1 [SEP]:1`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust_tokenizers::TemplateProcessing;
+    /// let post_processor =
+    ///     TemplateProcessing::new("[CLS] $A [SEP]", "[CLS] $A [SEP] $B:1 [SEP]:1").unwrap();
+    /// ```
+    pub fn new(single: &str, pair: &str) -> Result<TemplateProcessing, TokenizerError> {
+        let parse = |template: &str| {
+            template
+                .split_whitespace()
+                .map(TemplatePiece::parse)
+                .collect::<Result<Vec<TemplatePiece>, TokenizerError>>()
+        };
+        TemplateProcessing::from_pieces(parse(single)?, parse(pair)?)
+    }
+
+    /// Create a new template post-processor from lists of pieces. The single sequence template
+    /// must contain the first sequence exactly once, the pair template must contain both sequences
+    /// exactly once.
+    pub fn from_pieces(
+        single: Vec<TemplatePiece>,
+        pair: Vec<TemplatePiece>,
+    ) -> Result<TemplateProcessing, TokenizerError> {
+        let count = |pieces: &[TemplatePiece], id: u8| {
+            pieces
+                .iter()
+                .filter(|piece| {
+                    matches!(piece, TemplatePiece::Sequence { sequence_id, .. } if *sequence_id == id)
+                })
+                .count()
+        };
+        if count(&single, 0) != 1 || count(&single, 1) != 0 {
+            return Err(TokenizerError::InvalidTemplate(
+                "the single sequence template must contain $A once and no $B".to_owned(),
+            ));
+        }
+        if count(&pair, 0) != 1 || count(&pair, 1) != 1 {
+            return Err(TokenizerError::InvalidTemplate(
+                "the pair template must contain $A and $B once".to_owned(),
+            ));
+        }
+        Ok(TemplateProcessing { single, pair })
+    }
+
+    ///Returns the template for single sequences
+    pub fn single(&self) -> &[TemplatePiece] {
+        &self.single
+    }
+
+    ///Returns the template for pairs of sequences
+    pub fn pair(&self) -> &[TemplatePiece] {
+        &self.pair
+    }
+
+    ///Returns the special tokens used by the templates
+    pub fn special_tokens(&self) -> Vec<&str> {
+        let mut special_tokens: Vec<&str> = vec![];
+        for piece in self.single.iter().chain(self.pair.iter()) {
+            if let TemplatePiece::SpecialToken { token, .. } = piece {
+                if !special_tokens.contains(&token.as_str()) {
+                    special_tokens.push(token);
+                }
+            }
+        }
+        special_tokens
+    }
+
+    ///Checks that all the special tokens of the templates are part of a vocabulary
+    pub fn check_special_tokens<V: Vocab>(&self, vocab: &V) -> Result<(), TokenizerError> {
+        match self.special_tokens().into_iter().find(|token| {
+            !vocab.values().contains_key(*token) && !vocab.special_values().contains_key(*token)
+        }) {
+            Some(token) => Err(TokenizerError::TokenNotFound(format!(
+                "The template special token {} could not be found in the vocabulary",
+                token
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    ///Adds the special tokens of the template to a sequence or pair of sequences. Returns the
+    ///token IDs, segment IDs, special tokens mask, offsets, reference offsets, masks, word IDs
+    ///and sequence IDs of the output (see `Tokenizer::build_input_with_special_tokens`).
+    pub fn apply<V: Vocab>(
+        &self,
+        vocab: &V,
+        sequence_1: TemplateSequence,
+        sequence_2: Option<TemplateSequence>,
+    ) -> TokenIdsWithSpecialTokens {
+        let template = if sequence_2.is_some() {
+            &self.pair
+        } else {
+            &self.single
+        };
+        let mut sequences = [Some(sequence_1), sequence_2];
+
+        let mut output: Vec<i64> = vec![];
+        let mut token_segment_ids: Vec<i8> = vec![];
+        let mut special_tokens_mask: Vec<i8> = vec![];
+        let mut offsets: Vec<Option<Offset>> = vec![];
+        let mut original_offsets: Vec<Vec<OffsetSize>> = vec![];
+        let mut mask: Vec<Mask> = vec![];
+        let mut word_ids: Vec<Option<u32>> = vec![];
+        let mut sequence_ids: Vec<Option<u8>> = vec![];
+        for piece in template {
+            match piece {
+                TemplatePiece::SpecialToken { token, type_id } => {
+                    output.push(vocab.token_to_id(token));
+                    token_segment_ids.push(*type_id);
+                    special_tokens_mask.push(1);
+                    offsets.push(None);
+                    original_offsets.push(vec![]);
+                    mask.push(Mask::Special);
+                    word_ids.push(None);
+                    sequence_ids.push(None);
+                }
+                TemplatePiece::Sequence {
+                    sequence_id,
+                    type_id,
+                } => {
+                    let sequence = match sequences
+                        .get_mut(*sequence_id as usize)
+                        .and_then(|sequence| sequence.take())
+                    {
+                        Some(sequence) => sequence,
+                        None => continue,
+                    };
+                    let length = sequence.token_ids.len();
+                    output.extend(sequence.token_ids);
+                    token_segment_ids.extend(vec![*type_id; length]);
+                    special_tokens_mask.extend(vec![0; length]);
+                    offsets.extend(sequence.offsets.unwrap_or_else(|| vec![None; length]));
+                    original_offsets.extend(
+                        sequence
+                            .reference_offsets
+                            .unwrap_or_else(|| vec![vec![]; length]),
+                    );
+                    match sequence.mask {
+                        Some(sequence_mask) => {
+                            word_ids.extend(get_word_ids(&sequence_mask));
+                            mask.extend(sequence_mask);
+                        }
+                        None => {
+                            word_ids.extend((0..length as u32).map(Some));
+                            mask.extend(vec![Mask::None; length]);
+                        }
+                    }
+                    sequence_ids.extend(vec![Some(*sequence_id); length]);
+                }
+            }
+        }
+        (
+            output,
+            token_segment_ids,
+            special_tokens_mask,
+            offsets,
+            original_offsets,
+            mask,
+            word_ids,
+            sequence_ids,
+        )
+    }
+}
+
+/// # Template sequence
+/// Encoded sequence passed to a `TemplateProcessing` post-processor: token IDs with their offsets,
+/// reference offsets and masks. Missing offsets are filled with `None`, missing masks with
+/// `Mask::None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateSequence {
+    pub token_ids: Vec<i64>,
+    pub offsets: Option<Vec<Option<Offset>>>,
+    pub reference_offsets: Option<Vec<Vec<OffsetSize>>>,
+    pub mask: Option<Vec<Mask>>,
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessing::vocab::base_vocab::swap_key_values;
+    use crate::BertVocab;
+    use std::collections::HashMap;
+
+    fn generate_test_vocab() -> BertVocab {
+        let values: HashMap<String, i64> = [
+            ("hello".to_owned(), 0),
+            ("world".to_owned(), 1),
+            ("[UNK]".to_owned(), 2),
+            ("[CLS]".to_owned(), 3),
+            ("[SEP]".to_owned(), 4),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let special_values: HashMap<String, i64> = [
+            ("[UNK]".to_owned(), 2),
+            ("[CLS]".to_owned(), 3),
+            ("[SEP]".to_owned(), 4),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        BertVocab {
+            indices: swap_key_values(&values),
+            special_indices: swap_key_values(&special_values),
+            values,
+            special_token_map: BertVocab::default_special_token_map(),
+            special_values,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_template() -> anyhow::Result<()> {
+        //        Given
+        let single = "[CLS] $A [SEP]:0";
+        let pair = "$A:0 <sep>:0 $B:1 <sep>:1 <cls>:2";
+
+        //        When
+        let post_processor = TemplateProcessing::new(single, pair)?;
+        let missing_sequence = TemplateProcessing::new("[CLS] $A", "[CLS] $A $A:1");
+        let unknown_sequence = TemplateProcessing::new("[CLS] $A", "[CLS] $A $C:1");
+        let invalid_type_id = TemplateProcessing::new("[CLS] $A", "[CLS] $A [SEP]:x $B:1");
+
+        //        Then
+        assert_eq!(
+            post_processor.single(),
+            vec![
+                TemplatePiece::special_token("[CLS]", 0),
+                TemplatePiece::sequence(0, 0),
+                TemplatePiece::special_token("[SEP]", 0),
+            ]
+        );
+        assert_eq!(
+            post_processor.pair()[4],
+            TemplatePiece::special_token("<cls>", 2)
+        );
+        assert_eq!(
+            post_processor.special_tokens(),
+            vec!["[CLS]", "[SEP]", "<sep>", "<cls>"]
+        );
+        assert!(matches!(
+            missing_sequence,
+            Err(TokenizerError::InvalidTemplate(_))
+        ));
+        assert!(matches!(
+            unknown_sequence,
+            Err(TokenizerError::InvalidTemplate(_))
+        ));
+        assert!(matches!(
+            invalid_type_id,
+            Err(TokenizerError::InvalidTemplate(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_template() -> anyhow::Result<()> {
+        //        Given
+        let vocab = generate_test_vocab();
+        let post_processor =
+            TemplateProcessing::new("[CLS] $A [SEP]", "[CLS] $A [SEP] $B:1 [SEP]:1")?;
+        let sequence_1 = TemplateSequence {
+            token_ids: vec![0, 1],
+            offsets: Some(vec![Some(Offset::new(0, 5)), Some(Offset::new(6, 11))]),
+            reference_offsets: Some(vec![vec![0, 1, 2, 3, 4], vec![6, 7, 8, 9, 10]]),
+            mask: Some(vec![Mask::None, Mask::None]),
+        };
+        let sequence_2 = TemplateSequence {
+            token_ids: vec![1],
+            offsets: None,
+            reference_offsets: None,
+            mask: None,
+        };
+
+        //        When
+        let (
+            token_ids,
+            segment_ids,
+            special_tokens_mask,
+            offsets,
+            reference_offsets,
+            mask,
+            word_ids,
+            sequence_ids,
+        ) = post_processor.apply(&vocab, sequence_1, Some(sequence_2));
+
+        //        Then
+        assert_eq!(token_ids, vec![3, 0, 1, 4, 1, 4]);
+        assert_eq!(segment_ids, vec![0, 0, 0, 0, 1, 1]);
+        assert_eq!(special_tokens_mask, vec![1, 0, 0, 1, 0, 1]);
+        assert_eq!(
+            offsets,
+            vec![
+                None,
+                Some(Offset::new(0, 5)),
+                Some(Offset::new(6, 11)),
+                None,
+                None,
+                None
+            ]
+        );
+        assert_eq!(reference_offsets[1], vec![0, 1, 2, 3, 4]);
+        assert_eq!(reference_offsets[4], Vec::<OffsetSize>::new());
+        assert_eq!(
+            mask,
+            vec![
+                Mask::Special,
+                Mask::None,
+                Mask::None,
+                Mask::Special,
+                Mask::None,
+                Mask::Special
+            ]
+        );
+        assert_eq!(word_ids, vec![None, Some(0), Some(1), None, Some(0), None]);
+        assert_eq!(
+            sequence_ids,
+            vec![None, Some(0), Some(0), None, Some(1), None]
+        );
+        assert!(post_processor.check_special_tokens(&vocab).is_ok());
+        assert!(TemplateProcessing::new("<s> $A", "<s> $A $B")?
+            .check_special_tokens(&vocab)
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,417 @@
+// Copyright 2019-2020 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::preprocessing::tokenizer::base_tokenizer::{Mask, Offset, OffsetSize, TokenRef};
+use crate::preprocessing::tokenizer::tokenization_utils::{
+    split_on_punct, split_on_regex_with_lookahead, tokenize_cjk_chars, whitespace_tokenize,
+};
+use regex::Regex;
+use std::fmt::Debug;
+
+/// # Pre-tokenizer
+/// Splits a token into words before the tokenization model runs. Pre-tokenizers borrow the text of
+/// the token and return sub-tokens pointing to slices of it, with offsets relative to the original
+/// text.
+///
+/// Tokens with a mask (special tokens, or separators identified by a previous pre-tokenizer such as
+/// punctuation) are returned unchanged. Pre-tokenizers can be chained with a `Sequence`.
+pub trait PreTokenizer: Debug + Send + Sync {
+    ///Splits a token into sub-tokens
+    fn pre_tokenize<'a>(&self, token: TokenRef<'a>) -> Vec<TokenRef<'a>>;
+
+    ///Returns true if the words produced are expected to be encoded as bytes by the model (GPT2
+    ///byte-level BPE).
+    fn is_byte_level(&self) -> bool {
+        false
+    }
+}
+
+/// # Whitespace splitting
+/// Splits on whitespace characters, which are removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhitespaceSplit;
+
+impl PreTokenizer for WhitespaceSplit {
+    fn pre_tokenize<'a>(&self, token: TokenRef<'a>) -> Vec<TokenRef<'a>> {
+        whitespace_tokenize(token)
+    }
+}
+
+/// # Whitespace and word boundaries splitting
+/// Splits the text into sequences of word characters and sequences of other non-whitespace
+/// characters (`\w+|[^\w\s]+`). Whitespaces are removed.
+#[derive(Debug, Clone)]
+pub struct Whitespace {
+    split: Split,
+}
+
+impl Default for Whitespace {
+    fn default() -> Self {
+        Whitespace {
+            split: Split::new(
+                Regex::new(r"\w+|[^\w\s]+").unwrap(),
+                SplitBehavior::Removed,
+                true,
+            ),
+        }
+    }
+}
+
+impl PreTokenizer for Whitespace {
+    fn pre_tokenize<'a>(&self, token: TokenRef<'a>) -> Vec<TokenRef<'a>> {
+        self.split.pre_tokenize(token)
+    }
+}
+
+/// # Punctuation splitting
+/// Isolates each punctuation character in its own token (with a `Mask::Punctuation` mask)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Punctuation;
+
+impl PreTokenizer for Punctuation {
+    fn pre_tokenize<'a>(&self, token: TokenRef<'a>) -> Vec<TokenRef<'a>> {
+        split_on_punct(token)
+    }
+}
+
+/// # CJK characters splitting
+/// Isolates each CJK character in its own token (with a `Mask::CJK` mask)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CjkChars;
+
+impl PreTokenizer for CjkChars {
+    fn pre_tokenize<'a>(&self, token: TokenRef<'a>) -> Vec<TokenRef<'a>> {
+        tokenize_cjk_chars(token)
+    }
+}
+
+/// # BERT pre-tokenizer
+/// Splits on whitespaces and punctuation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BertPreTokenizer;
+
+impl PreTokenizer for BertPreTokenizer {
+    fn pre_tokenize<'a>(&self, token: TokenRef<'a>) -> Vec<TokenRef<'a>> {
+        whitespace_tokenize(token)
+            .into_iter()
+            .flat_map(split_on_punct)
+            .collect()
+    }
+}
+
+/// # Split behavior
+/// Defines what happens to the delimiters found by a `Split` pre-tokenizer:
+/// - Removed: the delimiters are dropped
+/// - Isolated: each delimiter becomes its own token
+/// - MergedWithPrevious: the delimiter is appended to the previous token
+/// - MergedWithNext: the delimiter is prepended to the next token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitBehavior {
+    Removed,
+    Isolated,
+    MergedWithPrevious,
+    MergedWithNext,
+}
+
+/// # Regular expression splitting
+/// Splits the text on the matches of a regular expression. If `invert` is true, the matches are
+/// the tokens to keep and the text between them is treated as the delimiters.
+#[derive(Debug, Clone)]
+pub struct Split {
+    pub pattern: Regex,
+    pub behavior: SplitBehavior,
+    pub invert: bool,
+}
+
+impl Split {
+    pub fn new(pattern: Regex, behavior: SplitBehavior, invert: bool) -> Split {
+        Split {
+            pattern,
+            behavior,
+            invert,
+        }
+    }
+}
+
+impl PreTokenizer for Split {
+    fn pre_tokenize<'a>(&self, token: TokenRef<'a>) -> Vec<TokenRef<'a>> {
+        if token.mask != Mask::None {
+            return vec![token];
+        }
+        //byte ranges of the text, flagged with true for delimiters
+        let mut pieces: Vec<(usize, usize, bool)> = vec![];
+        let mut last_end = 0;
+        for hit in self.pattern.find_iter(token.text) {
+            if hit.start() == hit.end() {
+                continue;
+            }
+            if hit.start() > last_end {
+                pieces.push((last_end, hit.start(), self.invert));
+            }
+            pieces.push((hit.start(), hit.end(), !self.invert));
+            last_end = hit.end();
+        }
+        if last_end < token.text.len() {
+            pieces.push((last_end, token.text.len(), self.invert));
+        }
+
+        let ranges: Vec<(usize, usize)> = match self.behavior {
+            SplitBehavior::Removed => pieces
+                .into_iter()
+                .filter(|(_, _, is_delimiter)| !is_delimiter)
+                .map(|(start, end, _)| (start, end))
+                .collect(),
+            SplitBehavior::Isolated => pieces
+                .into_iter()
+                .map(|(start, end, _)| (start, end))
+                .collect(),
+            SplitBehavior::MergedWithPrevious => {
+                let mut ranges: Vec<(usize, usize)> = vec![];
+                let mut previous_delimiter = false;
+                for (start, end, is_delimiter) in pieces {
+                    match ranges.last_mut() {
+                        Some(last) if is_delimiter && !previous_delimiter => last.1 = end,
+                        _ => ranges.push((start, end)),
+                    }
+                    previous_delimiter = is_delimiter;
+                }
+                ranges
+            }
+            SplitBehavior::MergedWithNext => {
+                let mut ranges: Vec<(usize, usize)> = vec![];
+                let mut next_delimiter = false;
+                for (start, end, is_delimiter) in pieces.into_iter().rev() {
+                    match ranges.last_mut() {
+                        Some(last) if is_delimiter && !next_delimiter => last.0 = start,
+                        _ => ranges.push((start, end)),
+                    }
+                    next_delimiter = is_delimiter;
+                }
+                ranges.reverse();
+                ranges
+            }
+        };
+
+        let mut tokens: Vec<TokenRef<'a>> = Vec::with_capacity(ranges.len());
+        let mut char_position = 0;
+        let mut byte_position = 0;
+        for (start, end) in ranges {
+            let begin_char = char_position + token.text[byte_position..start].chars().count();
+            let end_char = begin_char + token.text[start..end].chars().count();
+            tokens.push(TokenRef {
+                text: &token.text[start..end],
+                offset: Offset::new(
+                    token.offset.begin + begin_char as OffsetSize,
+                    token.offset.begin + end_char as OffsetSize,
+                ),
+                reference_offsets: &token.reference_offsets[begin_char..end_char],
+                mask: Mask::None,
+            });
+            char_position = end_char;
+            byte_position = end;
+        }
+        tokens
+    }
+}
+
+/// # Digits splitting
+/// Isolates the sequences of digits, or each digit individually if `individual_digits` is true
+#[derive(Debug, Clone)]
+pub struct Digits {
+    split: Split,
+}
+
+impl Digits {
+    pub fn new(individual_digits: bool) -> Digits {
+        let pattern = if individual_digits {
+            Regex::new(r"\p{N}").unwrap()
+        } else {
+            Regex::new(r"\p{N}+").unwrap()
+        };
+        Digits {
+            split: Split::new(pattern, SplitBehavior::Isolated, false),
+        }
+    }
+}
+
+impl PreTokenizer for Digits {
+    fn pre_tokenize<'a>(&self, token: TokenRef<'a>) -> Vec<TokenRef<'a>> {
+        self.split.pre_tokenize(token)
+    }
+}
+
+/// # Byte-level pre-tokenizer
+/// Splitting of the GPT2 tokenizer: contractions, words, numbers and punctuation are separated,
+/// keeping the preceding space with the following word. The words are then encoded as bytes by the
+/// model. If `use_regex` is false, the text is passed to the model without splitting.
+#[derive(Debug, Clone)]
+pub struct ByteLevel {
+    use_regex: bool,
+    pattern_lookahead: Regex,
+    pattern_tokenization: Regex,
+}
+
+impl ByteLevel {
+    pub fn new(use_regex: bool) -> ByteLevel {
+        ByteLevel {
+            use_regex,
+            pattern_lookahead: Regex::new(r"\s+\S").unwrap(),
+            pattern_tokenization: Regex::new(
+                r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+",
+            )
+            .unwrap(),
+        }
+    }
+}
+
+impl Default for ByteLevel {
+    fn default() -> Self {
+        ByteLevel::new(true)
+    }
+}
+
+impl PreTokenizer for ByteLevel {
+    fn pre_tokenize<'a>(&self, token: TokenRef<'a>) -> Vec<TokenRef<'a>> {
+        if self.use_regex {
+            split_on_regex_with_lookahead(
+                token,
+                &self.pattern_lookahead,
+                &self.pattern_tokenization,
+            )
+        } else {
+            vec![token]
+        }
+    }
+
+    fn is_byte_level(&self) -> bool {
+        true
+    }
+}
+
+/// # Sequence of pre-tokenizers
+/// Applies a list of pre-tokenizers in order, each of them splitting the tokens produced by the
+/// previous one
+#[derive(Debug, Default)]
+pub struct Sequence {
+    pub pre_tokenizers: Vec<Box<dyn PreTokenizer>>,
+}
+
+impl Sequence {
+    pub fn new(pre_tokenizers: Vec<Box<dyn PreTokenizer>>) -> Sequence {
+        Sequence { pre_tokenizers }
+    }
+}
+
+impl PreTokenizer for Sequence {
+    fn pre_tokenize<'a>(&self, token: TokenRef<'a>) -> Vec<TokenRef<'a>> {
+        let mut tokens = vec![token];
+        for pre_tokenizer in &self.pre_tokenizers {
+            tokens = tokens
+                .into_iter()
+                .flat_map(|token| pre_tokenizer.pre_tokenize(token))
+                .collect();
+        }
+        tokens
+    }
+
+    fn is_byte_level(&self) -> bool {
+        self.pre_tokenizers
+            .iter()
+            .any(|pre_tokenizer| pre_tokenizer.is_byte_level())
+    }
+}
+
+///Splitting on whitespaces, punctuation and CJK characters applied by the BERT-like tokenizers
+pub(crate) fn base_pre_tokenizer() -> Sequence {
+    Sequence::new(vec![Box::new(BertPreTokenizer), Box::new(CjkChars)])
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(tokens: Vec<TokenRef>) -> Vec<&str> {
+        tokens.iter().map(|token| token.text).collect()
+    }
+
+    #[test]
+    fn test_split_behaviors() {
+        //        Given
+        let text = "the-final--countdown";
+        let reference_offsets = (0..text.chars().count() as OffsetSize).collect::<Vec<_>>();
+        let token = TokenRef::new(text, &reference_offsets);
+        let split = |behavior| Split::new(Regex::new("-").unwrap(), behavior, false);
+
+        //        When & Then
+        assert_eq!(
+            texts(split(SplitBehavior::Removed).pre_tokenize(token)),
+            vec!["the", "final", "countdown"]
+        );
+        assert_eq!(
+            texts(split(SplitBehavior::Isolated).pre_tokenize(token)),
+            vec!["the", "-", "final", "-", "-", "countdown"]
+        );
+        assert_eq!(
+            texts(split(SplitBehavior::MergedWithPrevious).pre_tokenize(token)),
+            vec!["the-", "final-", "-", "countdown"]
+        );
+        assert_eq!(
+            texts(split(SplitBehavior::MergedWithNext).pre_tokenize(token)),
+            vec!["the", "-final", "-", "-countdown"]
+        );
+        let tokens = split(SplitBehavior::MergedWithNext).pre_tokenize(token);
+        assert_eq!(tokens[3].offset, Offset::new(10, 20));
+        assert_eq!(tokens[3].reference_offsets, &reference_offsets[10..20]);
+    }
+
+    #[test]
+    fn test_pre_tokenizer_sequence() {
+        //        Given
+        let pre_tokenizer = Sequence::new(vec![
+            Box::new(WhitespaceSplit),
+            Box::new(Punctuation),
+            Box::new(CjkChars),
+            Box::new(Digits::new(true)),
+        ]);
+        let text = "Année 2021: 你好!";
+        let reference_offsets = (0..text.chars().count() as OffsetSize).collect::<Vec<_>>();
+        let token = TokenRef::new(text, &reference_offsets);
+
+        //        When
+        let tokens = pre_tokenizer.pre_tokenize(token);
+
+        //        Then
+        assert_eq!(
+            texts(tokens.clone()),
+            vec!["Année", "2", "0", "2", "1", ":", "你", "好", "!"]
+        );
+        assert_eq!(
+            tokens.iter().map(|token| token.offset).collect::<Vec<_>>(),
+            vec![
+                Offset::new(0, 5),
+                Offset::new(6, 7),
+                Offset::new(7, 8),
+                Offset::new(8, 9),
+                Offset::new(9, 10),
+                Offset::new(10, 11),
+                Offset::new(12, 13),
+                Offset::new(13, 14),
+                Offset::new(14, 15),
+            ]
+        );
+        assert_eq!(tokens[5].mask, Mask::Punctuation);
+        assert_eq!(tokens[6].mask, Mask::CJK);
+        assert!(!pre_tokenizer.is_byte_level());
+    }
+}
@@ -14,21 +14,27 @@
 
 use crate::preprocessing::error::TokenizerError;
 use crate::preprocessing::tokenizer::base_tokenizer::{
-    Mask, Offset, OffsetSize, Token, TokenRef, Tokenizer,
+    Mask, MultiThreadedTokenizer, Token, TokenRef, Tokenizer,
 };
+use crate::preprocessing::tokenizer::bpe_cache::BpeCache;
 use crate::preprocessing::tokenizer::constants::UNICODE_TO_BYTES;
+use crate::preprocessing::tokenizer::normalizer::{base_normalizer, Normalizer};
+use crate::preprocessing::tokenizer::post_processor::{TemplatePiece, TemplateProcessing};
+use crate::preprocessing::tokenizer::pre_tokenizer::{ByteLevel, PreTokenizer};
 use crate::preprocessing::tokenizer::tokenization_utils::{
-    bpe, fix_mask, is_whitespace, split_on_bpe_pairs, split_on_regex_with_lookahead,
-    split_on_special_tokens,
+    bpe, fix_mask, is_whitespace, split_on_added_tokens, split_on_bpe_pairs,
+};
+use crate::preprocessing::tokenizer::tokenizer_json::{
+    bpe_model_json, check_default_components, get_section, post_processor_json,
+    read_add_prefix_space, read_merges, read_normalizer_flags, read_vocab, restore_post_processor,
+    tokenizer_json, TokenizerJson,
 };
 use crate::preprocessing::vocab::base_vocab::Vocab;
 use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
-use crate::tokenization_utils::lowercase;
 use crate::RobertaVocab;
 use itertools::Itertools;
-use regex::Regex;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use serde_json::{json, Value};
+use std::io::Read;
 use std::iter::Iterator;
 use std::sync::Arc;
 
@@ -36,11 +42,14 @@ use std::sync::Arc;
 pub struct RobertaTokenizer {
     vocab: Arc<RobertaVocab>,
     bpe_ranks: Arc<BpePairVocab>,
-    cache: RefCell<HashMap<String, (Vec<String>, Vec<usize>)>>,
-    pattern_lookahead: Regex,
-    pattern_tokenization: Regex,
+    cache: BpeCache,
+    pre_tokenizer: Box<dyn PreTokenizer>,
+    custom_pre_tokenizer: bool,
     lower_case: bool,
     add_prefix_space: bool,
+    normalizer: Box<dyn Normalizer>,
+    custom_normalizer: bool,
+    post_processor: Option<TemplateProcessing>,
 }
 
 impl RobertaTokenizer {
@@ -52,43 +61,84 @@ impl RobertaTokenizer {
     ) -> Result<RobertaTokenizer, TokenizerError> {
         let vocab = Arc::new(RobertaVocab::from_file(vocab_path)?);
         let bpe_ranks = Arc::new(BpePairVocab::from_file(merges_path)?);
-        let cache = RefCell::new(HashMap::new());
-        let pattern_lookahead = Regex::new(r"\s+\S").unwrap();
-        let pattern_tokenization =
-            Regex::new(r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+")
-                .unwrap();
+        let cache = BpeCache::new();
         Ok(RobertaTokenizer {
             vocab,
             bpe_ranks,
             cache,
-            pattern_lookahead,
-            pattern_tokenization,
+            pre_tokenizer: Box::new(ByteLevel::default()),
+            custom_pre_tokenizer: false,
             lower_case,
             add_prefix_space,
+            normalizer: Box::new(base_normalizer(lower_case, false)),
+            custom_normalizer: false,
+            post_processor: None,
         })
     }
 
+    /// Create a new instance of a `RobertaTokenizer` from readers for the vocabulary and merges (e.g. files
+    /// or network streams), with the same content as the files expected by `from_file`.
+    pub fn from_reader<R: Read, S: Read>(
+        vocab: R,
+        merges: S,
+        lower_case: bool,
+        add_prefix_space: bool,
+    ) -> Result<RobertaTokenizer, TokenizerError> {
+        let vocab = Arc::new(RobertaVocab::from_reader(vocab)?);
+        let bpe_ranks = Arc::new(BpePairVocab::from_reader(merges)?);
+        Ok(RobertaTokenizer::from_existing_vocab_and_merges(
+            vocab,
+            bpe_ranks,
+            lower_case,
+            add_prefix_space,
+        ))
+    }
+
+    /// Create a new instance of a `RobertaTokenizer` from the vocabulary and merges held in memory (e.g.
+    /// loaded with `include_bytes!`).
+    pub fn from_bytes(
+        vocab: &[u8],
+        merges: &[u8],
+        lower_case: bool,
+        add_prefix_space: bool,
+    ) -> Result<RobertaTokenizer, TokenizerError> {
+        RobertaTokenizer::from_reader(vocab, merges, lower_case, add_prefix_space)
+    }
+
     pub fn from_existing_vocab_and_merges(
         vocab: Arc<RobertaVocab>,
         merges: Arc<BpePairVocab>,
         lower_case: bool,
         add_prefix_space: bool,
     ) -> RobertaTokenizer {
-        let cache = RefCell::new(HashMap::new());
-        let pattern_lookahead = Regex::new(r"\s+\S").unwrap();
-        let pattern_tokenization =
-            Regex::new(r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+")
-                .unwrap();
+        let cache = BpeCache::new();
         RobertaTokenizer {
             vocab,
             bpe_ranks: merges,
             cache,
-            pattern_lookahead,
-            pattern_tokenization,
+            pre_tokenizer: Box::new(ByteLevel::default()),
+            custom_pre_tokenizer: false,
             lower_case,
             add_prefix_space,
+            normalizer: Box::new(base_normalizer(lower_case, false)),
+            custom_normalizer: false,
+            post_processor: None,
         }
     }
+
+    /// Replaces the BPE cache of the tokenizer by an empty cache holding up to `capacity` words.
+    ///
+    /// # Parameters
+    /// - capacity (`usize`): maximum number of words held by the BPE cache
+    pub fn with_cache_capacity(mut self, capacity: usize) -> RobertaTokenizer {
+        self.cache = BpeCache::with_capacity(capacity);
+        self
+    }
+
+    /// Returns a reference to the BPE cache, allowing to clear or disable it and to read its statistics.
+    pub fn cache(&self) -> &BpeCache {
+        &self.cache
+    }
 }
 
 impl Tokenizer<RobertaVocab> for RobertaTokenizer {
@@ -96,6 +146,36 @@ impl Tokenizer<RobertaVocab> for RobertaTokenizer {
         self.vocab.as_ref()
     }
 
+    fn vocab_mut(&mut self) -> &mut RobertaVocab {
+        Arc::make_mut(&mut self.vocab)
+    }
+
+    fn normalizer(&self) -> &dyn Normalizer {
+        self.normalizer.as_ref()
+    }
+
+    fn set_normalizer(&mut self, normalizer: Box<dyn Normalizer>) {
+        self.normalizer = normalizer;
+        self.custom_normalizer = true;
+    }
+
+    fn pre_tokenizer(&self) -> &dyn PreTokenizer {
+        self.pre_tokenizer.as_ref()
+    }
+
+    fn set_pre_tokenizer(&mut self, pre_tokenizer: Box<dyn PreTokenizer>) {
+        self.pre_tokenizer = pre_tokenizer;
+        self.custom_pre_tokenizer = true;
+    }
+
+    fn post_processor(&self) -> Option<&TemplateProcessing> {
+        self.post_processor.as_ref()
+    }
+
+    fn post_processor_mut(&mut self) -> &mut Option<TemplateProcessing> {
+        &mut self.post_processor
+    }
+
     fn tokenize_to_tokens(&self, initial_token: TokenRef) -> Vec<Token> {
         if initial_token.text.len() == 0 {
             return vec![];
@@ -105,23 +185,23 @@ impl Tokenizer<RobertaVocab> for RobertaTokenizer {
             initial_token.text.insert(0, ' ');
             initial_token.reference_offsets.insert(0, 0);
         };
-        let mut tokens: Vec<Token> =
-            split_on_special_tokens(initial_token.as_ref(), self.vocab.as_ref())
-                .into_iter()
-                .map(|token| token.to_owned())
-                .collect::<Vec<Token>>();
+        let mut tokens: Vec<Token> = split_on_added_tokens(
+            initial_token.as_ref(),
+            self.vocab.as_ref(),
+            self.normalizer.is_lowercasing(),
+        )
+        .into_iter()
+        .map(|token| token.to_owned())
+        .collect::<Vec<Token>>();
 
         let mut sub_tokens = Vec::new();
         for token in tokens.iter_mut() {
-            if token.mask != Mask::Special && token.mask != Mask::Unknown {
-                if self.lower_case {
-                    lowercase(token);
-                }
-                for token in split_on_regex_with_lookahead(
-                    token.as_ref(),
-                    &self.pattern_lookahead,
-                    &self.pattern_tokenization,
-                ) {
+            if token.mask != Mask::Special
+                && token.mask != Mask::Added
+                && token.mask != Mask::Unknown
+            {
+                self.normalizer.normalize(token);
+                for token in self.pre_tokenizer.pre_tokenize(token.as_ref()) {
                     sub_tokens.extend(split_on_bpe_pairs(
                         token,
                         bpe,
@@ -152,88 +232,94 @@ impl Tokenizer<RobertaVocab> for RobertaTokenizer {
         String::from_utf8_lossy(&tokens).to_string()
     }
 
-    fn build_input_with_special_tokens(
-        &self,
-        tokens_1: Vec<i64>,
-        tokens_2: Option<Vec<i64>>,
-        offsets_1: Vec<Option<Offset>>,
-        offsets_2: Option<Vec<Option<Offset>>>,
-        original_offsets_1: Vec<Vec<OffsetSize>>,
-        original_offsets_2: Option<Vec<Vec<OffsetSize>>>,
-        mask_1: Vec<Mask>,
-        mask_2: Option<Vec<Mask>>,
-    ) -> (
-        Vec<i64>,
-        Vec<i8>,
-        Vec<i8>,
-        Vec<Option<Offset>>,
-        Vec<Vec<OffsetSize>>,
-        Vec<Mask>,
-    ) {
-        let mut output: Vec<i64> = vec![];
-        let mut token_segment_ids: Vec<i8> = vec![];
-        let mut special_tokens_mask: Vec<i8> = vec![];
-        let mut offsets: Vec<Option<Offset>> = vec![];
-        let mut original_offsets: Vec<Vec<OffsetSize>> = vec![];
-        let mut mask: Vec<Mask> = vec![];
-        special_tokens_mask.push(1);
-        special_tokens_mask.extend(vec![0; tokens_1.len()]);
-        special_tokens_mask.push(1);
-        token_segment_ids.extend(vec![0; tokens_1.len() + 2]);
-        output.push(self.vocab.token_to_id(RobertaVocab::cls_value()));
-        output.extend(tokens_1);
-        output.push(self.vocab.token_to_id(RobertaVocab::sep_value()));
-        offsets.push(None);
-        offsets.extend(offsets_1);
-        offsets.push(None);
-        original_offsets.push(vec![]);
-        original_offsets.extend(original_offsets_1);
-        original_offsets.push(vec![]);
-        mask.push(Mask::Special);
-        mask.extend(mask_1);
-        mask.push(Mask::Special);
-        if let Some(add_tokens) = tokens_2 {
-            let length = add_tokens.len();
-            special_tokens_mask.push(1);
-            special_tokens_mask.extend(vec![0; add_tokens.len()]);
-            special_tokens_mask.push(1);
-            token_segment_ids.push(0);
-            token_segment_ids.extend(vec![1; add_tokens.len() + 1]);
-            output.push(self.vocab.token_to_id(RobertaVocab::sep_value()));
-            output.extend(add_tokens);
-            output.push(self.vocab.token_to_id(RobertaVocab::sep_value()));
-            if let Some(add_offsets) = offsets_2 {
-                offsets.extend(add_offsets);
-            } else {
-                offsets.extend(vec![None; length]);
-            }
-            if let Some(add_original_offsets) = original_offsets_2 {
-                original_offsets.extend(add_original_offsets);
-            }
-            offsets.push(None);
-            original_offsets.push(vec![]);
-            if let Some(mask_2) = mask_2 {
-                mask.extend(mask_2)
-            } else {
-                mask.extend(vec![Mask::None; length]);
-            }
-            mask.push(Mask::Special);
-        }
-        (
-            output,
-            token_segment_ids,
-            special_tokens_mask,
-            offsets,
-            original_offsets,
-            mask,
+    fn default_post_processor(&self) -> TemplateProcessing {
+        let (cls, sep) = (self.vocab.get_cls_value(), self.vocab.get_sep_value());
+        TemplateProcessing::from_pieces(
+            vec![
+                TemplatePiece::special_token(cls, 0),
+                TemplatePiece::sequence(0, 0),
+                TemplatePiece::special_token(sep, 0),
+            ],
+            vec![
+                TemplatePiece::special_token(cls, 0),
+                TemplatePiece::sequence(0, 0),
+                TemplatePiece::special_token(sep, 0),
+                TemplatePiece::special_token(sep, 0),
+                TemplatePiece::sequence(1, 1),
+                TemplatePiece::special_token(sep, 1),
+            ],
         )
+        .unwrap()
+    }
+}
+
+impl MultiThreadedTokenizer<RobertaVocab> for RobertaTokenizer {}
+
+impl TokenizerJson for RobertaTokenizer {
+    fn to_tokenizer_json(&self) -> Result<Value, TokenizerError> {
+        check_default_components(self.custom_normalizer, self.custom_pre_tokenizer)?;
+        let vocab = self.vocab.as_ref();
+        let normalizer = if self.lower_case {
+            json!({"type": "Lowercase"})
+        } else {
+            Value::Null
+        };
+        let byte_level = json!({
+            "type": "ByteLevel",
+            "add_prefix_space": self.add_prefix_space,
+            "trim_offsets": true,
+            "use_regex": true
+        });
+        Ok(tokenizer_json(
+            vocab,
+            normalizer,
+            byte_level.clone(),
+            bpe_model_json(vocab, self.bpe_ranks.as_ref(), None),
+            post_processor_json(self),
+            byte_level,
+        ))
+    }
+
+    fn from_tokenizer_json(tokenizer_json: &Value) -> Result<RobertaTokenizer, TokenizerError> {
+        let vocab = read_vocab(
+            tokenizer_json,
+            |values,
+             indices,
+             special_token_map,
+             special_values,
+             special_indices,
+             added_values,
+             added_tokens| {
+                RobertaVocab {
+                    values,
+                    indices,
+                    special_token_map,
+                    special_values,
+                    special_indices,
+                    added_values,
+                    added_tokens,
+                }
+            },
+        )?;
+        let merges = read_merges(get_section(tokenizer_json, "model")?)?;
+        let (lower_case, _) = read_normalizer_flags(tokenizer_json);
+        let mut tokenizer = RobertaTokenizer::from_existing_vocab_and_merges(
+            Arc::new(vocab),
+            Arc::new(merges),
+            lower_case,
+            read_add_prefix_space(tokenizer_json),
+        );
+        restore_post_processor(&mut tokenizer, tokenizer_json)?;
+        Ok(tokenizer)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::preprocessing::tokenizer::base_tokenizer::{TokenizedInput, TruncationStrategy};
+    use crate::preprocessing::tokenizer::base_tokenizer::{
+        Offset, PaddingOptions, TokenizedInput, TruncationStrategy,
+    };
     use crate::preprocessing::vocab::base_vocab::swap_key_values;
     use crate::RobertaVocab;
     use std::collections::HashMap;
@@ -276,9 +362,11 @@ mod tests {
         RobertaVocab {
             values,
             indices,
-            unknown_value: "<unk>",
+            special_token_map: RobertaVocab::default_special_token_map(),
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
         }
     }
 
@@ -355,7 +443,12 @@ mod tests {
         }
 
         assert_eq!(
-            roberta_tokenizer.tokenize_list(source_texts.clone()),
+            Tokenizer::tokenize_list(&roberta_tokenizer, source_texts.clone()),
+            expected_results
+        );
+
+        assert_eq!(
+            MultiThreadedTokenizer::tokenize_list(&roberta_tokenizer, source_texts.clone()),
             expected_results
         );
     }
@@ -442,7 +535,12 @@ mod tests {
         }
 
         assert_eq!(
-            roberta_tokenizer.tokenize_list(source_texts.clone()),
+            Tokenizer::tokenize_list(&roberta_tokenizer, source_texts.clone()),
+            expected_results
+        );
+
+        assert_eq!(
+            MultiThreadedTokenizer::tokenize_list(&roberta_tokenizer, source_texts.clone()),
             expected_results
         );
     }
@@ -471,6 +569,20 @@ mod tests {
                         Some(Offset { begin: 7, end: 9 }),
                         None,
                     ],
+                    byte_offsets: vec![
+                        None,
+                        Some(Offset { begin: 0, end: 3 }),
+                        Some(Offset { begin: 3, end: 7 }),
+                        Some(Offset { begin: 7, end: 9 }),
+                        None,
+                    ],
+                    utf16_offsets: vec![
+                        None,
+                        Some(Offset { begin: 0, end: 3 }),
+                        Some(Offset { begin: 3, end: 7 }),
+                        Some(Offset { begin: 7, end: 9 }),
+                        None,
+                    ],
                     reference_offsets: vec![
                         vec![],
                         vec![0, 0, 1, 2],
@@ -485,6 +597,11 @@ mod tests {
                         Mask::Continuation,
                         Mask::Special,
                     ],
+                    attention_mask: vec![1, 1, 1, 1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![None, Some(0), Some(1), Some(1), None],
+                    sequence_ids: vec![None, Some(0), Some(0), Some(0), None],
+                    alignment_index: Default::default(),
                 },
             ),
             (
@@ -503,6 +620,22 @@ mod tests {
                         Some(Offset { begin: 0, end: 1 }),
                         None,
                     ],
+                    byte_offsets: vec![
+                        None,
+                        Some(Offset { begin: 0, end: 3 }),
+                        Some(Offset { begin: 0, end: 3 }),
+                        Some(Offset { begin: 0, end: 3 }),
+                        Some(Offset { begin: 0, end: 3 }),
+                        None,
+                    ],
+                    utf16_offsets: vec![
+                        None,
+                        Some(Offset { begin: 0, end: 1 }),
+                        Some(Offset { begin: 0, end: 1 }),
+                        Some(Offset { begin: 0, end: 1 }),
+                        Some(Offset { begin: 0, end: 1 }),
+                        None,
+                    ],
                     reference_offsets: vec![vec![], vec![0], vec![0], vec![0], vec![0], vec![]],
                     mask: vec![
                         Mask::Special,
@@ -512,6 +645,11 @@ mod tests {
                         Mask::Continuation,
                         Mask::Special,
                     ],
+                    attention_mask: vec![1, 1, 1, 1, 1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![None, Some(0), Some(0), Some(0), Some(0), None],
+                    sequence_ids: vec![None, Some(0), Some(0), Some(0), Some(0), None],
+                    alignment_index: Default::default(),
                 },
             ),
             (
@@ -523,8 +661,15 @@ mod tests {
                     overflowing_tokens: vec![],
                     num_truncated_tokens: 0,
                     token_offsets: vec![None, None],
+                    byte_offsets: vec![None, None],
+                    utf16_offsets: vec![None, None],
                     reference_offsets: vec![vec![], vec![]],
                     mask: vec![Mask::Special, Mask::Special],
+                    attention_mask: vec![1, 1],
+                    overflow_to_sample_mapping: None,
+                    word_ids: vec![None, None],
+                    sequence_ids: vec![None, None],
+                    alignment_index: Default::default(),
                 },
             ),
         ];
@@ -535,12 +680,37 @@ mod tests {
         //        When & Then
         for (source_text, expected_result) in test_tuples.iter() {
             assert_eq!(
-                roberta_tokenizer.encode(source_text, None, 128, &truncation_strategy, 0),
+                roberta_tokenizer.encode(
+                    source_text,
+                    None,
+                    128,
+                    &truncation_strategy,
+                    0,
+                    &PaddingOptions::default()
+                ),
                 *expected_result
             );
         }
         assert_eq!(
-            roberta_tokenizer.encode_list(source_texts.clone(), 128, &truncation_strategy, 0),
+            Tokenizer::encode_list(
+                &roberta_tokenizer,
+                source_texts.clone(),
+                128,
+                &truncation_strategy,
+                0,
+                &PaddingOptions::default()
+            ),
+            expected_results
+        );
+        assert_eq!(
+            MultiThreadedTokenizer::encode_list(
+                &roberta_tokenizer,
+                source_texts.clone(),
+                128,
+                &truncation_strategy,
+                0,
+                &PaddingOptions::default()
+            ),
             expected_results
         );
     }
@@ -11,83 +11,168 @@
 // limitations under the License.
 
 use crate::preprocessing::error::TokenizerError;
-use crate::preprocessing::tokenizer::base_tokenizer::{Mask, Token, TokenRef};
+use crate::preprocessing::tokenizer::base_tokenizer::{
+    Mask, SubwordSamplingTokenizer, Token, TokenRef,
+};
+use crate::preprocessing::tokenizer::normalizer::{sentence_piece_normalizer, Normalizer};
+use crate::preprocessing::tokenizer::post_processor::TemplateProcessing;
+use crate::preprocessing::tokenizer::pre_tokenizer::{PreTokenizer, Sequence};
+use crate::preprocessing::tokenizer::tokenizer_json::{
+    check_default_components, custom_post_processor_json, read_normalizer_flags,
+    read_sentence_piece_model, read_vocab, restore_post_processor, sentence_piece_tokenizer_json,
+    TokenizerJson,
+};
 use crate::preprocessing::vocab::sentence_piece_vocab::SentencePieceModel;
 use crate::preprocessing::vocab::t5_vocab::T5Vocab;
-use crate::tokenization_utils::{
-    _clean_text, decompose_nfkc, is_whitespace, lowercase, split_on_special_tokens,
-};
+use crate::tokenization_utils::{merge_byte_pieces, split_on_added_tokens};
 use crate::{MultiThreadedTokenizer, Tokenizer, Vocab};
+use rand::Rng;
+use serde_json::Value;
+use std::io::Read;
 
 #[derive(Debug)]
 pub struct T5Tokenizer {
     model: SentencePieceModel,
     vocab: T5Vocab,
     lower_case: bool,
+    normalizer: Box<dyn Normalizer>,
+    custom_normalizer: bool,
+    pre_tokenizer: Box<dyn PreTokenizer>,
+    custom_pre_tokenizer: bool,
+    post_processor: Option<TemplateProcessing>,
 }
 
 impl T5Tokenizer {
     pub fn from_file(path: &str, lower_case: bool) -> Result<T5Tokenizer, TokenizerError> {
         let model = SentencePieceModel::from_file(path)?;
         let vocab = T5Vocab::from_file(path)?;
+        let normalizer =
+            sentence_piece_normalizer(lower_case, false, false, model.normalizer.as_ref());
         Ok(T5Tokenizer {
             model,
             vocab,
             lower_case,
+            normalizer: Box::new(normalizer),
+            custom_normalizer: false,
+            pre_tokenizer: Box::new(Sequence::default()),
+            custom_pre_tokenizer: false,
+            post_processor: None,
         })
     }
 
+    /// Create a new instance of a `T5Tokenizer` from a reader for the SentencePiece model (e.g. a file
+    /// or a network stream), with the same content as the file expected by `from_file`.
+    pub fn from_reader<R: Read>(
+        mut reader: R,
+        lower_case: bool,
+    ) -> Result<T5Tokenizer, TokenizerError> {
+        let mut contents = Vec::new();
+        reader
+            .read_to_end(&mut contents)
+            .map_err(|e| TokenizerError::VocabularyParsingError(e.to_string()))?;
+        T5Tokenizer::from_bytes(&contents, lower_case)
+    }
+
+    /// Create a new instance of a `T5Tokenizer` from a SentencePiece model held in memory (e.g. loaded
+    /// with `include_bytes!`).
+    pub fn from_bytes(bytes: &[u8], lower_case: bool) -> Result<T5Tokenizer, TokenizerError> {
+        let model = SentencePieceModel::from_bytes(bytes)?;
+        let vocab = T5Vocab::from_bytes(bytes)?;
+        Ok(T5Tokenizer::from_existing_vocab_and_model(
+            vocab, model, lower_case,
+        ))
+    }
+
     pub fn from_existing_vocab_and_model(
         vocab: T5Vocab,
         model: SentencePieceModel,
         lower_case: bool,
     ) -> T5Tokenizer {
+        let normalizer =
+            sentence_piece_normalizer(lower_case, false, false, model.normalizer.as_ref());
         T5Tokenizer {
             model,
             vocab,
             lower_case,
+            normalizer: Box::new(normalizer),
+            custom_normalizer: false,
+            pre_tokenizer: Box::new(Sequence::default()),
+            custom_pre_tokenizer: false,
+            post_processor: None,
         }
     }
-}
-
-impl Tokenizer<T5Vocab> for T5Tokenizer {
-    fn vocab(&self) -> &T5Vocab {
-        &self.vocab
-    }
 
-    fn tokenize_to_tokens(&self, text: TokenRef) -> Vec<Token> {
-        let mut tokens = split_on_special_tokens(text, &self.vocab)
+    ///Splits the added tokens, normalizes and pre-tokenizes the text, the remaining tokens being
+    ///segmented into pieces with `segment`
+    fn tokenize_with_segmentation<F: FnMut(TokenRef) -> Vec<Token>>(
+        &self,
+        text: TokenRef,
+        mut segment: F,
+    ) -> Vec<Token> {
+        let mut tokens = split_on_added_tokens(text, &self.vocab, self.normalizer.is_lowercasing())
             .into_iter()
             .map(|token| token.to_owned())
             .collect::<Vec<Token>>();
 
         let mut sub_tokens: Vec<Token> = Vec::new();
         for token in tokens.iter_mut() {
-            if token.mask != Mask::Special && token.mask != Mask::Unknown {
-                _clean_text(token, true);
-                decompose_nfkc(token);
-                if self.lower_case {
-                    lowercase(token);
+            if token.mask != Mask::Special
+                && token.mask != Mask::Added
+                && token.mask != Mask::Unknown
+            {
+                self.normalizer.normalize(token);
+                for token in self.pre_tokenizer.pre_tokenize(token.as_ref()) {
+                    sub_tokens.extend(segment(token))
                 }
-                token.text = token.text.replace(|c: char| is_whitespace(&c), "\u{2581}");
-                if !token.text.starts_with('\u{2581}') {
-                    token.text.insert(0, '\u{2581}');
-                    token.reference_offsets.insert(0, 0);
-                };
-                let output = self.model.decode_forward_token_ref(token.as_ref());
-                let decoded = self.model.decode_backward(&output);
-
-                let output: Vec<Token> = self.model.parse_nodes_to_tokens(decoded);
-                sub_tokens.extend(output)
             } else {
                 sub_tokens.push(token.clone());
             }
         }
         sub_tokens
     }
+}
+
+impl Tokenizer<T5Vocab> for T5Tokenizer {
+    fn vocab(&self) -> &T5Vocab {
+        &self.vocab
+    }
+
+    fn vocab_mut(&mut self) -> &mut T5Vocab {
+        &mut self.vocab
+    }
+
+    fn normalizer(&self) -> &dyn Normalizer {
+        self.normalizer.as_ref()
+    }
+
+    fn set_normalizer(&mut self, normalizer: Box<dyn Normalizer>) {
+        self.normalizer = normalizer;
+        self.custom_normalizer = true;
+    }
+
+    fn pre_tokenizer(&self) -> &dyn PreTokenizer {
+        self.pre_tokenizer.as_ref()
+    }
+
+    fn set_pre_tokenizer(&mut self, pre_tokenizer: Box<dyn PreTokenizer>) {
+        self.pre_tokenizer = pre_tokenizer;
+        self.custom_pre_tokenizer = true;
+    }
+
+    fn post_processor(&self) -> Option<&TemplateProcessing> {
+        self.post_processor.as_ref()
+    }
+
+    fn post_processor_mut(&mut self) -> &mut Option<TemplateProcessing> {
+        &mut self.post_processor
+    }
+
+    fn tokenize_to_tokens(&self, text: TokenRef) -> Vec<Token> {
+        self.tokenize_with_segmentation(text, |token| self.model.tokenize(token))
+    }
 
     fn convert_tokens_to_string(&self, tokens: Vec<String>) -> String {
-        tokens
+        merge_byte_pieces(tokens)
             .into_iter()
             .map(|v| v.replace('\u{2581}', " "))
             .collect::<Vec<String>>()
@@ -96,3 +181,60 @@ impl Tokenizer<T5Vocab> for T5Tokenizer {
 }
 
 impl MultiThreadedTokenizer<T5Vocab> for T5Tokenizer {}
+
+impl SubwordSamplingTokenizer<T5Vocab> for T5Tokenizer {
+    fn tokenize_to_tokens_with_sampling<R: Rng + ?Sized>(
+        &self,
+        text: TokenRef,
+        nbest_size: i64,
+        alpha: f32,
+        rng: &mut R,
+    ) -> Vec<Token> {
+        self.tokenize_with_segmentation(text, |token| {
+            self.model
+                .tokenize_with_sampling(token, nbest_size, alpha, &mut *rng)
+        })
+    }
+}
+
+impl TokenizerJson for T5Tokenizer {
+    fn to_tokenizer_json(&self) -> Result<Value, TokenizerError> {
+        check_default_components(self.custom_normalizer, self.custom_pre_tokenizer)?;
+        Ok(sentence_piece_tokenizer_json(
+            &self.vocab,
+            &self.model,
+            self.lower_case,
+            false,
+            false,
+            custom_post_processor_json(self, Value::Null),
+        ))
+    }
+
+    fn from_tokenizer_json(tokenizer_json: &Value) -> Result<T5Tokenizer, TokenizerError> {
+        let vocab = read_vocab(
+            tokenizer_json,
+            |values,
+             indices,
+             special_token_map,
+             special_values,
+             special_indices,
+             added_values,
+             added_tokens| {
+                T5Vocab {
+                    values,
+                    indices,
+                    special_token_map,
+                    special_values,
+                    special_indices,
+                    added_values,
+                    added_tokens,
+                }
+            },
+        )?;
+        let model = read_sentence_piece_model(tokenizer_json)?;
+        let (lower_case, _) = read_normalizer_flags(tokenizer_json);
+        let mut tokenizer = T5Tokenizer::from_existing_vocab_and_model(vocab, model, lower_case);
+        restore_post_processor(&mut tokenizer, tokenizer_json)?;
+        Ok(tokenizer)
+    }
+}
@@ -12,23 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::preprocessing::error::TokenizerError;
 use crate::preprocessing::tokenizer::base_tokenizer::{
-    Mask, Offset, OffsetSize, Token, TokenRef, TruncationStrategy,
+    Mask, Offset, OffsetSize, OffsetUnit, PaddingSide, Token, TokenRef, TokenizedInput,
+    TruncationStrategy,
 };
+use crate::preprocessing::tokenizer::bpe_cache::BpeCache;
 use crate::preprocessing::tokenizer::constants::{
     ACCENT_MARKERS, ADDITIONAL_WHITESPACE_CHARS, BYTES_TO_UNICODE, CONTROL_CHARS,
     PUNCTUATION_CHARS, WHITESPACE_CHARS,
 };
-use crate::preprocessing::vocab::base_vocab::Vocab;
+use crate::preprocessing::vocab::base_vocab::{AddedToken, Vocab};
 use crate::preprocessing::vocab::bpe_vocab::{BpePairRef, BpePairVocab};
-use crate::BertVocab;
 use regex::Regex;
-use std::cell::RefCell;
 use std::char;
 use std::char::REPLACEMENT_CHARACTER;
 use std::cmp::min;
 use std::collections::{HashMap, HashSet};
-use std::error::Error;
 use unicode_normalization::char::decompose_canonical;
 use unicode_normalization_alignments::UnicodeNormalization;
 
@@ -87,25 +87,331 @@ pub fn replace_string(token: &mut Token, pattern: &str, replacement_string: &str
     }
 }
 
-///Split a text on special tokens (like BOS/EOS/UNK markers), depending on the vocabulary
-pub fn split_on_special_tokens<'a>(token: TokenRef<'a>, vocab: &impl Vocab) -> Vec<TokenRef<'a>> {
-    let test_substr = |s: &str| {
-        for special_value in vocab.special_values().keys() {
-            if s.starts_with(special_value.as_str()) {
-                return (
-                    special_value.len(),
-                    special_value.chars().count(),
-                    if vocab.get_unknown_value() == special_value.as_str() {
-                        Mask::Unknown
-                    } else {
-                        Mask::Special
+/// Replaces the matches of a regular expression by a replacement &str keeping track of the offsets
+/// (all new characters in replacement have the same reference offset as the first matched character).
+/// The replacement is inserted literally, capture groups references are not expanded.
+pub fn replace_regex(token: &mut Token, pattern: &Regex, replacement_string: &str) {
+    let replacement_char_len = replacement_string.chars().count();
+    let mut replaced_string = String::with_capacity(token.text.len());
+    let mut character_mapping: Vec<OffsetSize> = Vec::with_capacity(token.reference_offsets.len());
+    let mut last_byte = 0;
+    let mut last_char = 0;
+    for hit in pattern.find_iter(&token.text) {
+        let begin_char = last_char + token.text[last_byte..hit.start()].chars().count();
+        let end_char = begin_char + hit.as_str().chars().count();
+        let reference_offset = token
+            .reference_offsets
+            .get(begin_char)
+            .or_else(|| token.reference_offsets.last())
+            .copied()
+            .unwrap_or(0);
+        replaced_string.push_str(&token.text[last_byte..hit.start()]);
+        replaced_string.push_str(replacement_string);
+        character_mapping.extend_from_slice(&token.reference_offsets[last_char..begin_char]);
+        character_mapping.extend(vec![reference_offset; replacement_char_len]);
+        last_byte = hit.end();
+        last_char = end_char;
+    }
+    replaced_string.push_str(&token.text[last_byte..]);
+    character_mapping.extend_from_slice(&token.reference_offsets[last_char..]);
+    token.text = replaced_string;
+    token.reference_offsets = character_mapping;
+}
+
+///Inserts a prefix at the beginning of a token, the prefix characters are mapped to the reference
+///offset of the first character of the token
+pub fn insert_prefix(token: &mut Token, prefix: &str) {
+    let reference_offset = *token.reference_offsets.first().unwrap_or(&0);
+    token.text.insert_str(0, prefix);
+    token
+        .reference_offsets
+        .splice(0..0, vec![reference_offset; prefix.chars().count()]);
+}
+
+///Surrounds CJK characters with whitespaces so that they are split as individual words
+pub fn pad_cjk_chars(token: &mut Token) {
+    let mut padded_string = String::with_capacity(token.text.len());
+    let mut character_mapping: Vec<OffsetSize> = Vec::with_capacity(token.text.len());
+    for (character, position) in token.text.chars().zip(token.reference_offsets.iter()) {
+        if is_cjk_char(&character) {
+            padded_string.push(' ');
+            padded_string.push(character);
+            padded_string.push(' ');
+            character_mapping.extend([*position; 3].iter());
+        } else {
+            padded_string.push(character);
+            character_mapping.push(*position);
+        }
+    }
+    token.text = padded_string;
+    token.reference_offsets = character_mapping;
+}
+
+///Removes the leading (`left`) and/or trailing (`right`) whitespaces of a token
+pub fn strip_whitespace(token: &mut Token, left: bool, right: bool) {
+    let characters = token.text.chars().collect::<Vec<char>>();
+    let mut begin = 0;
+    let mut end = characters.len();
+    if left {
+        while begin < end && characters[begin].is_whitespace() {
+            begin += 1;
+        }
+    }
+    if right {
+        while end > begin && characters[end - 1].is_whitespace() {
+            end -= 1;
+        }
+    }
+    token.text = characters[begin..end].iter().collect();
+    token.reference_offsets = token.reference_offsets[begin..end].to_vec();
+}
+
+///Split a text on special tokens (like BOS/EOS/UNK markers) and tokens added to the vocabulary,
+///depending on the vocabulary
+pub fn split_on_special_tokens<'a>(
+    token: TokenRef<'a>,
+    vocab: &'a impl Vocab,
+) -> Vec<TokenRef<'a>> {
+    split_on_added_tokens(token, vocab, false)
+}
+
+///Split a text on special tokens and tokens added to the vocabulary, following the matching options
+///of the vocabulary (see `AddedToken`). Tokens set to match the normalized text are matched
+///case-insensitively if `lower_case` is set. The text of the split tokens is the vocabulary entry,
+///their offsets include the whitespace absorbed by the `lstrip` and `rstrip` options.
+pub fn split_on_added_tokens<'a>(
+    token: TokenRef<'a>,
+    vocab: &'a impl Vocab,
+    lower_case: bool,
+) -> Vec<TokenRef<'a>> {
+    if token.text.is_empty() {
+        return vec![];
+    }
+    if token.mask != Mask::None {
+        return vec![token];
+    }
+    let candidates = vocab
+        .special_values()
+        .keys()
+        .map(|value| (value, Mask::Special))
+        .chain(
+            vocab
+                .added_values()
+                .keys()
+                .map(|value| (value, Mask::Added)),
+        )
+        .map(|(value, mask)| {
+            let mask = if vocab.get_unknown_value() == value.as_str() {
+                Mask::Unknown
+            } else {
+                mask
+            };
+            AddedTokenCandidate::new(value, mask, vocab.added_tokens().get(value), lower_case)
+        })
+        .collect::<Vec<AddedTokenCandidate>>();
+
+    let char_indices = token.text.char_indices().collect::<Vec<(usize, char)>>();
+    let mut tokens: Vec<TokenRef<'a>> = Vec::new();
+    let mut char_begin: usize = 0;
+    let mut bytes_begin: usize = 0;
+    let mut char_idx: usize = 0;
+    while char_idx < char_indices.len() {
+        let bytes_idx = char_indices[char_idx].0;
+        let added_token_match = match_added_token(token.text, bytes_idx, &candidates);
+        let (content, mask, matched_chars) = match added_token_match {
+            Some(value) => value,
+            None => {
+                char_idx += 1;
+                continue;
+            }
+        };
+        if char_begin < char_idx {
+            //add previous token
+            let trimmed_text = token.text[bytes_begin..bytes_idx].trim_end();
+            let trimmed_text_len = trimmed_text.chars().count();
+            if trimmed_text_len > 0 {
+                tokens.push(TokenRef {
+                    text: trimmed_text,
+                    offset: Offset {
+                        begin: token.offset.begin + char_begin as OffsetSize,
+                        end: token.offset.begin + (char_begin + trimmed_text_len) as OffsetSize,
                     },
-                );
+                    reference_offsets: &token.reference_offsets
+                        [char_begin..(char_begin + trimmed_text_len)],
+                    mask: Mask::None,
+                });
             }
         }
-        (0, 0, Mask::None)
+        tokens.push(TokenRef {
+            text: content,
+            offset: Offset {
+                begin: token.offset.begin + char_idx as OffsetSize,
+                end: token.offset.begin + (char_idx + matched_chars) as OffsetSize,
+            },
+            reference_offsets: &token.reference_offsets[char_idx..(char_idx + matched_chars)],
+            mask,
+        });
+        char_idx += matched_chars;
+        char_begin = char_idx;
+        bytes_begin = match char_indices.get(char_idx) {
+            Some((bytes_idx, _)) => *bytes_idx,
+            None => token.text.len(),
+        };
+    }
+    if bytes_begin < token.text.len() {
+        //add last buffered token if there is anything left
+        tokens.push(TokenRef {
+            text: &token.text[bytes_begin..],
+            offset: Offset {
+                begin: token.offset.begin + char_begin as OffsetSize,
+                end: token.offset.begin + char_indices.len() as OffsetSize,
+            },
+            reference_offsets: &token.reference_offsets[char_begin..char_indices.len()],
+            mask: Mask::None,
+        });
+    }
+    tokens
+}
+
+///Special or added token to match in a text, with its matching options
+struct AddedTokenCandidate<'a> {
+    content: &'a str,
+    ///Lower-cased content, set if the token is matched case-insensitively
+    lowercased_content: Option<String>,
+    mask: Mask,
+    single_word: bool,
+    lstrip: bool,
+    rstrip: bool,
+}
+
+impl<'a> AddedTokenCandidate<'a> {
+    fn new(
+        content: &'a str,
+        mask: Mask,
+        options: Option<&AddedToken>,
+        lower_case: bool,
+    ) -> AddedTokenCandidate<'a> {
+        let (single_word, lstrip, rstrip, normalized) = match options {
+            Some(options) => (
+                options.single_word,
+                options.lstrip,
+                options.rstrip,
+                options.normalized,
+            ),
+            None => (false, false, false, false),
+        };
+        AddedTokenCandidate {
+            content,
+            lowercased_content: if normalized && lower_case {
+                Some(content.to_lowercase())
+            } else {
+                None
+            },
+            mask,
+            single_word,
+            lstrip,
+            rstrip,
+        }
+    }
+}
+
+///Returns the number of bytes and characters of the whitespace at the beginning of a text
+fn leading_whitespace(text: &str) -> (usize, usize) {
+    let mut chars = 0;
+    for (bytes_idx, character) in text.char_indices() {
+        if !character.is_whitespace() {
+            return (bytes_idx, chars);
+        }
+        chars += 1;
+    }
+    (text.len(), chars)
+}
+
+///Finds the longest special or added token matching the text at a given byte position. Returns the
+///matched vocabulary entry, its mask and the number of characters consumed (including the whitespace
+///absorbed by the `lstrip` and `rstrip` options).
+fn match_added_token<'a>(
+    text: &str,
+    bytes_idx: usize,
+    candidates: &[AddedTokenCandidate<'a>],
+) -> Option<(&'a str, Mask, usize)> {
+    let is_word_char = |character: char| character.is_alphanumeric() || character == '_';
+    let remaining_text = &text[bytes_idx..];
+    let (leading_whitespace_bytes, leading_whitespace_chars) = leading_whitespace(remaining_text);
+
+    let mut longest_match: Option<(&'a str, Mask, usize)> = None;
+    for candidate in candidates {
+        let (skipped_bytes, skipped_chars) = if candidate.lstrip {
+            (leading_whitespace_bytes, leading_whitespace_chars)
+        } else {
+            (0, 0)
+        };
+        let candidate_text = &remaining_text[skipped_bytes..];
+        let matched_bytes = match match_content(
+            candidate_text,
+            candidate.content,
+            candidate.lowercased_content.as_deref(),
+        ) {
+            Some(matched_bytes) => matched_bytes,
+            None => continue,
+        };
+        let following_text = &candidate_text[matched_bytes..];
+        if candidate.single_word {
+            let previous_char = text[..bytes_idx + skipped_bytes].chars().next_back();
+            let next_char = following_text.chars().next();
+            if matches!(previous_char, Some(c) if is_word_char(c))
+                || matches!(next_char, Some(c) if is_word_char(c))
+            {
+                continue;
+            }
+        }
+        let is_longer = match longest_match {
+            Some((longest, _, _)) => candidate.content.len() > longest.len(),
+            None => true,
+        };
+        if is_longer {
+            let trailing_chars = if candidate.rstrip {
+                leading_whitespace(following_text).1
+            } else {
+                0
+            };
+            let matched_chars =
+                skipped_chars + candidate_text[..matched_bytes].chars().count() + trailing_chars;
+            longest_match = Some((candidate.content, candidate.mask, matched_chars));
+        }
+    }
+    longest_match
+}
+
+///Returns the length in bytes of the prefix of `text` matching `content`. Lower-cased characters are
+///compared to `lowercased_content` if provided.
+fn match_content(text: &str, content: &str, lowercased_content: Option<&str>) -> Option<usize> {
+    let lowercased_content = match lowercased_content {
+        Some(lowercased_content) => lowercased_content,
+        None => {
+            return if text.starts_with(content) {
+                Some(content.len())
+            } else {
+                None
+            };
+        }
     };
-    split_on_substr(token, test_substr, true)
+    let mut lowercased_text = String::with_capacity(lowercased_content.len());
+    for (bytes_idx, character) in text.char_indices() {
+        if lowercased_text.len() >= lowercased_content.len() {
+            return if lowercased_text == lowercased_content {
+                Some(bytes_idx)
+            } else {
+                None
+            };
+        }
+        lowercased_text.extend(character.to_lowercase());
+    }
+    if lowercased_text == lowercased_content {
+        Some(text.len())
+    } else {
+        None
+    }
 }
 
 ///Tokenizes CJK characters, each character will be a token
@@ -113,7 +419,7 @@ pub fn tokenize_cjk_chars(token: TokenRef) -> Vec<TokenRef> {
     split_on_char(token, is_cjk_char, true, Mask::CJK)
 }
 
-fn is_cjk_char(character: &char) -> bool {
+pub(crate) fn is_cjk_char(character: &char) -> bool {
     let u32_char = *character as u32;
     ((u32_char >= 0x4E00) & (u32_char <= 0x9FFF))
         | ((u32_char >= 0x3400) & (u32_char <= 0x4DBF))
@@ -222,18 +528,23 @@ pub fn strip_accents(token: &mut Token) {
     token.offset.end = *token.reference_offsets.last().unwrap_or(&(0 as OffsetSize)) + 1;
 }
 
-///NFKC decomposition
-pub fn decompose_nfkc(token: &mut Token) {
+///Replaces the text of a token by its normalized form, `normalize` returning the normalized
+///characters with the number of characters of the input they replace minus one (as the
+///`unicode_normalization_alignments` iterators do)
+fn normalize_aligned<F>(token: &mut Token, normalize: F)
+where
+    F: Fn(&str) -> Vec<(char, isize)>,
+{
     let capacity = token.text.capacity();
-    let mut decomposed_string: String = String::with_capacity(capacity);
+    let mut normalized_string: String = String::with_capacity(capacity);
     let mut character_mapping: Vec<OffsetSize> = Vec::with_capacity(capacity);
     let mut cur_position: isize = 0;
-    for (character, extra_char) in token.text.nfkc() {
-        decomposed_string.push(character);
+    for (character, extra_char) in normalize(&token.text) {
+        normalized_string.push(character);
         character_mapping.push(token.reference_offsets[cur_position as usize]);
         cur_position = cur_position + 1 - extra_char;
     }
-    token.text = decomposed_string;
+    token.text = normalized_string;
     token.reference_offsets = character_mapping;
     token.offset.begin = *token
         .reference_offsets
@@ -242,6 +553,26 @@ pub fn decompose_nfkc(token: &mut Token) {
     token.offset.end = *token.reference_offsets.last().unwrap_or(&(0 as OffsetSize)) + 1;
 }
 
+///NFKC decomposition
+pub fn decompose_nfkc(token: &mut Token) {
+    normalize_aligned(token, |text| text.nfkc().collect())
+}
+
+///NFKD decomposition
+pub fn decompose_nfkd(token: &mut Token) {
+    normalize_aligned(token, |text| text.nfkd().collect())
+}
+
+///NFD decomposition
+pub fn decompose_nfd(token: &mut Token) {
+    normalize_aligned(token, |text| text.nfd().collect())
+}
+
+///NFC composition
+pub fn compose_nfc(token: &mut Token) {
+    normalize_aligned(token, |text| text.nfc().collect())
+}
+
 ///Split a token on punctuation
 pub fn split_on_punct(token: TokenRef) -> Vec<TokenRef> {
     split_on_char(token, is_punctuation, true, Mask::Punctuation)
@@ -536,7 +867,7 @@ pub fn tokenize_wordpiece(token: TokenRef, vocab: &impl Vocab, max_word_len: usi
     let mut tokens: Vec<Token> = Vec::new();
     if token.text.chars().count() > max_word_len {
         tokens.push(Token {
-            text: BertVocab::unknown_value().to_owned(),
+            text: vocab.get_unknown_value().to_owned(),
             offset: token.offset,
             reference_offsets: token.reference_offsets.to_vec(),
             mask: Mask::Unknown,
@@ -585,7 +916,7 @@ pub fn tokenize_wordpiece(token: TokenRef, vocab: &impl Vocab, max_word_len: usi
             }
             if is_unk {
                 return vec![Token {
-                    text: BertVocab::unknown_value().to_owned(),
+                    text: vocab.get_unknown_value().to_owned(),
                     offset: token.offset,
                     reference_offsets: token.reference_offsets.to_vec(),
                     mask: Mask::Unknown,
@@ -618,9 +949,9 @@ pub fn tokenize_wordpiece(token: TokenRef, vocab: &impl Vocab, max_word_len: usi
 ///       - TruncationStrategy::LongestFirst (default) Iteratively reduce the inputs sequence until the input is under max_length
 ///           starting from the longest one at each token (when there is a pair of input sequences).
 ///           Overflowing tokens only contains overflow from the first sequence.
-///       - TruncationStrategy::OnlyFirst: Only truncate the first sequence. raise an error if the first sequence is shorter or equal to than num_tokens_to_remove.
+///       - TruncationStrategy::OnlyFirst: Only truncate the first sequence. Returns a `TokenizerError::SequenceTooShort` if the first sequence is shorter than num_tokens_to_remove.
 ///       - TruncationStrategy::OnlySecond: Only truncate the second sequence
-///       - TruncationStrategy::DoNotTruncate: Does not truncate (raise an error if the input sequence is longer than max_length)
+///       - TruncationStrategy::DoNotTruncate: Does not truncate (returns a `TokenizerError::SequenceTooLong` if the input sequence is longer than max_length)
 ///   * stride
 ///       If set to a number along with max_length, the overflowing tokens returned will contain some tokens
 ///       from the main sequence returned. The value of this argument defines the number of additional tokens.
@@ -650,7 +981,7 @@ pub fn truncate_sequences(
         Vec<i64>,
         Vec<Option<Offset>>,
     ),
-    Box<dyn Error>,
+    TokenizerError,
 > {
     if num_tokens_to_remove == 0 {
         Ok((
@@ -667,124 +998,132 @@ pub fn truncate_sequences(
         ))
     } else {
         match tokens_2 {
-            Some(mut tokens_2) => {
-                match truncation_strategy {
-                    TruncationStrategy::LongestFirst => {
-                        if (tokens_1.len() + tokens_2.len()) >= num_tokens_to_remove {
-                            let mut overflow_tokens: Vec<i64> =
-                                Vec::with_capacity(num_tokens_to_remove + stride);
-                            let mut overflow_offsets: Vec<Option<Offset>> =
-                                Vec::with_capacity(num_tokens_to_remove + stride);
-                            for _ in 0..num_tokens_to_remove {
-                                if tokens_1.len() >= tokens_2.len() {
-                                    overflow_tokens.insert(0, tokens_1.pop().unwrap());
-                                    if !offsets_1.is_empty() {
-                                        overflow_offsets.insert(0, offsets_1.pop().unwrap());
-                                    }
-                                    original_positions_1.pop();
-                                    if !mask_1.is_empty() {
-                                        mask_1.pop();
-                                    }
-                                } else {
-                                    tokens_2.pop();
-                                    offsets_2 = offsets_2.map(|mut offsets_2| {
-                                        offsets_2.pop();
-                                        offsets_2
-                                    });
-                                    original_positions_2 =
-                                        original_positions_2.map(|mut original_positions_2| {
-                                            original_positions_2.pop();
-                                            original_positions_2
-                                        });
-                                    mask_2 = mask_2.map(|mut mask_2| {
-                                        mask_2.pop();
-                                        mask_2
-                                    });
-                                }
-                            }
-                            let window_len = min(tokens_1.len(), stride);
-                            if window_len > 0 {
-                                let slice: &[i64] = &tokens_1[&tokens_1.len() - window_len..];
-                                overflow_tokens.splice(0..0, slice.iter().cloned());
+            Some(mut tokens_2) => match truncation_strategy {
+                TruncationStrategy::LongestFirst => {
+                    if (tokens_1.len() + tokens_2.len()) >= num_tokens_to_remove {
+                        let mut overflow_tokens: Vec<i64> =
+                            Vec::with_capacity(num_tokens_to_remove + stride);
+                        let mut overflow_offsets: Vec<Option<Offset>> =
+                            Vec::with_capacity(num_tokens_to_remove + stride);
+                        for _ in 0..num_tokens_to_remove {
+                            if tokens_1.len() >= tokens_2.len() {
+                                overflow_tokens.insert(0, tokens_1.pop().unwrap());
                                 if !offsets_1.is_empty() {
-                                    let offset_slice: &[Option<Offset>] =
-                                        &offsets_1[&offsets_1.len() - window_len..];
-                                    overflow_offsets.splice(0..0, offset_slice.iter().cloned());
+                                    overflow_offsets.insert(0, offsets_1.pop().unwrap());
+                                }
+                                original_positions_1.pop();
+                                if !mask_1.is_empty() {
+                                    mask_1.pop();
                                 }
+                            } else {
+                                tokens_2.pop();
+                                offsets_2 = offsets_2.map(|mut offsets_2| {
+                                    offsets_2.pop();
+                                    offsets_2
+                                });
+                                original_positions_2 =
+                                    original_positions_2.map(|mut original_positions_2| {
+                                        original_positions_2.pop();
+                                        original_positions_2
+                                    });
+                                mask_2 = mask_2.map(|mut mask_2| {
+                                    mask_2.pop();
+                                    mask_2
+                                });
                             }
-                            Ok((
-                                tokens_1,
-                                Some(tokens_2),
-                                offsets_1,
-                                offsets_2,
-                                original_positions_1,
-                                original_positions_2,
-                                mask_1,
-                                mask_2,
-                                overflow_tokens,
-                                overflow_offsets,
-                            ))
-                        } else {
-                            Err("Combined sequence length too short for requested truncation amount".into())
                         }
-                    }
-                    TruncationStrategy::OnlyFirst => {
-                        if tokens_1.len() >= num_tokens_to_remove {
-                            let (overflow_tokens, overflow_offsets) = truncate_with_overflow(
-                                &mut tokens_1,
-                                offsets_1.as_mut(),
-                                original_positions_1.as_mut(),
-                                mask_1.as_mut(),
-                                num_tokens_to_remove,
-                                stride,
-                            );
-                            Ok((
-                                tokens_1,
-                                Some(tokens_2),
-                                offsets_1,
-                                offsets_2,
-                                original_positions_1,
-                                original_positions_2,
-                                mask_1,
-                                mask_2,
-                                overflow_tokens,
-                                overflow_offsets,
-                            ))
-                        } else {
-                            Err("First sequence too short for first only truncation".into())
+                        let window_len = min(tokens_1.len(), stride);
+                        if window_len > 0 {
+                            let slice: &[i64] = &tokens_1[&tokens_1.len() - window_len..];
+                            overflow_tokens.splice(0..0, slice.iter().cloned());
+                            if !offsets_1.is_empty() {
+                                let offset_slice: &[Option<Offset>] =
+                                    &offsets_1[&offsets_1.len() - window_len..];
+                                overflow_offsets.splice(0..0, offset_slice.iter().cloned());
+                            }
                         }
+                        Ok((
+                            tokens_1,
+                            Some(tokens_2),
+                            offsets_1,
+                            offsets_2,
+                            original_positions_1,
+                            original_positions_2,
+                            mask_1,
+                            mask_2,
+                            overflow_tokens,
+                            overflow_offsets,
+                        ))
+                    } else {
+                        Err(TokenizerError::SequenceTooShort {
+                            sequence_length: tokens_1.len() + tokens_2.len(),
+                            num_tokens_to_remove,
+                        })
                     }
-                    TruncationStrategy::OnlySecond => {
-                        if tokens_2.len() >= num_tokens_to_remove {
-                            let (overflow_tokens, overflow_offsets) = truncate_with_overflow(
-                                &mut tokens_2,
-                                offsets_2.as_mut().unwrap_or(&mut vec![]),
-                                original_positions_2.as_mut().unwrap_or(&mut vec![]),
-                                mask_2.as_mut().unwrap_or(&mut vec![]),
-                                num_tokens_to_remove,
-                                stride,
-                            );
-                            Ok((
-                                tokens_1,
-                                Some(tokens_2),
-                                offsets_1,
-                                offsets_2,
-                                original_positions_1,
-                                original_positions_2,
-                                mask_1,
-                                mask_2,
-                                overflow_tokens,
-                                overflow_offsets,
-                            ))
-                        } else {
-                            Err("Second sequence too short for second only truncation".into())
-                        }
+                }
+                TruncationStrategy::OnlyFirst => {
+                    if tokens_1.len() >= num_tokens_to_remove {
+                        let (overflow_tokens, overflow_offsets) = truncate_with_overflow(
+                            &mut tokens_1,
+                            offsets_1.as_mut(),
+                            original_positions_1.as_mut(),
+                            mask_1.as_mut(),
+                            num_tokens_to_remove,
+                            stride,
+                        );
+                        Ok((
+                            tokens_1,
+                            Some(tokens_2),
+                            offsets_1,
+                            offsets_2,
+                            original_positions_1,
+                            original_positions_2,
+                            mask_1,
+                            mask_2,
+                            overflow_tokens,
+                            overflow_offsets,
+                        ))
+                    } else {
+                        Err(TokenizerError::SequenceTooShort {
+                            sequence_length: tokens_1.len(),
+                            num_tokens_to_remove,
+                        })
                     }
-                    TruncationStrategy::DoNotTruncate => {
-                        Err("Truncation needed but no truncation requested".into())
+                }
+                TruncationStrategy::OnlySecond => {
+                    if tokens_2.len() >= num_tokens_to_remove {
+                        let (overflow_tokens, overflow_offsets) = truncate_with_overflow(
+                            &mut tokens_2,
+                            offsets_2.as_mut().unwrap_or(&mut vec![]),
+                            original_positions_2.as_mut().unwrap_or(&mut vec![]),
+                            mask_2.as_mut().unwrap_or(&mut vec![]),
+                            num_tokens_to_remove,
+                            stride,
+                        );
+                        Ok((
+                            tokens_1,
+                            Some(tokens_2),
+                            offsets_1,
+                            offsets_2,
+                            original_positions_1,
+                            original_positions_2,
+                            mask_1,
+                            mask_2,
+                            overflow_tokens,
+                            overflow_offsets,
+                        ))
+                    } else {
+                        Err(TokenizerError::SequenceTooShort {
+                            sequence_length: tokens_2.len(),
+                            num_tokens_to_remove,
+                        })
                     }
                 }
-            }
+                TruncationStrategy::DoNotTruncate => Err(sequence_too_long(
+                    tokens_1.len() + tokens_2.len(),
+                    num_tokens_to_remove,
+                )),
+            },
             None => {
                 if tokens_1.len() >= num_tokens_to_remove {
                     match truncation_strategy {
@@ -811,20 +1150,35 @@ pub fn truncate_sequences(
                             ))
                         }
                         TruncationStrategy::OnlySecond => {
-                            Err("Invalid truncation strategy for single sentence truncation".into())
+                            Err(TokenizerError::InvalidTruncationStrategy(
+                                "OnlySecond truncation requires a pair of sequences".to_string(),
+                            ))
                         }
                         TruncationStrategy::DoNotTruncate => {
-                            Err("Truncation needed but no truncation requested".into())
+                            Err(sequence_too_long(tokens_1.len(), num_tokens_to_remove))
                         }
                     }
                 } else {
-                    Err("First sequence too short for first only truncation".into())
+                    Err(TokenizerError::SequenceTooShort {
+                        sequence_length: tokens_1.len(),
+                        num_tokens_to_remove,
+                    })
                 }
             }
         }
     }
 }
 
+///Error returned when `num_tokens_to_remove` tokens should be removed from sequences of
+///`sequence_length` tokens but no truncation was requested. The maximum length is expressed in
+///tokens of the sequences passed to `truncate_sequences` (excluding special tokens).
+fn sequence_too_long(sequence_length: usize, num_tokens_to_remove: usize) -> TokenizerError {
+    TokenizerError::SequenceTooLong {
+        sequence_length,
+        max_length: sequence_length.saturating_sub(num_tokens_to_remove),
+    }
+}
+
 fn truncate_with_overflow(
     sequence: &mut Vec<i64>,
     offsets: &mut Vec<Option<Offset>>,
@@ -862,6 +1216,168 @@ fn truncate_with_overflow(
     (overflow_tokens, overflow_offsets)
 }
 
+/// # Maps character positions of a text to positions in the requested offset unit.
+///
+///Returns a vector of length `text.chars().count() + 1`, where the element at index `i` is the
+///position of the `i`-th character of `text` expressed in `offset_unit` (bytes, UTF-16 code units
+///or characters). The last element is the length of the text, so that an exclusive character
+///offset end can be mapped as well.
+pub fn get_char_positions(text: &str, offset_unit: &OffsetUnit) -> Vec<OffsetSize> {
+    let mut positions: Vec<OffsetSize> = Vec::with_capacity(text.len() + 1);
+    let mut position: OffsetSize = 0;
+    for character in text.chars() {
+        positions.push(position);
+        position += match offset_unit {
+            OffsetUnit::Char => 1,
+            OffsetUnit::Byte => character.len_utf8() as OffsetSize,
+            OffsetUnit::Utf16 => character.len_utf16() as OffsetSize,
+        };
+    }
+    positions.push(position);
+    positions
+}
+
+/// # Assigns a word index to each token of a sequence based on its mask.
+///
+///A new word starts at every token that is not a `Mask::Continuation` of the previous one, i.e.
+///all sub-tokens returned as a group by `ConsolidatedTokenIterator` share the same word index.
+///Word indices start at 0 for every sequence.
+pub fn get_word_ids(mask: &[Mask]) -> Vec<Option<u32>> {
+    let mut word_ids: Vec<Option<u32>> = Vec::with_capacity(mask.len());
+    let mut current_word: Option<u32> = None;
+    for token_mask in mask {
+        current_word = match (current_word, token_mask) {
+            (Some(word_id), Mask::Continuation) => Some(word_id),
+            (Some(word_id), _) => Some(word_id + 1),
+            (None, _) => Some(0),
+        };
+        word_ids.push(current_word);
+    }
+    word_ids
+}
+
+/// # Pads a tokenized input in place up to the target length.
+///
+///   * tokenized_input: `TokenizedInput` to pad. Inputs already longer than `target_length` are left unchanged.
+///   * target_length: length of the padded input
+///   * pad_token_id: ID of the padding token for the vocabulary used
+///   * padding_side: side of the input to insert the padding tokens at
+///       - PaddingSide::Right: padding tokens are appended after the input tokens
+///       - PaddingSide::Left: padding tokens are inserted before the input tokens
+///
+///Per-token vectors (`token_ids`, `segment_ids`, `special_tokens_mask`, `token_offsets`,
+///`byte_offsets`, `utf16_offsets`, `reference_offsets`, `mask`, `attention_mask`, `word_ids` and
+///`sequence_ids`) are only padded if they are populated, i.e. if they have the same length as
+///`token_ids`.
+pub fn pad_tokenized_input(
+    tokenized_input: &mut TokenizedInput,
+    target_length: usize,
+    pad_token_id: i64,
+    padding_side: &PaddingSide,
+) {
+    let sequence_length = tokenized_input.token_ids.len();
+    if sequence_length >= target_length {
+        return;
+    }
+    let num_padding_tokens = target_length - sequence_length;
+    pad_vector(
+        &mut tokenized_input.token_ids,
+        sequence_length,
+        num_padding_tokens,
+        pad_token_id,
+        padding_side,
+    );
+    pad_vector(
+        &mut tokenized_input.segment_ids,
+        sequence_length,
+        num_padding_tokens,
+        0,
+        padding_side,
+    );
+    pad_vector(
+        &mut tokenized_input.special_tokens_mask,
+        sequence_length,
+        num_padding_tokens,
+        1,
+        padding_side,
+    );
+    pad_vector(
+        &mut tokenized_input.token_offsets,
+        sequence_length,
+        num_padding_tokens,
+        None,
+        padding_side,
+    );
+    pad_vector(
+        &mut tokenized_input.byte_offsets,
+        sequence_length,
+        num_padding_tokens,
+        None,
+        padding_side,
+    );
+    pad_vector(
+        &mut tokenized_input.utf16_offsets,
+        sequence_length,
+        num_padding_tokens,
+        None,
+        padding_side,
+    );
+    pad_vector(
+        &mut tokenized_input.reference_offsets,
+        sequence_length,
+        num_padding_tokens,
+        vec![],
+        padding_side,
+    );
+    pad_vector(
+        &mut tokenized_input.mask,
+        sequence_length,
+        num_padding_tokens,
+        Mask::Special,
+        padding_side,
+    );
+    pad_vector(
+        &mut tokenized_input.attention_mask,
+        sequence_length,
+        num_padding_tokens,
+        0,
+        padding_side,
+    );
+    pad_vector(
+        &mut tokenized_input.word_ids,
+        sequence_length,
+        num_padding_tokens,
+        None,
+        padding_side,
+    );
+    pad_vector(
+        &mut tokenized_input.sequence_ids,
+        sequence_length,
+        num_padding_tokens,
+        None,
+        padding_side,
+    );
+}
+
+fn pad_vector<T: Clone>(
+    vector: &mut Vec<T>,
+    sequence_length: usize,
+    num_padding_tokens: usize,
+    pad_value: T,
+    padding_side: &PaddingSide,
+) {
+    if vector.len() != sequence_length {
+        return;
+    }
+    let padding = vec![pad_value; num_padding_tokens];
+    match padding_side {
+        PaddingSide::Right => vector.extend(padding),
+        PaddingSide::Left => {
+            vector.splice(0..0, padding);
+        }
+    }
+}
+
 pub fn get_pairs(token: &Vec<String>) -> Option<HashSet<BpePairRef>> {
     match token.len() {
         0 | 1 => None,
@@ -1020,7 +1536,7 @@ pub fn split_on_bpe_pairs<'a, F>(
     token: TokenRef<'a>,
     bpe_function: F,
     bpe_ranks: &BpePairVocab,
-    cache: &RefCell<HashMap<String, (Vec<String>, Vec<usize>)>>,
+    cache: &BpeCache,
     as_bytes: bool,
 ) -> Vec<Token>
 where
@@ -1044,7 +1560,7 @@ where
     } else {
         (token.text, token.reference_offsets)
     };
-    let cached: bool = match cache.borrow().get(text) {
+    let cached: bool = match cache.get(text) {
         Some((cached_tokens, char_counts)) => {
             let mut start = 0;
             for (idx, (sub_token, &char_count)) in
@@ -1079,9 +1595,7 @@ where
     };
     if !cached {
         let (bpe_output, char_counts) = bpe_function(text, bpe_ranks);
-        cache
-            .borrow_mut()
-            .insert(text.to_owned(), (bpe_output.clone(), char_counts.clone()));
+        cache.insert(text.to_owned(), (bpe_output.clone(), char_counts.clone()));
         let mut start = 0;
         for (idx, (sub_token, &char_count)) in bpe_output.iter().zip(char_counts.iter()).enumerate()
         {
@@ -1121,6 +1635,38 @@ pub fn fix_mask(tokens: &mut Vec<Token>) {
     }
 }
 
+///Returns the byte encoded by a SentencePiece byte piece (`<0x41>` for `A`)
+pub fn byte_piece_value(piece: &str) -> Option<u8> {
+    if piece.len() == 6 && piece.starts_with("<0x") && piece.ends_with('>') {
+        u8::from_str_radix(&piece[3..5], 16).ok()
+    } else {
+        None
+    }
+}
+
+///Reassembles the consecutive SentencePiece byte pieces (produced by models trained with byte
+///fallback) into strings. Invalid UTF-8 sequences are replaced by `U+FFFD`.
+pub fn merge_byte_pieces(tokens: Vec<String>) -> Vec<String> {
+    let mut output = Vec::with_capacity(tokens.len());
+    let mut bytes = vec![];
+    for token in tokens {
+        match byte_piece_value(&token) {
+            Some(byte) => bytes.push(byte),
+            None => {
+                if !bytes.is_empty() {
+                    output.push(String::from_utf8_lossy(&bytes).into_owned());
+                    bytes.clear();
+                }
+                output.push(token);
+            }
+        }
+    }
+    if !bytes.is_empty() {
+        output.push(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    output
+}
+
 //==============================
 // Unit tests
 //==============================
@@ -1128,6 +1674,7 @@ pub fn fix_mask(tokens: &mut Vec<Token>) {
 mod tests {
     use super::*;
     use crate::preprocessing::vocab::base_vocab::swap_key_values;
+    use crate::BertVocab;
     use std::collections::HashMap;
     use std::iter::FromIterator;
 
@@ -1169,9 +1716,11 @@ mod tests {
         BertVocab {
             values,
             indices,
-            unknown_value: "[UNK]",
+            special_token_map: BertVocab::default_special_token_map(),
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
         }
     }
 
@@ -1253,6 +1802,94 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_split_on_added_tokens() {
+        //        Given
+        let mut vocab = generate_test_vocab();
+        vocab.add_special_tokens(&["[MASK]_LONG"]);
+        vocab.add_tokens(&["covid19"]);
+        let source_text = "A [MASK]_LONG or [MASK] covid19 token";
+        let offsets = (0..source_text.chars().count() as OffsetSize).collect::<Vec<OffsetSize>>();
+
+        //        When
+        let tokens: Vec<(&str, Mask)> =
+            split_on_special_tokens(TokenRef::new(source_text, offsets.as_slice()), &vocab)
+                .into_iter()
+                .map(|t| (t.text, t.mask))
+                .collect();
+
+        //        Then
+        assert_eq!(
+            tokens,
+            vec![
+                ("A", Mask::None),
+                ("[MASK]_LONG", Mask::Special),
+                (" or", Mask::None),
+                ("[MASK]", Mask::Special),
+                ("covid19", Mask::Added),
+                (" token", Mask::None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_on_added_tokens_with_options() {
+        //        Given
+        let mut vocab = generate_test_vocab();
+        vocab.add_tokens_with_options(&[
+            AddedToken {
+                lstrip: true,
+                ..AddedToken::new("<mask>", true)
+            },
+            AddedToken {
+                single_word: true,
+                ..AddedToken::new("ing", false)
+            },
+            AddedToken {
+                rstrip: true,
+                ..AddedToken::new("Covid19", false)
+            },
+        ]);
+        let source_text = "Sing ing  <mask> COVID19 !";
+        let offsets = (0..source_text.chars().count() as OffsetSize).collect::<Vec<OffsetSize>>();
+
+        //        When
+        let case_sensitive_tokens: Vec<(&str, Mask, Offset)> = split_on_added_tokens(
+            TokenRef::new(source_text, offsets.as_slice()),
+            &vocab,
+            false,
+        )
+        .into_iter()
+        .map(|t| (t.text, t.mask, t.offset))
+        .collect();
+        let lower_cased_tokens: Vec<(&str, Mask, Offset)> =
+            split_on_added_tokens(TokenRef::new(source_text, offsets.as_slice()), &vocab, true)
+                .into_iter()
+                .map(|t| (t.text, t.mask, t.offset))
+                .collect();
+
+        //        Then
+        assert_eq!(
+            case_sensitive_tokens,
+            vec![
+                ("Sing", Mask::None, Offset::new(0, 4)),
+                ("ing", Mask::Added, Offset::new(5, 8)),
+                ("<mask>", Mask::Special, Offset::new(8, 16)),
+                (" COVID19 !", Mask::None, Offset::new(16, 26)),
+            ]
+        );
+        assert_eq!(
+            lower_cased_tokens,
+            vec![
+                ("Sing", Mask::None, Offset::new(0, 4)),
+                ("ing", Mask::Added, Offset::new(5, 8)),
+                ("<mask>", Mask::Special, Offset::new(8, 16)),
+                ("Covid19", Mask::Added, Offset::new(17, 25)),
+                ("!", Mask::None, Offset::new(25, 26)),
+            ]
+        );
+    }
+
     #[test]
     fn test_tokenize_cjk_chars() {
         //        Given
@@ -1750,7 +2387,7 @@ mod tests {
                     Vec<i64>,
                     Vec<Option<Offset>>,
                 ),
-                Box<dyn Error>,
+                TokenizerError,
             >,
         ); 12] = [
             //            Baseline
@@ -1804,7 +2441,10 @@ mod tests {
             //            Truncate amount larger than sequence length
             (
                 (20, &TruncationStrategy::LongestFirst, 0),
-                Err("First sequence too short for first only truncation".into()),
+                Err(TokenizerError::SequenceTooShort {
+                    sequence_length: 15,
+                    num_tokens_to_remove: 20,
+                }),
             ),
             //            Truncate entire sequence with stride = 2
             (
@@ -1905,12 +2545,17 @@ mod tests {
             //            No truncation requested, but needed
             (
                 (1, &TruncationStrategy::DoNotTruncate, 0),
-                Err("Truncation needed but no truncation requested".into()),
+                Err(TokenizerError::SequenceTooLong {
+                    sequence_length: 15,
+                    max_length: 14,
+                }),
             ),
             //            Invalid truncation requested
             (
                 (1, &TruncationStrategy::OnlySecond, 0),
-                Err("Invalid truncation strategy for single sentence truncation".into()),
+                Err(TokenizerError::InvalidTruncationStrategy(
+                    "OnlySecond truncation requires a pair of sequences".to_string(),
+                )),
             ),
         ];
 
@@ -1932,7 +2577,7 @@ mod tests {
                 Ok(value) => assert_eq!(value, *expected_outputs.as_ref().unwrap()),
                 Err(e) => assert_eq!(
                     e.to_string(),
-                    (**expected_outputs.as_ref().err().unwrap()).to_string()
+                    expected_outputs.as_ref().err().unwrap().to_string()
                 ),
             }
         }
@@ -1958,7 +2603,7 @@ mod tests {
                     Vec<i64>,
                     Vec<Option<Offset>>,
                 ),
-                Box<dyn Error>,
+                TokenizerError,
             >,
         ); 10] = [
             //            Baseline
@@ -2060,7 +2705,10 @@ mod tests {
             //            Request truncation amount greater than combined length
             (
                 (15 + 9 + 1, &TruncationStrategy::LongestFirst, 2),
-                Err("Combined sequence length too short for requested truncation amount".into()),
+                Err(TokenizerError::SequenceTooShort {
+                    sequence_length: 24,
+                    num_tokens_to_remove: 25,
+                }),
             ),
             //            No truncation
             (
@@ -2097,7 +2745,10 @@ mod tests {
             //            No truncation requested, but needed
             (
                 (1, &TruncationStrategy::DoNotTruncate, 0),
-                Err("Truncation needed but no truncation requested".into()),
+                Err(TokenizerError::SequenceTooLong {
+                    sequence_length: 24,
+                    max_length: 23,
+                }),
             ),
         ];
 
@@ -2121,7 +2772,7 @@ mod tests {
                 }
                 Err(e) => assert_eq!(
                     e.to_string(),
-                    (**expected_outputs.as_ref().err().unwrap()).to_string()
+                    expected_outputs.as_ref().err().unwrap().to_string()
                 ),
             }
         }
@@ -2147,7 +2798,7 @@ mod tests {
                     Vec<i64>,
                     Vec<Option<Offset>>,
                 ),
-                Box<dyn Error>,
+                TokenizerError,
             >,
         ); 5] = [
             //            Baseline
@@ -2201,7 +2852,10 @@ mod tests {
             //            Request truncation amount greater than sentence 1
             (
                 (16, &TruncationStrategy::OnlyFirst, 2),
-                Err("First sequence too short for first only truncation".into()),
+                Err(TokenizerError::SequenceTooShort {
+                    sequence_length: 15,
+                    num_tokens_to_remove: 16,
+                }),
             ),
             //            No truncation
             (
@@ -2239,7 +2893,7 @@ mod tests {
                 Ok(value) => assert_eq!(value, *expected_outputs.as_ref().unwrap()),
                 Err(e) => assert_eq!(
                     e.to_string(),
-                    (**expected_outputs.as_ref().err().unwrap()).to_string()
+                    expected_outputs.as_ref().err().unwrap().to_string()
                 ),
             }
         }
@@ -2265,7 +2919,7 @@ mod tests {
                     Vec<i64>,
                     Vec<Option<Offset>>,
                 ),
-                Box<dyn Error>,
+                TokenizerError,
             >,
         ); 5] = [
             //            Baseline
@@ -2319,7 +2973,10 @@ mod tests {
             //            Request truncation amount greater than sentence 1
             (
                 (10, &TruncationStrategy::OnlySecond, 2),
-                Err("Second sequence too short for second only truncation".into()),
+                Err(TokenizerError::SequenceTooShort {
+                    sequence_length: 9,
+                    num_tokens_to_remove: 10,
+                }),
             ),
             //            No truncation
             (
@@ -2357,7 +3014,7 @@ mod tests {
                 Ok(value) => assert_eq!(value, *expected_outputs.as_ref().unwrap()),
                 Err(e) => assert_eq!(
                     e.to_string(),
-                    (**expected_outputs.as_ref().err().unwrap()).to_string()
+                    expected_outputs.as_ref().err().unwrap().to_string()
                 ),
             }
         }
@@ -2586,4 +3243,34 @@ mod tests {
             assert_eq!(ctrl_bpe(input, &bpe_pairs), *expected_output);
         }
     }
+
+    #[test]
+    fn test_get_word_ids() {
+        //        Given
+        let test_tuples = [
+            (
+                vec![
+                    Mask::None,
+                    Mask::Begin,
+                    Mask::Continuation,
+                    Mask::Punctuation,
+                ],
+                vec![Some(0), Some(1), Some(1), Some(2)],
+            ),
+            (
+                vec![Mask::Continuation, Mask::Continuation, Mask::Begin],
+                vec![Some(0), Some(0), Some(1)],
+            ),
+            (
+                vec![Mask::Unknown, Mask::Special, Mask::CJK],
+                vec![Some(0), Some(1), Some(2)],
+            ),
+            (vec![], vec![]),
+        ];
+
+        //        When & Then
+        for (input, expected_output) in &test_tuples {
+            assert_eq!(get_word_ids(input), *expected_output);
+        }
+    }
 }
@@ -0,0 +1,1518 @@
+// Copyright 2019-2020 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::base_tokenizer::Tokenizer;
+use crate::preprocessing::tokenizer::normalizer::{Precompiled, SentencePieceNormalizer};
+use crate::preprocessing::tokenizer::post_processor::{TemplatePiece, TemplateProcessing};
+use crate::preprocessing::vocab::base_vocab::{
+    swap_key_values, AddedToken, SpecialTokenMap, Vocab,
+};
+use crate::preprocessing::vocab::bpe_vocab::BpePairVocab;
+use crate::preprocessing::vocab::sentence_piece_vocab::{
+    SentencePieceModel, SentencePieceModelType,
+};
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read};
+
+/// # Serialization to a `tokenizer.json` file
+/// Exports a tokenizer to a single self-contained file following the Hugging Face `tokenizer.json`
+/// format: vocabulary, BPE merges or unigram scores, normalization options and special tokens
+/// template. The file can be reloaded into the same tokenizer type with `from_tokenizer_json_file`,
+/// giving identical encoding outputs.
+///
+/// Implemented by the BERT, GPT2, RoBERTa, OpenAI GPT, SentencePiece, ALBERT, XLNet, T5 and
+/// XLM-RoBERTa tokenizers.
+pub trait TokenizerJson: Sized {
+    /// Returns the `tokenizer.json` representation of the tokenizer. The normalizer and
+    /// pre-tokenizer sections are built from the options of the tokenizer: fails with a
+    /// `TokenizerError::UnsupportedComponent` error if the normalizer or the pre-tokenizer was
+    /// replaced with `Tokenizer::set_normalizer` or `Tokenizer::set_pre_tokenizer`.
+    fn to_tokenizer_json(&self) -> Result<Value, TokenizerError>;
+
+    /// Creates a tokenizer from a `tokenizer.json` representation generated by `to_tokenizer_json`.
+    /// The unknown token is read from the `model` section and the special token map is rebuilt
+    /// from the special `added_tokens`: the roles of the default map of the vocabulary are kept
+    /// for the tokens present in the file, the other special tokens become additional special
+    /// tokens. A `TemplateProcessing` post-processor differing from the default template of the
+    /// tokenizer is restored with `Tokenizer::set_post_processor`.
+    fn from_tokenizer_json(tokenizer_json: &Value) -> Result<Self, TokenizerError>;
+
+    /// Writes the `tokenizer.json` representation of the tokenizer to a file
+    ///
+    /// # Parameters
+    /// - path (`&str`): path of the file to create
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_tokenizers::{BertTokenizer, TokenizerJson};
+    /// let tokenizer = BertTokenizer::from_file("path/to/vocab", true, true).unwrap();
+    /// tokenizer.save_tokenizer_json("path/to/tokenizer.json").unwrap();
+    /// let reloaded = BertTokenizer::from_tokenizer_json_file("path/to/tokenizer.json").unwrap();
+    /// ```
+    fn save_tokenizer_json(&self, path: &str) -> Result<(), TokenizerError> {
+        let f = File::create(path).map_err(|e| {
+            TokenizerError::FileWriteError(format!("{} could not be created: {}", path, e))
+        })?;
+        serde_json::to_writer_pretty(BufWriter::new(f), &self.to_tokenizer_json()?)
+            .map_err(|e| TokenizerError::FileWriteError(format!("{}: {}", path, e)))
+    }
+
+    /// Creates a tokenizer from a `tokenizer.json` file generated by `save_tokenizer_json`
+    ///
+    /// # Parameters
+    /// - path (`&str`): path to the `tokenizer.json` file
+    fn from_tokenizer_json_file(path: &str) -> Result<Self, TokenizerError> {
+        Self::from_tokenizer_json(&read_tokenizer_json_file(path)?)
+    }
+
+    /// Creates a tokenizer from a reader for a `tokenizer.json` content generated by
+    /// `save_tokenizer_json`
+    fn from_tokenizer_json_reader<R: Read>(reader: R) -> Result<Self, TokenizerError> {
+        Self::from_tokenizer_json(&read_tokenizer_json(reader)?)
+    }
+
+    /// Creates a tokenizer from a `tokenizer.json` content held in memory (e.g. loaded with
+    /// `include_bytes!`)
+    fn from_tokenizer_json_bytes(bytes: &[u8]) -> Result<Self, TokenizerError> {
+        Self::from_tokenizer_json_reader(bytes)
+    }
+}
+
+pub(crate) fn read_tokenizer_json_file(path: &str) -> Result<Value, TokenizerError> {
+    let f = File::open(path).map_err(|e| {
+        TokenizerError::FileNotFound(format!("{} tokenizer file not found :{}", path, e))
+    })?;
+    read_tokenizer_json(f)
+}
+
+pub(crate) fn read_tokenizer_json<R: Read>(reader: R) -> Result<Value, TokenizerError> {
+    let br = BufReader::new(reader);
+    match serde_json::from_reader(br) {
+        Ok(value) => Ok(value),
+        Err(e) => Err(TokenizerError::VocabularyParsingError(e.to_string())),
+    }
+}
+
+//==============================
+// Serialization helpers
+//==============================
+
+///Fails if the normalizer or the pre-tokenizer of a tokenizer was replaced with
+///`Tokenizer::set_normalizer` or `Tokenizer::set_pre_tokenizer`: arbitrary components cannot be
+///serialized, and exporting the defaults of the tokenizer would silently change its outputs
+pub(crate) fn check_default_components(
+    custom_normalizer: bool,
+    custom_pre_tokenizer: bool,
+) -> Result<(), TokenizerError> {
+    if custom_normalizer {
+        return Err(TokenizerError::UnsupportedComponent(
+            "normalizer set with `set_normalizer`".to_owned(),
+        ));
+    }
+    if custom_pre_tokenizer {
+        return Err(TokenizerError::UnsupportedComponent(
+            "pre-tokenizer set with `set_pre_tokenizer`".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn tokenizer_json<V: Vocab>(
+    vocab: &V,
+    normalizer: Value,
+    pre_tokenizer: Value,
+    model: Value,
+    post_processor: Value,
+    decoder: Value,
+) -> Value {
+    let mut added_tokens = vocab
+        .special_values()
+        .iter()
+        .map(|(content, id)| (content, id, true))
+        .chain(
+            vocab
+                .added_values()
+                .iter()
+                .map(|(content, id)| (content, id, false)),
+        )
+        .collect::<Vec<_>>();
+    added_tokens.sort_by_key(|(_, id, _)| **id);
+    let added_tokens = added_tokens
+        .into_iter()
+        .map(|(content, id, special)| {
+            let options = match vocab.added_tokens().get(content) {
+                Some(options) => options.clone(),
+                None => AddedToken::new(content, special),
+            };
+            json!({
+                "id": id,
+                "content": content,
+                "single_word": options.single_word,
+                "lstrip": options.lstrip,
+                "rstrip": options.rstrip,
+                "normalized": options.normalized,
+                "special": special
+            })
+        })
+        .collect::<Vec<Value>>();
+    json!({
+        "version": "1.0",
+        "truncation": null,
+        "padding": null,
+        "added_tokens": added_tokens,
+        "normalizer": normalizer,
+        "pre_tokenizer": pre_tokenizer,
+        "model": model,
+        "post_processor": post_processor,
+        "decoder": decoder
+    })
+}
+
+fn vocab_map_json<V: Vocab>(vocab: &V) -> Value {
+    Value::Object(
+        vocab
+            .values()
+            .iter()
+            .map(|(token, id)| (token.clone(), json!(id)))
+            .collect::<Map<String, Value>>(),
+    )
+}
+
+pub(crate) fn word_piece_model_json<V: Vocab>(vocab: &V) -> Value {
+    json!({
+        "type": "WordPiece",
+        "unk_token": vocab.get_unknown_value(),
+        "continuing_subword_prefix": "##",
+        "max_input_chars_per_word": 100,
+        "vocab": vocab_map_json(vocab)
+    })
+}
+
+pub(crate) fn bpe_model_json<V: Vocab>(
+    vocab: &V,
+    bpe_ranks: &BpePairVocab,
+    end_of_word_suffix: Option<&str>,
+) -> Value {
+    let mut merges = bpe_ranks.values.iter().collect::<Vec<_>>();
+    merges.sort_by_key(|(_, rank)| **rank);
+    let merges = merges
+        .into_iter()
+        .map(|((first, second), _)| format!("{} {}", first, second))
+        .collect::<Vec<String>>();
+    json!({
+        "type": "BPE",
+        "dropout": null,
+        "unk_token": vocab.get_unknown_value(),
+        "continuing_subword_prefix": null,
+        "end_of_word_suffix": end_of_word_suffix,
+        "fuse_unk": false,
+        "vocab": vocab_map_json(vocab),
+        "merges": merges
+    })
+}
+
+///Builds the `model` section of a SentencePiece model: a `Unigram` model for unigram models, a `BPE`
+///model for BPE models
+pub(crate) fn sentence_piece_model_json<V: Vocab>(vocab: &V, model: &SentencePieceModel) -> Value {
+    match model.model_type {
+        SentencePieceModelType::Unigram => unigram_model_json(vocab, model),
+        SentencePieceModelType::Bpe => sentence_piece_bpe_model_json(vocab, model),
+    }
+}
+
+///Entries of the vocabulary that are pieces of the SentencePiece model, sorted by index
+fn model_pieces<'a, V: Vocab>(
+    vocab: &'a V,
+    scores: &HashMap<&str, f32>,
+) -> Vec<(&'a i64, &'a String)> {
+    let mut indices = vocab.indices().iter().collect::<Vec<_>>();
+    indices.sort_by_key(|(id, _)| **id);
+    //tokens added to the vocabulary after its creation are not part of the model: they are only
+    //listed in the `added_tokens` section
+    while let Some((_, piece)) = indices.last() {
+        let is_added = vocab.added_values().contains_key(piece.as_str())
+            || vocab.special_values().contains_key(piece.as_str());
+        if is_added && !scores.contains_key(piece.as_str()) {
+            indices.pop();
+        } else {
+            break;
+        }
+    }
+    indices
+}
+
+///The pieces are listed in the order of the vocabulary indices, with the score of the matching piece
+///in the SentencePiece model (0 for entries of the vocabulary missing from the model)
+fn unigram_model_json<V: Vocab>(vocab: &V, model: &SentencePieceModel) -> Value {
+    let scores = model
+        .get_pieces()
+        .into_iter()
+        .map(|node| (node.text.as_str(), node.score))
+        .collect::<HashMap<&str, f32>>();
+    let pieces = model_pieces(vocab, &scores)
+        .into_iter()
+        .map(|(_, piece)| json!([piece, scores.get(piece.as_str()).copied().unwrap_or(0.0)]))
+        .collect::<Vec<Value>>();
+    json!({
+        "type": "Unigram",
+        "unk_id": vocab.token_to_id(vocab.get_unknown_value()),
+        "byte_fallback": model.byte_fallback,
+        "vocab": pieces
+    })
+}
+
+///The merges of a SentencePiece BPE model are all the splits of a piece into two pieces, ranked by
+///decreasing score of the merged piece (the priority of the merge in the BPE segmentation)
+fn sentence_piece_bpe_model_json<V: Vocab>(vocab: &V, model: &SentencePieceModel) -> Value {
+    let pieces = model.get_pieces();
+    let scores = pieces
+        .iter()
+        .map(|node| (node.text.as_str(), node.score))
+        .collect::<HashMap<&str, f32>>();
+    let mut merges = vec![];
+    for node in &pieces {
+        for (split, _) in node.text.char_indices().skip(1) {
+            let (first, second) = node.text.split_at(split);
+            if scores.contains_key(first) && scores.contains_key(second) {
+                merges.push((node.score, node.index, first, second));
+            }
+        }
+    }
+    merges.sort_by(|(score_1, index_1, _, _), (score_2, index_2, _, _)| {
+        score_2
+            .partial_cmp(score_1)
+            .unwrap_or(Ordering::Equal)
+            .then(index_1.cmp(index_2))
+    });
+    let merges = merges
+        .into_iter()
+        .map(|(_, _, first, second)| format!("{} {}", first, second))
+        .collect::<Vec<String>>();
+    let values = model_pieces(vocab, &scores)
+        .into_iter()
+        .map(|(id, piece)| (piece.clone(), json!(id)))
+        .collect::<Map<String, Value>>();
+    json!({
+        "type": "BPE",
+        "dropout": null,
+        "unk_token": vocab.get_unknown_value(),
+        "continuing_subword_prefix": null,
+        "end_of_word_suffix": null,
+        "fuse_unk": true,
+        "byte_fallback": model.byte_fallback,
+        "vocab": values,
+        "merges": merges
+    })
+}
+
+///Builds the `TemplateProcessing` post-processor of a tokenizer: the template set with
+///`Tokenizer::set_post_processor` if any, the default template of the tokenizer otherwise
+pub(crate) fn post_processor_json<V: Vocab, T: Tokenizer<V>>(tokenizer: &T) -> Value {
+    match tokenizer.post_processor() {
+        Some(post_processor) => template_processing_json(tokenizer.vocab(), post_processor),
+        None => template_processing_json(tokenizer.vocab(), &tokenizer.default_post_processor()),
+    }
+}
+
+///Builds the `TemplateProcessing` post-processor set with `Tokenizer::set_post_processor` on a
+///tokenizer whose default template is not exported, `default` otherwise
+pub(crate) fn custom_post_processor_json<V: Vocab, T: Tokenizer<V>>(
+    tokenizer: &T,
+    default: Value,
+) -> Value {
+    match tokenizer.post_processor() {
+        Some(post_processor) => template_processing_json(tokenizer.vocab(), post_processor),
+        None => default,
+    }
+}
+
+///Builds a `TemplateProcessing` post-processor, special tokens being listed with their vocabulary id
+pub(crate) fn template_processing_json<V: Vocab>(
+    vocab: &V,
+    post_processor: &TemplateProcessing,
+) -> Value {
+    let template_json = |template: &[TemplatePiece]| {
+        template
+            .iter()
+            .map(|piece| match piece {
+                TemplatePiece::Sequence {
+                    sequence_id,
+                    type_id,
+                } => {
+                    let id = if *sequence_id == 0 { "A" } else { "B" };
+                    json!({"Sequence": {"id": id, "type_id": type_id}})
+                }
+                TemplatePiece::SpecialToken { token, type_id } => {
+                    json!({"SpecialToken": {"id": token, "type_id": type_id}})
+                }
+            })
+            .collect::<Vec<Value>>()
+    };
+    let special_tokens = post_processor
+        .special_tokens()
+        .into_iter()
+        .map(|token| {
+            (
+                token.to_owned(),
+                json!({"id": token, "ids": [vocab.token_to_id(token)], "tokens": [token]}),
+            )
+        })
+        .collect::<Map<String, Value>>();
+    json!({
+        "type": "TemplateProcessing",
+        "single": template_json(post_processor.single()),
+        "pair": template_json(post_processor.pair()),
+        "special_tokens": special_tokens
+    })
+}
+
+///Reads a `TemplateProcessing` post-processor. Special tokens spanning several tokens (listed in
+///its `special_tokens` entry) are expanded to one piece per token.
+pub(crate) fn read_template_processing(
+    post_processor: &Value,
+) -> Result<TemplateProcessing, TokenizerError> {
+    let invalid = |message: &str| {
+        TokenizerError::VocabularyParsingError(format!(
+            "invalid TemplateProcessing post_processor: {}",
+            message
+        ))
+    };
+    let read_template = |key: &str| -> Result<Vec<TemplatePiece>, TokenizerError> {
+        let mut pieces = vec![];
+        for piece in post_processor
+            .get(key)
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid(&format!("missing `{}` template", key)))?
+        {
+            if let Some(sequence) = piece.get("Sequence") {
+                let sequence_id = match sequence.get("id").and_then(Value::as_str) {
+                    Some("A") => 0,
+                    Some("B") => 1,
+                    _ => return Err(invalid("sequence id should be A or B")),
+                };
+                pieces.push(TemplatePiece::sequence(
+                    sequence_id,
+                    read_type_id(sequence).ok_or_else(|| invalid("invalid type_id"))?,
+                ));
+            } else if let Some(special_token) = piece.get("SpecialToken") {
+                let id = special_token
+                    .get("id")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| invalid("missing special token id"))?;
+                let type_id =
+                    read_type_id(special_token).ok_or_else(|| invalid("invalid type_id"))?;
+                match post_processor["special_tokens"][id]["tokens"].as_array() {
+                    Some(tokens) => {
+                        for token in tokens {
+                            let token = token
+                                .as_str()
+                                .ok_or_else(|| invalid("invalid special token"))?;
+                            pieces.push(TemplatePiece::special_token(token, type_id));
+                        }
+                    }
+                    None => pieces.push(TemplatePiece::special_token(id, type_id)),
+                }
+            } else {
+                return Err(invalid("unknown template piece"));
+            }
+        }
+        Ok(pieces)
+    };
+    TemplateProcessing::from_pieces(read_template("single")?, read_template("pair")?)
+}
+
+fn read_type_id(piece: &Value) -> Option<i8> {
+    match piece.get("type_id") {
+        Some(type_id) => type_id
+            .as_u64()
+            .and_then(|type_id| i8::try_from(type_id).ok()),
+        None => Some(0),
+    }
+}
+
+///Builds the `tokenizer.json` representation of a tokenizer relying on a SentencePiece model,
+///optionally replacing the `` and '' quotes by " (ALBERT and XLNet)
+pub(crate) fn sentence_piece_tokenizer_json<V: Vocab>(
+    vocab: &V,
+    model: &SentencePieceModel,
+    lower_case: bool,
+    strip_accents: bool,
+    replace_quotes: bool,
+    post_processor: Value,
+) -> Value {
+    let mut normalizers = vec![];
+    if replace_quotes {
+        normalizers.push(json!({"type": "Replace", "pattern": {"String": "``"}, "content": "\""}));
+        normalizers.push(json!({"type": "Replace", "pattern": {"String": "''"}, "content": "\""}));
+    }
+    if model.normalizer.is_none() {
+        normalizers.push(json!({
+            "type": "BertNormalizer",
+            "clean_text": true,
+            "handle_chinese_chars": false,
+            "strip_accents": false,
+            "lowercase": false
+        }));
+        normalizers.push(json!({"type": "NFKC"}));
+    }
+    if lower_case {
+        normalizers.push(json!({"type": "Lowercase"}));
+    }
+    if strip_accents {
+        normalizers.push(json!({"type": "StripAccents"}));
+    }
+    //the `NormalizerSpec` of the model is applied last (see `sentence_piece_normalizer`)
+    let add_prefix_space = match &model.normalizer {
+        Some(normalizer) => {
+            normalizers.extend(sentence_piece_normalizer_json(normalizer));
+            normalizer.add_dummy_prefix
+        }
+        None => true,
+    };
+    tokenizer_json(
+        vocab,
+        json!({"type": "Sequence", "normalizers": normalizers}),
+        json!({
+            "type": "Metaspace",
+            "replacement": "\u{2581}",
+            "add_prefix_space": add_prefix_space,
+            "prepend_scheme": if add_prefix_space { "always" } else { "never" },
+            "split": false
+        }),
+        sentence_piece_model_json(vocab, model),
+        post_processor,
+        json!({
+            "type": "Metaspace",
+            "replacement": "\u{2581}",
+            "add_prefix_space": add_prefix_space,
+            "prepend_scheme": if add_prefix_space { "always" } else { "never" }
+        }),
+    )
+}
+
+///Builds a `Precompiled` normalizer holding the base64-encoded charsmap of a `NormalizerSpec`
+///(empty if the spec has no charsmap) and its options, followed by the normalizers removing extra
+///whitespaces for readers ignoring these options
+fn sentence_piece_normalizer_json(normalizer: &SentencePieceNormalizer) -> Vec<Value> {
+    let precompiled_charsmap = match &normalizer.precompiled_charsmap {
+        Some(precompiled_charsmap) => base64::encode(precompiled_charsmap.to_bytes()),
+        None => String::new(),
+    };
+    let mut normalizers = vec![json!({
+        "type": "Precompiled",
+        "precompiled_charsmap": precompiled_charsmap,
+        "add_dummy_prefix": normalizer.add_dummy_prefix,
+        "remove_extra_whitespaces": normalizer.remove_extra_whitespaces,
+        "escape_whitespaces": normalizer.escape_whitespaces
+    })];
+    if normalizer.remove_extra_whitespaces {
+        normalizers.push(json!({"type": "Strip", "strip_left": true, "strip_right": true}));
+        normalizers.push(json!({"type": "Replace", "pattern": {"Regex": " {2,}"}, "content": " "}));
+    }
+    normalizers
+}
+
+//==============================
+// Deserialization helpers
+//==============================
+
+pub(crate) fn get_section<'a>(
+    tokenizer_json: &'a Value,
+    key: &str,
+) -> Result<&'a Value, TokenizerError> {
+    tokenizer_json
+        .get(key)
+        .filter(|value| !value.is_null())
+        .ok_or_else(|| TokenizerError::VocabularyParsingError(format!("missing `{}` section", key)))
+}
+
+///Returns the first component of the given type of a normalizer or pre-tokenizer section, looking
+///into `Sequence` components
+pub(crate) fn find_component<'a>(section: &'a Value, component_type: &str) -> Option<&'a Value> {
+    if section.get("type").and_then(|value| value.as_str()) == Some(component_type) {
+        return Some(section);
+    }
+    ["normalizers", "pretokenizers"].iter().find_map(|key| {
+        section
+            .get(*key)
+            .and_then(|value| value.as_array())
+            .and_then(|components| {
+                components
+                    .iter()
+                    .find_map(|component| find_component(component, component_type))
+            })
+    })
+}
+
+///Checks if a normalizer or pre-tokenizer section contains a component of the given type, looking
+///into `Sequence` components
+pub(crate) fn contains_component(section: &Value, component_type: &str) -> bool {
+    find_component(section, component_type).is_some()
+}
+
+///Values of a vocabulary read from a `tokenizer.json` representation
+pub(crate) struct TokenizerJsonValues {
+    ///Values of the `model` section and of the `added_tokens` section
+    pub(crate) values: HashMap<String, i64>,
+    ///Added tokens flagged as special
+    pub(crate) special_values: HashMap<String, i64>,
+    ///Added tokens not flagged as special
+    pub(crate) added_values: HashMap<String, i64>,
+    ///Matching options of the added tokens
+    pub(crate) added_tokens: HashMap<String, AddedToken>,
+}
+
+///Reads the vocabulary of the `model` section and the `added_tokens` with their matching options.
+pub(crate) fn read_values(tokenizer_json: &Value) -> Result<TokenizerJsonValues, TokenizerError> {
+    let model = get_section(tokenizer_json, "model")?;
+    let mut values: HashMap<String, i64> = HashMap::new();
+    match model.get("vocab") {
+        Some(Value::Object(vocab)) => {
+            for (token, index) in vocab {
+                let index = index.as_i64().ok_or_else(|| {
+                    TokenizerError::VocabularyParsingError(format!(
+                        "invalid index for token {}",
+                        token
+                    ))
+                })?;
+                values.insert(token.clone(), index);
+            }
+        }
+        Some(Value::Array(_)) => {
+            for (index, (piece, _)) in read_unigram_pieces(model)?.into_iter().enumerate() {
+                values.insert(piece, index as i64);
+            }
+        }
+        _ => {
+            return Err(TokenizerError::VocabularyParsingError(
+                "missing or invalid `model.vocab` section".to_owned(),
+            ));
+        }
+    }
+
+    let mut special_values: HashMap<String, i64> = HashMap::new();
+    let mut added_values: HashMap<String, i64> = HashMap::new();
+    let mut added_tokens: HashMap<String, AddedToken> = HashMap::new();
+    if let Some(Value::Array(added_token_list)) = tokenizer_json.get("added_tokens") {
+        for added_token in added_token_list {
+            let content = added_token.get("content").and_then(|value| value.as_str());
+            let id = added_token.get("id").and_then(|value| value.as_i64());
+            match (content, id) {
+                (Some(content), Some(id)) => {
+                    let flag = |key: &str, default: bool| {
+                        added_token
+                            .get(key)
+                            .and_then(|value| value.as_bool())
+                            .unwrap_or(default)
+                    };
+                    let special = flag("special", true);
+                    values.insert(content.to_owned(), id);
+                    if special {
+                        special_values.insert(content.to_owned(), id);
+                    } else {
+                        added_values.insert(content.to_owned(), id);
+                    }
+                    added_tokens.insert(
+                        content.to_owned(),
+                        AddedToken {
+                            content: content.to_owned(),
+                            single_word: flag("single_word", false),
+                            lstrip: flag("lstrip", false),
+                            rstrip: flag("rstrip", false),
+                            normalized: flag("normalized", !special),
+                            special,
+                        },
+                    );
+                }
+                _ => {
+                    return Err(TokenizerError::VocabularyParsingError(format!(
+                        "invalid added token {}",
+                        added_token
+                    )));
+                }
+            }
+        }
+    }
+    Ok(TokenizerJsonValues {
+        values,
+        special_values,
+        added_values,
+        added_tokens,
+    })
+}
+
+///Builds a vocabulary of type `V` from a `tokenizer.json` representation. The unknown value is read
+///from the `model` section (`unk_id` or `unk_token`), falling back to the unknown value of the
+///vocabulary type, and must be part of the values. The special token map is rebuilt from the
+///special `added_tokens` (see `read_special_token_map`).
+pub(crate) fn read_vocab<V: Vocab, F>(tokenizer_json: &Value, build: F) -> Result<V, TokenizerError>
+where
+    F: FnOnce(
+        HashMap<String, i64>,
+        HashMap<i64, String>,
+        SpecialTokenMap,
+        HashMap<String, i64>,
+        HashMap<i64, String>,
+        HashMap<String, i64>,
+        HashMap<String, AddedToken>,
+    ) -> V,
+{
+    let TokenizerJsonValues {
+        values,
+        mut special_values,
+        mut added_values,
+        added_tokens,
+    } = read_values(tokenizer_json)?;
+    let indices = swap_key_values(&values);
+    let model = get_section(tokenizer_json, "model")?;
+    let unknown_value = match model.get("unk_id").and_then(|value| value.as_i64()) {
+        Some(unknown_id) => indices.get(&unknown_id).map(|value| value.as_str()),
+        None => model.get("unk_token").and_then(|value| value.as_str()),
+    }
+    .unwrap_or_else(|| V::unknown_value())
+    .to_owned();
+    let unknown_id = match values.get(&unknown_value) {
+        Some(unknown_id) => *unknown_id,
+        None => {
+            return Err(TokenizerError::TokenNotFound(format!(
+                "The special value {} could not be found in the vocabulary",
+                unknown_value
+            )));
+        }
+    };
+    added_values.remove(&unknown_value);
+    special_values.insert(unknown_value.clone(), unknown_id);
+    let special_token_map = read_special_token_map::<V>(unknown_value, &special_values);
+    let special_indices = swap_key_values(&special_values);
+    Ok(build(
+        values,
+        indices,
+        special_token_map,
+        special_values,
+        special_indices,
+        added_values,
+        added_tokens,
+    ))
+}
+
+///Rebuilds the special token map of a vocabulary of type `V` from its special values. The file does
+///not record the role of the special tokens: the roles of the default map of the vocabulary type are
+///kept if their token is a special value, the other special values are registered as additional
+///special tokens (ordered by ID).
+fn read_special_token_map<V: Vocab>(
+    unknown_value: String,
+    special_values: &HashMap<String, i64>,
+) -> SpecialTokenMap {
+    let defaults = V::default_special_token_map();
+    let role = |token: Option<String>| token.filter(|token| special_values.contains_key(token));
+    let mut special_token_map = SpecialTokenMap {
+        pad_token: role(defaults.pad_token),
+        bos_token: role(defaults.bos_token),
+        sep_token: role(defaults.sep_token),
+        cls_token: role(defaults.cls_token),
+        eos_token: role(defaults.eos_token),
+        mask_token: role(defaults.mask_token),
+        ..SpecialTokenMap::new(&unknown_value)
+    };
+    let mut additional_special_tokens = special_values
+        .iter()
+        .filter(|(token, _)| !special_token_map.tokens().contains(&token.as_str()))
+        .collect::<Vec<_>>();
+    additional_special_tokens.sort_by_key(|(_, id)| **id);
+    special_token_map.additional_special_tokens = additional_special_tokens
+        .into_iter()
+        .map(|(token, _)| token.clone())
+        .collect();
+    special_token_map
+}
+
+///Reads the `TemplateProcessing` post-processor of a `tokenizer.json` representation, if any
+pub(crate) fn read_post_processor(
+    tokenizer_json: &Value,
+) -> Result<Option<TemplateProcessing>, TokenizerError> {
+    match tokenizer_json.get("post_processor") {
+        Some(post_processor)
+            if post_processor.get("type").and_then(|value| value.as_str())
+                == Some("TemplateProcessing") =>
+        {
+            Ok(Some(read_template_processing(post_processor)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+///Sets the `TemplateProcessing` post-processor of a `tokenizer.json` representation on a tokenizer
+///(see `Tokenizer::set_post_processor`) if it differs from the default template of the tokenizer
+pub(crate) fn restore_post_processor<V: Vocab, T: Tokenizer<V>>(
+    tokenizer: &mut T,
+    tokenizer_json: &Value,
+) -> Result<(), TokenizerError> {
+    if let Some(post_processor) = read_post_processor(tokenizer_json)? {
+        if post_processor != tokenizer.default_post_processor() {
+            tokenizer.set_post_processor(post_processor)?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn read_merges(model: &Value) -> Result<BpePairVocab, TokenizerError> {
+    let merges = model
+        .get("merges")
+        .and_then(|value| value.as_array())
+        .ok_or_else(|| {
+            TokenizerError::VocabularyParsingError("missing `merges` list".to_owned())
+        })?;
+    let mut values = HashMap::new();
+    for (rank, merge) in merges.iter().enumerate() {
+        let pair = match merge {
+            Value::String(merge) => {
+                let mut split = merge.splitn(2, ' ');
+                split
+                    .next()
+                    .zip(split.next())
+                    .map(|(first, second)| (first.to_owned(), second.to_owned()))
+            }
+            Value::Array(pair) if pair.len() == 2 => pair[0]
+                .as_str()
+                .zip(pair[1].as_str())
+                .map(|(first, second)| (first.to_owned(), second.to_owned())),
+            _ => None,
+        };
+        match pair {
+            Some(pair) => {
+                values.insert(pair, rank as i64);
+            }
+            None => {
+                return Err(TokenizerError::VocabularyParsingError(format!(
+                    "invalid merge {}",
+                    merge
+                )));
+            }
+        }
+    }
+    Ok(BpePairVocab { values })
+}
+
+pub(crate) fn read_unigram_pieces(model: &Value) -> Result<Vec<(String, f32)>, TokenizerError> {
+    let vocab = model
+        .get("vocab")
+        .and_then(|value| value.as_array())
+        .ok_or_else(|| TokenizerError::VocabularyParsingError("missing `vocab` list".to_owned()))?;
+    let mut pieces = Vec::with_capacity(vocab.len());
+    for entry in vocab {
+        let piece = entry.get(0).and_then(|piece| piece.as_str());
+        let score = entry.get(1).and_then(|score| score.as_f64());
+        match piece.zip(score) {
+            Some((piece, score)) => pieces.push((piece.to_owned(), score as f32)),
+            None => {
+                return Err(TokenizerError::VocabularyParsingError(format!(
+                    "invalid unigram vocabulary entry {}",
+                    entry
+                )));
+            }
+        }
+    }
+    Ok(pieces)
+}
+
+///Reads the SentencePiece model of a `model` section written by `sentence_piece_model_json`. The
+///scores of the pieces of a BPE model are recovered from the ranks of the merges producing them.
+pub(crate) fn read_sentence_piece_model(
+    tokenizer_json: &Value,
+) -> Result<SentencePieceModel, TokenizerError> {
+    let model_section = get_section(tokenizer_json, "model")?;
+    let mut model = match model_section.get("type").and_then(|value| value.as_str()) {
+        Some("BPE") => read_sentence_piece_bpe_model(model_section)?,
+        _ => {
+            let pieces = read_unigram_pieces(model_section)?;
+            let pieces = pieces
+                .iter()
+                .map(|(piece, score)| (piece.as_str(), *score))
+                .collect::<Vec<(&str, f32)>>();
+            SentencePieceModel::from_pieces(&pieces)
+        }
+    };
+    model.byte_fallback = model_section
+        .get("byte_fallback")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    model.normalizer = match tokenizer_json
+        .get("normalizer")
+        .and_then(|normalizer| find_component(normalizer, "Precompiled"))
+    {
+        Some(precompiled) => Some(SentencePieceNormalizer {
+            precompiled_charsmap: read_precompiled_charsmap(precompiled)?,
+            add_dummy_prefix: read_flag(precompiled, "add_dummy_prefix"),
+            remove_extra_whitespaces: read_flag(precompiled, "remove_extra_whitespaces"),
+            escape_whitespaces: read_flag(precompiled, "escape_whitespaces"),
+        }),
+        None => None,
+    };
+    Ok(model)
+}
+
+fn read_sentence_piece_bpe_model(model: &Value) -> Result<SentencePieceModel, TokenizerError> {
+    let mut pieces = match model.get("vocab") {
+        Some(Value::Object(vocab)) => vocab
+            .iter()
+            .map(|(piece, index)| {
+                index
+                    .as_i64()
+                    .map(|index| (piece.as_str(), index))
+                    .ok_or_else(|| {
+                        TokenizerError::VocabularyParsingError(format!(
+                            "invalid index for token {}",
+                            piece
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<(&str, i64)>, TokenizerError>>()?,
+        _ => {
+            return Err(TokenizerError::VocabularyParsingError(
+                "missing or invalid `model.vocab` section".to_owned(),
+            ));
+        }
+    };
+    pieces.sort_by_key(|(_, index)| *index);
+    let mut ranks: HashMap<String, usize> = HashMap::new();
+    let mut merges = read_merges(model)?.values.into_iter().collect::<Vec<_>>();
+    merges.sort_by_key(|(_, rank)| *rank);
+    for ((first, second), rank) in merges {
+        ranks.entry(first + &second).or_insert(rank as usize);
+    }
+    let pieces = pieces
+        .into_iter()
+        .map(|(piece, _)| {
+            let score = ranks.get(piece).map_or(0.0, |rank| -(*rank as f32) - 1.0);
+            (piece, score)
+        })
+        .collect::<Vec<(&str, f32)>>();
+    let mut model = SentencePieceModel::from_pieces(&pieces);
+    model.model_type = SentencePieceModelType::Bpe;
+    Ok(model)
+}
+
+fn read_flag(component: &Value, key: &str) -> bool {
+    component
+        .get(key)
+        .and_then(|value| value.as_bool())
+        .unwrap_or(true)
+}
+
+///Decodes the base64-encoded charsmap of a `Precompiled` normalizer, `None` if it is empty
+pub(crate) fn read_precompiled_charsmap(
+    precompiled: &Value,
+) -> Result<Option<Precompiled>, TokenizerError> {
+    let precompiled_charsmap = precompiled
+        .get("precompiled_charsmap")
+        .and_then(|value| value.as_str())
+        .unwrap_or("");
+    if precompiled_charsmap.is_empty() {
+        return Ok(None);
+    }
+    let precompiled_charsmap = base64::decode(precompiled_charsmap).map_err(|e| {
+        TokenizerError::VocabularyParsingError(format!("invalid precompiled charsmap: {}", e))
+    })?;
+    Ok(Some(Precompiled::from_bytes(&precompiled_charsmap)?))
+}
+
+///Reads the `lowercase` and `strip_accents` options of the tokenizer normalizer
+pub(crate) fn read_normalizer_flags(tokenizer_json: &Value) -> (bool, bool) {
+    match tokenizer_json.get("normalizer") {
+        Some(normalizer)
+            if normalizer.get("type").and_then(|value| value.as_str())
+                == Some("BertNormalizer") =>
+        {
+            let lower_case = normalizer
+                .get("lowercase")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(true);
+            let strip_accents = normalizer
+                .get("strip_accents")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(lower_case);
+            (lower_case, strip_accents)
+        }
+        Some(normalizer) => (
+            contains_component(normalizer, "Lowercase"),
+            contains_component(normalizer, "StripAccents"),
+        ),
+        None => (false, false),
+    }
+}
+
+///Reads the `add_prefix_space` option of the tokenizer pre-tokenizer
+pub(crate) fn read_add_prefix_space(tokenizer_json: &Value) -> bool {
+    tokenizer_json
+        .get("pre_tokenizer")
+        .and_then(|pre_tokenizer| pre_tokenizer.get("add_prefix_space"))
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false)
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessing::tokenizer::base_tokenizer::{
+        PaddingOptions, Tokenizer, TruncationStrategy,
+    };
+    use crate::preprocessing::tokenizer::bert_tokenizer::BertTokenizer;
+    use crate::preprocessing::tokenizer::gpt2_tokenizer::Gpt2Tokenizer;
+    use crate::preprocessing::tokenizer::hf_tokenizer::HFTokenizer;
+    use crate::preprocessing::tokenizer::normalizer::tests::build_charsmap;
+    use crate::preprocessing::tokenizer::normalizer::Nfc;
+    use crate::preprocessing::tokenizer::pre_tokenizer::WhitespaceSplit;
+    use crate::preprocessing::tokenizer::roberta_tokenizer::RobertaTokenizer;
+    use crate::preprocessing::tokenizer::xlnet_tokenizer::XLNetTokenizer;
+    use crate::preprocessing::vocab::bert_vocab::BertVocab;
+    use crate::preprocessing::vocab::gpt2_vocab::Gpt2Vocab;
+    use crate::preprocessing::vocab::roberta_vocab::RobertaVocab;
+    use crate::preprocessing::vocab::xlnet_vocab::XLNetVocab;
+    use crate::TokenizedInput;
+    use std::sync::Arc;
+
+    fn encode_pairs<T: Vocab, U: Tokenizer<T>>(tokenizer: &U) -> Vec<TokenizedInput> {
+        let inputs = [
+            ("Hello, world! Unaffable hello", Some("world hello")),
+            ("hello World", None),
+            ("", Some("Hello")),
+        ];
+        inputs
+            .iter()
+            .map(|(text_1, text_2)| {
+                tokenizer.encode(
+                    text_1,
+                    *text_2,
+                    128,
+                    &TruncationStrategy::LongestFirst,
+                    0,
+                    &PaddingOptions::default(),
+                )
+            })
+            .collect()
+    }
+
+    fn generate_bert_vocab() -> BertVocab {
+        let values: HashMap<String, i64> = [
+            ("hello", 0),
+            ("world", 1),
+            ("[UNK]", 2),
+            ("!", 3),
+            ("[CLS]", 4),
+            ("[SEP]", 5),
+            ("[MASK]", 6),
+            ("[PAD]", 7),
+            ("un", 8),
+            ("##aff", 9),
+            ("##able", 10),
+            (",", 11),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), *value))
+        .collect();
+        let special_values: HashMap<String, i64> = [
+            ("[UNK]", 2),
+            ("[CLS]", 4),
+            ("[SEP]", 5),
+            ("[MASK]", 6),
+            ("[PAD]", 7),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), *value))
+        .collect();
+        BertVocab {
+            indices: swap_key_values(&values),
+            special_indices: swap_key_values(&special_values),
+            values,
+            special_token_map: BertVocab::default_special_token_map(),
+            special_values,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
+        }
+    }
+
+    fn generate_bpe_values() -> (HashMap<String, i64>, HashMap<String, i64>, BpePairVocab) {
+        let values: HashMap<String, i64> = [
+            ("<s>", 0),
+            ("<pad>", 1),
+            ("</s>", 2),
+            ("<unk>", 3),
+            ("<mask>", 4),
+            ("<|endoftext|>", 5),
+            ("hello", 6),
+            ("Ġworld", 7),
+            ("Ġhello", 8),
+            (",", 9),
+            ("!", 10),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), *value))
+        .collect();
+        let merges: HashMap<(String, String), i64> = [
+            ("h", "e", 1),
+            ("l", "l", 2),
+            ("he", "ll", 3),
+            ("hell", "o", 4),
+            ("Ġ", "w", 5),
+            ("o", "r", 6),
+            ("Ġw", "or", 7),
+            ("l", "d", 8),
+            ("Ġwor", "ld", 9),
+            ("Ġ", "hello", 10),
+        ]
+        .iter()
+        .map(|(first, second, rank)| ((first.to_string(), second.to_string()), *rank))
+        .collect();
+        (values, HashMap::new(), BpePairVocab { values: merges })
+    }
+
+    fn xlnet_pieces() -> Vec<(&'static str, f32)> {
+        vec![
+            ("<unk>", 0.0),
+            ("<s>", 0.0),
+            ("</s>", 0.0),
+            ("<cls>", 0.0),
+            ("<sep>", 0.0),
+            ("<pad>", 0.0),
+            ("<mask>", 0.0),
+            ("<eod>", 0.0),
+            ("<eop>", 0.0),
+            ("\u{2581}hello", -1.0),
+            ("\u{2581}world", -1.5),
+            ("\u{2581}", -2.0),
+            ("un", -3.0),
+            ("aff", -3.5),
+            ("able", -3.0),
+            (",", -2.5),
+            ("!", -2.5),
+        ]
+    }
+
+    fn generate_xlnet_tokenizer() -> XLNetTokenizer {
+        generate_xlnet_tokenizer_from_pieces(&xlnet_pieces(), |_| {})
+    }
+
+    ///The first 9 pieces are the XLNet special tokens
+    fn generate_xlnet_tokenizer_from_pieces(
+        pieces: &[(&str, f32)],
+        update_model: impl FnOnce(&mut SentencePieceModel),
+    ) -> XLNetTokenizer {
+        let mut model = SentencePieceModel::from_pieces(pieces);
+        update_model(&mut model);
+        let values: HashMap<String, i64> = pieces
+            .iter()
+            .enumerate()
+            .map(|(index, (piece, _))| (piece.to_string(), index as i64))
+            .collect();
+        let special_values: HashMap<String, i64> = pieces
+            .iter()
+            .enumerate()
+            .take(9)
+            .map(|(index, (piece, _))| (piece.to_string(), index as i64))
+            .collect();
+        let vocab = XLNetVocab {
+            indices: swap_key_values(&values),
+            special_indices: swap_key_values(&special_values),
+            values,
+            special_token_map: XLNetVocab::default_special_token_map(),
+            special_values,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
+        };
+        XLNetTokenizer::from_existing_vocab_and_model(vocab, model, true, false)
+    }
+
+    #[test]
+    fn test_bert_round_trip() -> anyhow::Result<()> {
+        //        Given
+        let tokenizer =
+            BertTokenizer::from_existing_vocab(Arc::new(generate_bert_vocab()), true, true);
+        let directory = tempfile::tempdir()?;
+        let path = directory.path().join("tokenizer.json");
+        let path = path.to_str().unwrap();
+
+        //        When
+        tokenizer.save_tokenizer_json(path)?;
+        let reloaded = BertTokenizer::from_tokenizer_json_file(path)?;
+
+        //        Then
+        assert_eq!(encode_pairs(&reloaded), encode_pairs(&tokenizer));
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_components_not_serialized() {
+        //        Given
+        let mut tokenizer =
+            BertTokenizer::from_existing_vocab(Arc::new(generate_bert_vocab()), true, true);
+        tokenizer.set_normalizer(Box::new(Nfc));
+        let mut xlnet_tokenizer = generate_xlnet_tokenizer();
+        xlnet_tokenizer.set_pre_tokenizer(Box::new(WhitespaceSplit));
+
+        //        When
+        let result = tokenizer.to_tokenizer_json();
+        let xlnet_result = xlnet_tokenizer.to_tokenizer_json();
+
+        //        Then
+        assert!(matches!(
+            result,
+            Err(TokenizerError::UnsupportedComponent(_))
+        ));
+        assert!(matches!(
+            xlnet_result,
+            Err(TokenizerError::UnsupportedComponent(_))
+        ));
+    }
+
+    #[test]
+    fn test_bpe_round_trip() -> anyhow::Result<()> {
+        //        Given
+        let (values, _, merges) = generate_bpe_values();
+        let mut special_values = HashMap::new();
+        special_values.insert("<|endoftext|>".to_owned(), 5);
+        let gpt2_vocab = Gpt2Vocab {
+            indices: swap_key_values(&values),
+            special_indices: swap_key_values(&special_values),
+            values: values.clone(),
+            special_token_map: Gpt2Vocab::default_special_token_map(),
+            special_values,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
+        };
+        let gpt2_tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(
+            Arc::new(gpt2_vocab),
+            Arc::new(BpePairVocab {
+                values: merges.values.clone(),
+            }),
+            false,
+        );
+        let special_values: HashMap<String, i64> = ["<s>", "<pad>", "</s>", "<unk>", "<mask>"]
+            .iter()
+            .map(|value| (value.to_string(), values[*value]))
+            .collect();
+        let roberta_vocab = RobertaVocab {
+            indices: swap_key_values(&values),
+            special_indices: swap_key_values(&special_values),
+            values,
+            special_token_map: RobertaVocab::default_special_token_map(),
+            special_values,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
+        };
+        let roberta_tokenizer = RobertaTokenizer::from_existing_vocab_and_merges(
+            Arc::new(roberta_vocab),
+            Arc::new(merges),
+            true,
+            true,
+        );
+
+        //        When
+        let reloaded_gpt2 =
+            Gpt2Tokenizer::from_tokenizer_json(&gpt2_tokenizer.to_tokenizer_json()?)?;
+        let reloaded_roberta =
+            RobertaTokenizer::from_tokenizer_json(&roberta_tokenizer.to_tokenizer_json()?)?;
+        let hf_tokenizer = HFTokenizer::from_json_value(&gpt2_tokenizer.to_tokenizer_json()?)?;
+
+        //        Then
+        assert_eq!(encode_pairs(&reloaded_gpt2), encode_pairs(&gpt2_tokenizer));
+        assert_eq!(
+            encode_pairs(&reloaded_roberta),
+            encode_pairs(&roberta_tokenizer)
+        );
+        assert_eq!(
+            hf_tokenizer.tokenize("hello world hello"),
+            gpt2_tokenizer.tokenize("hello world hello")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sentence_piece_round_trip() -> anyhow::Result<()> {
+        //        Given
+        let tokenizer = generate_xlnet_tokenizer();
+
+        //        When
+        let tokenizer_json = tokenizer.to_tokenizer_json()?;
+        let reloaded = XLNetTokenizer::from_tokenizer_json(&tokenizer_json)?;
+
+        //        Then
+        assert_eq!(encode_pairs(&reloaded), encode_pairs(&tokenizer));
+        assert_eq!(tokenizer_json["model"]["unk_id"], json!(0));
+        assert_eq!(
+            tokenizer_json["model"]["vocab"][10],
+            json!(["\u{2581}world", -1.5])
+        );
+        assert_eq!(
+            tokenizer_json["post_processor"]["pair"][4],
+            json!({"SpecialToken": {"id": "<cls>", "type_id": 2}})
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sentence_piece_bpe_round_trip() -> anyhow::Result<()> {
+        //        Given
+        let mut pieces = xlnet_pieces()[..9].to_vec();
+        pieces.extend(
+            [
+                "\u{2581}h",
+                "ll",
+                "\u{2581}he",
+                "llo",
+                "\u{2581}hello",
+                "or",
+                "\u{2581}w",
+                "ld",
+                "\u{2581}wor",
+                "\u{2581}world",
+                "\u{2581}",
+                "h",
+                "e",
+                "l",
+                "o",
+                "w",
+                "r",
+                "d",
+                ",",
+            ]
+            .iter()
+            .enumerate()
+            .map(|(index, piece)| (*piece, -(index as f32))),
+        );
+        let tokenizer = generate_xlnet_tokenizer_from_pieces(&pieces, |model| {
+            model.model_type = SentencePieceModelType::Bpe
+        });
+
+        //        When
+        let tokenizer_json = tokenizer.to_tokenizer_json()?;
+        let reloaded = XLNetTokenizer::from_tokenizer_json(&tokenizer_json)?;
+
+        //        Then
+        assert_eq!(
+            tokenizer.tokenize("hello world, hold"),
+            vec![
+                "\u{2581}hello",
+                "\u{2581}world",
+                ",",
+                "\u{2581}h",
+                "o",
+                "ld"
+            ]
+        );
+        assert_eq!(encode_pairs(&reloaded), encode_pairs(&tokenizer));
+        assert_eq!(
+            reloaded.tokenize("hello world, hold"),
+            tokenizer.tokenize("hello world, hold")
+        );
+        assert_eq!(tokenizer_json["model"]["type"], json!("BPE"));
+        assert_eq!(tokenizer_json["model"]["merges"][0], json!("\u{2581} h"));
+        assert_eq!(tokenizer_json["model"]["vocab"]["\u{2581}world"], json!(18));
+        Ok(())
+    }
+
+    #[test]
+    fn test_byte_fallback_round_trip() -> anyhow::Result<()> {
+        //        Given
+        let mut pieces = xlnet_pieces();
+        pieces.extend_from_slice(&[("<0xE2>", 0.0), ("<0x82>", 0.0), ("<0xAC>", 0.0)]);
+        let tokenizer =
+            generate_xlnet_tokenizer_from_pieces(&pieces, |model| model.byte_fallback = true);
+
+        //        When
+        let tokenizer_json = tokenizer.to_tokenizer_json()?;
+        let reloaded = XLNetTokenizer::from_tokenizer_json(&tokenizer_json)?;
+
+        //        Then
+        assert_eq!(
+            reloaded.tokenize("hello \u{20AC}"),
+            vec!["\u{2581}hello", "\u{2581}", "<0xE2>", "<0x82>", "<0xAC>"]
+        );
+        assert_eq!(
+            reloaded.convert_tokens_to_ids(&reloaded.tokenize("hello \u{20AC}")),
+            vec![9, 11, 17, 18, 19]
+        );
+        assert_eq!(tokenizer_json["model"]["byte_fallback"], json!(true));
+        assert_eq!(tokenizer_json["model"]["vocab"][17], json!(["<0xE2>", 0.0]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sentence_piece_normalizer_round_trip() -> anyhow::Result<()> {
+        //        Given
+        let normalizer = SentencePieceNormalizer {
+            precompiled_charsmap: Some(Precompiled::from_bytes(&build_charsmap(&[
+                ("\u{FB01}", "fi"),
+                ("\u{3000}", " "),
+            ]))?),
+            add_dummy_prefix: false,
+            remove_extra_whitespaces: true,
+            escape_whitespaces: true,
+        };
+        let tokenizer = generate_xlnet_tokenizer_from_pieces(&xlnet_pieces(), |model| {
+            model.normalizer = Some(normalizer)
+        });
+
+        //        When
+        let tokenizer_json = tokenizer.to_tokenizer_json()?;
+        let reloaded = XLNetTokenizer::from_tokenizer_json(&tokenizer_json)?;
+        let hf_tokenizer = HFTokenizer::from_json_value(&tokenizer_json)?;
+
+        //        Then
+        let text = "\u{FB01}ne  hello\u{3000}world";
+        assert_eq!(reloaded.tokenize(text), tokenizer.tokenize(text));
+        assert_eq!(hf_tokenizer.tokenize(text), tokenizer.tokenize(text));
+        assert_eq!(encode_pairs(&reloaded), encode_pairs(&tokenizer));
+        assert_eq!(
+            find_component(&tokenizer_json["normalizer"], "Precompiled")
+                .map(|precompiled| &precompiled["add_dummy_prefix"]),
+            Some(&json!(false))
+        );
+        assert_eq!(
+            tokenizer_json["pre_tokenizer"]["add_prefix_space"],
+            json!(false)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_added_tokens_round_trip() -> anyhow::Result<()> {
+        //        Given
+        let mut tokenizer = generate_xlnet_tokenizer();
+        tokenizer.add_tokens(&["unaffable"]);
+        tokenizer.add_special_tokens(&["<PRODUCT>"]);
+        let options = AddedToken {
+            single_word: true,
+            rstrip: true,
+            ..AddedToken::new("<SEP_2>", true)
+        };
+        tokenizer
+            .vocab_mut()
+            .add_tokens_with_options(&[options.clone()]);
+
+        //        When
+        let tokenizer_json = tokenizer.to_tokenizer_json()?;
+        let reloaded = XLNetTokenizer::from_tokenizer_json(&tokenizer_json)?;
+
+        //        Then
+        assert_eq!(
+            tokenizer.tokenize("hello<PRODUCT> unaffable"),
+            vec!["\u{2581}hello", "<PRODUCT>", "unaffable"]
+        );
+        assert_eq!(encode_pairs(&reloaded), encode_pairs(&tokenizer));
+        assert_eq!(
+            Tokenizer::vocab(&reloaded).added_values.get("unaffable"),
+            Some(&17)
+        );
+        assert_eq!(
+            Tokenizer::vocab(&reloaded).special_values.get("<PRODUCT>"),
+            Some(&18)
+        );
+        assert_eq!(
+            tokenizer_json["model"]["vocab"].as_array().unwrap().len(),
+            17
+        );
+        assert_eq!(tokenizer_json["added_tokens"][9]["special"], json!(false));
+        assert_eq!(
+            Tokenizer::vocab(&reloaded).added_tokens.get("<SEP_2>"),
+            Some(&options)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_special_token_map_and_template_round_trip() -> anyhow::Result<()> {
+        //        Given
+        let mut vocab = generate_bert_vocab();
+        for (token, id) in [("<unk>", 12), ("[ENT]", 13)].iter() {
+            vocab.values.insert(token.to_string(), *id);
+            vocab.indices.insert(*id, token.to_string());
+        }
+        let special_token_map = SpecialTokenMap {
+            unk_token: "<unk>".to_owned(),
+            mask_token: None,
+            additional_special_tokens: vec!["[ENT]".to_owned()],
+            ..BertVocab::default_special_token_map()
+        };
+        vocab.special_values = special_token_map
+            .tokens()
+            .into_iter()
+            .map(|token| (token.to_owned(), vocab.values[token]))
+            .collect();
+        vocab.special_indices = swap_key_values(&vocab.special_values);
+        vocab.special_token_map = special_token_map.clone();
+        let mut tokenizer = BertTokenizer::from_existing_vocab(Arc::new(vocab), true, true);
+        let post_processor =
+            TemplateProcessing::new("[CLS] $A [ENT]", "[CLS] $A [SEP] $B:1 [ENT]:1")?;
+        tokenizer.set_post_processor(post_processor.clone())?;
+        let (values, _, merges) = generate_bpe_values();
+        let mut gpt2_tokenizer = Gpt2Tokenizer::from_existing_vocab_and_merges(
+            Arc::new(Gpt2Vocab {
+                indices: swap_key_values(&values),
+                special_indices: HashMap::new(),
+                values,
+                special_token_map: Gpt2Vocab::default_special_token_map(),
+                special_values: HashMap::new(),
+                added_values: HashMap::new(),
+                added_tokens: HashMap::new(),
+            }),
+            Arc::new(merges),
+            false,
+        );
+        let gpt2_post_processor = TemplateProcessing::new("$A <|endoftext|>", "$A $B:1")?;
+        gpt2_tokenizer.set_post_processor(gpt2_post_processor.clone())?;
+
+        //        When
+        let reloaded = BertTokenizer::from_tokenizer_json(&tokenizer.to_tokenizer_json()?)?;
+        let reloaded_gpt2 =
+            Gpt2Tokenizer::from_tokenizer_json(&gpt2_tokenizer.to_tokenizer_json()?)?;
+
+        //        Then
+        assert_eq!(
+            Tokenizer::vocab(&reloaded).special_token_map,
+            special_token_map
+        );
+        assert_eq!(reloaded.post_processor(), Some(&post_processor));
+        assert_eq!(encode_pairs(&reloaded), encode_pairs(&tokenizer));
+        assert_eq!(reloaded_gpt2.post_processor(), Some(&gpt2_post_processor));
+        assert_eq!(encode_pairs(&reloaded_gpt2), encode_pairs(&gpt2_tokenizer));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_tokenizer_json() {
+        //        Given
+        let missing_model = json!({"added_tokens": []});
+        let missing_unknown = json!({"model": {"type": "WordPiece", "vocab": {"hello": 0}}});
+
+        //        When
+        let missing_model_result = BertTokenizer::from_tokenizer_json(&missing_model);
+        let missing_unknown_result = BertTokenizer::from_tokenizer_json(&missing_unknown);
+
+        //        Then
+        assert!(matches!(
+            missing_model_result,
+            Err(TokenizerError::VocabularyParsingError(_))
+        ));
+        assert!(matches!(
+            missing_unknown_result,
+            Err(TokenizerError::TokenNotFound(_))
+        ));
+    }
+}
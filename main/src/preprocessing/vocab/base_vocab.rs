@@ -10,10 +10,11 @@
 // limitations under the License.
 
 use crate::error::TokenizerError;
+use serde_json::Value;
 use std::collections::HashMap;
 use std::fs::File;
 use std::hash::Hash;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
 
 pub fn swap_key_values<T: Clone, U: Hash + Eq + Copy>(
     input_hashmap: &HashMap<T, U>,
@@ -24,12 +25,269 @@ pub fn swap_key_values<T: Clone, U: Hash + Eq + Copy>(
         .collect()
 }
 
+/// # Added token
+/// Matching options of a special or added token. Special and added tokens are split out of the text
+/// before the tokenization model runs, these options control how they are matched:
+/// - `single_word`: the token is not split out of a longer word (it is not surrounded by word characters)
+/// - `lstrip`: the whitespace preceding the token is absorbed by the token (and its offsets)
+/// - `rstrip`: the whitespace following the token is absorbed by the token (and its offsets)
+/// - `normalized`: the token is matched case-insensitively if the tokenizer lower-cases its input
+///
+/// Otherwise the token is matched exactly against the original text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddedToken {
+    ///Token string
+    pub content: String,
+    ///Match the token only if it is not part of a longer word
+    pub single_word: bool,
+    ///Absorb the whitespace preceding the token
+    pub lstrip: bool,
+    ///Absorb the whitespace following the token
+    pub rstrip: bool,
+    ///Match the token against the normalized (e.g. lower-cased) text
+    pub normalized: bool,
+    ///Register the token as a special value (as opposed to a regular added value)
+    pub special: bool,
+}
+
+impl AddedToken {
+    ///Creates a new added token with the default options: matched anywhere in the text, without
+    ///absorbing the surrounding whitespace. Regular tokens are matched against the normalized text,
+    ///special tokens against the original text.
+    pub fn new(content: &str, special: bool) -> AddedToken {
+        AddedToken {
+            content: content.to_owned(),
+            single_word: false,
+            lstrip: false,
+            rstrip: false,
+            normalized: !special,
+            special,
+        }
+    }
+}
+
+/// # Special token map
+/// Strings of the special tokens of a vocabulary: unknown, padding, beginning and end of sequence,
+/// separation, classification and mask markers, and any additional special token. The map is read
+/// when the vocabulary is created: its tokens are registered as special values, and tokenizers
+/// insert the configured markers when building model inputs. Each vocabulary provides a default map
+/// with the markers of the original pretrained models (see `Vocab::default_special_token_map`),
+/// which can be replaced to load checkpoints using different markers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecialTokenMap {
+    ///Unknown (out of vocabulary) token, required
+    pub unk_token: String,
+    ///Padding token
+    pub pad_token: Option<String>,
+    ///Beginning of sequence token
+    pub bos_token: Option<String>,
+    ///Separation token
+    pub sep_token: Option<String>,
+    ///Classification token
+    pub cls_token: Option<String>,
+    ///End of sequence token
+    pub eos_token: Option<String>,
+    ///Mask token
+    pub mask_token: Option<String>,
+    ///Other special tokens, without a dedicated role
+    pub additional_special_tokens: Vec<String>,
+    ///Matching options of the special tokens, by token (see `AddedToken`). Registered in the
+    ///`added_tokens` of the vocabulary, tokens without options are matched exactly
+    pub token_options: HashMap<String, AddedToken>,
+}
+
+impl SpecialTokenMap {
+    ///Creates a special token map with an unknown token only
+    pub fn new(unk_token: &str) -> SpecialTokenMap {
+        SpecialTokenMap {
+            unk_token: unk_token.to_owned(),
+            pad_token: None,
+            bos_token: None,
+            sep_token: None,
+            cls_token: None,
+            eos_token: None,
+            mask_token: None,
+            additional_special_tokens: vec![],
+            token_options: HashMap::new(),
+        }
+    }
+
+    ///Read a Hugging Face `special_tokens_map.json` file. Tokens missing from the file keep the
+    ///value they have in `defaults` (typically the default map of the vocabulary to create).
+    pub fn from_file(
+        path: &str,
+        defaults: SpecialTokenMap,
+    ) -> Result<SpecialTokenMap, TokenizerError> {
+        let f = File::open(path).map_err(|e| {
+            TokenizerError::FileNotFound(format!(
+                "{} special token map file not found :{}",
+                path, e
+            ))
+        })?;
+        SpecialTokenMap::from_reader(BufReader::new(f), defaults)
+    }
+
+    ///Read a special token map with the format of a `special_tokens_map.json` file from any reader
+    ///(see `from_file`). Tokens are given either as strings or as objects with a `content` field:
+    ///the `single_word`, `lstrip`, `rstrip` and `normalized` flags of objects are kept as matching
+    ///options of the token (see `AddedToken`). `null` entries and flags keep their default value.
+    pub fn from_reader<R: Read>(
+        reader: R,
+        defaults: SpecialTokenMap,
+    ) -> Result<SpecialTokenMap, TokenizerError> {
+        let value: Value = serde_json::from_reader(reader)
+            .map_err(|e| TokenizerError::VocabularyParsingError(e.to_string()))?;
+        let invalid_entry = |key: &str| {
+            TokenizerError::VocabularyParsingError(format!(
+                "invalid special token map entry {}",
+                key
+            ))
+        };
+        let entries = value.as_object().ok_or_else(|| {
+            TokenizerError::VocabularyParsingError(
+                "the special token map must be a JSON object".to_owned(),
+            )
+        })?;
+
+        let mut token_options = HashMap::new();
+        let mut content = |key: &str, token: &Value| match token {
+            Value::String(token) => Ok(token.clone()),
+            Value::Object(token) => match token.get("content") {
+                Some(Value::String(content)) => {
+                    let default_token = AddedToken::new(content, true);
+                    let flag = |name: &str, default: bool| match token.get(name) {
+                        Some(Value::Bool(flag)) => Ok(*flag),
+                        None | Some(Value::Null) => Ok(default),
+                        Some(_) => Err(invalid_entry(key)),
+                    };
+                    let options = AddedToken {
+                        single_word: flag("single_word", default_token.single_word)?,
+                        lstrip: flag("lstrip", default_token.lstrip)?,
+                        rstrip: flag("rstrip", default_token.rstrip)?,
+                        normalized: flag("normalized", default_token.normalized)?,
+                        ..default_token
+                    };
+                    token_options.insert(content.clone(), options);
+                    Ok(content.clone())
+                }
+                _ => Err(invalid_entry(key)),
+            },
+            _ => Err(invalid_entry(key)),
+        };
+
+        let mut special_token_map = defaults;
+        for (key, token) in entries.iter().filter(|(_, token)| !token.is_null()) {
+            match key.as_str() {
+                "unk_token" => special_token_map.unk_token = content(key, token)?,
+                "pad_token" => special_token_map.pad_token = Some(content(key, token)?),
+                "bos_token" => special_token_map.bos_token = Some(content(key, token)?),
+                "sep_token" => special_token_map.sep_token = Some(content(key, token)?),
+                "cls_token" => special_token_map.cls_token = Some(content(key, token)?),
+                "eos_token" => special_token_map.eos_token = Some(content(key, token)?),
+                "mask_token" => special_token_map.mask_token = Some(content(key, token)?),
+                "additional_special_tokens" => {
+                    special_token_map.additional_special_tokens = token
+                        .as_array()
+                        .ok_or_else(|| invalid_entry(key))?
+                        .iter()
+                        .map(|token| content(key, token))
+                        .collect::<Result<Vec<String>, TokenizerError>>()?
+                }
+                _ => {}
+            }
+        }
+        special_token_map.token_options.extend(token_options);
+        Ok(special_token_map)
+    }
+
+    ///Returns all the tokens of the map, starting with the unknown token
+    pub fn tokens(&self) -> Vec<&str> {
+        let mut tokens = vec![self.unk_token.as_str()];
+        tokens.extend(
+            [
+                &self.pad_token,
+                &self.bos_token,
+                &self.sep_token,
+                &self.cls_token,
+                &self.eos_token,
+                &self.mask_token,
+            ]
+            .iter()
+            .filter_map(|token| token.as_deref()),
+        );
+        tokens.extend(
+            self.additional_special_tokens
+                .iter()
+                .map(|token| token.as_str()),
+        );
+        tokens
+    }
+}
+
 pub trait Vocab: std::fmt::Debug {
     ///Associative function returning the unknown value
     fn unknown_value() -> &'static str;
 
+    ///Associative function returning the default special token map of the vocabulary, with the
+    ///markers of the original pretrained models
+    fn default_special_token_map() -> SpecialTokenMap;
+
+    ///Returns the special token map of the instance
+    fn special_token_map(&self) -> &SpecialTokenMap;
+
     ///Returns the unknown value on an instance
-    fn get_unknown_value(&self) -> &'static str;
+    fn get_unknown_value(&self) -> &str {
+        self.special_token_map().unk_token.as_str()
+    }
+
+    ///Returns the padding value on an instance. Defaults to the unknown value for vocabularies
+    ///without a dedicated padding token.
+    fn get_pad_value(&self) -> &str {
+        self.special_token_map()
+            .pad_token
+            .as_deref()
+            .unwrap_or_else(|| self.get_unknown_value())
+    }
+
+    ///Returns the beginning of sequence value on an instance (unknown value if not configured)
+    fn get_bos_value(&self) -> &str {
+        self.special_token_map()
+            .bos_token
+            .as_deref()
+            .unwrap_or_else(|| self.get_unknown_value())
+    }
+
+    ///Returns the separation value on an instance (unknown value if not configured)
+    fn get_sep_value(&self) -> &str {
+        self.special_token_map()
+            .sep_token
+            .as_deref()
+            .unwrap_or_else(|| self.get_unknown_value())
+    }
+
+    ///Returns the classification value on an instance (unknown value if not configured)
+    fn get_cls_value(&self) -> &str {
+        self.special_token_map()
+            .cls_token
+            .as_deref()
+            .unwrap_or_else(|| self.get_unknown_value())
+    }
+
+    ///Returns the end of sequence value on an instance (unknown value if not configured)
+    fn get_eos_value(&self) -> &str {
+        self.special_token_map()
+            .eos_token
+            .as_deref()
+            .unwrap_or_else(|| self.get_unknown_value())
+    }
+
+    ///Returns the mask value on an instance (unknown value if not configured)
+    fn get_mask_value(&self) -> &str {
+        self.special_token_map()
+            .mask_token
+            .as_deref()
+            .unwrap_or_else(|| self.get_unknown_value())
+    }
 
     ///Return the map of token strings to IDs
     fn values(&self) -> &HashMap<String, i64>;
@@ -43,17 +301,80 @@ pub trait Vocab: std::fmt::Debug {
     ///Return the map of token IDs to strings for special values
     fn special_indices(&self) -> &HashMap<i64, String>;
 
-    ///Read a vocabulary file from file
+    ///Return the map of token strings to IDs for regular tokens added after the vocabulary creation
+    fn added_values(&self) -> &HashMap<String, i64>;
+
+    ///Return the matching options of special and added values, by token string. Values without
+    ///options are matched exactly, wherever they appear in the text
+    fn added_tokens(&self) -> &HashMap<String, AddedToken>;
+
+    ///Read a vocabulary from file
     fn from_file(path: &str) -> Result<Self, TokenizerError>
+    where
+        Self: std::marker::Sized,
+    {
+        let f = File::open(path).map_err(|e| {
+            TokenizerError::FileNotFound(format!("{} vocabulary file not found :{}", path, e))
+        })?;
+        Self::from_reader(BufReader::new(f))
+    }
+
+    ///Read a vocabulary from file, registering the tokens of a Hugging Face
+    ///`special_tokens_map.json` file as special values. Tokens missing from the special token map
+    ///file keep their default value.
+    fn from_file_with_special_token_mapping(
+        path: &str,
+        special_token_mapping_path: &str,
+    ) -> Result<Self, TokenizerError>
+    where
+        Self: std::marker::Sized,
+    {
+        let special_token_map = SpecialTokenMap::from_file(
+            special_token_mapping_path,
+            Self::default_special_token_map(),
+        )?;
+        let f = File::open(path).map_err(|e| {
+            TokenizerError::FileNotFound(format!("{} vocabulary file not found :{}", path, e))
+        })?;
+        Self::from_reader_with_special_token_map(BufReader::new(f), special_token_map)
+    }
+
+    ///Read a vocabulary from any reader (e.g. a file or a network stream)
+    fn from_reader<R: Read>(reader: R) -> Result<Self, TokenizerError>
+    where
+        Self: std::marker::Sized,
+    {
+        Self::from_reader_with_special_token_map(reader, Self::default_special_token_map())
+    }
+
+    ///Read a vocabulary from any reader with a custom special token map. All the tokens of the map
+    ///must be part of the vocabulary.
+    fn from_reader_with_special_token_map<R: Read>(
+        reader: R,
+        special_token_map: SpecialTokenMap,
+    ) -> Result<Self, TokenizerError>
     where
         Self: std::marker::Sized;
 
+    ///Read a vocabulary from bytes held in memory (e.g. loaded with `include_bytes!`)
+    fn from_bytes(bytes: &[u8]) -> Result<Self, TokenizerError>
+    where
+        Self: std::marker::Sized,
+    {
+        Self::from_reader(bytes)
+    }
+
     ///Read a Bert-style vocab.txt file (single column, one token per line)
     fn read_vocab_file(path: &str) -> Result<HashMap<String, i64>, TokenizerError> {
         let f = File::open(path).map_err(|e| {
             TokenizerError::FileNotFound(format!("{} vocabulary file not found :{}", path, e))
         })?;
-        let br = BufReader::new(f);
+        Self::read_vocab(f)
+    }
+
+    ///Read a Bert-style vocabulary (single column, one token per line) from a reader
+    fn read_vocab<R: Read>(reader: R) -> Result<HashMap<String, i64>, TokenizerError> {
+        let br = BufReader::new(reader);
         let mut data = HashMap::new();
         let mut index = 0;
 
@@ -120,6 +441,91 @@ pub trait Vocab: std::fmt::Debug {
         Ok(())
     }
 
+    fn _register_special_token_map(
+        special_token_map: &SpecialTokenMap,
+        values: &HashMap<String, i64>,
+        special_values: &mut HashMap<String, i64>,
+        added_tokens: &mut HashMap<String, AddedToken>,
+    ) -> Result<(), TokenizerError> {
+        for token in special_token_map.tokens() {
+            Self::_register_as_special_value(token, values, special_values)?;
+        }
+        for (token, options) in &special_token_map.token_options {
+            added_tokens.insert(token.clone(), options.clone());
+        }
+        Ok(())
+    }
+
+    fn _add_tokens(
+        tokens: &[AddedToken],
+        values: &mut HashMap<String, i64>,
+        indices: &mut HashMap<i64, String>,
+        special_values: &mut HashMap<String, i64>,
+        special_indices: &mut HashMap<i64, String>,
+        added_values: &mut HashMap<String, i64>,
+        added_tokens: &mut HashMap<String, AddedToken>,
+    ) -> usize {
+        let mut next_id = indices
+            .keys()
+            .chain(special_indices.keys())
+            .max()
+            .map_or(0, |id| id + 1);
+        let mut added_count = 0;
+        for token in tokens.iter().filter(|token| !token.content.is_empty()) {
+            let content = token.content.as_str();
+            let token_id = match values.get(content) {
+                Some(index) => *index,
+                None => {
+                    let token_id = next_id;
+                    values.insert(content.to_owned(), token_id);
+                    indices.insert(token_id, content.to_owned());
+                    next_id += 1;
+                    added_count += 1;
+                    token_id
+                }
+            };
+            if token.special {
+                added_values.remove(content);
+                special_values.insert(content.to_owned(), token_id);
+                special_indices.insert(token_id, content.to_owned());
+            } else if !special_values.contains_key(content) {
+                added_values.insert(content.to_owned(), token_id);
+            } else {
+                continue;
+            }
+            added_tokens.insert(content.to_owned(), token.clone());
+        }
+        added_count
+    }
+
+    ///Adds tokens with their matching options to the vocabulary (see `AddedToken`). Tokens missing
+    ///from the vocabulary get fresh IDs following the largest existing ID, existing tokens keep their
+    ///ID. Special tokens are registered as special values, the other ones as added values: both are
+    ///matched before the tokenization model runs and are never split.
+    ///Returns the number of tokens that were not yet in the vocabulary.
+    fn add_tokens_with_options(&mut self, tokens: &[AddedToken]) -> usize;
+
+    ///Adds regular tokens to the vocabulary with the default matching options (see
+    ///`add_tokens_with_options`). Returns the number of tokens that were not yet in the vocabulary.
+    fn add_tokens(&mut self, tokens: &[&str]) -> usize {
+        let tokens = tokens
+            .iter()
+            .map(|token| AddedToken::new(token, false))
+            .collect::<Vec<AddedToken>>();
+        self.add_tokens_with_options(&tokens)
+    }
+
+    ///Adds special tokens to the vocabulary with the default matching options (see
+    ///`add_tokens_with_options`). Existing tokens are registered as special values with their
+    ///current ID. Returns the number of tokens that were not yet in the vocabulary.
+    fn add_special_tokens(&mut self, tokens: &[&str]) -> usize {
+        let tokens = tokens
+            .iter()
+            .map(|token| AddedToken::new(token, true))
+            .collect::<Vec<AddedToken>>();
+        self.add_tokens_with_options(&tokens)
+    }
+
     fn token_to_id(&self, token: &str) -> i64;
 
     fn id_to_token(&self, id: &i64) -> String;
@@ -129,7 +535,7 @@ pub trait Vocab: std::fmt::Debug {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BaseVocab {
     ///A mapping of tokens as string to indices (i.e. the encoder base)
     pub values: HashMap<String, i64>,
@@ -137,8 +543,8 @@ pub struct BaseVocab {
     ///A mapping of token IDs to strings (i.e. the decoder base)
     pub indices: HashMap<i64, String>,
 
-    ///The string to use for unknown (out of vocabulary) tokens
-    pub unknown_value: &'static str,
+    ///The special tokens of the vocabulary (unknown value, padding, separation and class markers...)
+    pub special_token_map: SpecialTokenMap,
 
     ///A mapping of special value tokens as strings to IDs (i.e. the encoder base for special
     ///values), special values typically include things like BOS/EOS markers, class markers, mask
@@ -147,6 +553,14 @@ pub struct BaseVocab {
 
     ///A mapping of special value tokens as IDs to strings (i.e. the decoder base for special values)
     pub special_indices: HashMap<i64, String>,
+
+    ///A mapping of regular (non-special) tokens added to the vocabulary after its creation as
+    ///strings to IDs. Like special values, added tokens are never split by the tokenization model
+    pub added_values: HashMap<String, i64>,
+
+    ///The matching options of special and added tokens, by token. Tokens without options are
+    ///matched exactly
+    pub added_tokens: HashMap<String, AddedToken>,
 }
 
 impl Vocab for BaseVocab {
@@ -154,8 +568,12 @@ impl Vocab for BaseVocab {
         "[UNK]"
     }
 
-    fn get_unknown_value(&self) -> &'static str {
-        "[UNK]"
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap::new(BaseVocab::unknown_value())
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
     }
 
     fn values(&self) -> &HashMap<String, i64> {
@@ -174,11 +592,27 @@ impl Vocab for BaseVocab {
         &self.special_indices
     }
 
-    fn from_file(path: &str) -> Result<BaseVocab, TokenizerError> {
-        let values = BaseVocab::read_vocab_file(path)?;
+    fn added_values(&self) -> &HashMap<String, i64> {
+        &self.added_values
+    }
+
+    fn added_tokens(&self) -> &HashMap<String, AddedToken> {
+        &self.added_tokens
+    }
+
+    fn from_reader_with_special_token_map<R: Read>(
+        reader: R,
+        special_token_map: SpecialTokenMap,
+    ) -> Result<BaseVocab, TokenizerError> {
+        let values = BaseVocab::read_vocab(reader)?;
+        let mut added_tokens = HashMap::new();
         let mut special_values = HashMap::new();
-        let unknown_value = BaseVocab::unknown_value();
-        BaseVocab::_register_as_special_value(unknown_value, &values, &mut special_values)?;
+        BaseVocab::_register_special_token_map(
+            &special_token_map,
+            &values,
+            &mut special_values,
+            &mut added_tokens,
+        )?;
 
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
@@ -186,18 +620,32 @@ impl Vocab for BaseVocab {
         Ok(BaseVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map,
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens,
         })
     }
 
+    fn add_tokens_with_options(&mut self, tokens: &[AddedToken]) -> usize {
+        Self::_add_tokens(
+            tokens,
+            &mut self.values,
+            &mut self.indices,
+            &mut self.special_values,
+            &mut self.special_indices,
+            &mut self.added_values,
+            &mut self.added_tokens,
+        )
+    }
+
     fn token_to_id(&self, token: &str) -> i64 {
         self._token_to_id(
             token,
             &self.values,
             &self.special_values,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 
@@ -206,7 +654,7 @@ impl Vocab for BaseVocab {
             &id,
             &self.indices,
             &self.special_indices,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 }
@@ -228,20 +676,21 @@ mod tests {
         let special_values: HashMap<String, i64> = HashMap::new();
         let indices: HashMap<i64, String> = HashMap::new();
         let special_indices: HashMap<i64, String> = HashMap::new();
-        let unknown_value = BaseVocab::unknown_value();
 
         //        When
         let base_vocab = BaseVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map: BaseVocab::default_special_token_map(),
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
         };
 
         //        Then
-        assert_eq!(base_vocab.unknown_value, "[UNK]");
-        assert_eq!(base_vocab.unknown_value, BaseVocab::unknown_value());
+        assert_eq!(base_vocab.special_token_map.unk_token, "[UNK]");
+        assert_eq!(base_vocab.get_unknown_value(), BaseVocab::unknown_value());
         assert_eq!(base_vocab.values, *base_vocab.values());
         assert_eq!(base_vocab.special_values, *base_vocab.special_values());
     }
@@ -269,7 +718,7 @@ mod tests {
         let base_vocab = BaseVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
         //        Then
-        assert_eq!(base_vocab.unknown_value, "[UNK]");
+        assert_eq!(base_vocab.special_token_map.unk_token, "[UNK]");
         assert_eq!(base_vocab.values, target_values);
         assert_eq!(base_vocab.special_values, special_values);
         drop(path);
@@ -288,6 +737,112 @@ mod tests {
         let _base_vocab = BaseVocab::from_file(path.to_path_buf().to_str().unwrap()).unwrap();
     }
 
+    #[test]
+    fn test_add_tokens() -> anyhow::Result<()> {
+        //        Given
+        let mut vocab_file = tempfile::NamedTempFile::new()?;
+        write!(vocab_file, "hello \n world \n [UNK] \n !")?;
+        let path = vocab_file.into_temp_path();
+        let mut base_vocab = BaseVocab::from_file(path.to_path_buf().to_str().unwrap())?;
+
+        //        When
+        let added_regular = base_vocab.add_tokens(&["covid19", "hello", ""]);
+        let added_special = base_vocab.add_special_tokens(&["<PRODUCT>", "world", "covid19"]);
+
+        //        Then
+        assert_eq!(added_regular, 1);
+        assert_eq!(added_special, 1);
+        assert_eq!(base_vocab.token_to_id("covid19"), 4);
+        assert_eq!(base_vocab.token_to_id("<PRODUCT>"), 5);
+        assert_eq!(base_vocab.id_to_token(&5), "<PRODUCT>");
+        assert_eq!(base_vocab.added_values.get("hello"), Some(&0));
+        assert!(!base_vocab.added_values.contains_key("covid19"));
+        assert_eq!(base_vocab.special_values.get("covid19"), Some(&4));
+        assert_eq!(
+            base_vocab.special_indices.get(&1),
+            Some(&"world".to_owned())
+        );
+        drop(path);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_special_token_map() -> anyhow::Result<()> {
+        //        Given
+        let special_token_map_json = r#"{
+            "pad_token": "<|endoftext|>",
+            "cls_token": null,
+            "mask_token": {"content": "<mask>", "lstrip": true, "single_word": false, "normalized": null},
+            "additional_special_tokens": ["<eop>", {"content": "<eod>", "rstrip": true}]
+        }"#;
+        let defaults = SpecialTokenMap {
+            cls_token: Some("[CLS]".to_owned()),
+            ..SpecialTokenMap::new("[UNK]")
+        };
+        let vocab = "[UNK]\n<|endoftext|>\n[CLS]\n<mask>\n<eop>\n<eod>";
+
+        //        When
+        let special_token_map =
+            SpecialTokenMap::from_reader(special_token_map_json.as_bytes(), defaults)?;
+        let base_vocab = BaseVocab::from_reader_with_special_token_map(
+            vocab.as_bytes(),
+            special_token_map.clone(),
+        )?;
+        let invalid_result = SpecialTokenMap::from_reader(
+            r#"{"pad_token": 0}"#.as_bytes(),
+            SpecialTokenMap::new("[UNK]"),
+        );
+        let invalid_flag_result = SpecialTokenMap::from_reader(
+            r#"{"pad_token": {"content": "<pad>", "lstrip": "yes"}}"#.as_bytes(),
+            SpecialTokenMap::new("[UNK]"),
+        );
+
+        //        Then
+        assert_eq!(special_token_map.unk_token, "[UNK]");
+        assert_eq!(special_token_map.cls_token.as_deref(), Some("[CLS]"));
+        assert_eq!(
+            special_token_map.pad_token.as_deref(),
+            Some("<|endoftext|>")
+        );
+        assert_eq!(special_token_map.mask_token.as_deref(), Some("<mask>"));
+        assert_eq!(special_token_map.sep_token, None);
+        assert_eq!(
+            special_token_map.tokens(),
+            vec![
+                "[UNK]",
+                "<|endoftext|>",
+                "[CLS]",
+                "<mask>",
+                "<eop>",
+                "<eod>"
+            ]
+        );
+        assert_eq!(
+            base_vocab.added_tokens.get("<mask>"),
+            Some(&AddedToken {
+                lstrip: true,
+                ..AddedToken::new("<mask>", true)
+            })
+        );
+        assert_eq!(
+            base_vocab.added_tokens.get("<eod>"),
+            Some(&AddedToken {
+                rstrip: true,
+                ..AddedToken::new("<eod>", true)
+            })
+        );
+        assert_eq!(base_vocab.added_tokens.get("<eop>"), None);
+        assert!(matches!(
+            invalid_result,
+            Err(TokenizerError::VocabularyParsingError(_))
+        ));
+        assert!(matches!(
+            invalid_flag_result,
+            Err(TokenizerError::VocabularyParsingError(_))
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_encode_tokens() -> anyhow::Result<()> {
         //        Given
@@ -12,10 +12,13 @@
 // limitations under the License.
 
 use crate::preprocessing::error::TokenizerError;
-use crate::preprocessing::vocab::base_vocab::{swap_key_values, Vocab};
+use crate::preprocessing::vocab::base_vocab::{
+    swap_key_values, AddedToken, SpecialTokenMap, Vocab,
+};
 use std::collections::HashMap;
+use std::io::Read;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BertVocab {
     ///A mapping of tokens as string to indices (i.e. the encoder base)
     pub values: HashMap<String, i64>,
@@ -23,8 +26,8 @@ pub struct BertVocab {
     ///A mapping of token IDs to strings (i.e. the decoder base)
     pub indices: HashMap<i64, String>,
 
-    ///The string to use for unknown (out of vocabulary) tokens
-    pub unknown_value: &'static str,
+    ///The special tokens of the vocabulary (unknown value, padding, separation and class markers...)
+    pub special_token_map: SpecialTokenMap,
 
     ///A mapping of special value tokens as strings to IDs (i.e. the encoder base for special
     ///values), special values typically include things like BOS/EOS markers, class markers, mask
@@ -33,6 +36,8 @@ pub struct BertVocab {
 
     ///A mapping of special value tokens as IDs to strings (i.e. the decoder base for special values)
     pub special_indices: HashMap<i64, String>,
+    pub added_values: HashMap<String, i64>,
+    pub added_tokens: HashMap<String, AddedToken>,
 }
 
 impl BertVocab {
@@ -55,8 +60,22 @@ impl Vocab for BertVocab {
         "[UNK]"
     }
 
-    fn get_unknown_value(&self) -> &'static str {
-        "[UNK]"
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap {
+            unk_token: BertVocab::unknown_value().to_owned(),
+            pad_token: Some(BertVocab::pad_value().to_owned()),
+            bos_token: None,
+            sep_token: Some(BertVocab::sep_value().to_owned()),
+            cls_token: Some(BertVocab::cls_value().to_owned()),
+            eos_token: None,
+            mask_token: Some(BertVocab::mask_value().to_owned()),
+            additional_special_tokens: vec![],
+            token_options: HashMap::new(),
+        }
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
     }
 
     fn values(&self) -> &HashMap<String, i64> {
@@ -75,24 +94,28 @@ impl Vocab for BertVocab {
         &self.special_indices
     }
 
-    fn from_file(path: &str) -> Result<BertVocab, TokenizerError> {
-        let values = BertVocab::read_vocab_file(path)?;
-        let mut special_values = HashMap::new();
-
-        let unknown_value = BertVocab::unknown_value();
-        BertVocab::_register_as_special_value(unknown_value, &values, &mut special_values)?;
-
-        let pad_value = BertVocab::pad_value();
-        BertVocab::_register_as_special_value(pad_value, &values, &mut special_values)?;
+    fn added_values(&self) -> &HashMap<String, i64> {
+        &self.added_values
+    }
 
-        let sep_value = BertVocab::sep_value();
-        BertVocab::_register_as_special_value(sep_value, &values, &mut special_values)?;
+    fn added_tokens(&self) -> &HashMap<String, AddedToken> {
+        &self.added_tokens
+    }
 
-        let cls_value = BertVocab::cls_value();
-        BertVocab::_register_as_special_value(cls_value, &values, &mut special_values)?;
+    fn from_reader_with_special_token_map<R: Read>(
+        reader: R,
+        special_token_map: SpecialTokenMap,
+    ) -> Result<BertVocab, TokenizerError> {
+        let values = BertVocab::read_vocab(reader)?;
+        let mut added_tokens = HashMap::new();
+        let mut special_values = HashMap::new();
 
-        let mask_value = BertVocab::mask_value();
-        BertVocab::_register_as_special_value(mask_value, &values, &mut special_values)?;
+        BertVocab::_register_special_token_map(
+            &special_token_map,
+            &values,
+            &mut special_values,
+            &mut added_tokens,
+        )?;
 
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
@@ -100,18 +123,32 @@ impl Vocab for BertVocab {
         Ok(BertVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map,
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens,
         })
     }
 
+    fn add_tokens_with_options(&mut self, tokens: &[AddedToken]) -> usize {
+        Self::_add_tokens(
+            tokens,
+            &mut self.values,
+            &mut self.indices,
+            &mut self.special_values,
+            &mut self.special_indices,
+            &mut self.added_values,
+            &mut self.added_tokens,
+        )
+    }
+
     fn token_to_id(&self, token: &str) -> i64 {
         self._token_to_id(
             token,
             &self.values,
             &self.special_values,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 
@@ -120,7 +157,7 @@ impl Vocab for BertVocab {
             &id,
             &self.indices,
             &self.special_indices,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 }
@@ -141,20 +178,21 @@ mod tests {
         let special_values: HashMap<String, i64> = HashMap::new();
         let indices: HashMap<i64, String> = HashMap::new();
         let special_indices: HashMap<i64, String> = HashMap::new();
-        let unknown_value = BertVocab::unknown_value();
 
         //        When
         let base_vocab = BertVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map: BertVocab::default_special_token_map(),
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
         };
 
         //        Then
-        assert_eq!(base_vocab.unknown_value, "[UNK]");
-        assert_eq!(base_vocab.unknown_value, BertVocab::unknown_value());
+        assert_eq!(base_vocab.special_token_map.unk_token, "[UNK]");
+        assert_eq!(base_vocab.get_unknown_value(), BertVocab::unknown_value());
         assert_eq!(BertVocab::pad_value(), "[PAD]");
         assert_eq!(BertVocab::sep_value(), "[SEP]");
         assert_eq!(BertVocab::cls_value(), "[CLS]");
@@ -201,13 +239,29 @@ mod tests {
         let base_vocab = BertVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
         //        Then
-        assert_eq!(base_vocab.unknown_value, "[UNK]");
+        assert_eq!(base_vocab.special_token_map.unk_token, "[UNK]");
         assert_eq!(base_vocab.values, target_values);
         assert_eq!(base_vocab.special_values, special_values);
         drop(path);
         Ok(())
     }
 
+    #[test]
+    fn test_create_object_from_bytes() -> anyhow::Result<()> {
+        //        Given
+        let vocab_bytes = b"hello \n world \n [UNK] \n ! \n [CLS] \n [SEP] \n [MASK] \n [PAD]";
+
+        //        When
+        let base_vocab = BertVocab::from_bytes(vocab_bytes)?;
+
+        //        Then
+        assert_eq!(base_vocab.special_token_map.unk_token, "[UNK]");
+        assert_eq!(base_vocab.values.len(), 8);
+        assert_eq!(base_vocab.token_to_id("world"), 1);
+        assert_eq!(base_vocab.special_values.len(), 5);
+        Ok(())
+    }
+
     #[test]
     #[should_panic]
     fn test_create_object_from_file_without_unknown_token() {
@@ -220,6 +274,41 @@ mod tests {
         let _base_vocab = BertVocab::from_file(path.to_path_buf().to_str().unwrap()).unwrap();
     }
 
+    #[test]
+    fn test_create_object_with_special_token_mapping() -> anyhow::Result<()> {
+        //        Given
+        let mut vocab_file = tempfile::NamedTempFile::new()?;
+        write!(
+            vocab_file,
+            "hello \n world \n [UNK] \n [UNUSED0] \n [CLS] \n [SEP] \n <|endoftext|>"
+        )?;
+        let mut special_token_map_file = tempfile::NamedTempFile::new()?;
+        write!(
+            special_token_map_file,
+            "{{\"pad_token\": \"<|endoftext|>\", \"mask_token\": {{\"content\": \"[UNUSED0]\"}}}}"
+        )?;
+        let path = vocab_file.into_temp_path();
+        let special_token_map_path = special_token_map_file.into_temp_path();
+
+        //        When
+        let default_vocab_result = BertVocab::from_file(path.to_path_buf().to_str().unwrap());
+        let bert_vocab = BertVocab::from_file_with_special_token_mapping(
+            path.to_path_buf().to_str().unwrap(),
+            special_token_map_path.to_path_buf().to_str().unwrap(),
+        )?;
+
+        //        Then
+        assert!(default_vocab_result.is_err());
+        assert_eq!(bert_vocab.get_unknown_value(), "[UNK]");
+        assert_eq!(bert_vocab.get_mask_value(), "[UNUSED0]");
+        assert_eq!(bert_vocab.get_pad_value(), "<|endoftext|>");
+        assert_eq!(bert_vocab.get_cls_value(), "[CLS]");
+        assert_eq!(bert_vocab.special_values.get("[UNUSED0]"), Some(&3));
+        assert_eq!(bert_vocab.special_values.get("<|endoftext|>"), Some(&6));
+        assert_eq!(bert_vocab.special_values.len(), 5);
+        Ok(())
+    }
+
     #[test]
     fn test_encode_tokens() -> anyhow::Result<()> {
         //        Given
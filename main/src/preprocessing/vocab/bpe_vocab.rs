@@ -13,7 +13,7 @@
 use crate::preprocessing::error::TokenizerError;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
 use std::mem::ManuallyDrop;
 use std::ptr;
 
@@ -33,7 +33,12 @@ impl BpePairVocab {
         let f = File::open(path).map_err(|e| {
             TokenizerError::FileNotFound(format!("{} vocabulary file not found :{}", path, e))
         })?;
-        let br = BufReader::new(f);
+        BpePairVocab::from_reader(f)
+    }
+
+    ///Read the merges (one space-separated pair per line, after a header line) from any reader
+    pub fn from_reader<R: Read>(reader: R) -> Result<BpePairVocab, TokenizerError> {
+        let br = BufReader::new(reader);
         let mut data = HashMap::new();
         let mut index = 0;
         for line in br.lines().skip(1) {
@@ -53,6 +58,11 @@ impl BpePairVocab {
         Ok(BpePairVocab { values: data })
     }
 
+    ///Read the merges from bytes held in memory (e.g. loaded with `include_bytes!`)
+    pub fn from_bytes(bytes: &[u8]) -> Result<BpePairVocab, TokenizerError> {
+        BpePairVocab::from_reader(bytes)
+    }
+
     pub fn byte_pair_to_id(&self, byte_pair: &BpePairRef) -> Option<&i64> {
         unsafe {
             let byte_1 = byte_pair.byte_1;
@@ -113,6 +123,25 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_create_pair_vocab_from_bytes() -> anyhow::Result<()> {
+        //        Given
+        let merges_bytes = b"#version: 0.1\n t h\na n\ni n\nth e</w>";
+
+        //        When
+        let pair_vocab = BpePairVocab::from_bytes(merges_bytes)?;
+
+        //        Then
+        assert_eq!(pair_vocab.values.len(), 4);
+        assert_eq!(
+            pair_vocab
+                .values
+                .get(&("th".to_owned(), "e</w>".to_owned())),
+            Some(&3)
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_encode_byte_pairs() -> anyhow::Result<()> {
         //        Given
@@ -12,18 +12,21 @@
 // limitations under the License.
 
 use crate::preprocessing::error::TokenizerError;
-use crate::preprocessing::vocab::base_vocab::{swap_key_values, Vocab};
+use crate::preprocessing::vocab::base_vocab::{
+    swap_key_values, AddedToken, SpecialTokenMap, Vocab,
+};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Read};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Gpt2Vocab {
     pub values: HashMap<String, i64>,
     pub indices: HashMap<i64, String>,
-    pub unknown_value: &'static str,
+    pub special_token_map: SpecialTokenMap,
     pub special_values: HashMap<String, i64>,
     pub special_indices: HashMap<i64, String>,
+    pub added_values: HashMap<String, i64>,
+    pub added_tokens: HashMap<String, AddedToken>,
 }
 
 impl Gpt2Vocab {
@@ -40,8 +43,22 @@ impl Vocab for Gpt2Vocab {
         "<|endoftext|>"
     }
 
-    fn get_unknown_value(&self) -> &'static str {
-        "<|endoftext|>"
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap {
+            unk_token: Gpt2Vocab::unknown_value().to_owned(),
+            pad_token: None,
+            bos_token: Some(Gpt2Vocab::bos_value().to_owned()),
+            sep_token: None,
+            cls_token: None,
+            eos_token: Some(Gpt2Vocab::eos_value().to_owned()),
+            mask_token: None,
+            additional_special_tokens: vec![],
+            token_options: HashMap::new(),
+        }
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
     }
 
     fn values(&self) -> &HashMap<String, i64> {
@@ -60,26 +77,33 @@ impl Vocab for Gpt2Vocab {
         &self.special_indices
     }
 
-    fn from_file(path: &str) -> Result<Gpt2Vocab, TokenizerError> {
-        let f = File::open(path).map_err(|e| {
-            TokenizerError::FileNotFound(format!("{} vocabulary file not found :{}", path, e))
-        })?;
-        let br = BufReader::new(f);
+    fn added_values(&self) -> &HashMap<String, i64> {
+        &self.added_values
+    }
+
+    fn added_tokens(&self) -> &HashMap<String, AddedToken> {
+        &self.added_tokens
+    }
+
+    fn from_reader_with_special_token_map<R: Read>(
+        reader: R,
+        special_token_map: SpecialTokenMap,
+    ) -> Result<Gpt2Vocab, TokenizerError> {
+        let br = BufReader::new(reader);
         let values: HashMap<String, i64> = match serde_json::from_reader(br) {
             Ok(value) => value,
             Err(e) => {
                 return Err(TokenizerError::VocabularyParsingError(e.to_string()));
             }
         };
+        let mut added_tokens = HashMap::new();
         let mut special_values = HashMap::new();
-        let unknown_value = Gpt2Vocab::unknown_value();
-        Gpt2Vocab::_register_as_special_value(unknown_value, &values, &mut special_values)?;
-
-        let bos_value = Gpt2Vocab::bos_value();
-        Gpt2Vocab::_register_as_special_value(bos_value, &values, &mut special_values)?;
-
-        let eos_value = Gpt2Vocab::eos_value();
-        Gpt2Vocab::_register_as_special_value(eos_value, &values, &mut special_values)?;
+        Gpt2Vocab::_register_special_token_map(
+            &special_token_map,
+            &values,
+            &mut special_values,
+            &mut added_tokens,
+        )?;
 
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
@@ -87,18 +111,32 @@ impl Vocab for Gpt2Vocab {
         Ok(Gpt2Vocab {
             values,
             indices,
-            unknown_value,
+            special_token_map,
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens,
         })
     }
 
+    fn add_tokens_with_options(&mut self, tokens: &[AddedToken]) -> usize {
+        Self::_add_tokens(
+            tokens,
+            &mut self.values,
+            &mut self.indices,
+            &mut self.special_values,
+            &mut self.special_indices,
+            &mut self.added_values,
+            &mut self.added_tokens,
+        )
+    }
+
     fn token_to_id(&self, token: &str) -> i64 {
         self._token_to_id(
             token,
             &self.values,
             &self.special_values,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 
@@ -107,7 +145,7 @@ impl Vocab for Gpt2Vocab {
             &id,
             &self.indices,
             &self.special_indices,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 }
@@ -128,22 +166,23 @@ mod tests {
         let special_values: HashMap<String, i64> = HashMap::new();
         let indices: HashMap<i64, String> = HashMap::new();
         let special_indices: HashMap<i64, String> = HashMap::new();
-        let unknown_value = Gpt2Vocab::unknown_value();
 
         //        When
         let gpt2_vocab = Gpt2Vocab {
             values,
             indices,
-            unknown_value,
+            special_token_map: Gpt2Vocab::default_special_token_map(),
             special_indices,
             special_values,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
         };
 
         //        Then
-        assert_eq!(gpt2_vocab.unknown_value, "<|endoftext|>");
+        assert_eq!(gpt2_vocab.special_token_map.unk_token, "<|endoftext|>");
         assert_eq!(Gpt2Vocab::bos_value(), "<|endoftext|>");
         assert_eq!(Gpt2Vocab::eos_value(), "<|endoftext|>");
-        assert_eq!(gpt2_vocab.unknown_value, Gpt2Vocab::unknown_value());
+        assert_eq!(gpt2_vocab.get_unknown_value(), Gpt2Vocab::unknown_value());
         assert_eq!(gpt2_vocab.values, *gpt2_vocab.values());
         assert_eq!(gpt2_vocab.special_values, *gpt2_vocab.special_values());
     }
@@ -174,7 +213,7 @@ mod tests {
         let gpt2_vocab = Gpt2Vocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
         //        Then
-        assert_eq!(gpt2_vocab.unknown_value, "<|endoftext|>");
+        assert_eq!(gpt2_vocab.special_token_map.unk_token, "<|endoftext|>");
         assert_eq!(gpt2_vocab.values, target_values);
         assert_eq!(gpt2_vocab.special_values, special_values);
         drop(path);
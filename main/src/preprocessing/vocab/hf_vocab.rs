@@ -0,0 +1,287 @@
+// Copyright 2019-2020 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::preprocessing::error::TokenizerError;
+use crate::preprocessing::tokenizer::tokenizer_json::{
+    get_section, read_tokenizer_json, read_values, TokenizerJsonValues,
+};
+use crate::preprocessing::vocab::base_vocab::{
+    swap_key_values, AddedToken, SpecialTokenMap, Vocab,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Read;
+
+/// # Vocabulary of a Hugging Face `tokenizer.json` file
+/// Contains the vocabulary of the `model` section (WordPiece, BPE or Unigram) of the file, extended
+/// with the entries of the `added_tokens` section. Added tokens flagged as `special` are registered
+/// as special values, the other ones as added values: neither are split by the tokenization model.
+#[derive(Debug, Clone)]
+pub struct HFVocab {
+    pub values: HashMap<String, i64>,
+    pub indices: HashMap<i64, String>,
+    pub special_token_map: SpecialTokenMap,
+    pub special_values: HashMap<String, i64>,
+    pub special_indices: HashMap<i64, String>,
+    pub added_values: HashMap<String, i64>,
+    pub added_tokens: HashMap<String, AddedToken>,
+}
+
+impl HFVocab {
+    /// Builds the vocabulary from a parsed `tokenizer.json` file.
+    ///
+    /// The unknown token is the `unk_token` (WordPiece, BPE) or `unk_id` (Unigram) of the model
+    /// section. Models without unknown token (e.g. byte-level BPE) fall back to the first special
+    /// added token. The padding token is read from the `padding` section if present.
+    pub fn from_json_value(tokenizer_json: &Value) -> Result<HFVocab, TokenizerError> {
+        let TokenizerJsonValues {
+            values,
+            mut special_values,
+            mut added_values,
+            added_tokens,
+        } = read_values(tokenizer_json)?;
+        let indices = swap_key_values(&values);
+        let model = get_section(tokenizer_json, "model")?;
+        let unknown_value = match model.get("unk_id").and_then(|value| value.as_i64()) {
+            Some(unknown_id) => indices.get(&unknown_id).cloned(),
+            None => model
+                .get("unk_token")
+                .and_then(|value| value.as_str())
+                .map(|value| value.to_owned()),
+        }
+        .or_else(|| {
+            special_values
+                .iter()
+                .min_by_key(|(_, id)| **id)
+                .map(|(value, _)| value.clone())
+        });
+
+        let unknown_value = match unknown_value {
+            Some(value) => value,
+            None => {
+                return Err(TokenizerError::VocabularyParsingError(
+                    "no unknown token or special token found in the vocabulary".to_owned(),
+                ));
+            }
+        };
+        if !values.contains_key(&unknown_value) {
+            return Err(TokenizerError::TokenNotFound(format!(
+                "The special value {} could not be found in the vocabulary",
+                unknown_value
+            )));
+        }
+        added_values.remove(&unknown_value);
+        special_values.insert(unknown_value.clone(), values[&unknown_value]);
+        let pad_value = tokenizer_json
+            .get("padding")
+            .and_then(|padding| padding.get("pad_token"))
+            .and_then(|pad_token| pad_token.as_str())
+            .filter(|pad_token| special_values.contains_key(*pad_token));
+        let special_token_map = SpecialTokenMap {
+            pad_token: pad_value.map(|pad_token| pad_token.to_owned()),
+            ..SpecialTokenMap::new(&unknown_value)
+        };
+        let special_indices = swap_key_values(&special_values);
+
+        Ok(HFVocab {
+            values,
+            indices,
+            special_token_map,
+            special_values,
+            special_indices,
+            added_values,
+            added_tokens,
+        })
+    }
+
+    fn with_special_token_map(
+        mut self,
+        special_token_map: SpecialTokenMap,
+    ) -> Result<HFVocab, TokenizerError> {
+        HFVocab::_register_special_token_map(
+            &special_token_map,
+            &self.values,
+            &mut self.special_values,
+            &mut self.added_tokens,
+        )?;
+        for token in special_token_map.tokens() {
+            self.added_values.remove(token);
+        }
+        self.special_indices = swap_key_values(&self.special_values);
+        self.special_token_map = special_token_map;
+        Ok(self)
+    }
+}
+
+impl Vocab for HFVocab {
+    fn unknown_value() -> &'static str {
+        "<unk>"
+    }
+
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap::new(HFVocab::unknown_value())
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
+    }
+
+    fn values(&self) -> &HashMap<String, i64> {
+        &self.values
+    }
+
+    fn indices(&self) -> &HashMap<i64, String> {
+        &self.indices
+    }
+
+    fn special_values(&self) -> &HashMap<String, i64> {
+        &self.special_values
+    }
+
+    fn special_indices(&self) -> &HashMap<i64, String> {
+        &self.special_indices
+    }
+
+    fn added_values(&self) -> &HashMap<String, i64> {
+        &self.added_values
+    }
+
+    fn added_tokens(&self) -> &HashMap<String, AddedToken> {
+        &self.added_tokens
+    }
+
+    ///Read a special token map file on top of the special tokens of the `tokenizer.json` file: the
+    ///tokens missing from the special token map file keep the value found in the `tokenizer.json`
+    ///file.
+    fn from_file_with_special_token_mapping(
+        path: &str,
+        special_token_mapping_path: &str,
+    ) -> Result<HFVocab, TokenizerError> {
+        let vocab = HFVocab::from_file(path)?;
+        let special_token_map = SpecialTokenMap::from_file(
+            special_token_mapping_path,
+            vocab.special_token_map.clone(),
+        )?;
+        vocab.with_special_token_map(special_token_map)
+    }
+
+    fn from_reader<R: Read>(reader: R) -> Result<HFVocab, TokenizerError> {
+        HFVocab::from_json_value(&read_tokenizer_json(reader)?)
+    }
+
+    fn from_reader_with_special_token_map<R: Read>(
+        reader: R,
+        special_token_map: SpecialTokenMap,
+    ) -> Result<HFVocab, TokenizerError> {
+        HFVocab::from_reader(reader)?.with_special_token_map(special_token_map)
+    }
+
+    fn add_tokens_with_options(&mut self, tokens: &[AddedToken]) -> usize {
+        Self::_add_tokens(
+            tokens,
+            &mut self.values,
+            &mut self.indices,
+            &mut self.special_values,
+            &mut self.special_indices,
+            &mut self.added_values,
+            &mut self.added_tokens,
+        )
+    }
+
+    fn token_to_id(&self, token: &str) -> i64 {
+        self._token_to_id(
+            token,
+            &self.values,
+            &self.special_values,
+            &self.special_token_map.unk_token,
+        )
+    }
+
+    fn id_to_token(&self, id: &i64) -> String {
+        self._id_to_token(
+            id,
+            &self.indices,
+            &self.special_indices,
+            &self.special_token_map.unk_token,
+        )
+    }
+}
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_create_vocab_from_word_piece() -> anyhow::Result<()> {
+        //        Given
+        let tokenizer_json = json!({
+            "added_tokens": [
+                {"id": 0, "content": "[PAD]", "special": true},
+                {"id": 1, "content": "[UNK]", "special": true}
+            ],
+            "model": {
+                "type": "WordPiece",
+                "unk_token": "[UNK]",
+                "vocab": {"[PAD]": 0, "[UNK]": 1, "hello": 2}
+            }
+        });
+
+        //        When
+        let vocab = HFVocab::from_json_value(&tokenizer_json)?;
+
+        //        Then
+        assert_eq!(vocab.get_unknown_value(), "[UNK]");
+        assert_eq!(vocab.token_to_id("hello"), 2);
+        assert_eq!(vocab.token_to_id("world"), 1);
+        assert_eq!(vocab.id_to_token(&0), "[PAD]");
+        assert_eq!(vocab.special_values.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_vocab_from_unigram() -> anyhow::Result<()> {
+        //        Given
+        let tokenizer_json = json!({
+            "model": {
+                "type": "Unigram",
+                "unk_id": 1,
+                "vocab": [["<pad>", 0.0], ["<unk>", 0.0], ["\u{2581}hello", -1.5]]
+            }
+        });
+
+        //        When
+        let vocab = HFVocab::from_json_value(&tokenizer_json)?;
+
+        //        Then
+        assert_eq!(vocab.get_unknown_value(), "<unk>");
+        assert_eq!(vocab.token_to_id("\u{2581}hello"), 2);
+        assert_eq!(vocab.token_to_id("world"), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_unknown_token() {
+        //        Given
+        let tokenizer_json = json!({
+            "model": {"type": "BPE", "unk_token": null, "vocab": {"a": 0}, "merges": []}
+        });
+
+        //        When
+        let vocab = HFVocab::from_json_value(&tokenizer_json);
+
+        //        Then
+        assert!(vocab.is_err());
+    }
+}
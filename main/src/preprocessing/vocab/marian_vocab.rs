@@ -13,19 +13,20 @@
 // limitations under the License.
 
 use crate::error::TokenizerError;
-use crate::preprocessing::vocab::base_vocab::swap_key_values;
+use crate::preprocessing::vocab::base_vocab::{swap_key_values, AddedToken, SpecialTokenMap};
 use crate::Vocab;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Read};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MarianVocab {
     pub values: HashMap<String, i64>,
     pub indices: HashMap<i64, String>,
-    pub unknown_value: &'static str,
+    pub special_token_map: SpecialTokenMap,
     pub special_values: HashMap<String, i64>,
     pub special_indices: HashMap<i64, String>,
+    pub added_values: HashMap<String, i64>,
+    pub added_tokens: HashMap<String, AddedToken>,
 }
 
 impl MarianVocab {
@@ -42,8 +43,22 @@ impl Vocab for MarianVocab {
         "<unk>"
     }
 
-    fn get_unknown_value(&self) -> &'static str {
-        "<unk>"
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap {
+            unk_token: MarianVocab::unknown_value().to_owned(),
+            pad_token: Some(MarianVocab::pad_value().to_owned()),
+            bos_token: None,
+            sep_token: None,
+            cls_token: None,
+            eos_token: Some(MarianVocab::eos_value().to_owned()),
+            mask_token: None,
+            additional_special_tokens: vec![],
+            token_options: HashMap::new(),
+        }
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
     }
 
     fn values(&self) -> &HashMap<String, i64> {
@@ -62,11 +77,19 @@ impl Vocab for MarianVocab {
         &self.special_indices
     }
 
-    fn from_file(path: &str) -> Result<MarianVocab, TokenizerError> {
-        let f = File::open(path).map_err(|e| {
-            TokenizerError::FileNotFound(format!("{} vocabulary file not found :{}", path, e))
-        })?;
-        let br = BufReader::new(f);
+    fn added_values(&self) -> &HashMap<String, i64> {
+        &self.added_values
+    }
+
+    fn added_tokens(&self) -> &HashMap<String, AddedToken> {
+        &self.added_tokens
+    }
+
+    fn from_reader_with_special_token_map<R: Read>(
+        reader: R,
+        special_token_map: SpecialTokenMap,
+    ) -> Result<MarianVocab, TokenizerError> {
+        let br = BufReader::new(reader);
         let values: HashMap<String, i64> = match serde_json::from_reader(br) {
             Ok(value) => value,
             Err(e) => {
@@ -74,15 +97,14 @@ impl Vocab for MarianVocab {
             }
         };
 
+        let mut added_tokens = HashMap::new();
         let mut special_values = HashMap::new();
-        let unknown_value = MarianVocab::unknown_value();
-        MarianVocab::_register_as_special_value(unknown_value, &values, &mut special_values)?;
-
-        let pad_value = MarianVocab::pad_value();
-        MarianVocab::_register_as_special_value(pad_value, &values, &mut special_values)?;
-
-        let eos_value = MarianVocab::eos_value();
-        MarianVocab::_register_as_special_value(eos_value, &values, &mut special_values)?;
+        MarianVocab::_register_special_token_map(
+            &special_token_map,
+            &values,
+            &mut special_values,
+            &mut added_tokens,
+        )?;
 
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
@@ -90,18 +112,32 @@ impl Vocab for MarianVocab {
         Ok(MarianVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map,
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens,
         })
     }
 
+    fn add_tokens_with_options(&mut self, tokens: &[AddedToken]) -> usize {
+        Self::_add_tokens(
+            tokens,
+            &mut self.values,
+            &mut self.indices,
+            &mut self.special_values,
+            &mut self.special_indices,
+            &mut self.added_values,
+            &mut self.added_tokens,
+        )
+    }
+
     fn token_to_id(&self, token: &str) -> i64 {
         self._token_to_id(
             token,
             &self.values,
             &self.special_values,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 
@@ -110,7 +146,7 @@ impl Vocab for MarianVocab {
             &id,
             &self.indices,
             &self.special_indices,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 }
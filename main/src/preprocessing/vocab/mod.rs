@@ -14,6 +14,7 @@ pub mod base_vocab;
 pub mod bert_vocab;
 pub mod bpe_vocab;
 pub mod gpt2_vocab;
+pub mod hf_vocab;
 pub mod marian_vocab;
 pub mod openai_gpt_vocab;
 pub mod roberta_vocab;
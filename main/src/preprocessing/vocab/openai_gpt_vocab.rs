@@ -12,18 +12,21 @@
 // limitations under the License.
 
 use crate::preprocessing::error::TokenizerError;
-use crate::preprocessing::vocab::base_vocab::{swap_key_values, Vocab};
+use crate::preprocessing::vocab::base_vocab::{
+    swap_key_values, AddedToken, SpecialTokenMap, Vocab,
+};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Read};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OpenAiGptVocab {
     pub values: HashMap<String, i64>,
     pub indices: HashMap<i64, String>,
-    pub unknown_value: &'static str,
+    pub special_token_map: SpecialTokenMap,
     pub special_values: HashMap<String, i64>,
     pub special_indices: HashMap<i64, String>,
+    pub added_values: HashMap<String, i64>,
+    pub added_tokens: HashMap<String, AddedToken>,
 }
 
 impl Vocab for OpenAiGptVocab {
@@ -31,8 +34,12 @@ impl Vocab for OpenAiGptVocab {
         "<unk>"
     }
 
-    fn get_unknown_value(&self) -> &'static str {
-        "<unk>"
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap::new(OpenAiGptVocab::unknown_value())
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
     }
 
     fn values(&self) -> &HashMap<String, i64> {
@@ -51,20 +58,33 @@ impl Vocab for OpenAiGptVocab {
         &self.special_indices
     }
 
-    fn from_file(path: &str) -> Result<OpenAiGptVocab, TokenizerError> {
-        let f = File::open(path).map_err(|e| {
-            TokenizerError::FileNotFound(format!("{} vocabulary file not found :{}", path, e))
-        })?;
-        let br = BufReader::new(f);
+    fn added_values(&self) -> &HashMap<String, i64> {
+        &self.added_values
+    }
+
+    fn added_tokens(&self) -> &HashMap<String, AddedToken> {
+        &self.added_tokens
+    }
+
+    fn from_reader_with_special_token_map<R: Read>(
+        reader: R,
+        special_token_map: SpecialTokenMap,
+    ) -> Result<OpenAiGptVocab, TokenizerError> {
+        let br = BufReader::new(reader);
         let values: HashMap<String, i64> = match serde_json::from_reader(br) {
             Ok(value) => value,
             Err(e) => {
                 return Err(TokenizerError::VocabularyParsingError(e.to_string()));
             }
         };
+        let mut added_tokens = HashMap::new();
         let mut special_values = HashMap::new();
-        let unknown_value = OpenAiGptVocab::unknown_value();
-        OpenAiGptVocab::_register_as_special_value(unknown_value, &values, &mut special_values)?;
+        OpenAiGptVocab::_register_special_token_map(
+            &special_token_map,
+            &values,
+            &mut special_values,
+            &mut added_tokens,
+        )?;
 
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
@@ -72,18 +92,32 @@ impl Vocab for OpenAiGptVocab {
         Ok(OpenAiGptVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map,
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens,
         })
     }
 
+    fn add_tokens_with_options(&mut self, tokens: &[AddedToken]) -> usize {
+        Self::_add_tokens(
+            tokens,
+            &mut self.values,
+            &mut self.indices,
+            &mut self.special_values,
+            &mut self.special_indices,
+            &mut self.added_values,
+            &mut self.added_tokens,
+        )
+    }
+
     fn token_to_id(&self, token: &str) -> i64 {
         self._token_to_id(
             token,
             &self.values,
             &self.special_values,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 
@@ -92,7 +126,7 @@ impl Vocab for OpenAiGptVocab {
             &id,
             &self.indices,
             &self.special_indices,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 }
@@ -114,21 +148,22 @@ mod tests {
         let special_values: HashMap<String, i64> = HashMap::new();
         let indices: HashMap<i64, String> = HashMap::new();
         let special_indices: HashMap<i64, String> = HashMap::new();
-        let unknown_value = OpenAiGptVocab::unknown_value();
 
         //        When
         let openai_gpt_vocab = OpenAiGptVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map: OpenAiGptVocab::default_special_token_map(),
             special_indices,
             special_values,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
         };
 
         //        Then
-        assert_eq!(openai_gpt_vocab.unknown_value, "<unk>");
+        assert_eq!(openai_gpt_vocab.special_token_map.unk_token, "<unk>");
         assert_eq!(
-            openai_gpt_vocab.unknown_value,
+            openai_gpt_vocab.get_unknown_value(),
             OpenAiGptVocab::unknown_value()
         );
         assert_eq!(openai_gpt_vocab.values, *openai_gpt_vocab.values());
@@ -164,7 +199,7 @@ mod tests {
         let openai_gpt_vocab = OpenAiGptVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
         //        Then
-        assert_eq!(openai_gpt_vocab.unknown_value, "<unk>");
+        assert_eq!(openai_gpt_vocab.special_token_map.unk_token, "<unk>");
         assert_eq!(openai_gpt_vocab.values, target_values);
         assert_eq!(openai_gpt_vocab.special_values, special_values);
         drop(path);
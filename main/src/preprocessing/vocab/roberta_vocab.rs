@@ -12,12 +12,13 @@
 // limitations under the License.
 
 use crate::preprocessing::error::TokenizerError;
-use crate::preprocessing::vocab::base_vocab::{swap_key_values, Vocab};
+use crate::preprocessing::vocab::base_vocab::{
+    swap_key_values, AddedToken, SpecialTokenMap, Vocab,
+};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Read};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RobertaVocab {
     ///A mapping of tokens as string to indices (i.e. the encoder base)
     pub values: HashMap<String, i64>,
@@ -25,8 +26,8 @@ pub struct RobertaVocab {
     ///A mapping of token IDs to strings (i.e. the decoder base)
     pub indices: HashMap<i64, String>,
 
-    ///The string to use for unknown (out of vocabulary) tokens
-    pub unknown_value: &'static str,
+    ///The special tokens of the vocabulary (unknown value, padding, separation and class markers...)
+    pub special_token_map: SpecialTokenMap,
 
     ///A mapping of special value tokens as strings to IDs (i.e. the encoder base for special
     ///values), special values typically include things like BOS/EOS markers, class markers, mask
@@ -35,6 +36,8 @@ pub struct RobertaVocab {
 
     ///A mapping of special value tokens as IDs to strings (i.e. the decoder base for special values)
     pub special_indices: HashMap<i64, String>,
+    pub added_values: HashMap<String, i64>,
+    pub added_tokens: HashMap<String, AddedToken>,
 }
 
 impl RobertaVocab {
@@ -63,8 +66,22 @@ impl Vocab for RobertaVocab {
         "<unk>"
     }
 
-    fn get_unknown_value(&self) -> &'static str {
-        "<unk>"
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap {
+            unk_token: RobertaVocab::unknown_value().to_owned(),
+            pad_token: Some(RobertaVocab::pad_value().to_owned()),
+            bos_token: Some(RobertaVocab::bos_value().to_owned()),
+            sep_token: Some(RobertaVocab::sep_value().to_owned()),
+            cls_token: Some(RobertaVocab::cls_value().to_owned()),
+            eos_token: Some(RobertaVocab::eos_value().to_owned()),
+            mask_token: Some(RobertaVocab::mask_value().to_owned()),
+            additional_special_tokens: vec![],
+            token_options: HashMap::new(),
+        }
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
     }
 
     fn values(&self) -> &HashMap<String, i64> {
@@ -83,39 +100,44 @@ impl Vocab for RobertaVocab {
         &self.special_indices
     }
 
+    fn added_values(&self) -> &HashMap<String, i64> {
+        &self.added_values
+    }
+
+    fn added_tokens(&self) -> &HashMap<String, AddedToken> {
+        &self.added_tokens
+    }
+
     ///Read a Roberta-style vocab.json file
-    fn from_file(path: &str) -> Result<RobertaVocab, TokenizerError> {
-        let f = File::open(path).map_err(|e| {
-            TokenizerError::FileNotFound(format!("{} vocabulary file not found :{}", path, e))
-        })?;
-        let br = BufReader::new(f);
+    fn from_reader_with_special_token_map<R: Read>(
+        reader: R,
+        special_token_map: SpecialTokenMap,
+    ) -> Result<RobertaVocab, TokenizerError> {
+        let br = BufReader::new(reader);
         let values: HashMap<String, i64> = match serde_json::from_reader(br) {
             Ok(value) => value,
             Err(e) => {
                 return Err(TokenizerError::VocabularyParsingError(e.to_string()));
             }
         };
-        let mut special_values = HashMap::new();
-        let unknown_value = RobertaVocab::unknown_value();
-        RobertaVocab::_register_as_special_value(unknown_value, &values, &mut special_values)?;
-
-        let pad_value = RobertaVocab::pad_value();
-        RobertaVocab::_register_as_special_value(pad_value, &values, &mut special_values)?;
-
-        let sep_value = RobertaVocab::sep_value();
-        RobertaVocab::_register_as_special_value(sep_value, &values, &mut special_values)?;
+        let mut added_tokens = HashMap::new();
+        if let Some(mask_value) = &special_token_map.mask_token {
+            added_tokens.insert(
+                mask_value.clone(),
+                AddedToken {
+                    lstrip: true,
+                    ..AddedToken::new(mask_value, true)
+                },
+            );
+        }
 
-        let cls_value = RobertaVocab::cls_value();
-        RobertaVocab::_register_as_special_value(cls_value, &values, &mut special_values)?;
-
-        let mask_value = RobertaVocab::mask_value();
-        RobertaVocab::_register_as_special_value(mask_value, &values, &mut special_values)?;
-
-        let bos_value = RobertaVocab::bos_value();
-        RobertaVocab::_register_as_special_value(bos_value, &values, &mut special_values)?;
-
-        let eos_value = RobertaVocab::eos_value();
-        RobertaVocab::_register_as_special_value(eos_value, &values, &mut special_values)?;
+        let mut special_values = HashMap::new();
+        RobertaVocab::_register_special_token_map(
+            &special_token_map,
+            &values,
+            &mut special_values,
+            &mut added_tokens,
+        )?;
 
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
@@ -123,18 +145,32 @@ impl Vocab for RobertaVocab {
         Ok(RobertaVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map,
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens,
         })
     }
 
+    fn add_tokens_with_options(&mut self, tokens: &[AddedToken]) -> usize {
+        Self::_add_tokens(
+            tokens,
+            &mut self.values,
+            &mut self.indices,
+            &mut self.special_values,
+            &mut self.special_indices,
+            &mut self.added_values,
+            &mut self.added_tokens,
+        )
+    }
+
     fn token_to_id(&self, token: &str) -> i64 {
         self._token_to_id(
             token,
             &self.values,
             &self.special_values,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 
@@ -143,7 +179,7 @@ impl Vocab for RobertaVocab {
             &id,
             &self.indices,
             &self.special_indices,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 }
@@ -165,26 +201,30 @@ mod tests {
         let special_values: HashMap<String, i64> = HashMap::new();
         let indices: HashMap<i64, String> = HashMap::new();
         let special_indices: HashMap<i64, String> = HashMap::new();
-        let unknown_value = RobertaVocab::unknown_value();
 
         //        When
         let roberta_vocab = RobertaVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map: RobertaVocab::default_special_token_map(),
             special_indices,
             special_values,
+            added_values: HashMap::new(),
+            added_tokens: HashMap::new(),
         };
 
         //        Then
-        assert_eq!(roberta_vocab.unknown_value, "<unk>");
+        assert_eq!(roberta_vocab.special_token_map.unk_token, "<unk>");
         assert_eq!(RobertaVocab::pad_value(), "<pad>");
         assert_eq!(RobertaVocab::sep_value(), "</s>");
         assert_eq!(RobertaVocab::bos_value(), "<s>");
         assert_eq!(RobertaVocab::eos_value(), "</s>");
         assert_eq!(RobertaVocab::cls_value(), "<s>");
         assert_eq!(RobertaVocab::mask_value(), "<mask>");
-        assert_eq!(roberta_vocab.unknown_value, RobertaVocab::unknown_value());
+        assert_eq!(
+            roberta_vocab.get_unknown_value(),
+            RobertaVocab::unknown_value()
+        );
         assert_eq!(roberta_vocab.values, *roberta_vocab.values());
         assert_eq!(
             roberta_vocab.special_values,
@@ -227,7 +267,7 @@ mod tests {
         let roberta_vocab = RobertaVocab::from_file(path.to_path_buf().to_str().unwrap())?;
 
         //        Then
-        assert_eq!(roberta_vocab.unknown_value, "<unk>");
+        assert_eq!(roberta_vocab.special_token_map.unk_token, "<unk>");
         assert_eq!(roberta_vocab.values, target_values);
         assert_eq!(roberta_vocab.special_values, special_values);
         drop(path);
@@ -12,14 +12,21 @@
 
 use crate::error::TokenizerError;
 use crate::preprocessing::tokenizer::base_tokenizer::{Mask, Offset, OffsetSize, Token, TokenRef};
-use crate::preprocessing::tokenizer::tokenization_utils::{is_punctuation, is_whitespace};
-use crate::preprocessing::vocab::base_vocab::swap_key_values;
-use crate::preprocessing::vocab::sentencepiece_proto::sentencepiece_model::ModelProto;
+use crate::preprocessing::tokenizer::normalizer::SentencePieceNormalizer;
+use crate::preprocessing::tokenizer::tokenization_utils::{
+    byte_piece_value, is_punctuation, is_whitespace,
+};
+use crate::preprocessing::vocab::base_vocab::{swap_key_values, AddedToken, SpecialTokenMap};
+use crate::preprocessing::vocab::sentencepiece_proto::sentencepiece_model::{
+    ModelProto, ModelProto_SentencePiece_Type, TrainerSpec_ModelType,
+};
 use crate::Vocab;
 use hashbrown::HashMap as BrownHashMap;
 use itertools::Itertools;
 use protobuf::parse_from_bytes;
-use std::collections::HashMap;
+use rand::Rng;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
 use std::fs::File;
 use std::io::Read;
 
@@ -31,6 +38,8 @@ pub struct Node<'a> {
     pub start: usize,
     pub end: usize,
     pub reference_offsets: &'a [OffsetSize],
+    ///Set for the characters not covered by any piece of the model
+    pub unknown: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -57,18 +66,178 @@ impl TrieNode {
     }
 }
 
+/// # Type of SentencePiece model
+/// Segmentation algorithm of the model, read from the `trainer_spec` of the `ModelProto`:
+/// - Unigram: most likely segmentation (Viterbi decoding) given the scores of the pieces
+/// - Bpe: greedy merges of adjacent symbols, the piece scores giving the merge priorities
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SentencePieceModelType {
+    Unigram,
+    Bpe,
+}
+
+///Merge of two adjacent symbols considered by the BPE segmentation
+#[derive(Debug, Clone, Copy)]
+struct BpeCandidate {
+    score: f32,
+    left: usize,
+    right: usize,
+    size: usize,
+}
+
+impl PartialEq for BpeCandidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for BpeCandidate {}
+
+impl PartialOrd for BpeCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BpeCandidate {
+    //highest score first, leftmost merge first for equal scores
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .partial_cmp(&other.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.left.cmp(&self.left))
+    }
+}
+
+///Penalty applied to the score of the unknown characters (below the lowest piece score) in the
+///lattice of the segmentations, as in the reference implementation
+const UNKNOWN_PENALTY: f32 = 10.0;
+
+///Size of the agenda of the n-best search above which it is pruned to its `MIN_AGENDA_SIZE` best
+///hypotheses, as in the reference implementation
+const MAX_AGENDA_SIZE: usize = 100_000;
+const MIN_AGENDA_SIZE: usize = 512;
+
+///Node of the lattice of all the segmentations of a text (positions in characters)
+#[derive(Debug, Clone, Copy)]
+struct LatticeNode {
+    start: usize,
+    end: usize,
+    score: f32,
+    index: i64,
+    unknown: bool,
+}
+
+///Partial path of the n-best search, from `position` to the end of the text
+#[derive(Debug, Clone)]
+struct Hypothesis {
+    priority: f32,
+    score: f32,
+    position: usize,
+    nodes: Vec<LatticeNode>,
+}
+
+impl PartialEq for Hypothesis {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Hypothesis {}
+
+impl PartialOrd for Hypothesis {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Hypothesis {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .partial_cmp(&other.priority)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+///Samples an index with probabilities proportional to `exp(log_weights)`
+fn sample_index<R: Rng + ?Sized>(log_weights: &[f32], rng: &mut R) -> usize {
+    let max = log_weights
+        .iter()
+        .cloned()
+        .fold(f32::NEG_INFINITY, f32::max);
+    let weights = log_weights
+        .iter()
+        .map(|log_weight| (log_weight - max).exp())
+        .collect::<Vec<f32>>();
+    let mut threshold = rng.gen::<f32>() * weights.iter().sum::<f32>();
+    for (idx, weight) in weights.iter().enumerate() {
+        if threshold < *weight {
+            return idx;
+        }
+        threshold -= weight;
+    }
+    weights.len() - 1
+}
+
+fn log_sum_exp(values: impl Iterator<Item = f32>) -> f32 {
+    let values = values.collect::<Vec<f32>>();
+    let max = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    if max == f32::NEG_INFINITY {
+        return max;
+    }
+    max + values
+        .iter()
+        .map(|value| (value - max).exp())
+        .sum::<f32>()
+        .ln()
+}
+
+///Returns the nodes of the best segmentation found by `decode_forward_token_ref`, following the
+///best node ending at each position back from the end of the text
+fn best_path<'a, 'b>(nodes: &'b [Option<Node<'a>>]) -> Vec<&'b Node<'a>> {
+    let mut best_sequence = vec![];
+    let mut next_node = match nodes.last() {
+        Some(value) => value,
+        None => {
+            return best_sequence;
+        }
+    };
+
+    while next_node.is_some() {
+        let node_value = next_node.as_ref().unwrap();
+        best_sequence.push(node_value);
+        next_node = &nodes[node_value.start];
+    }
+    best_sequence.reverse();
+    best_sequence
+}
+
 #[derive(Debug)]
 pub struct SentencePieceModel {
     pub root: TrieNode,
+    pub model_type: SentencePieceModelType,
+    ///Encodes the characters not covered by any piece as UTF-8 byte pieces (`<0xE4>`) instead of
+    ///unknown tokens
+    pub byte_fallback: bool,
+    ///Indices of the byte pieces (`<0xE4>`), only used by the byte fallback
+    byte_pieces: HashMap<u8, i64>,
+    min_score: f32,
+    ///Normalization described by the `normalizer_spec` of the model, if any
+    pub normalizer: Option<SentencePieceNormalizer>,
 }
 
 impl SentencePieceModel {
     pub fn from_file(path: &str) -> Result<SentencePieceModel, TokenizerError> {
-        let mut f = File::open(path).map_err(|e| {
+        let f = File::open(path).map_err(|e| {
             TokenizerError::FileNotFound(format!("{} vocabulary file not found :{}", path, e))
         })?;
+        SentencePieceModel::from_reader(f)
+    }
+
+    ///Read a SentencePiece model from any reader (e.g. a file or a network stream)
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<SentencePieceModel, TokenizerError> {
         let mut contents = Vec::new();
-        let proto = match f.read_to_end(&mut contents) {
+        let proto = match reader.read_to_end(&mut contents) {
             Ok(_) => match parse_from_bytes::<ModelProto>(contents.as_slice()) {
                 Ok(proto_value) => proto_value,
                 Err(e) => {
@@ -79,23 +248,106 @@ impl SentencePieceModel {
                 return Err(TokenizerError::VocabularyParsingError(e.to_string()));
             }
         };
+        SentencePieceModel::try_from_proto(&proto)
+    }
+
+    ///Read a SentencePiece model from bytes held in memory (e.g. loaded with `include_bytes!`)
+    pub fn from_bytes(bytes: &[u8]) -> Result<SentencePieceModel, TokenizerError> {
+        SentencePieceModel::from_reader(bytes)
+    }
+
+    ///Builds a model from a parsed SentencePiece `ModelProto`, including its `normalizer_spec`.
+    ///Panics for the `WORD` and `CHAR` model types, which are not supported.
+    #[deprecated(note = "use `try_from_proto`, which returns an error for unsupported models")]
+    pub fn from_proto(proto: &ModelProto) -> SentencePieceModel {
+        SentencePieceModel::try_from_proto(proto).unwrap()
+    }
+
+    ///Builds a model from a parsed SentencePiece `ModelProto`, including its `normalizer_spec`.
+    ///Fails for the `WORD` and `CHAR` model types, which are not supported.
+    pub fn try_from_proto(proto: &ModelProto) -> Result<SentencePieceModel, TokenizerError> {
         let root = TrieNode::new("".to_string());
-        let mut vocab = SentencePieceModel { root };
+        let model_type = match proto.get_trainer_spec().get_model_type() {
+            TrainerSpec_ModelType::UNIGRAM => SentencePieceModelType::Unigram,
+            TrainerSpec_ModelType::BPE => SentencePieceModelType::Bpe,
+            model_type => {
+                return Err(TokenizerError::UnsupportedComponent(format!(
+                    "SentencePiece model of type {:?}",
+                    model_type
+                )))
+            }
+        };
+        let normalizer = if proto.has_normalizer_spec() {
+            Some(SentencePieceNormalizer::from_proto(
+                proto.get_normalizer_spec(),
+            )?)
+        } else {
+            None
+        };
+        let mut vocab = SentencePieceModel {
+            root,
+            model_type,
+            byte_fallback: proto.get_trainer_spec().get_byte_fallback(),
+            byte_pieces: HashMap::new(),
+            min_score: f32::MAX,
+            normalizer,
+        };
+        //control, unknown and unused pieces are never matched in the text
         for (idx, piece) in proto.get_pieces().iter().enumerate() {
-            vocab.insert(piece.get_piece(), piece.get_score(), idx as i64);
+            match piece.get_field_type() {
+                ModelProto_SentencePiece_Type::NORMAL
+                | ModelProto_SentencePiece_Type::USER_DEFINED => {
+                    vocab.insert(piece.get_piece(), piece.get_score(), idx as i64)
+                }
+                ModelProto_SentencePiece_Type::BYTE => {
+                    if let Some(byte) = byte_piece_value(piece.get_piece()) {
+                        vocab.byte_pieces.insert(byte, idx as i64);
+                    }
+                }
+                _ => {}
+            }
         }
         Ok(vocab)
     }
 
-    pub fn from_proto(proto: &ModelProto) -> SentencePieceModel {
+    ///Builds a model from a list of pieces and their scores, the index of each piece being its
+    ///position in the list (as for the `pieces` of a SentencePiece `ModelProto`). Byte pieces
+    ///(`<0xE4>`) are only used by the byte fallback.
+    pub fn from_pieces(pieces: &[(&str, f32)]) -> SentencePieceModel {
         let root = TrieNode::new("".to_string());
-        let mut vocab = SentencePieceModel { root };
-        for (idx, piece) in proto.get_pieces().iter().enumerate() {
-            vocab.insert(piece.get_piece(), piece.get_score(), idx as i64);
+        let mut vocab = SentencePieceModel {
+            root,
+            model_type: SentencePieceModelType::Unigram,
+            byte_fallback: false,
+            byte_pieces: HashMap::new(),
+            min_score: f32::MAX,
+            normalizer: None,
+        };
+        for (idx, (piece, score)) in pieces.iter().enumerate() {
+            match byte_piece_value(piece) {
+                Some(byte) => {
+                    vocab.byte_pieces.insert(byte, idx as i64);
+                }
+                None => vocab.insert(piece, *score, idx as i64),
+            }
         }
         vocab
     }
 
+    ///Returns the pieces of the model (nodes of the trie marking the end of a piece), sorted by index
+    pub fn get_pieces(&self) -> Vec<&TrieNode> {
+        let mut pieces = vec![];
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            if node.end {
+                pieces.push(node);
+            }
+            stack.extend(node.children.values());
+        }
+        pieces.sort_by_key(|node| node.index);
+        pieces
+    }
+
     fn insert(&mut self, word: &str, score: f32, index: i64) {
         let char_count = word.chars().count();
         let mut node = &mut self.root;
@@ -114,6 +366,20 @@ impl SentencePieceModel {
                 node.index = index;
             }
         }
+        self.min_score = self.min_score.min(score);
+    }
+
+    ///Returns the node of the trie for a piece of the model
+    fn get_piece(&self, text: &str) -> Option<&TrieNode> {
+        let mut node = &self.root;
+        for character in text.chars() {
+            node = node.children.get(&character)?;
+        }
+        if node.end {
+            Some(node)
+        } else {
+            None
+        }
     }
 
     pub fn common_prefix_search<'a>(&'a self, text: &'a str) -> Vec<&TrieNode> {
@@ -153,9 +419,11 @@ impl SentencePieceModel {
         let mut scores = vec![std::f32::NEG_INFINITY; char_positions.len()];
         scores[0] = 0f32;
 
+        let unknown_score = self.unknown_score();
         for char_start in 0..char_positions.len() - 1 {
-            let matches = self.common_prefix_search(&token.text[char_positions[char_start]..]);
-            for node in matches {
+            let mut has_single_node = false;
+            for node in self.common_prefix_search(&token.text[char_positions[char_start]..]) {
+                has_single_node |= node.len == 1;
                 let local_score = scores[char_start] + node.score;
                 let char_end = char_start + node.len;
                 if local_score > scores[char_end] {
@@ -166,49 +434,381 @@ impl SentencePieceModel {
                         start: char_start,
                         end: char_end,
                         reference_offsets: &token.reference_offsets[char_start..char_end],
+                        unknown: false,
                     });
                     scores[char_end] = local_score;
                 }
             }
-            if scores[char_start + 1] <= std::f32::MIN {
+            //characters not starting any single-character piece compete as unknown nodes, scored
+            //as in the lattice used by `nbest` and `sample`
+            let local_score = scores[char_start] + unknown_score;
+            if !has_single_node && local_score > scores[char_start + 1] {
                 results[char_start + 1] = Some(Node {
                     text: &token.text[char_positions[char_start]..char_positions[char_start + 1]],
-                    score: std::f32::MIN,
+                    score: local_score,
                     index: 0,
                     start: char_start,
                     end: char_start + 1,
                     reference_offsets: &token.reference_offsets[char_start..char_start + 1],
+                    unknown: true,
                 });
-                scores[char_start + 1] = 0f32;
+                scores[char_start + 1] = local_score;
             }
         }
         results
     }
 
-    pub fn decode_backward<'a>(&'a self, nodes: &'a Vec<Option<Node<'a>>>) -> Vec<&'a Node> {
-        let mut best_sequence = vec![];
-        let mut next_node = match nodes.last() {
-            Some(value) => value,
-            None => {
-                return best_sequence;
+    ///Score of the characters not covered by any piece: the lowest piece score minus a penalty
+    fn unknown_score(&self) -> f32 {
+        let min_score = if self.min_score == f32::MAX {
+            0.0
+        } else {
+            self.min_score
+        };
+        min_score - UNKNOWN_PENALTY
+    }
+
+    ///Segments a token into the pieces of the model, using the algorithm of the model type.
+    ///Characters not covered by any piece are returned as `unknown` nodes.
+    pub fn decode<'a>(&'a self, token: TokenRef<'a>) -> Vec<Node<'a>> {
+        match self.model_type {
+            SentencePieceModelType::Unigram => {
+                let nodes = self.decode_forward_token_ref(token);
+                best_path(&nodes).into_iter().copied().collect()
+            }
+            SentencePieceModelType::Bpe => self.decode_bpe(token),
+        }
+    }
+
+    ///Samples a segmentation of a token from the lattice of a unigram model (see
+    ///`SubwordSamplingTokenizer` for the meaning of `nbest_size` and `alpha`). The most likely
+    ///segmentation is returned for BPE models.
+    pub fn sample<'a, R: Rng + ?Sized>(
+        &'a self,
+        token: TokenRef<'a>,
+        nbest_size: i64,
+        alpha: f32,
+        rng: &mut R,
+    ) -> Vec<Node<'a>> {
+        if self.model_type != SentencePieceModelType::Unigram || nbest_size == 0 || nbest_size == 1
+        {
+            let nodes = self.decode_forward_token_ref(token);
+            return match self.model_type {
+                SentencePieceModelType::Unigram => best_path(&nodes).into_iter().copied().collect(),
+                SentencePieceModelType::Bpe => self.decode_bpe(token),
+            };
+        }
+        let mut char_positions = token.text.char_indices().map(|(pos, _)| pos).collect_vec();
+        char_positions.push(token.text.len());
+        let lattice = self.build_lattice(token.text, &char_positions);
+
+        let path = if nbest_size < 0 {
+            //forward filtering: log of the total weight of the paths ending at each position
+            let mut forward = vec![0f32; lattice.len()];
+            for position in 1..lattice.len() {
+                forward[position] = log_sum_exp(
+                    lattice[position]
+                        .iter()
+                        .map(|node| forward[node.start] + alpha * node.score),
+                );
+            }
+            //backward sampling
+            let mut path = vec![];
+            let mut position = lattice.len() - 1;
+            while position > 0 {
+                let log_weights = lattice[position]
+                    .iter()
+                    .map(|node| forward[node.start] + alpha * node.score)
+                    .collect::<Vec<f32>>();
+                let node = lattice[position][sample_index(&log_weights, rng)];
+                path.push(node);
+                position = node.start;
+            }
+            path.reverse();
+            path
+        } else {
+            let mut nbest = Self::nbest_paths(&lattice, nbest_size as usize);
+            let log_weights = nbest
+                .iter()
+                .map(|(_, score)| alpha * score)
+                .collect::<Vec<f32>>();
+            if nbest.is_empty() {
+                vec![]
+            } else {
+                nbest.swap_remove(sample_index(&log_weights, rng)).0
             }
         };
+        path.iter()
+            .map(|node| self.lattice_node_to_node(token, &char_positions, node))
+            .collect()
+    }
 
-        while next_node.is_some() {
-            let node_value = next_node.as_ref().unwrap();
-            best_sequence.push(node_value);
-            next_node = &nodes[node_value.start];
+    ///Returns the `n` most likely segmentations of a token with their scores (sum of the scores
+    ///of the pieces), from the most likely. The offsets of the tokens are set from the reference
+    ///offsets of the token. Unlike `sample`, the result is deterministic. BPE models only have a
+    ///single segmentation.
+    pub fn nbest(&self, token: TokenRef, n: usize) -> Vec<(Vec<Token>, f32)> {
+        if n == 0 {
+            return vec![];
+        }
+        match self.model_type {
+            SentencePieceModelType::Unigram => {
+                let mut char_positions =
+                    token.text.char_indices().map(|(pos, _)| pos).collect_vec();
+                char_positions.push(token.text.len());
+                let lattice = self.build_lattice(token.text, &char_positions);
+                Self::nbest_paths(&lattice, n)
+                    .into_iter()
+                    .map(|(path, score)| {
+                        let nodes = path
+                            .iter()
+                            .map(|node| self.lattice_node_to_node(token, &char_positions, node))
+                            .collect::<Vec<Node>>();
+                        (self.nodes_to_tokens_with_offsets(&nodes), score)
+                    })
+                    .collect()
+            }
+            SentencePieceModelType::Bpe => {
+                let nodes = self.decode_bpe(token);
+                let score = nodes.iter().map(|node| node.score).sum();
+                vec![(self.nodes_to_tokens_with_offsets(&nodes), score)]
+            }
         }
-        best_sequence.reverse();
-        best_sequence
+    }
+
+    ///Converts nodes to tokens, setting the offsets of the tokens from their reference offsets
+    fn nodes_to_tokens_with_offsets(&self, nodes: &[Node]) -> Vec<Token> {
+        let mut tokens = self.parse_nodes_to_tokens(nodes.iter().collect());
+        for token in tokens.iter_mut() {
+            if let (Some(begin), Some(end)) = (
+                token.reference_offsets.first(),
+                token.reference_offsets.last(),
+            ) {
+                token.offset = Offset::new(*begin, *end + 1);
+            }
+        }
+        tokens
+    }
+
+    ///Builds the lattice of all the segmentations of a text: the nodes ending at each character
+    ///position. Characters not starting any single-character piece are added as unknown nodes.
+    fn build_lattice(&self, text: &str, char_positions: &[usize]) -> Vec<Vec<LatticeNode>> {
+        let char_count = char_positions.len() - 1;
+        let unknown_score = self.unknown_score();
+        let mut lattice = vec![vec![]; char_count + 1];
+        for start in 0..char_count {
+            let mut has_single_node = false;
+            for node in self.common_prefix_search(&text[char_positions[start]..]) {
+                has_single_node |= node.len == 1;
+                lattice[start + node.len].push(LatticeNode {
+                    start,
+                    end: start + node.len,
+                    score: node.score,
+                    index: node.index,
+                    unknown: false,
+                });
+            }
+            if !has_single_node {
+                lattice[start + 1].push(LatticeNode {
+                    start,
+                    end: start + 1,
+                    score: unknown_score,
+                    index: 0,
+                    unknown: true,
+                });
+            }
+        }
+        lattice
+    }
+
+    ///Returns the `n` most likely paths of a lattice with their scores, from the most likely.
+    ///Paths are expanded from the end of the text (A* search), using the score of the best path
+    ///from the beginning of the text as heuristic. The agenda is pruned to its best hypotheses
+    ///when it grows beyond `MAX_AGENDA_SIZE`, bounding the memory used for long texts.
+    fn nbest_paths(lattice: &[Vec<LatticeNode>], n: usize) -> Vec<(Vec<LatticeNode>, f32)> {
+        let mut best_scores = vec![f32::NEG_INFINITY; lattice.len()];
+        best_scores[0] = 0.0;
+        for position in 1..lattice.len() {
+            for node in &lattice[position] {
+                best_scores[position] =
+                    best_scores[position].max(best_scores[node.start] + node.score);
+            }
+        }
+
+        let mut results = vec![];
+        let mut agenda = BinaryHeap::new();
+        agenda.push(Hypothesis {
+            priority: best_scores[lattice.len() - 1],
+            score: 0.0,
+            position: lattice.len() - 1,
+            nodes: vec![],
+        });
+        while let Some(hypothesis) = agenda.pop() {
+            if hypothesis.position == 0 {
+                let mut nodes = hypothesis.nodes;
+                nodes.reverse();
+                results.push((nodes, hypothesis.score));
+                if results.len() == n {
+                    break;
+                }
+                continue;
+            }
+            for node in &lattice[hypothesis.position] {
+                let score = hypothesis.score + node.score;
+                let mut nodes = hypothesis.nodes.clone();
+                nodes.push(*node);
+                agenda.push(Hypothesis {
+                    priority: score + best_scores[node.start],
+                    score,
+                    position: node.start,
+                    nodes,
+                });
+            }
+            if agenda.len() >= MAX_AGENDA_SIZE {
+                let mut hypotheses = agenda.into_sorted_vec();
+                hypotheses.drain(..hypotheses.len() - MIN_AGENDA_SIZE);
+                agenda = BinaryHeap::from(hypotheses);
+            }
+        }
+        results
+    }
+
+    fn lattice_node_to_node<'a>(
+        &'a self,
+        token: TokenRef<'a>,
+        char_positions: &[usize],
+        node: &LatticeNode,
+    ) -> Node<'a> {
+        Node {
+            text: &token.text[char_positions[node.start]..char_positions[node.end]],
+            score: node.score,
+            index: node.index,
+            start: node.start,
+            end: node.end,
+            reference_offsets: &token.reference_offsets[node.start..node.end],
+            unknown: node.unknown,
+        }
+    }
+
+    ///Segments a token and converts the pieces to tokens
+    pub fn tokenize(&self, token: TokenRef) -> Vec<Token> {
+        let nodes = self.decode(token);
+        self.parse_nodes_to_tokens(nodes.iter().collect())
+    }
+
+    ///Samples the segmentation of a token (see `sample`) and converts the pieces to tokens
+    pub fn tokenize_with_sampling<R: Rng + ?Sized>(
+        &self,
+        token: TokenRef,
+        nbest_size: i64,
+        alpha: f32,
+        rng: &mut R,
+    ) -> Vec<Token> {
+        let nodes = self.sample(token, nbest_size, alpha, rng);
+        self.parse_nodes_to_tokens(nodes.iter().collect())
+    }
+
+    ///BPE segmentation: starting from the characters of the token, the pair of adjacent symbols
+    ///forming the piece with the highest score is merged until no pair forms a piece.
+    pub fn decode_bpe<'a>(&'a self, token: TokenRef<'a>) -> Vec<Node<'a>> {
+        let mut byte_positions = token.text.char_indices().map(|(pos, _)| pos).collect_vec();
+        byte_positions.push(token.text.len());
+        let char_count = byte_positions.len() - 1;
+        //(start, end) character positions of the symbols, empty once merged into their left neighbour
+        let mut symbols: Vec<(usize, usize)> = (0..char_count).map(|idx| (idx, idx + 1)).collect();
+        let mut previous: Vec<Option<usize>> =
+            (0..char_count).map(|idx| idx.checked_sub(1)).collect();
+        let mut next: Vec<Option<usize>> = (0..char_count)
+            .map(|idx| Some(idx + 1).filter(|&next| next < char_count))
+            .collect();
+
+        let text =
+            |(start, end): (usize, usize)| &token.text[byte_positions[start]..byte_positions[end]];
+        let mut agenda = BinaryHeap::new();
+        let add_candidate = |agenda: &mut BinaryHeap<BpeCandidate>,
+                             symbols: &[(usize, usize)],
+                             left: usize,
+                             right: Option<usize>| {
+            if let Some(right) = right {
+                if let Some(node) = self.get_piece(text((symbols[left].0, symbols[right].1))) {
+                    agenda.push(BpeCandidate {
+                        score: node.score,
+                        left,
+                        right,
+                        size: symbols[right].1 - symbols[left].0,
+                    });
+                }
+            }
+        };
+        for (idx, &right) in next.iter().enumerate() {
+            add_candidate(&mut agenda, &symbols, idx, right);
+        }
+
+        while let Some(candidate) = agenda.pop() {
+            let (left, right) = (symbols[candidate.left], symbols[candidate.right]);
+            //skips the candidates invalidated by a previous merge
+            if left.0 == left.1 || right.0 == right.1 || right.1 - left.0 != candidate.size {
+                continue;
+            }
+            symbols[candidate.left] = (left.0, right.1);
+            symbols[candidate.right] = (right.1, right.1);
+            next[candidate.left] = next[candidate.right];
+            if let Some(following) = next[candidate.right] {
+                previous[following] = Some(candidate.left);
+            }
+            if let Some(preceding) = previous[candidate.left] {
+                add_candidate(&mut agenda, &symbols, preceding, Some(candidate.left));
+            }
+            add_candidate(&mut agenda, &symbols, candidate.left, next[candidate.left]);
+        }
+
+        let mut nodes = vec![];
+        let mut symbol = if char_count > 0 { Some(0) } else { None };
+        while let Some(idx) = symbol {
+            let (start, end) = symbols[idx];
+            let piece = self.get_piece(text((start, end)));
+            nodes.push(Node {
+                text: text((start, end)),
+                score: piece.map_or(self.unknown_score(), |node| node.score),
+                index: piece.map_or(0, |node| node.index),
+                start,
+                end,
+                reference_offsets: &token.reference_offsets[start..end],
+                unknown: piece.is_none(),
+            });
+            symbol = next[idx];
+        }
+        nodes
+    }
+
+    pub fn decode_backward<'a>(&'a self, nodes: &'a Vec<Option<Node<'a>>>) -> Vec<&'a Node> {
+        best_path(nodes)
     }
 
     pub fn parse_nodes_to_tokens(&self, nodes: Vec<&Node>) -> Vec<Token> {
         let mut output: Vec<Token> = Vec::with_capacity(nodes.len() + 1);
         let mut is_prev_unknown = false;
         for node in nodes {
+            // Encode unknown characters as bytes for models trained with byte fallback
+            if self.byte_fallback && node.unknown && self.has_byte_pieces(node.text) {
+                for (character, reference_offset) in
+                    node.text.chars().zip(node.reference_offsets.iter())
+                {
+                    let mut buffer = [0; 4];
+                    for byte in character.encode_utf8(&mut buffer).bytes() {
+                        output.push(Token {
+                            text: format!("<0x{:02X}>", byte),
+                            offset: Offset { begin: 0, end: 0 },
+                            reference_offsets: vec![*reference_offset],
+                            mask: Default::default(),
+                        });
+                    }
+                }
+                is_prev_unknown = false;
+                continue;
+            }
             // Group unknown tokens
-            if is_prev_unknown & (node.index == 0) {
+            if is_prev_unknown & node.unknown {
                 let prev_token = output.last().unwrap();
                 let mut text = prev_token.text.clone();
                 text.push_str(node.text);
@@ -230,12 +830,17 @@ impl SentencePieceModel {
                     mask: Default::default(),
                 });
             }
-            is_prev_unknown = node.index == 0;
+            is_prev_unknown = node.unknown;
         }
         self.populate_masks(output.as_mut_slice(), '\u{2581}');
         output
     }
 
+    fn has_byte_pieces(&self, text: &str) -> bool {
+        text.bytes()
+            .all(|byte| self.byte_pieces.contains_key(&byte))
+    }
+
     pub fn populate_masks(&self, tokens: &mut [Token], whitespace_token: char) {
         let mut previous_mask = Mask::None;
         for token in tokens {
@@ -272,13 +877,15 @@ impl SentencePieceModel {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SentencePieceVocab {
     pub values: HashMap<String, i64>,
     pub indices: HashMap<i64, String>,
-    pub unknown_value: &'static str,
+    pub special_token_map: SpecialTokenMap,
     pub special_values: HashMap<String, i64>,
     pub special_indices: HashMap<i64, String>,
+    pub added_values: HashMap<String, i64>,
+    pub added_tokens: HashMap<String, AddedToken>,
 }
 
 impl SentencePieceVocab {
@@ -307,8 +914,19 @@ impl Vocab for SentencePieceVocab {
         "<unk>"
     }
 
-    fn get_unknown_value(&self) -> &'static str {
-        "<unk>"
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap::new(SentencePieceVocab::unknown_value())
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
+    }
+
+    fn get_pad_value(&self) -> &str {
+        match &self.special_token_map.pad_token {
+            Some(pad_value) => pad_value,
+            None => SentencePieceVocab::pad_value(),
+        }
     }
 
     fn values(&self) -> &HashMap<String, i64> {
@@ -327,18 +945,20 @@ impl Vocab for SentencePieceVocab {
         &self.special_indices
     }
 
-    fn from_file(path: &str) -> Result<SentencePieceVocab, TokenizerError> {
-        let mut f = match File::open(path) {
-            Ok(file) => file,
-            Err(_) => {
-                return Err(TokenizerError::FileNotFound(format!(
-                    "{} vocabulary file not found",
-                    path
-                )));
-            }
-        };
+    fn added_values(&self) -> &HashMap<String, i64> {
+        &self.added_values
+    }
+
+    fn added_tokens(&self) -> &HashMap<String, AddedToken> {
+        &self.added_tokens
+    }
+
+    fn from_reader_with_special_token_map<R: Read>(
+        mut reader: R,
+        special_token_map: SpecialTokenMap,
+    ) -> Result<SentencePieceVocab, TokenizerError> {
         let mut contents = Vec::new();
-        let proto = match f.read_to_end(&mut contents) {
+        let proto = match reader.read_to_end(&mut contents) {
             Ok(_) => match parse_from_bytes::<ModelProto>(contents.as_slice()) {
                 Ok(proto_value) => proto_value,
                 Err(e) => {
@@ -355,12 +975,13 @@ impl Vocab for SentencePieceVocab {
             values.insert(piece.get_piece().to_owned(), idx as i64);
         }
 
+        let mut added_tokens = HashMap::new();
         let mut special_values = HashMap::new();
-        let unknown_value = SentencePieceVocab::unknown_value();
-        SentencePieceVocab::_register_as_special_value(
-            unknown_value,
+        SentencePieceVocab::_register_special_token_map(
+            &special_token_map,
             &values,
             &mut special_values,
+            &mut added_tokens,
         )?;
 
         let indices = swap_key_values(&values);
@@ -369,18 +990,32 @@ impl Vocab for SentencePieceVocab {
         Ok(SentencePieceVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map,
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens,
         })
     }
 
+    fn add_tokens_with_options(&mut self, tokens: &[AddedToken]) -> usize {
+        Self::_add_tokens(
+            tokens,
+            &mut self.values,
+            &mut self.indices,
+            &mut self.special_values,
+            &mut self.special_indices,
+            &mut self.added_values,
+            &mut self.added_tokens,
+        )
+    }
+
     fn token_to_id(&self, token: &str) -> i64 {
         self._token_to_id(
             token,
             &self.values,
             &self.special_values,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 
@@ -389,7 +1024,349 @@ impl Vocab for SentencePieceVocab {
             &id,
             &self.indices,
             &self.special_indices,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 }
+
+//==============================
+// Unit tests
+//==============================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocessing::tokenizer::tokenization_utils::merge_byte_pieces;
+    use crate::preprocessing::vocab::sentencepiece_proto::sentencepiece_model::{
+        ModelProto_SentencePiece, ModelProto_SentencePiece_Type, TrainerSpec,
+    };
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashSet;
+
+    fn test_model(model_type: SentencePieceModelType) -> SentencePieceModel {
+        let mut model = SentencePieceModel::from_pieces(&[
+            ("<unk>", 0.0),
+            ("a", -1.0),
+            ("b", -1.0),
+            ("c", -1.0),
+            ("ab", -2.0),
+            ("bc", -1.5),
+            ("abc", -3.0),
+            ("aa", -2.0),
+        ]);
+        model.model_type = model_type;
+        model
+    }
+
+    fn decode(model: &SentencePieceModel, text: &str) -> Vec<(String, i64)> {
+        let reference_offsets = (0..text.chars().count() as OffsetSize).collect::<Vec<_>>();
+        model
+            .decode(TokenRef::new(text, &reference_offsets))
+            .iter()
+            .map(|node| (node.text.to_owned(), node.index))
+            .collect()
+    }
+
+    #[test]
+    fn test_bpe_segmentation() {
+        //        Given
+        let bpe_model = test_model(SentencePieceModelType::Bpe);
+        let unigram_model = test_model(SentencePieceModelType::Unigram);
+
+        //        When & Then
+        assert_eq!(
+            decode(&bpe_model, "abcd"),
+            vec![("abc".to_owned(), 6), ("d".to_owned(), 0)]
+        );
+        assert_eq!(
+            decode(&unigram_model, "abc"),
+            vec![("a".to_owned(), 1), ("bc".to_owned(), 5)]
+        );
+        assert_eq!(
+            decode(&bpe_model, "aaa"),
+            vec![("aa".to_owned(), 7), ("a".to_owned(), 1)]
+        );
+        assert!(decode(&bpe_model, "").is_empty());
+    }
+
+    fn sample(
+        model: &SentencePieceModel,
+        text: &str,
+        nbest_size: i64,
+        alpha: f32,
+        rng: &mut StdRng,
+    ) -> Vec<String> {
+        let reference_offsets = (0..text.chars().count() as OffsetSize).collect::<Vec<_>>();
+        model
+            .sample(
+                TokenRef::new(text, &reference_offsets),
+                nbest_size,
+                alpha,
+                rng,
+            )
+            .iter()
+            .map(|node| node.text.to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_subword_sampling() {
+        //        Given
+        let model = test_model(SentencePieceModelType::Unigram);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        //        When
+        let sample_set = |nbest_size: i64, alpha: f32, rng: &mut StdRng| {
+            (0..200)
+                .map(|_| sample(&model, "abc", nbest_size, alpha, rng))
+                .collect::<HashSet<Vec<String>>>()
+        };
+        let viterbi = sample_set(1, 0.0, &mut rng);
+        let all_segmentations = sample_set(-1, 0.0, &mut rng);
+        let two_best = sample_set(2, 0.0, &mut rng);
+        let sharp = sample_set(-1, 100.0, &mut rng);
+
+        //        Then
+        let best = vec!["a".to_owned(), "bc".to_owned()];
+        assert_eq!(viterbi, [best.clone()].iter().cloned().collect());
+        assert_eq!(all_segmentations.len(), 4);
+        assert!(all_segmentations
+            .iter()
+            .all(|segmentation| segmentation.concat() == "abc"));
+        assert_eq!(two_best.len(), 2);
+        assert!(two_best.contains(&best));
+        assert_eq!(sharp, [best].iter().cloned().collect());
+        assert_eq!(
+            sample(&model, "bcd", -1, 100.0, &mut rng),
+            vec!["bc".to_owned(), "d".to_owned()]
+        );
+    }
+
+    #[test]
+    fn test_subword_sampling_seed() {
+        //        Given
+        let model = test_model(SentencePieceModelType::Unigram);
+        let reference_offsets = (0..6).collect::<Vec<OffsetSize>>();
+        let token = TokenRef::new("abcabc", &reference_offsets);
+        let mut segmentations = vec![];
+
+        //        When
+        for _ in 0..2 {
+            let mut rng = StdRng::seed_from_u64(42);
+            segmentations.push(
+                (0..20)
+                    .map(|_| {
+                        model
+                            .tokenize_with_sampling(token, -1, 0.5, &mut rng)
+                            .into_iter()
+                            .map(|token| token.text)
+                            .collect::<Vec<String>>()
+                    })
+                    .collect::<Vec<Vec<String>>>(),
+            );
+        }
+
+        //        Then
+        assert_eq!(segmentations[0], segmentations[1]);
+        assert!(segmentations[0].iter().any(|s| s != &segmentations[0][0]));
+    }
+
+    #[test]
+    fn test_nbest_segmentations() {
+        //        Given
+        let model = test_model(SentencePieceModelType::Unigram);
+        let reference_offsets = (10..13).collect::<Vec<OffsetSize>>();
+        let token = TokenRef::new("abc", &reference_offsets);
+
+        //        When
+        let nbest = model.nbest(token, 10);
+        let segmentations = nbest
+            .iter()
+            .map(|(tokens, _)| {
+                tokens
+                    .iter()
+                    .map(|token| token.text.as_str())
+                    .collect::<Vec<&str>>()
+            })
+            .collect::<Vec<Vec<&str>>>();
+
+        //        Then
+        assert_eq!(nbest.len(), 4);
+        assert_eq!(segmentations[0], vec!["a", "bc"]);
+        assert!((nbest[0].1 + 2.5).abs() < 1e-6);
+        assert!(nbest[1..]
+            .iter()
+            .all(|(_, score)| (score + 3.0).abs() < 1e-6));
+        assert!(segmentations.contains(&vec!["a", "b", "c"]));
+        assert!(segmentations.contains(&vec!["ab", "c"]));
+        assert!(segmentations.contains(&vec!["abc"]));
+        assert_eq!(nbest[0].0[1].offset, Offset::new(11, 13));
+        assert_eq!(nbest[0].0[1].reference_offsets, vec![11, 12]);
+        assert_eq!(model.nbest(token, 10), nbest);
+        assert_eq!(model.nbest(token, 2)[..], nbest[..2]);
+        assert!(model.nbest(token, 0).is_empty());
+        assert_eq!(
+            test_model(SentencePieceModelType::Bpe)
+                .nbest(token, 10)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_nbest_unknown_characters() {
+        //        Given
+        let unigram_model = test_model(SentencePieceModelType::Unigram);
+        let bpe_model = test_model(SentencePieceModelType::Bpe);
+        let texts = ["abdc", "dd", "dab", "d"];
+
+        //        When & Then
+        for text in texts.iter() {
+            let reference_offsets = (0..text.chars().count() as OffsetSize).collect::<Vec<_>>();
+            let token = TokenRef::new(text, &reference_offsets);
+            for model in [&unigram_model, &bpe_model].iter() {
+                let nbest = model.nbest(token, 3);
+                let texts_and_offsets = |tokens: Vec<Token>| {
+                    tokens
+                        .into_iter()
+                        .map(|token| (token.text, token.reference_offsets))
+                        .collect::<Vec<(String, Vec<OffsetSize>)>>()
+                };
+                assert_eq!(
+                    texts_and_offsets(nbest[0].0.clone()),
+                    texts_and_offsets(model.tokenize(token))
+                );
+                assert!(nbest[0].1.is_finite());
+            }
+        }
+        let reference_offsets = (0..4).collect::<Vec<OffsetSize>>();
+        assert_eq!(
+            decode(&unigram_model, "abdc"),
+            vec![
+                ("ab".to_owned(), 4),
+                ("d".to_owned(), 0),
+                ("c".to_owned(), 3)
+            ]
+        );
+        assert!(
+            (unigram_model.nbest(TokenRef::new("abdc", &reference_offsets), 1)[0].1 + 16.0).abs()
+                < 1e-6
+        );
+    }
+
+    #[test]
+    fn test_model_type_from_proto() {
+        //        Given
+        let mut proto = ModelProto::new();
+        let mut piece = ModelProto_SentencePiece::new();
+        piece.set_piece("a".to_owned());
+        proto.mut_pieces().push(piece);
+        let mut trainer_spec = TrainerSpec::new();
+
+        //        When & Then
+        trainer_spec.set_model_type(TrainerSpec_ModelType::BPE);
+        proto.set_trainer_spec(trainer_spec.clone());
+        assert_eq!(
+            SentencePieceModel::try_from_proto(&proto)
+                .unwrap()
+                .model_type,
+            SentencePieceModelType::Bpe
+        );
+        trainer_spec.set_model_type(TrainerSpec_ModelType::WORD);
+        proto.set_trainer_spec(trainer_spec);
+        assert!(matches!(
+            SentencePieceModel::try_from_proto(&proto),
+            Err(TokenizerError::UnsupportedComponent(_))
+        ));
+    }
+
+    #[test]
+    fn test_piece_types_from_proto() {
+        //        Given
+        let mut proto = ModelProto::new();
+        for (text, piece_type) in [
+            ("<unk>", ModelProto_SentencePiece_Type::UNKNOWN),
+            ("<s>", ModelProto_SentencePiece_Type::CONTROL),
+            ("<0xE2>", ModelProto_SentencePiece_Type::BYTE),
+            ("<0x82>", ModelProto_SentencePiece_Type::BYTE),
+            ("<0xAC>", ModelProto_SentencePiece_Type::BYTE),
+            ("<", ModelProto_SentencePiece_Type::NORMAL),
+            ("s", ModelProto_SentencePiece_Type::NORMAL),
+            (">", ModelProto_SentencePiece_Type::NORMAL),
+            ("<sep>", ModelProto_SentencePiece_Type::USER_DEFINED),
+        ]
+        .iter()
+        {
+            let mut piece = ModelProto_SentencePiece::new();
+            piece.set_piece(text.to_string());
+            piece.set_field_type(*piece_type);
+            proto.mut_pieces().push(piece);
+        }
+        let mut model = SentencePieceModel::try_from_proto(&proto).unwrap();
+        model.byte_fallback = true;
+        let text = "<s><sep>\u{20AC}";
+        let reference_offsets = (0..text.chars().count() as OffsetSize).collect::<Vec<_>>();
+
+        //        When
+        let tokens = model.tokenize(TokenRef::new(text, &reference_offsets));
+        let nodes = model.decode(TokenRef::new(text, &reference_offsets));
+
+        //        Then
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| token.text.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["<", "s", ">", "<sep>", "<0xE2>", "<0x82>", "<0xAC>"]
+        );
+        assert_eq!(
+            nodes
+                .iter()
+                .map(|node| (node.index, node.unknown))
+                .collect::<Vec<(i64, bool)>>(),
+            vec![(5, false), (6, false), (7, false), (8, false), (0, true)]
+        );
+        assert_eq!(
+            model
+                .get_pieces()
+                .iter()
+                .map(|node| node.text.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["<", "s", ">", "<sep>"]
+        );
+    }
+
+    #[test]
+    fn test_byte_fallback() {
+        //        Given
+        let mut model = SentencePieceModel::from_pieces(&[
+            ("<unk>", 0.0),
+            ("a", -1.0),
+            ("b", -1.0),
+            ("<0xE2>", -5.0),
+            ("<0x82>", -5.0),
+            ("<0xAC>", -5.0),
+        ]);
+        model.byte_fallback = true;
+        let text = "a\u{20AC}b";
+        let reference_offsets = (0..text.chars().count() as OffsetSize).collect::<Vec<_>>();
+
+        //        When
+        let tokens = model.tokenize(TokenRef::new(text, &reference_offsets));
+        let texts = tokens
+            .iter()
+            .map(|token| token.text.clone())
+            .collect::<Vec<String>>();
+
+        //        Then
+        assert_eq!(texts, vec!["a", "<0xE2>", "<0x82>", "<0xAC>", "b"]);
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| token.reference_offsets.clone())
+                .collect::<Vec<_>>(),
+            vec![vec![0], vec![1], vec![1], vec![1], vec![2]]
+        );
+        assert_eq!(merge_byte_pieces(texts), vec!["a", "\u{20AC}", "b"]);
+    }
+}
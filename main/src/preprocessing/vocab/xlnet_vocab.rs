@@ -11,21 +11,22 @@
 // limitations under the License.
 
 use crate::preprocessing::error::TokenizerError;
-use crate::preprocessing::vocab::base_vocab::swap_key_values;
+use crate::preprocessing::vocab::base_vocab::{swap_key_values, AddedToken, SpecialTokenMap};
 use crate::preprocessing::vocab::sentencepiece_proto::sentencepiece_model::ModelProto;
 use crate::Vocab;
 use protobuf::parse_from_bytes;
 use std::collections::HashMap;
-use std::fs::File;
 use std::io::Read;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct XLNetVocab {
     pub values: HashMap<String, i64>,
     pub indices: HashMap<i64, String>,
-    pub unknown_value: &'static str,
+    pub special_token_map: SpecialTokenMap,
     pub special_values: HashMap<String, i64>,
     pub special_indices: HashMap<i64, String>,
+    pub added_values: HashMap<String, i64>,
+    pub added_tokens: HashMap<String, AddedToken>,
 }
 
 impl XLNetVocab {
@@ -60,8 +61,25 @@ impl Vocab for XLNetVocab {
         "<unk>"
     }
 
-    fn get_unknown_value(&self) -> &'static str {
-        "<unk>"
+    fn default_special_token_map() -> SpecialTokenMap {
+        SpecialTokenMap {
+            unk_token: XLNetVocab::unknown_value().to_owned(),
+            pad_token: Some(XLNetVocab::pad_value().to_owned()),
+            bos_token: Some(XLNetVocab::bos_value().to_owned()),
+            sep_token: Some(XLNetVocab::sep_value().to_owned()),
+            cls_token: Some(XLNetVocab::cls_value().to_owned()),
+            eos_token: Some(XLNetVocab::eos_value().to_owned()),
+            mask_token: Some(XLNetVocab::mask_value().to_owned()),
+            additional_special_tokens: vec![
+                XLNetVocab::eop_value().to_owned(),
+                XLNetVocab::eod_value().to_owned(),
+            ],
+            token_options: HashMap::new(),
+        }
+    }
+
+    fn special_token_map(&self) -> &SpecialTokenMap {
+        &self.special_token_map
     }
 
     fn values(&self) -> &HashMap<String, i64> {
@@ -80,12 +98,20 @@ impl Vocab for XLNetVocab {
         &self.special_indices
     }
 
-    fn from_file(path: &str) -> Result<XLNetVocab, TokenizerError> {
-        let mut f = File::open(path).map_err(|e| {
-            TokenizerError::FileNotFound(format!("{} vocabulary file not found :{}", path, e))
-        })?;
+    fn added_values(&self) -> &HashMap<String, i64> {
+        &self.added_values
+    }
+
+    fn added_tokens(&self) -> &HashMap<String, AddedToken> {
+        &self.added_tokens
+    }
+
+    fn from_reader_with_special_token_map<R: Read>(
+        mut reader: R,
+        special_token_map: SpecialTokenMap,
+    ) -> Result<XLNetVocab, TokenizerError> {
         let mut contents = Vec::new();
-        let proto = match f.read_to_end(&mut contents) {
+        let proto = match reader.read_to_end(&mut contents) {
             Ok(_) => match parse_from_bytes::<ModelProto>(contents.as_slice()) {
                 Ok(proto_value) => proto_value,
                 Err(e) => {
@@ -102,33 +128,24 @@ impl Vocab for XLNetVocab {
             values.insert(piece.get_piece().to_owned(), idx as i64);
         }
 
-        let mut special_values = HashMap::new();
-        let unknown_value = XLNetVocab::unknown_value();
-        XLNetVocab::_register_as_special_value(unknown_value, &values, &mut special_values)?;
-
-        let bos_value = XLNetVocab::bos_value();
-        XLNetVocab::_register_as_special_value(bos_value, &values, &mut special_values)?;
-
-        let eos_value = XLNetVocab::eos_value();
-        XLNetVocab::_register_as_special_value(eos_value, &values, &mut special_values)?;
-
-        let cls_value = XLNetVocab::cls_value();
-        XLNetVocab::_register_as_special_value(cls_value, &values, &mut special_values)?;
-
-        let mask_value = XLNetVocab::mask_value();
-        XLNetVocab::_register_as_special_value(mask_value, &values, &mut special_values)?;
-
-        let pad_value = XLNetVocab::pad_value();
-        XLNetVocab::_register_as_special_value(pad_value, &values, &mut special_values)?;
-
-        let sep_value = XLNetVocab::sep_value();
-        XLNetVocab::_register_as_special_value(sep_value, &values, &mut special_values)?;
-
-        let eop_value = XLNetVocab::eop_value();
-        XLNetVocab::_register_as_special_value(eop_value, &values, &mut special_values)?;
+        let mut added_tokens = HashMap::new();
+        if let Some(mask_value) = &special_token_map.mask_token {
+            added_tokens.insert(
+                mask_value.clone(),
+                AddedToken {
+                    lstrip: true,
+                    ..AddedToken::new(mask_value, true)
+                },
+            );
+        }
 
-        let eod_value = XLNetVocab::eod_value();
-        XLNetVocab::_register_as_special_value(eod_value, &values, &mut special_values)?;
+        let mut special_values = HashMap::new();
+        XLNetVocab::_register_special_token_map(
+            &special_token_map,
+            &values,
+            &mut special_values,
+            &mut added_tokens,
+        )?;
 
         let indices = swap_key_values(&values);
         let special_indices = swap_key_values(&special_values);
@@ -136,18 +153,32 @@ impl Vocab for XLNetVocab {
         Ok(XLNetVocab {
             values,
             indices,
-            unknown_value,
+            special_token_map,
             special_values,
             special_indices,
+            added_values: HashMap::new(),
+            added_tokens,
         })
     }
 
+    fn add_tokens_with_options(&mut self, tokens: &[AddedToken]) -> usize {
+        Self::_add_tokens(
+            tokens,
+            &mut self.values,
+            &mut self.indices,
+            &mut self.special_values,
+            &mut self.special_indices,
+            &mut self.added_values,
+            &mut self.added_tokens,
+        )
+    }
+
     fn token_to_id(&self, token: &str) -> i64 {
         self._token_to_id(
             token,
             &self.values,
             &self.special_values,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 
@@ -156,7 +187,7 @@ impl Vocab for XLNetVocab {
             &id,
             &self.indices,
             &self.special_indices,
-            &self.unknown_value,
+            &self.special_token_map.unk_token,
         )
     }
 }
@@ -1,5 +1,5 @@
 use rust_tokenizers::preprocessing::tokenizer::base_tokenizer::Offset;
-use rust_tokenizers::{TokenizedInput, Tokenizer, TruncationStrategy};
+use rust_tokenizers::{PaddingOptions, TokenizedInput, Tokenizer, TruncationStrategy};
 
 mod test_utils;
 
@@ -44,8 +44,15 @@ fn test_albert_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 38, end: 42 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![2, 5712, 184, 48, 129, 164, 20, 2853, 1333, 13, 1, 13, 60, 3],
@@ -69,8 +76,15 @@ fn test_albert_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 40, end: 41 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -105,8 +119,15 @@ fn test_albert_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 28, end: 31 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -154,8 +175,15 @@ fn test_albert_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 44, end: 45 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -200,8 +228,15 @@ fn test_albert_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 44, end: 45 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
     ]
     .to_vec();
@@ -211,6 +246,7 @@ fn test_albert_tokenization() -> anyhow::Result<()> {
         128,
         &TruncationStrategy::LongestFirst,
         0,
+        &PaddingOptions::default(),
     );
 
     for (_idx, (predicted, expected)) in output.iter().zip(expected_results.iter()).enumerate() {
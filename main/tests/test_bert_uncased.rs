@@ -1,5 +1,7 @@
 use rust_tokenizers::preprocessing::tokenizer::base_tokenizer::Offset;
-use rust_tokenizers::{BertTokenizer, TokenizedInput, Tokenizer, TruncationStrategy, Vocab};
+use rust_tokenizers::{
+    BertTokenizer, PaddingOptions, TokenizedInput, Tokenizer, TruncationStrategy, Vocab,
+};
 use std::sync::Arc;
 
 mod test_utils;
@@ -51,8 +53,15 @@ fn test_bert_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 38, end: 42 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -75,8 +84,15 @@ fn test_bert_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 40, end: 41 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -107,8 +123,15 @@ fn test_bert_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 30, end: 31 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -143,8 +166,15 @@ fn test_bert_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 46, end: 47 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -175,8 +205,15 @@ fn test_bert_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 39, end: 40 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -209,8 +246,15 @@ fn test_bert_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 45, end: 46 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
     ]
     .to_vec();
@@ -220,6 +264,7 @@ fn test_bert_tokenization() -> anyhow::Result<()> {
         128,
         &TruncationStrategy::LongestFirst,
         0,
+        &PaddingOptions::default(),
     );
 
     for (_idx, (predicted, expected)) in output.iter().zip(expected_results.iter()).enumerate() {
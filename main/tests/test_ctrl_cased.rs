@@ -1,5 +1,7 @@
 use rust_tokenizers::preprocessing::tokenizer::base_tokenizer::Offset;
-use rust_tokenizers::{CtrlTokenizer, TokenizedInput, Tokenizer, TruncationStrategy};
+use rust_tokenizers::{
+    CtrlTokenizer, PaddingOptions, TokenizedInput, Tokenizer, TruncationStrategy,
+};
 
 mod test_utils;
 
@@ -53,8 +55,15 @@ fn test_ctrl_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 37, end: 39 }),
                 Some(Offset { begin: 39, end: 42 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![192851, 162, 32, 57, 213, 113421, 13389, 246532, 415],
@@ -73,8 +82,15 @@ fn test_ctrl_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 38, end: 39 }),
                 Some(Offset { begin: 40, end: 41 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -105,8 +121,15 @@ fn test_ctrl_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 28, end: 29 }),
                 Some(Offset { begin: 29, end: 31 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -139,8 +162,15 @@ fn test_ctrl_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 44, end: 45 }),
                 Some(Offset { begin: 45, end: 47 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -171,8 +201,15 @@ fn test_ctrl_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 37, end: 38 }),
                 Some(Offset { begin: 38, end: 40 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -211,8 +248,15 @@ fn test_ctrl_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 43, end: 44 }),
                 Some(Offset { begin: 44, end: 46 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
     ]
     .to_vec();
@@ -222,6 +266,7 @@ fn test_ctrl_tokenization() -> anyhow::Result<()> {
         128,
         &TruncationStrategy::LongestFirst,
         0,
+        &PaddingOptions::default(),
     );
 
     for (_idx, (predicted, expected)) in output.iter().zip(expected_results.iter()).enumerate() {
@@ -1,5 +1,7 @@
 use rust_tokenizers::preprocessing::tokenizer::base_tokenizer::Offset;
-use rust_tokenizers::{Gpt2Tokenizer, TokenizedInput, Tokenizer, TruncationStrategy};
+use rust_tokenizers::{
+    Gpt2Tokenizer, PaddingOptions, TokenizedInput, Tokenizer, TruncationStrategy,
+};
 
 mod test_utils;
 
@@ -58,8 +60,15 @@ fn test_gpt2_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 37, end: 38 }),
                 Some(Offset { begin: 38, end: 42 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -84,8 +93,15 @@ fn test_gpt2_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 38, end: 39 }),
                 Some(Offset { begin: 39, end: 41 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -138,8 +154,15 @@ fn test_gpt2_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 28, end: 29 }),
                 Some(Offset { begin: 29, end: 31 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -199,8 +222,15 @@ fn test_gpt2_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 60, end: 61 }),
                 Some(Offset { begin: 61, end: 63 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -266,8 +296,15 @@ fn test_gpt2_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 43, end: 44 }),
                 Some(Offset { begin: 44, end: 45 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -334,8 +371,15 @@ fn test_gpt2_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 49, end: 50 }),
                 Some(Offset { begin: 50, end: 51 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
     ]
     .to_vec();
@@ -345,6 +389,7 @@ fn test_gpt2_tokenization() -> anyhow::Result<()> {
         128,
         &TruncationStrategy::LongestFirst,
         0,
+        &PaddingOptions::default(),
     );
 
     for (_idx, (predicted, expected)) in output.iter().zip(expected_results.iter()).enumerate() {
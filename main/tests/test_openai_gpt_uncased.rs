@@ -1,5 +1,7 @@
 use rust_tokenizers::preprocessing::tokenizer::base_tokenizer::Offset;
-use rust_tokenizers::{OpenAiGptTokenizer, TokenizedInput, Tokenizer, TruncationStrategy};
+use rust_tokenizers::{
+    OpenAiGptTokenizer, PaddingOptions, TokenizedInput, Tokenizer, TruncationStrategy,
+};
 
 mod test_utils;
 
@@ -53,8 +55,15 @@ fn test_openai_gpt_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 34, end: 38 }),
                 Some(Offset { begin: 38, end: 42 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![3039, 718, 616, 812, 727, 571, 2987, 4780, 0, 257],
@@ -74,8 +83,15 @@ fn test_openai_gpt_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 38, end: 39 }),
                 Some(Offset { begin: 40, end: 41 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -103,8 +119,15 @@ fn test_openai_gpt_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 27, end: 30 }),
                 Some(Offset { begin: 30, end: 31 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -134,8 +157,15 @@ fn test_openai_gpt_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 43, end: 46 }),
                 Some(Offset { begin: 46, end: 47 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -163,8 +193,15 @@ fn test_openai_gpt_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 36, end: 39 }),
                 Some(Offset { begin: 39, end: 40 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -203,8 +240,15 @@ fn test_openai_gpt_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 42, end: 45 }),
                 Some(Offset { begin: 45, end: 46 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
     ]
     .to_vec();
@@ -214,6 +258,7 @@ fn test_openai_gpt_tokenization() -> anyhow::Result<()> {
         128,
         &TruncationStrategy::LongestFirst,
         0,
+        &PaddingOptions::default(),
     );
 
     for (_idx, (predicted, expected)) in output.iter().zip(expected_results.iter()).enumerate() {
@@ -1,5 +1,7 @@
 use rust_tokenizers::preprocessing::tokenizer::base_tokenizer::Offset;
-use rust_tokenizers::{RobertaTokenizer, TokenizedInput, Tokenizer, TruncationStrategy};
+use rust_tokenizers::{
+    PaddingOptions, RobertaTokenizer, TokenizedInput, Tokenizer, TruncationStrategy,
+};
 
 mod test_utils;
 
@@ -61,8 +63,15 @@ fn test_roberta_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 38, end: 42 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -88,8 +97,15 @@ fn test_roberta_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 39, end: 41 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -138,8 +154,15 @@ fn test_roberta_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 29, end: 31 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -195,8 +218,15 @@ fn test_roberta_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 43, end: 45 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -259,8 +289,15 @@ fn test_roberta_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 44, end: 45 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -324,8 +361,15 @@ fn test_roberta_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 50, end: 51 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
     ]
     .to_vec();
@@ -335,6 +379,7 @@ fn test_roberta_tokenization() -> anyhow::Result<()> {
         128,
         &TruncationStrategy::LongestFirst,
         0,
+        &PaddingOptions::default(),
     );
 
     for (_idx, (predicted, expected)) in output.iter().zip(expected_results.iter()).enumerate() {
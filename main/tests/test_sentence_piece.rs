@@ -1,5 +1,7 @@
 use rust_tokenizers::preprocessing::tokenizer::base_tokenizer::Offset;
-use rust_tokenizers::{SentencePieceTokenizer, TokenizedInput, Tokenizer, TruncationStrategy};
+use rust_tokenizers::{
+    PaddingOptions, SentencePieceTokenizer, TokenizedInput, Tokenizer, TruncationStrategy,
+};
 
 mod test_utils;
 
@@ -45,8 +47,15 @@ fn test_sentence_piece_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 37, end: 38 }),
                 Some(Offset { begin: 38, end: 42 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![14748, 56, 160, 52, 53, 133, 17366, 1227, 17, 0, 17, 82],
@@ -68,8 +77,15 @@ fn test_sentence_piece_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 39, end: 40 }),
                 Some(Offset { begin: 40, end: 41 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -109,8 +125,15 @@ fn test_sentence_piece_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 28, end: 29 }),
                 Some(Offset { begin: 29, end: 31 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -163,8 +186,15 @@ fn test_sentence_piece_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 43, end: 44 }),
                 Some(Offset { begin: 44, end: 45 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -214,8 +244,15 @@ fn test_sentence_piece_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 43, end: 44 }),
                 Some(Offset { begin: 44, end: 45 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
     ]
     .to_vec();
@@ -225,6 +262,7 @@ fn test_sentence_piece_tokenization() -> anyhow::Result<()> {
         128,
         &TruncationStrategy::LongestFirst,
         0,
+        &PaddingOptions::default(),
     );
 
     for (_idx, (predicted, expected)) in output.iter().zip(expected_results.iter()).enumerate() {
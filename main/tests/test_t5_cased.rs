@@ -4,7 +4,7 @@ mod test_utils;
 
 use rust_tokenizers::preprocessing::tokenizer::base_tokenizer::Offset;
 use rust_tokenizers::preprocessing::tokenizer::t5_tokenizer::T5Tokenizer;
-use rust_tokenizers::{TokenizedInput, Tokenizer, TruncationStrategy};
+use rust_tokenizers::{PaddingOptions, TokenizedInput, Tokenizer, TruncationStrategy};
 use test_utils::download_file_to_cache;
 
 #[test]
@@ -47,8 +47,15 @@ fn test_t5_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 37, end: 38 }),
                 Some(Offset { begin: 38, end: 42 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![16347, 53, 149, 48, 56, 129, 14145, 1601, 3, 2, 3, 58],
@@ -70,8 +77,15 @@ fn test_t5_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 39, end: 40 }),
                 Some(Offset { begin: 40, end: 41 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -112,8 +126,15 @@ fn test_t5_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 28, end: 29 }),
                 Some(Offset { begin: 29, end: 31 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -167,8 +188,15 @@ fn test_t5_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 43, end: 44 }),
                 Some(Offset { begin: 44, end: 45 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -219,8 +247,15 @@ fn test_t5_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 43, end: 44 }),
                 Some(Offset { begin: 44, end: 45 }),
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
     ]
     .to_vec();
@@ -230,6 +265,7 @@ fn test_t5_tokenization() -> anyhow::Result<()> {
         128,
         &TruncationStrategy::LongestFirst,
         0,
+        &PaddingOptions::default(),
     );
 
     for (_idx, (predicted, expected)) in output.iter().zip(expected_results.iter()).enumerate() {
@@ -1,5 +1,5 @@
 use rust_tokenizers::preprocessing::tokenizer::base_tokenizer::Offset;
-use rust_tokenizers::{TokenizedInput, Tokenizer, TruncationStrategy};
+use rust_tokenizers::{PaddingOptions, TokenizedInput, Tokenizer, TruncationStrategy};
 
 mod test_utils;
 
@@ -45,8 +45,15 @@ fn test_xlm_roberta_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 38, end: 42 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -72,8 +79,15 @@ fn test_xlm_roberta_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 39, end: 41 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -104,8 +118,15 @@ fn test_xlm_roberta_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 26, end: 31 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -149,8 +170,15 @@ fn test_xlm_roberta_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 44, end: 45 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
         TokenizedInput {
             token_ids: vec![
@@ -191,8 +219,15 @@ fn test_xlm_roberta_tokenization() -> anyhow::Result<()> {
                 Some(Offset { begin: 44, end: 45 }),
                 None,
             ],
+            byte_offsets: vec![],
+            utf16_offsets: vec![],
             reference_offsets: vec![],
             mask: vec![],
+            attention_mask: vec![],
+            overflow_to_sample_mapping: None,
+            word_ids: vec![],
+            sequence_ids: vec![],
+            alignment_index: Default::default(),
         },
     ]
     .to_vec();
@@ -202,6 +237,7 @@ fn test_xlm_roberta_tokenization() -> anyhow::Result<()> {
         128,
         &TruncationStrategy::LongestFirst,
         0,
+        &PaddingOptions::default(),
     );
 
     for (_idx, (predicted, expected)) in output.iter().zip(expected_results.iter()).enumerate() {
@@ -11,7 +11,7 @@ use rust_tokenizers::preprocessing::vocab::t5_vocab::T5Vocab;
 use rust_tokenizers::preprocessing::vocab::xlnet_vocab::XLNetVocab;
 use rust_tokenizers::{
     BertTokenizer, BertVocab, CtrlTokenizer, Gpt2Tokenizer, Gpt2Vocab, MultiThreadedTokenizer,
-    OpenAiGptTokenizer, OpenAiGptVocab, RobertaTokenizer, RobertaVocab, Tokenizer,
+    OpenAiGptTokenizer, OpenAiGptVocab, PaddingOptions, RobertaTokenizer, RobertaVocab, Tokenizer,
     TruncationStrategy, Vocab, XLMRobertaVocab, XLNetTokenizer,
 };
 
@@ -57,9 +57,14 @@ trait PyTokenizer<T: Tokenizer<U>, U: Vocab> {
         };
         match truncation_strategy {
             Ok(truncation_strategy) => {
-                let tokenized_input =
-                    self.tokenizer()
-                        .encode(&text, None, max_len, &truncation_strategy, stride);
+                let tokenized_input = self.tokenizer().encode(
+                    &text,
+                    None,
+                    max_len,
+                    &truncation_strategy,
+                    stride,
+                    &PaddingOptions::default(),
+                );
                 Ok(PyTokenizedInput {
                     token_ids: tokenized_input.token_ids,
                     segment_ids: tokenized_input.segment_ids,
@@ -95,6 +100,7 @@ trait PyTokenizer<T: Tokenizer<U>, U: Vocab> {
                     max_len,
                     &truncation_strategy,
                     stride,
+                    &PaddingOptions::default(),
                 );
                 Ok(PyTokenizedInput {
                     token_ids: tokenized_input.token_ids,
@@ -124,9 +130,13 @@ trait PyTokenizer<T: Tokenizer<U>, U: Vocab> {
         };
         match truncation_strategy {
             Ok(truncation_strategy) => {
-                let tokenized_inputs =
-                    self.tokenizer()
-                        .encode_list(text_list, max_len, &truncation_strategy, stride);
+                let tokenized_inputs = self.tokenizer().encode_list(
+                    text_list,
+                    max_len,
+                    &truncation_strategy,
+                    stride,
+                    &PaddingOptions::default(),
+                );
                 Ok(tokenized_inputs
                     .into_iter()
                     .map(|tokenized_input| PyTokenizedInput {
@@ -163,6 +173,7 @@ trait PyTokenizer<T: Tokenizer<U>, U: Vocab> {
                     max_len,
                     &truncation_strategy,
                     stride,
+                    &PaddingOptions::default(),
                 );
                 Ok(tokenized_inputs
                     .into_iter()
@@ -213,6 +224,7 @@ where
                     max_len,
                     &truncation_strategy,
                     stride,
+                    &PaddingOptions::default(),
                 );
                 Ok(tokenized_inputs
                     .into_iter()
@@ -251,6 +263,7 @@ where
                     max_len,
                     &truncation_strategy,
                     stride,
+                    &PaddingOptions::default(),
                 );
                 Ok(tokenized_inputs
                     .into_iter()